@@ -0,0 +1,65 @@
+use criterion::{
+    black_box, criterion_group, criterion_main, Criterion, Throughput,
+};
+
+/// A large document with a navigation bar repeated in every section, like
+/// the pages generated by most static site generators.
+fn markdown_document(sections: usize) -> String {
+    let mut src = String::new();
+
+    for i in 0..sections {
+        src.push_str(&format!("# Section {}\n\n", i));
+        src.push_str("[Home](index.md) | [Guide](guide/index.md) | ");
+        src.push_str("[API](https://docs.rs/linkcheck)\n\n");
+        src.push_str(&format!(
+            "Some text with a [link](chapter-{}.md#part-{}) and an \
+             autolink to <https://example.com/{}>.\n\n",
+            i, i, i
+        ));
+    }
+
+    src
+}
+
+fn html_document(sections: usize) -> String {
+    let mut src = String::from("<html><body>\n");
+
+    for i in 0..sections {
+        src.push_str(&format!("<h1 id=\"section-{}\">Section {}</h1>\n", i, i));
+        src.push_str("<nav><a href=\"index.html\">Home</a> ");
+        src.push_str("<a href='guide/index.html'>Guide</a></nav>\n");
+        src.push_str(&format!(
+            "<p>Some text with a <a href=\"chapter-{}.html#part-{}\">link</a> \
+             and an <img src=\"images/{}.png\" alt=\"image\"></p>\n",
+            i, i, i
+        ));
+    }
+
+    src.push_str("</body></html>\n");
+    src
+}
+
+fn scanning(c: &mut Criterion) {
+    let markdown = markdown_document(1000);
+    let html = html_document(1000);
+
+    let mut group = c.benchmark_group("scanners");
+
+    group.throughput(Throughput::Bytes(markdown.len() as u64));
+    group.bench_function("markdown", |b| {
+        b.iter(|| linkcheck::scanners::markdown(black_box(&markdown)).count())
+    });
+    group.bench_function("plaintext", |b| {
+        b.iter(|| linkcheck::scanners::plaintext(black_box(&markdown)).count())
+    });
+
+    group.throughput(Throughput::Bytes(html.len() as u64));
+    group.bench_function("html", |b| {
+        b.iter(|| linkcheck::scanners::html(black_box(&html)).count())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, scanning);
+criterion_main!(benches);
@@ -0,0 +1,83 @@
+use codespan::Files;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use linkcheck::{BasicContext, Link};
+use std::path::Path;
+
+const PAGES: usize = 200;
+
+/// Create a directory full of pages to link to.
+fn setup() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+
+    for i in 0..PAGES {
+        std::fs::write(temp.path().join(format!("page-{}.md", i)), "").unwrap();
+    }
+    std::fs::write(temp.path().join("index.md"), "").unwrap();
+
+    temp
+}
+
+/// Every page links to the index and its neighbours, so most hrefs are
+/// duplicates.
+fn links(files: &mut Files<String>) -> Vec<Link> {
+    let mut links = Vec::new();
+
+    for i in 0..PAGES {
+        let file = files.add(format!("page-{}.md", i), String::new());
+        let hrefs = [
+            String::from("index.md"),
+            format!("page-{}.md", (i + 1) % PAGES),
+            format!("page-{}.md", (i + PAGES - 1) % PAGES),
+            String::from("missing.md"),
+        ];
+        links.extend(
+            hrefs
+                .iter()
+                .map(|href| Link::new(href.as_str(), Default::default(), file)),
+        );
+    }
+
+    links
+}
+
+fn validation(c: &mut Criterion) {
+    let temp = setup();
+    let mut files = Files::new();
+    let links = links(&mut files);
+    let ctx = BasicContext::default();
+
+    let mut group = c.benchmark_group("validation");
+
+    group.bench_function("validate_local_links", |b| {
+        b.iter_batched(
+            || links.clone(),
+            |links| {
+                futures::executor::block_on(linkcheck::validate(
+                    temp.path(),
+                    links,
+                    &ctx,
+                ))
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("validate_local", |b| {
+        b.iter_batched(
+            || {
+                links
+                    .iter()
+                    .cloned()
+                    .map(|link| (link, temp.path()))
+                    .collect::<Vec<(Link, &Path)>>()
+            },
+            |links| linkcheck::validation::validate_local(links, &ctx),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, validation);
+criterion_main!(benches);
@@ -0,0 +1,5 @@
+fn main() {
+    // let newer compilers know about the cfg that turns on reqwest's
+    // unstable HTTP/3 support
+    println!("cargo:rustc-check-cfg=cfg(reqwest_unstable)");
+}
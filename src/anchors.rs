@@ -0,0 +1,775 @@
+//! An index of the anchors (heading slugs and HTML `id`s) in each document,
+//! used when checking links like `file.md#section`.
+//!
+//! Anchors can be read from markdown, HTML, reStructuredText, and AsciiDoc
+//! documents.
+//!
+//! Building an [`AnchorDb`] up front means each document only gets parsed
+//! once, no matter how many links point into it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use linkcheck::anchors::AnchorDb;
+//!
+//! let mut db = AnchorDb::new();
+//! db.insert_markdown(
+//!     "guide.md",
+//!     "# Getting Started\n\n<a id=\"setup\"></a>\n\n## Getting Started",
+//! );
+//!
+//! assert_eq!(db.contains("guide.md", "getting-started"), Some(true));
+//! assert_eq!(db.contains("guide.md", "getting-started-1"), Some(true));
+//! assert_eq!(db.contains("guide.md", "setup"), Some(true));
+//! assert_eq!(db.contains("guide.md", "installation"), Some(false));
+//! // we know nothing about this file
+//! assert_eq!(db.contains("other.md", "setup"), None);
+//! ```
+
+use crate::{
+    lints::{Lint, LintKind},
+    scanners::html::tags,
+    site::Document,
+    validation::Outcomes,
+    Link,
+};
+use codespan::{Files, Span};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// The attributes which can be linked to with a fragment.
+const ANCHOR_ATTRIBUTES: &[&str] = &["id", "name"];
+
+/// The anchors available in each document.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AnchorDb {
+    documents: HashMap<PathBuf, HashSet<String>>,
+    duplicates: HashMap<PathBuf, Vec<DuplicateAnchor>>,
+}
+
+/// An anchor which is used more than once in the same document.
+///
+/// Renderers quietly give duplicate headings a `-1`, `-2`, ... suffix, so a
+/// link to the heading's anchor goes to the first section with that name,
+/// which might not be the one the author intended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateAnchor {
+    /// The anchor which is used more than once.
+    pub anchor: String,
+    /// The anchor the renderer gives this heading instead (e.g.
+    /// `installation-1`), or `None` for a duplicate HTML `id`.
+    pub renamed: Option<String>,
+    /// Where the duplicate heading or `id` is in its document.
+    pub span: Span,
+}
+
+impl AnchorDb {
+    /// Create a new, empty [`AnchorDb`].
+    pub fn new() -> Self { AnchorDb::default() }
+
+    /// Build an [`AnchorDb`] from documents which have already been loaded
+    /// into `files` (e.g. by [`crate::site::Site::scan()`]).
+    pub fn from_documents<S>(documents: &[Document], files: &Files<S>) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let mut db = AnchorDb::new();
+
+        for doc in documents {
+            db.insert_source(&doc.path, files.source(doc.file).as_ref());
+        }
+
+        db
+    }
+
+    /// Report every [`DuplicateAnchor`] in the `documents` as a [`Lint`].
+    ///
+    /// Each [`Lint`]'s [`Link`] points at the duplicate, with the anchor it
+    /// was given by the renderer as its `href` (e.g. `#installation-1`). The
+    /// returned [`Outcomes`] only contain [`Outcomes::lints`].
+    pub fn lint_duplicates(&self, documents: &[Document]) -> Outcomes {
+        let mut outcomes = Outcomes::empty();
+
+        for doc in documents {
+            for duplicate in self.duplicates(&doc.path) {
+                let anchor =
+                    duplicate.renamed.as_ref().unwrap_or(&duplicate.anchor);
+                let href = format!("#{}", anchor);
+
+                outcomes.lints.push(Lint {
+                    link: Link::new(href, duplicate.span, doc.file),
+                    kind: LintKind::DuplicateAnchor(duplicate.anchor.clone()),
+                });
+            }
+        }
+
+        outcomes
+    }
+
+    /// Read a file from disk and record its anchors, picking a parser based
+    /// on its extension.
+    pub fn insert_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)?;
+        self.insert_source(path, &src);
+
+        Ok(())
+    }
+
+    /// Record the anchors in a markdown document.
+    ///
+    /// Any [`DuplicateAnchor`]s are logged as warnings and can be retrieved
+    /// with [`AnchorDb::duplicates()`].
+    pub fn insert_markdown<P: AsRef<Path>>(&mut self, path: P, src: &str) {
+        let anchors = markdown_anchors(src);
+        self.insert(&path, anchors.found);
+        self.set_duplicates(path.as_ref(), anchors.duplicates);
+    }
+
+    /// Record the anchors in a HTML document.
+    pub fn insert_html<P: AsRef<Path>>(&mut self, path: P, src: &str) {
+        let anchors = html_anchors(src);
+        self.insert(&path, anchors.found);
+        self.set_duplicates(path.as_ref(), anchors.duplicates);
+    }
+
+    /// Record the anchors in a reStructuredText document, using the same ids
+    /// as `docutils` for section titles and explicit targets (e.g.
+    /// `.. _installation:`).
+    pub fn insert_rst<P: AsRef<Path>>(&mut self, path: P, src: &str) {
+        let anchors = rst_anchors(src);
+        self.insert(&path, anchors.found);
+        self.set_duplicates(path.as_ref(), anchors.duplicates);
+    }
+
+    /// Record the anchors in an AsciiDoc document, using the same ids as
+    /// Asciidoctor for section titles and explicit anchors (e.g. `[[setup]]`
+    /// or `[#setup]`).
+    pub fn insert_asciidoc<P: AsRef<Path>>(&mut self, path: P, src: &str) {
+        let anchors = asciidoc_anchors(src);
+        self.insert(&path, anchors.found);
+        self.set_duplicates(path.as_ref(), anchors.duplicates);
+    }
+
+    /// The anchors which were used more than once in a document.
+    pub fn duplicates<P: AsRef<Path>>(&self, path: P) -> &[DuplicateAnchor] {
+        self.duplicates
+            .get(&key(path.as_ref()))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn set_duplicates(
+        &mut self,
+        path: &Path,
+        duplicates: Vec<DuplicateAnchor>,
+    ) {
+        for duplicate in &duplicates {
+            log::warn!(
+                "The \"{}\" anchor is used more than once in \"{}\"",
+                duplicate.anchor,
+                path.display()
+            );
+        }
+
+        if duplicates.is_empty() {
+            self.duplicates.remove(&key(path));
+        } else {
+            self.duplicates.insert(key(path), duplicates);
+        }
+    }
+
+    /// Record a set of anchors for a document, replacing any that were there
+    /// before.
+    ///
+    /// Anchors are stored in Unicode Normalization Form C, so `#café` matches
+    /// regardless of how the accent was typed.
+    pub fn insert<P, I>(&mut self, path: P, anchors: I)
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = String>,
+    {
+        self.documents.insert(
+            key(path.as_ref()),
+            anchors.into_iter().map(normalise).collect(),
+        );
+    }
+
+    /// Get all the anchors in a document, if we know about it.
+    pub fn anchors<P: AsRef<Path>>(&self, path: P) -> Option<&HashSet<String>> {
+        self.documents.get(&key(path.as_ref()))
+    }
+
+    /// Does this document contain a particular anchor?
+    ///
+    /// Returns `None` when the document isn't in the [`AnchorDb`].
+    pub fn contains<P: AsRef<Path>>(
+        &self,
+        path: P,
+        fragment: &str,
+    ) -> Option<bool> {
+        let anchors = self.anchors(path)?;
+
+        if is_nfc(fragment) {
+            Some(anchors.contains(fragment))
+        } else {
+            Some(anchors.contains(&fragment.nfc().collect::<String>()))
+        }
+    }
+
+    /// Find the anchor in a document which is closest to `fragment` (e.g.
+    /// `installation` for `#instalation`), for suggesting a fix when the
+    /// fragment doesn't exist.
+    ///
+    /// Anchors which would need more than a third of the fragment changed
+    /// aren't considered close.
+    pub fn closest<P: AsRef<Path>>(
+        &self,
+        path: P,
+        fragment: &str,
+    ) -> Option<&str> {
+        let fragment = fragment.nfc().collect::<String>().to_lowercase();
+        let max_distance = std::cmp::max(fragment.chars().count() / 3, 1);
+
+        self.anchors(path)?
+            .iter()
+            .map(|anchor| {
+                (edit_distance(&fragment, &anchor.to_lowercase()), anchor)
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            // break ties alphabetically so the suggestion is deterministic
+            .min()
+            .map(|(_, anchor)| anchor.as_str())
+    }
+
+    fn insert_source(&mut self, path: &Path, src: &str) {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "md" | "markdown" => self.insert_markdown(path, src),
+            "html" | "htm" => self.insert_html(path, src),
+            "rst" => self.insert_rst(path, src),
+            "adoc" | "asciidoc" | "asc" => self.insert_asciidoc(path, src),
+            _ => log::debug!(
+                "Not looking for anchors in \"{}\" because it isn't a \
+                 supported format",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Convert to NFC, avoiding allocations for the common case.
+fn normalise(anchor: String) -> String {
+    if is_nfc(&anchor) {
+        anchor
+    } else {
+        anchor.nfc().collect()
+    }
+}
+
+/// The edit distance between two strings, where swapping two adjacent
+/// characters counts as one edit (the optimal string alignment distance).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(d[i - 2][j - 2] + 1);
+            }
+            d[i][j] = best;
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+fn key(path: &Path) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The anchors found in a single document.
+#[derive(Debug, Default)]
+struct Anchors {
+    found: HashSet<String>,
+    duplicates: Vec<DuplicateAnchor>,
+}
+
+impl Anchors {
+    /// Add an anchor which can't be renamed (e.g. a HTML `id`).
+    fn add(&mut self, anchor: String, span: Span) {
+        if self.found.contains(&anchor) {
+            self.duplicates.push(DuplicateAnchor {
+                anchor,
+                renamed: None,
+                span,
+            });
+        } else {
+            self.found.insert(anchor);
+        }
+    }
+}
+
+/// Find every heading slug (using the same rules as GitHub and `mdbook`) and
+/// HTML `id` in a markdown document.
+fn markdown_anchors(src: &str) -> Anchors {
+    let mut anchors = Anchors::default();
+    let mut slug_counts: HashMap<String, usize> = HashMap::new();
+    let mut heading: Option<String> = None;
+
+    let events = Parser::new_ext(src, Options::all()).into_offset_iter();
+
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::Heading(_)) => heading = Some(String::new()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = heading.as_mut() {
+                    heading.push_str(&text);
+                }
+            },
+            Event::End(Tag::Heading(_)) => {
+                let slug = slugify(&heading.take().unwrap_or_default());
+                let count = slug_counts.entry(slug.clone()).or_insert(0);
+
+                if *count == 0 {
+                    anchors.found.insert(slug);
+                } else {
+                    let renamed = format!("{}-{}", slug, count);
+                    anchors.found.insert(renamed.clone());
+                    anchors.duplicates.push(DuplicateAnchor {
+                        anchor: slug,
+                        renamed: Some(renamed),
+                        span: Span::new(range.start as u32, range.end as u32),
+                    });
+                }
+                *count += 1;
+            },
+            Event::Html(html) => {
+                for (anchor, span) in html_ids(&html, range.start) {
+                    anchors.add(anchor, span);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    anchors
+}
+
+fn html_anchors(src: &str) -> Anchors {
+    let mut anchors = Anchors::default();
+
+    for (anchor, span) in html_ids(src, 0) {
+        anchors.add(anchor, span);
+    }
+
+    anchors
+}
+
+/// Find every section title and explicit target in a reStructuredText
+/// document.
+fn rst_anchors(src: &str) -> Anchors {
+    let mut anchors = Anchors::default();
+    let lines: Vec<_> = lines_with_offsets(src).collect();
+
+    for (i, &(offset, line)) in lines.iter().enumerate() {
+        let span = Span::new(offset as u32, (offset + line.len()) as u32);
+        let trimmed = line.trim();
+
+        if let Some(name) = trimmed
+            .strip_prefix(".. _")
+            .and_then(|rest| rest.strip_suffix(':'))
+        {
+            let name = name.trim_matches('`');
+            // ".. __:" is an anonymous target
+            if !name.is_empty() && name != "_" {
+                anchors.add(docutils_id(name), span);
+            }
+            continue;
+        }
+
+        let underline = lines.get(i + 1).map(|(_, l)| l.trim_end());
+        let is_title = !trimmed.is_empty()
+            && !line.starts_with(char::is_whitespace)
+            && !is_rst_adornment(trimmed)
+            && underline.is_some_and(|underline| {
+                is_rst_adornment(underline)
+                    && underline.len() >= trimmed.chars().count()
+            });
+
+        if is_title {
+            anchors.add(docutils_id(trimmed), span);
+        }
+    }
+
+    anchors
+}
+
+/// Is this line a section title's underline or overline (e.g. `=====`)?
+fn is_rst_adornment(line: &str) -> bool {
+    let mut chars = line.chars();
+
+    match chars.next() {
+        Some(first) if first.is_ascii_punctuation() => {
+            line.len() > 1 && chars.all(|c| c == first)
+        },
+        _ => false,
+    }
+}
+
+/// The id `docutils` gives a section or target (its `make_id()` function).
+fn docutils_id(name: &str) -> String {
+    let ascii: String = name
+        .nfkd()
+        .filter(char::is_ascii)
+        .collect::<String>()
+        .to_lowercase();
+
+    let mut id = String::new();
+    for c in ascii.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c);
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+
+    id.trim_start_matches(|c: char| c.is_ascii_digit() || c == '-')
+        .trim_end_matches('-')
+        .to_string()
+}
+
+/// Find every section title and explicit anchor in an AsciiDoc document.
+fn asciidoc_anchors(src: &str) -> Anchors {
+    let mut anchors = Anchors::default();
+    // a block anchor (e.g. "[[setup]]") on the line before a section title
+    // replaces the generated id
+    let mut block_anchor = false;
+
+    for (offset, line) in lines_with_offsets(src) {
+        let span = Span::new(offset as u32, (offset + line.len()) as u32);
+        let trimmed = line.trim();
+        let explicit = asciidoc_explicit_anchors(trimmed);
+        let is_block_anchor = !explicit.is_empty()
+            && trimmed.starts_with('[')
+            && trimmed.ends_with(']');
+
+        for id in explicit {
+            anchors.add(id, span);
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '=').count();
+        let title = trimmed[level..].strip_prefix(' ').map(str::trim);
+
+        // level 0 is the document title, which doesn't get an id
+        if let (2..=6, Some(title), false) = (level, title, block_anchor) {
+            anchors.add(asciidoctor_id(title), span);
+        }
+
+        block_anchor = is_block_anchor;
+    }
+
+    anchors
+}
+
+/// Find explicit anchors like `[[id]]`, `[[id,text]]`, `[#id.role]`, and
+/// `anchor:id[]`.
+fn asciidoc_explicit_anchors(line: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let is_id = |id: &str| {
+        !id.is_empty()
+            && id.chars().all(|c| {
+                c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.')
+            })
+    };
+
+    let mut rest = line;
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        let end = match after.find("]]") {
+            Some(end) => end,
+            None => break,
+        };
+        let id = after[..end].split(',').next().unwrap_or_default().trim();
+        if is_id(id) {
+            ids.push(id.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+
+    if let Some(attributes) = line
+        .strip_prefix("[#")
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        let id = attributes.split(['.', '%', ',']).next().unwrap_or_default();
+        if is_id(id) {
+            ids.push(id.to_string());
+        }
+    }
+
+    let mut rest = line;
+    while let Some(start) = rest.find("anchor:") {
+        let after = &rest[start + "anchor:".len()..];
+        let end = after.find('[').unwrap_or(after.len());
+        if is_id(&after[..end]) {
+            ids.push(after[..end].to_string());
+        }
+        rest = &after[end..];
+    }
+
+    ids
+}
+
+/// The id Asciidoctor generates for a section title, using the default
+/// `idprefix` (`_`) and `idseparator` (`_`).
+fn asciidoctor_id(title: &str) -> String {
+    let mut id = String::from("_");
+
+    for c in title.to_lowercase().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            id.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '.')
+            && !id.ends_with('_')
+        {
+            id.push('_');
+        }
+    }
+
+    if id.len() > 1 {
+        id.trim_end_matches('_').to_string()
+    } else {
+        id
+    }
+}
+
+/// Split a document into lines, keeping track of where each one starts.
+fn lines_with_offsets(src: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+
+    src.split('\n').map(move |line| {
+        let start = offset;
+        offset += line.len() + 1;
+        (start, line.trim_end_matches('\r'))
+    })
+}
+
+/// Find the `id` (or `name`) of each HTML element in some text which starts
+/// `offset` bytes into its document.
+fn html_ids(src: &str, offset: usize) -> Vec<(String, Span)> {
+    let mut ids = Vec::new();
+
+    for tag in tags(src) {
+        let mut seen = HashSet::new();
+
+        for attr in &tag.attributes {
+            if !ANCHOR_ATTRIBUTES.contains(&attr.name.as_str()) {
+                continue;
+            }
+            let (value, span) = match (attr.value_in(src), attr.value) {
+                (Some(value), Some(span)) if !value.is_empty() => (value, span),
+                _ => continue,
+            };
+
+            // <a id="x" name="x"> only defines one anchor
+            if seen.insert(value) {
+                let start = (offset + span.start().to_usize()) as u32;
+                let end = (offset + span.end().to_usize()) as u32;
+                ids.push((value.to_string(), Span::new(start, end)));
+            }
+        }
+    }
+
+    ids
+}
+
+/// Turn a heading into the slug used for its anchor.
+pub fn slugify(heading: &str) -> String {
+    heading
+        .trim()
+        .nfc()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                Some(c.to_lowercase().collect::<String>())
+            } else if c.is_whitespace() {
+                Some(String::from("-"))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_anchors_in_markdown() {
+        let src = r#"
+# Hello, `World`!
+
+Some text <span id="inline-anchor">here</span>.
+
+## Hello, World
+
+### Ünïcode & Things
+
+```html
+<a id="in-a-code-block"></a>
+```
+"#;
+
+        let mut got: Vec<_> = markdown_anchors(src).found.into_iter().collect();
+        got.sort();
+
+        assert_eq!(
+            got,
+            vec![
+                "hello-world",
+                "hello-world-1",
+                "inline-anchor",
+                "ünïcode--things"
+            ]
+        );
+    }
+
+    #[test]
+    fn check_fragments_using_the_anchor_db() {
+        let temp = tempfile::tempdir().unwrap();
+        let page = temp.path().join("page.html");
+        std::fs::write(&page, r#"<h1 id="title">Title</h1>"#).unwrap();
+        let mut ctx = crate::BasicContext::default();
+        let mut db = AnchorDb::new();
+        db.insert_file(&page).unwrap();
+        ctx.anchors = Some(db);
+        let check = |fragment| {
+            crate::validation::check_filesystem(
+                temp.path(),
+                Path::new("page.html"),
+                Some(fragment),
+                &ctx,
+            )
+        };
+
+        assert!(check("title").is_ok());
+        match check("nope").unwrap_err() {
+            crate::validation::Reason::MissingAnchor(fragment, None) => {
+                assert_eq!(fragment, "nope")
+            },
+            other => panic!("Unexpected error: {}", other),
+        }
+        let got = check("titel").unwrap_err();
+        assert_eq!(
+            got.to_string(),
+            "The \"#titel\" anchor doesn't exist (did you mean \"#title\"?)"
+        );
+    }
+
+    #[test]
+    fn find_anchors_in_rst_and_asciidoc() {
+        let rst = "=====\nTitle\n=====\n\n.. _install-guide:\n\n\
+                   Installing on Café OS\n---------------------\n\n\
+                   ----\n\n.. _`Other Target`:\n";
+        let adoc = "= Document\n\n== Getting Started\n\n[[setup]]\n\
+                    === Set-up the v1.0 tools\n\n[#faq.big]\n== FAQ\n\n\
+                    Some text with an anchor:inline[] and [[ref,Ref]].\n";
+        let mut db = AnchorDb::new();
+        db.insert_rst("guide.rst", rst);
+        db.insert_asciidoc("guide.adoc", adoc);
+
+        let mut got: Vec<_> = db.anchors("guide.rst").unwrap().iter().collect();
+        got.sort();
+        assert_eq!(
+            got,
+            vec![
+                "install-guide",
+                "installing-on-cafe-os",
+                "other-target",
+                "title",
+            ]
+        );
+
+        let mut got: Vec<_> =
+            db.anchors("guide.adoc").unwrap().iter().collect();
+        got.sort();
+        assert_eq!(
+            got,
+            vec!["_getting_started", "faq", "inline", "ref", "setup"]
+        );
+    }
+
+    #[test]
+    fn detect_duplicate_anchors() {
+        let src = "# Setup\n\n## Install\n\n## Setup\n\n\
+                   <a id=\"faq\" name=\"faq\"></a> <span id=\"faq\"></span>";
+        let mut files = Files::new();
+        let file = files.add("guide.md", src);
+        let doc = Document {
+            file,
+            path: PathBuf::from("guide.md"),
+            links: Vec::new(),
+        };
+        let documents = vec![doc];
+        let db = AnchorDb::from_documents(&documents, &files);
+
+        let got: Vec<_> = db
+            .lint_duplicates(&documents)
+            .lints
+            .into_iter()
+            .map(|lint| {
+                let span = lint.link.span;
+                let text = &src[span.start().to_usize()..span.end().to_usize()];
+                (lint.link.href, lint.kind, text.to_string())
+            })
+            .collect();
+
+        let should_be = vec![
+            (
+                String::from("#setup-1"),
+                LintKind::DuplicateAnchor(String::from("setup")),
+                String::from("## Setup\n"),
+            ),
+            (
+                String::from("#faq"),
+                LintKind::DuplicateAnchor(String::from("faq")),
+                String::from("faq"),
+            ),
+        ];
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn composed_and_decomposed_anchors_match() {
+        let mut db = AnchorDb::new();
+        // a decomposed "é", as typed on some platforms
+        let src = "# Cafe\u{301}\n\n<a id=\"Cre\u{300}me\"></a>";
+        db.insert_markdown("menu.md", src);
+
+        assert_eq!(db.contains("menu.md", "caf\u{e9}"), Some(true));
+        assert_eq!(db.contains("menu.md", "Cr\u{e8}me"), Some(true));
+        assert_eq!(db.contains("menu.md", "Cre\u{300}me"), Some(true));
+    }
+}
@@ -0,0 +1,204 @@
+//! A reverse index for finding every [`Link`] which points at a particular
+//! file or anchor.
+
+use crate::{
+    validation::{resolve_link, Options},
+    Category, Link,
+};
+use codespan::{FileId, Files};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// A lookup table answering "what links to this file?".
+///
+/// This is handy when refactoring documentation, letting you find every
+/// reference to a page (or one of its sections) before moving or renaming it.
+///
+/// # Examples
+///
+/// ```rust
+/// use codespan::Files;
+/// use linkcheck::{backlinks::Backlinks, validation::Options, Link};
+/// use std::path::Path;
+///
+/// let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+/// let mut files = Files::new();
+/// let src = "Check out [the README](README.md#license).";
+/// let file_id = files.add(manifest_dir.join("index.md"), src);
+///
+/// let links = linkcheck::scanners::markdown(src)
+///     .map(|(href, span)| Link::new(href, span, file_id));
+/// let backlinks = Backlinks::from_links(links, &files, &Options::default());
+///
+/// let readme = manifest_dir.join("README.md");
+/// assert_eq!(backlinks.links_to(&readme).count(), 1);
+/// assert_eq!(backlinks.links_to_anchor(&readme, "license").count(), 1);
+/// assert_eq!(backlinks.links_to_anchor(&readme, "usage").count(), 0);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Backlinks {
+    targets: HashMap<PathBuf, Vec<Backlink>>,
+}
+
+impl Backlinks {
+    /// Create a new, empty [`Backlinks`] index.
+    pub fn new() -> Self { Backlinks::default() }
+
+    /// Build an index from a set of [`Link`]s.
+    ///
+    /// Each [`Link`] is resolved relative to the directory containing its
+    /// [`Link::file`] (as named in `files`) using the same rules as
+    /// [`resolve_link()`]. Web links and links which can't be resolved to an
+    /// existing file are skipped.
+    pub fn from_links<L, S>(
+        links: L,
+        files: &Files<S>,
+        options: &Options,
+    ) -> Self
+    where
+        L: IntoIterator<Item = Link>,
+        S: AsRef<str>,
+    {
+        let mut backlinks = Backlinks::new();
+
+        for link in links {
+            let source = Path::new(files.name(link.file));
+            let current_directory =
+                source.parent().unwrap_or_else(|| Path::new("."));
+
+            match link.category() {
+                Some(Category::FileSystem { path, fragment, .. }) => {
+                    match resolve_link(current_directory, &path, options) {
+                        Ok(target) => {
+                            backlinks.insert(target, fragment, link)
+                        },
+                        Err(e) => log::debug!(
+                            "Unable to resolve \"{}\" for the backlink index: {}",
+                            link.href,
+                            e
+                        ),
+                    }
+                },
+                Some(Category::CurrentFile { fragment }) => {
+                    let target = dunce::canonicalize(source)
+                        .unwrap_or_else(|_| source.to_path_buf());
+                    backlinks.insert(target, Some(fragment), link);
+                },
+                _ => {},
+            }
+        }
+
+        backlinks
+    }
+
+    /// Record that `link` points at the `target` file (and optionally an
+    /// anchor within it).
+    pub fn insert(
+        &mut self,
+        target: PathBuf,
+        fragment: Option<String>,
+        link: Link,
+    ) {
+        self.targets
+            .entry(target)
+            .or_default()
+            .push(Backlink { link, fragment });
+    }
+
+    /// Find every [`Link`] pointing at a particular file, regardless of which
+    /// anchor it points to.
+    pub fn links_to(&self, target: &Path) -> impl Iterator<Item = &Link> + '_ {
+        self.backlinks(target).map(|backlink| &backlink.link)
+    }
+
+    /// Find every [`Link`] pointing at a specific anchor within a file.
+    pub fn links_to_anchor<'a>(
+        &'a self,
+        target: &Path,
+        fragment: &'a str,
+    ) -> impl Iterator<Item = &'a Link> + 'a {
+        self.backlinks(target)
+            .filter(move |backlink| {
+                backlink.fragment.as_deref() == Some(fragment)
+            })
+            .map(|backlink| &backlink.link)
+    }
+
+    /// Get every [`Backlink`] pointing at a particular file.
+    pub fn backlinks(
+        &self,
+        target: &Path,
+    ) -> impl Iterator<Item = &Backlink> + '_ {
+        let target = dunce::canonicalize(target)
+            .unwrap_or_else(|_| target.to_path_buf());
+
+        self.targets.get(&target).into_iter().flatten()
+    }
+
+    /// Iterate over every file which has at least one incoming [`Link`].
+    pub fn targets(&self) -> impl Iterator<Item = &Path> + '_ {
+        self.targets.keys().map(|path| path.as_path())
+    }
+
+    /// Find all the files that the [`Link`]s in a particular document point
+    /// to.
+    pub fn outgoing(&self, file: FileId) -> impl Iterator<Item = &Path> + '_ {
+        self.targets
+            .iter()
+            .filter(move |(_, backlinks)| {
+                backlinks.iter().any(|backlink| backlink.link.file == file)
+            })
+            .map(|(path, _)| path.as_path())
+    }
+}
+
+/// A [`Link`] pointing at a file, as recorded by [`Backlinks`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backlink {
+    /// The original [`Link`].
+    pub link: Link,
+    /// The anchor being linked to, if there was one.
+    pub fragment: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codespan::Span;
+
+    #[test]
+    fn index_links_to_files_and_anchors() {
+        let temp = tempfile::tempdir().unwrap();
+        let temp = dunce::canonicalize(temp.path()).unwrap();
+        let target = temp.join("target.md");
+        std::fs::write(&target, "# Heading").unwrap();
+        let mut files = Files::new();
+        let first = files.add(temp.join("first.md"), "");
+        let second = files.add(temp.join("second.md"), "");
+        let links = vec![
+            Link::new("./target.md", Span::new(0, 1), first),
+            Link::new("target.md#heading", Span::new(2, 3), second),
+            Link::new("#local", Span::new(4, 5), second),
+            Link::new("missing.md", Span::new(6, 7), second),
+            Link::new("https://example.com/", Span::new(8, 9), second),
+        ];
+
+        let backlinks =
+            Backlinks::from_links(links.clone(), &files, &Options::default());
+
+        let got: Vec<_> = backlinks.links_to(&target).cloned().collect();
+        assert_eq!(got, &links[..2]);
+        let got: Vec<_> =
+            backlinks.links_to_anchor(&target, "heading").collect();
+        assert_eq!(got, vec![&links[1]]);
+        let got: Vec<_> = backlinks
+            .links_to_anchor(&temp.join("second.md"), "local")
+            .collect();
+        assert_eq!(got, vec![&links[2]]);
+        assert_eq!(backlinks.targets().count(), 2);
+        let got: Vec<_> = backlinks.outgoing(first).collect();
+        assert_eq!(got, vec![&target]);
+    }
+}
@@ -0,0 +1,760 @@
+//! A command-line tool for checking the links in a set of documents or a
+//! deployed website.
+
+use clap::{Parser, ValueEnum};
+use codespan::{Files, Span};
+use futures::StreamExt;
+#[cfg(feature = "hickory-dns")]
+use linkcheck::dns::{DnsCache, Nameservers};
+use linkcheck::{
+    anchors::AnchorDb,
+    crawl::Crawler,
+    dns::IpPreference,
+    scanners::{self, FileKind},
+    site::{self, Document, Site},
+    validation::{
+        Baseline, CheckLevel, CheckLevels, Context, ContextExt, DomainPolicy,
+        ExpectedFailures, ForgeApi, KnownBroken, Options, Outcomes,
+        PackageRegistries, ProxySettings, Redactor, SafetyLimits, Unlisted,
+        WaybackMachine,
+    },
+    BasicContext, Link,
+};
+#[cfg(feature = "hickory-dns")]
+use std::net::{IpAddr, SocketAddr};
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+/// Check the links in some documents or a website.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// Files, directories, or `http(s)://` URLs to check. Websites are
+    /// crawled, following links to pages on the same origin.
+    #[arg(required = true)]
+    inputs: Vec<String>,
+    /// A JSON file containing the settings to use (e.g. `ignore`,
+    /// `known_broken`, `redact_params`, `limits`, `concurrency`, and the
+    /// options for checking links to local files). Flags take precedence.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Only scan files in a directory which match this glob.
+    #[arg(long)]
+    include: Vec<String>,
+    /// Skip files in a directory which match this glob.
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// Also check markdown links with unencoded spaces in their destination
+    /// (e.g. `[doc](./my file.md)`).
+    #[arg(long)]
+    lenient: bool,
+    /// Show the surrounding text and heading for each broken link.
+    #[arg(long)]
+    context: bool,
+    /// How many links away from the starting URL to crawl.
+    #[arg(long, default_value_t = Crawler::DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
+    /// The maximum number of web pages to crawl.
+    #[arg(long, default_value_t = Crawler::DEFAULT_MAX_PAGES)]
+    max_pages: usize,
+    /// How the results should be reported.
+    #[arg(short, long, value_enum, default_value_t = Format::Human)]
+    format: Format,
+    /// Where to write the report (defaults to stdout).
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Stop sending web requests after this many seconds, reporting any
+    /// links which haven't been checked yet as unchecked.
+    #[arg(long)]
+    time_budget: Option<u64>,
+    /// A file listing links which are known to be broken, one glob per line
+    /// with an optional `YYYY-MM-DD` expiry date.
+    #[arg(long)]
+    known_broken: Option<PathBuf>,
+    /// Links to this domain (and its subdomains) are supposed to be broken,
+    /// like the reserved `.example`, `.invalid`, and `.test` domains. May be
+    /// repeated.
+    #[arg(long = "expect-failure", value_name = "DOMAIN")]
+    expect_failure: Vec<String>,
+    /// A JSON file recording links which were already broken, so they are
+    /// accepted until somebody fixes them. Stale entries are printed.
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+    /// Record every broken link in the `--baseline` file instead of reading
+    /// it.
+    #[arg(long, requires = "baseline")]
+    update_baseline: bool,
+    /// Print how each link would be checked (e.g. the files that would be
+    /// looked for, or the API that would be asked), without checking it.
+    #[arg(long)]
+    dry_run: bool,
+    /// Only check web links to this domain (and its subdomains). May be
+    /// repeated.
+    #[arg(long = "allow-domain", value_name = "DOMAIN")]
+    allow_domains: Vec<String>,
+    /// Fail any web links to this domain (and its subdomains). May be
+    /// repeated.
+    #[arg(long = "block-domain", value_name = "DOMAIN")]
+    block_domains: Vec<String>,
+    /// Fail web links to domains which aren't allowed with `--allow-domain`,
+    /// instead of ignoring them.
+    #[arg(long)]
+    fail_unlisted_domains: bool,
+    /// Limit the number of links, URL length, redirects, and response size,
+    /// and only check `http(s)` URLs. Use this for documents you don't trust.
+    #[arg(long)]
+    hardened: bool,
+    /// Send web requests through this proxy. May be repeated.
+    #[arg(long = "proxy", value_name = "URL")]
+    proxies: Vec<String>,
+    /// Ignore the proxies configured with `HTTP_PROXY` and friends (or the
+    /// platform's settings on Windows and macOS).
+    #[arg(long)]
+    no_system_proxy: bool,
+    /// Send web requests through a SOCKS5 proxy (e.g. `localhost:1080` for
+    /// `ssh -D 1080`).
+    #[cfg(feature = "socks")]
+    #[arg(long, value_name = "ADDRESS")]
+    socks5: Option<String>,
+    /// Which address family to use for hosts with both IPv4 and IPv6
+    /// addresses.
+    #[arg(long, value_enum, default_value_t = IpFamily::Race)]
+    ip_family: IpFamily,
+    /// Only use IPv4 for this domain (and its subdomains), when possible.
+    /// May be repeated.
+    #[arg(long = "prefer-ipv4", value_name = "DOMAIN")]
+    prefer_ipv4: Vec<String>,
+    /// Only use IPv6 for this domain (and its subdomains), when possible.
+    /// May be repeated.
+    #[arg(long = "prefer-ipv6", value_name = "DOMAIN")]
+    prefer_ipv6: Vec<String>,
+    /// Look hosts up using this DNS server instead of the system's resolver.
+    /// May be repeated.
+    #[cfg(feature = "hickory-dns")]
+    #[arg(long = "nameserver", value_name = "ADDRESS")]
+    nameservers: Vec<String>,
+    /// Look up a domain (and its subdomains) using a particular DNS server,
+    /// e.g. `corp.example.com=10.0.0.53`. May be repeated.
+    #[cfg(feature = "hickory-dns")]
+    #[arg(long = "domain-nameserver", value_name = "DOMAIN=ADDRESS")]
+    domain_nameservers: Vec<String>,
+    /// Use the GitHub and GitLab APIs to check links to issues, pull
+    /// requests, and files. Tokens are read from `GITHUB_TOKEN` and
+    /// `GITLAB_TOKEN`.
+    #[arg(long)]
+    check_issues: bool,
+    /// Ask npm, PyPI, and Maven Central whether linked packages (and
+    /// versions) exist.
+    #[arg(long)]
+    check_packages: bool,
+    /// Make sure links to the Wayback Machine point to a real snapshot, and
+    /// suggest linking to the original page when it is still online.
+    #[arg(long)]
+    check_archives: bool,
+    /// How thoroughly to check links (defaults to `deep`).
+    #[arg(long, value_enum)]
+    level: Option<Level>,
+    /// How thoroughly to check web links, overriding `--level`.
+    #[arg(long, value_enum)]
+    web_level: Option<Level>,
+    /// Show every request that was sent for each broken web link.
+    #[arg(long)]
+    show_attempts: bool,
+    /// Send the username and password embedded in links, instead of
+    /// stripping them before the link is checked.
+    #[arg(long)]
+    send_credentials: bool,
+    /// Hide the value of this query parameter (e.g. an access token) in logs
+    /// and reports. May be used multiple times.
+    #[arg(long, value_name = "NAME")]
+    redact_param: Vec<String>,
+    /// Print the hosts which took the longest to respond.
+    #[arg(long, value_name = "N")]
+    slowest_hosts: Option<usize>,
+}
+
+/// The settings which can be loaded with `--config`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct Config {
+    /// How links to local files are checked.
+    #[serde(flatten)]
+    options: Options,
+    /// Skip links with an `href` matching any of these globs.
+    ignore: Vec<String>,
+    /// Like `--known-broken`.
+    known_broken: Option<PathBuf>,
+    /// Like `--expect-failure`.
+    expect_failure: Vec<String>,
+    /// Which domains web links may point to.
+    domains: Option<DomainPolicy>,
+    /// Like `--redact-param`.
+    redact_params: Vec<String>,
+    /// Limits for checking documents you don't trust (see `--hardened`).
+    limits: Option<SafetyLimits>,
+    /// How many links to check at a time.
+    concurrency: Option<usize>,
+    /// How many links on the same host to check at a time.
+    concurrency_per_host: Option<usize>,
+    /// How thoroughly each kind of link is checked (see `--level`).
+    check_levels: Option<CheckLevels>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+enum Format {
+    /// One line per broken link.
+    Human,
+    /// A JSON snapshot of every link that was checked.
+    Json,
+    /// A standalone HTML page.
+    Html,
+    /// One JSON object per link, written as soon as it has been checked.
+    Jsonl,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+enum Level {
+    /// Only make sure links are well-formed, without any IO.
+    Syntax,
+    /// Make sure files exist and servers respond, ignoring fragments.
+    Exists,
+    /// Check everything, including fragments.
+    Deep,
+}
+
+impl From<Level> for CheckLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Syntax => CheckLevel::Syntax,
+            Level::Exists => CheckLevel::Exists,
+            Level::Deep => CheckLevel::Deep,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+enum IpFamily {
+    /// Race IPv4 and IPv6 connections, using whichever connects first.
+    Race,
+    /// Prefer IPv4.
+    Ipv4,
+    /// Prefer IPv6.
+    Ipv6,
+}
+
+impl From<IpFamily> for IpPreference {
+    fn from(family: IpFamily) -> Self {
+        match family {
+            IpFamily::Race => IpPreference::Race,
+            IpFamily::Ipv4 => IpPreference::PreferIpv4,
+            IpFamily::Ipv6 => IpPreference::PreferIpv6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Input {
+    Path(PathBuf),
+    Url(Url),
+}
+
+impl Input {
+    fn parse(raw: &str) -> Self {
+        match Url::parse(raw) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+                Input::Url(url)
+            },
+            _ => Input::Path(PathBuf::from(raw)),
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Unable to start the async runtime");
+
+    match runtime.block_on(run(args)) {
+        Ok(true) => {},
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(2);
+        },
+    }
+}
+
+/// Check everything, returning whether all the links were valid.
+async fn run(args: Args) -> Result<bool, Box<dyn Error>> {
+    let config: Config = match &args.config {
+        Some(path) => serde_json::from_reader(File::open(path)?)?,
+        None => Config::default(),
+    };
+    let limits = if args.hardened {
+        Some(SafetyLimits::hardened())
+    } else {
+        config.limits
+    };
+    let mut ctx = match limits {
+        Some(limits) => BasicContext::with_safety_limits(limits),
+        None => BasicContext::default(),
+    };
+    if let Some(proxies) = proxy_settings(&args)? {
+        ctx = ctx.set_proxies(proxies);
+    }
+    #[cfg(feature = "hickory-dns")]
+    if let Some(dns) = dns_cache(&args)? {
+        ctx = ctx.set_dns_cache(dns);
+    }
+    if let Some(dns) = ctx.dns_cache() {
+        dns.prefer(args.ip_family.into());
+        for domain in &args.prefer_ipv4 {
+            dns.prefer_for_domain(domain, IpPreference::PreferIpv4);
+        }
+        for domain in &args.prefer_ipv6 {
+            dns.prefer_for_domain(domain, IpPreference::PreferIpv6);
+        }
+    }
+    ctx.deadline = args
+        .time_budget
+        .map(|secs| Instant::now() + Duration::from_secs(secs));
+    ctx.options = config.options;
+    let known_broken = args.known_broken.as_ref();
+    if let Some(path) = known_broken.or(config.known_broken.as_ref()) {
+        let text = std::fs::read_to_string(path)?;
+        ctx.known_broken = Some(KnownBroken::parse(&text)?);
+    }
+    ctx.expected_failures = Some(
+        config
+            .expect_failure
+            .iter()
+            .chain(&args.expect_failure)
+            .fold(ExpectedFailures::new(), |expected, domain| {
+                expected.add_domain(domain.as_str())
+            }),
+    );
+    ctx.domains = domain_policy(&args).or(config.domains);
+    if args.check_issues {
+        ctx.forges = Some(forge_api());
+    }
+    if args.check_packages {
+        ctx.packages = Some(PackageRegistries::new());
+    }
+    if args.check_archives {
+        ctx.wayback = Some(WaybackMachine::new().set_check_original(true));
+    }
+    ctx.record_attempts = args.show_attempts;
+    ctx.send_credentials = args.send_credentials;
+    let names: Vec<&str> = config
+        .redact_params
+        .iter()
+        .chain(&args.redact_param)
+        .map(String::as_str)
+        .collect();
+    if !names.is_empty() {
+        ctx.redactor = Some(Redactor::query_parameters(&names));
+    }
+    ctx.check_levels = match args.level {
+        Some(level) => CheckLevels::all(level.into()),
+        None => config.check_levels.unwrap_or_default(),
+    };
+    if let Some(level) = args.web_level {
+        ctx.check_levels.web = level.into();
+    }
+
+    let mut files = Files::new();
+    let mut documents = Vec::new();
+    let mut seeds = Vec::new();
+
+    for input in args.inputs.iter().map(|raw| Input::parse(raw)) {
+        match input {
+            Input::Url(url) => seeds.push(url),
+            Input::Path(path) if path.is_dir() => {
+                let mut site = Site::new(path)
+                    .set_lenient(args.lenient)
+                    .set_capture_context(args.context);
+                for pattern in &args.include {
+                    site = site.include(pattern)?;
+                }
+                for pattern in &args.exclude {
+                    site = site.exclude(pattern)?;
+                }
+                documents.extend(site.scan(&mut files)?);
+            },
+            Input::Path(path) => {
+                documents.push(scan_file(path, args.context, &mut files)?)
+            },
+        }
+    }
+
+    ctx.anchors = Some(AnchorDb::from_documents(&documents, &files));
+    let concurrency = config.concurrency.unwrap_or(ctx.concurrency());
+    let per_host = config
+        .concurrency_per_host
+        .unwrap_or(ctx.concurrency_per_host());
+    let ctx = ctx
+        .with_ignore(&config.ignore)?
+        .with_concurrency(concurrency, per_host);
+
+    let mut writer = output(&args)?;
+    if args.dry_run {
+        dry_run(&documents, &seeds, &files, &ctx, &mut writer)?;
+        return Ok(true);
+    }
+    // JSON Lines are written as each link is checked, everything else waits
+    // until we have all the outcomes
+    let streaming = args.format == Format::Jsonl;
+    let mut baseline: Option<Baseline> = match &args.baseline {
+        Some(path) if !args.update_baseline => {
+            Some(serde_json::from_reader(File::open(path)?)?)
+        },
+        _ => None,
+    };
+
+    let mut outcomes = if streaming {
+        let mut outcomes = Outcomes::empty();
+        let mut checked = Box::pin(site::validate_stream(&documents, &ctx));
+        while let Some(outcome) = checked.next().await {
+            let mut outcome = outcome.redact(&ctx);
+            // accept baselined links before they get written out
+            if let Some(baseline) = &baseline {
+                outcome.apply_baseline(baseline, &files);
+            }
+            report(&outcome, &files, args.format, &mut writer)?;
+            outcomes.merge(outcome);
+        }
+        outcomes
+    } else {
+        site::validate(&documents, &ctx).await.redact(&ctx)
+    };
+
+    if !seeds.is_empty() {
+        let crawler = Crawler::new(seeds)
+            .set_max_depth(args.max_depth)
+            .set_max_pages(args.max_pages);
+        let mut crawled = crawler.crawl(&mut files, &ctx).await.redact(&ctx);
+        if streaming {
+            if let Some(baseline) = &baseline {
+                crawled.apply_baseline(baseline, &files);
+            }
+            report(&crawled, &files, args.format, &mut writer)?;
+        }
+        outcomes.merge(crawled);
+    }
+
+    if let Some(path) = &args.baseline {
+        if args.update_baseline {
+            let updated = Baseline::new(&outcomes, &files);
+            serde_json::to_writer_pretty(File::create(path)?, &updated)?;
+            baseline = Some(updated);
+        }
+    }
+    if let Some(baseline) = &baseline {
+        for entry in outcomes.apply_baseline(baseline, &files) {
+            eprintln!("Stale baseline entry: {}", entry);
+        }
+    }
+
+    if !streaming {
+        report(&outcomes, &files, args.format, &mut writer)?;
+    }
+    if let Some(n) = args.slowest_hosts {
+        let stats = outcomes.stats();
+        eprintln!("Slowest hosts:");
+        for (host, host_stats) in stats.slowest_hosts(n) {
+            eprintln!(
+                "    {}: {} links, {:?} max, {:?} mean",
+                host,
+                host_stats.links,
+                host_stats.max,
+                host_stats.mean()
+            );
+        }
+    }
+
+    Ok(outcomes.invalid.is_empty())
+}
+
+/// Say how each link would be checked.
+fn dry_run<C: Context, W: Write>(
+    documents: &[Document],
+    seeds: &[Url],
+    files: &Files<String>,
+    ctx: &C,
+    mut writer: W,
+) -> io::Result<()> {
+    for planned in site::plan(documents, ctx) {
+        let planned = planned.redact(ctx);
+        let link = &planned.link;
+        match link.location(files) {
+            Some(location) => write!(writer, "{}: ", location)?,
+            None => {
+                let name = files.name(link.file);
+                write!(writer, "{}: ", Path::new(name).display())?
+            },
+        }
+        writeln!(writer, "{} => {}", link.href, planned.check)?;
+    }
+    for url in seeds {
+        writeln!(writer, "{} => crawl", ctx.redact(url))?;
+    }
+
+    Ok(())
+}
+
+/// Where the report should be written.
+fn output(args: &Args) -> io::Result<Box<dyn Write>> {
+    match &args.output {
+        Some(path) => Ok(Box::new(File::create(path)?)),
+        None => Ok(Box::new(io::stdout().lock())),
+    }
+}
+
+/// The [`ProxySettings`] to use, if they differ from the defaults.
+fn proxy_settings(
+    args: &Args,
+) -> Result<Option<ProxySettings>, reqwest::Error> {
+    let mut proxies = ProxySettings::new().set_system(!args.no_system_proxy);
+    let mut customised = args.no_system_proxy;
+
+    for proxy in &args.proxies {
+        proxies = proxies.with_proxy(proxy)?;
+        customised = true;
+    }
+    #[cfg(feature = "socks")]
+    if let Some(address) = &args.socks5 {
+        proxies = proxies.with_socks5(address)?;
+        customised = true;
+    }
+
+    Ok(if customised { Some(proxies) } else { None })
+}
+
+/// A [`DnsCache`] using the DNS servers passed on the command-line, if there
+/// were any.
+#[cfg(feature = "hickory-dns")]
+fn dns_cache(args: &Args) -> Result<Option<DnsCache>, Box<dyn Error>> {
+    fn parse_address(raw: &str) -> Result<SocketAddr, Box<dyn Error>> {
+        match raw.parse::<IpAddr>() {
+            Ok(ip) => Ok(SocketAddr::new(ip, 53)),
+            Err(_) => Ok(raw.parse()?),
+        }
+    }
+
+    if args.nameservers.is_empty() && args.domain_nameservers.is_empty() {
+        return Ok(None);
+    }
+
+    let mut dns = DnsCache::new();
+    if !args.nameservers.is_empty() {
+        let addresses = args
+            .nameservers
+            .iter()
+            .map(|raw| parse_address(raw))
+            .collect::<Result<Vec<_>, _>>()?;
+        dns = dns.set_resolver(Nameservers::new(&addresses));
+    }
+    for route in &args.domain_nameservers {
+        let (domain, address) = route.split_once('=').ok_or_else(|| {
+            format!("Expected \"DOMAIN=ADDRESS\", found \"{}\"", route)
+        })?;
+        let address = parse_address(address)?;
+        dns = dns.route(domain, Nameservers::new(&[address]));
+    }
+
+    Ok(Some(dns))
+}
+
+fn domain_policy(args: &Args) -> Option<DomainPolicy> {
+    if args.allow_domains.is_empty() && args.block_domains.is_empty() {
+        return None;
+    }
+
+    let mut policy = DomainPolicy::new();
+    for domain in &args.allow_domains {
+        policy = policy.allow(domain);
+    }
+    for domain in &args.block_domains {
+        policy = policy.block(domain);
+    }
+    if args.fail_unlisted_domains {
+        policy = policy.set_unlisted(Unlisted::Fail);
+    }
+
+    Some(policy)
+}
+
+fn forge_api() -> ForgeApi {
+    let mut api = ForgeApi::new();
+    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+        api = api.set_github_token(token);
+    }
+    if let Ok(token) = std::env::var("GITLAB_TOKEN") {
+        api = api.set_gitlab_token(token);
+    }
+
+    api
+}
+
+fn scan_file(
+    path: PathBuf,
+    capture_context: bool,
+    files: &mut Files<String>,
+) -> Result<Document, Box<dyn Error>> {
+    let src = std::fs::read_to_string(&path)?;
+    let kind = scanners::detect(&path, &src).ok_or_else(|| {
+        format!("Unable to find a scanner for \"{}\"", path.display())
+    })?;
+    let file = files.add(&path, src);
+    let src = files.source(file);
+    // like Site::scan(), keep the title and reference label of markdown links
+    let links: Vec<(Link, Span)> = if kind == FileKind::Markdown {
+        scanners::markdown_links(src, None)
+            .map(|link| {
+                let element = link.element;
+                (link.into_link(file), element)
+            })
+            .collect()
+    } else {
+        Site::scanner_for_kind(kind)(src)
+            .into_iter()
+            .map(|(href, span)| (Link::new(href, span, file), span))
+            .collect()
+    };
+    let links = links
+        .into_iter()
+        .map(|(link, span)| {
+            if capture_context {
+                link.set_context(scanners::context(src, span))
+            } else {
+                link
+            }
+        })
+        .collect();
+
+    Ok(Document { file, path, links })
+}
+
+fn report<W: Write>(
+    outcomes: &Outcomes,
+    files: &Files<String>,
+    format: Format,
+    mut writer: W,
+) -> io::Result<()> {
+    match format {
+        Format::Human => {
+            for invalid in &outcomes.invalid {
+                match invalid.link.location(files) {
+                    Some(location) => write!(writer, "{}: ", location)?,
+                    None => {
+                        let name = files.name(invalid.link.file);
+                        write!(writer, "{}: ", Path::new(name).display())?
+                    },
+                }
+                if let Some(label) = &invalid.link.reference_label {
+                    write!(writer, "reference [{}] ", label)?;
+                }
+                writeln!(writer, "{} ({})", invalid.link.href, invalid.reason)?;
+                if let Some(context) = &invalid.link.context {
+                    if let Some(heading) = &context.heading {
+                        write!(writer, "    under \"{}\": ", heading)?;
+                    } else {
+                        write!(writer, "    ")?;
+                    }
+                    writeln!(writer, "{}", context.snippet)?;
+                }
+                let candidates = outcomes.candidates.get(&invalid.link);
+                for candidate in candidates.into_iter().flatten() {
+                    writeln!(writer, "    maybe {}", candidate.display())?;
+                }
+                let attempts = outcomes.attempts.get(&invalid.link);
+                for attempt in attempts.into_iter().flatten() {
+                    writeln!(writer, "    {}", attempt)?;
+                }
+            }
+            writeln!(
+                writer,
+                "{} valid, {} broken, {} ignored, {} unchecked, {} known \
+                 broken, {} unverifiable, {} expected failures",
+                outcomes.valid.len(),
+                outcomes.invalid.len(),
+                outcomes.ignored.len(),
+                outcomes.unchecked.len(),
+                outcomes.suppressed.len(),
+                outcomes.unverifiable.len(),
+                outcomes.expected_failures.len()
+            )
+        },
+        Format::Json => {
+            let snapshot = outcomes.snapshot(files);
+            serde_json::to_writer_pretty(&mut writer, &snapshot)?;
+            writeln!(writer)
+        },
+        Format::Html => linkcheck::report::write_html(outcomes, files, writer),
+        Format::Jsonl => {
+            linkcheck::report::write_jsonl(outcomes, files, writer)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_http_urls_are_crawled() {
+        let inputs = vec![
+            ("https://example.com/", true),
+            ("http://localhost:8000/docs/", true),
+            ("./README.md", false),
+            ("src", false),
+            ("C:/Users/me/book", false),
+            ("file:///etc/hosts", false),
+        ];
+
+        for (raw, is_url) in inputs {
+            let got = matches!(Input::parse(raw), Input::Url(_));
+            assert_eq!(got, is_url, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn old_config_files_only_contain_options() {
+        let src = r#"{ "links_may_traverse_the_root_directory": true }"#;
+
+        let config: Config = serde_json::from_str(src).unwrap();
+
+        assert!(config.ignore.is_empty());
+        assert!(config.limits.is_none());
+    }
+
+    #[test]
+    fn load_the_context_settings_from_a_config_file() {
+        let src = r#"{
+            "ignore": ["https://localhost/**"],
+            "expect_failure": ["example.com"],
+            "redact_params": ["token"],
+            "concurrency": 4,
+            "concurrency_per_host": 2
+        }"#;
+
+        let config: Config = serde_json::from_str(src).unwrap();
+
+        assert_eq!(config.ignore, vec!["https://localhost/**"]);
+        assert_eq!(config.expect_failure, vec!["example.com"]);
+        assert_eq!(config.redact_params, vec!["token"]);
+        assert_eq!(config.concurrency, Some(4));
+        assert_eq!(config.concurrency_per_host, Some(2));
+    }
+}
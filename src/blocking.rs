@@ -0,0 +1,131 @@
+//! A blocking API for validating links, for when you don't want to pull in
+//! an entire async runtime (e.g. in a build script or a simple CLI).
+//!
+//! Each function spins up a single-threaded runtime for the duration of the
+//! call, so they must *not* be called from within an async context.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use codespan::Files;
+//! use linkcheck::{BasicContext, Link};
+//! use std::path::Path;
+//!
+//! let mut files = Files::new();
+//! let src = "Check out [the README](./README.md).";
+//! let file_id = files.add("index.md", src);
+//! let links = linkcheck::scanners::markdown(src)
+//!     .map(|(href, span)| Link::new(href, span, file_id));
+//!
+//! let current_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+//! let ctx = BasicContext::default();
+//! let outcomes = linkcheck::blocking::validate(current_dir, links, &ctx);
+//!
+//! assert_eq!(outcomes.valid.len(), 1);
+//! ```
+
+use crate::{
+    site::{self, Document},
+    validation::{self, Checked, Context, Outcomes},
+    Link,
+};
+use std::{future::Future, path::Path};
+
+/// Validate several [`Link`]s relative to a particular directory.
+///
+/// This is the blocking equivalent of [`validation::validate()`].
+///
+/// # Panics
+///
+/// This will panic if called from within an async runtime or the runtime
+/// can't be created.
+pub fn validate<L, C>(current_directory: &Path, links: L, ctx: &C) -> Outcomes
+where
+    L: IntoIterator<Item = Link>,
+    C: Context + Sync + ?Sized,
+{
+    block_on(validation::validate(current_directory, links, ctx))
+}
+
+/// Validate the [`Link`]s in a set of [`Document`]s.
+///
+/// This is the blocking equivalent of [`site::validate()`].
+///
+/// # Panics
+///
+/// This will panic if called from within an async runtime or the runtime
+/// can't be created.
+pub fn validate_documents<C>(documents: &[Document], ctx: &C) -> Outcomes
+where
+    C: Context + Sync + ?Sized,
+{
+    block_on(site::validate(documents, ctx))
+}
+
+/// Check a single URL.
+///
+/// This is the blocking equivalent of [`validation::check_url()`].
+///
+/// # Panics
+///
+/// This will panic if called from within an async runtime or the runtime
+/// can't be created.
+pub fn check_url<C>(url: &str, ctx: &C) -> Checked
+where
+    C: Context + Sync + ?Sized,
+{
+    block_on(validation::check_url(url, ctx))
+}
+
+/// Check a single path relative to the `base` directory.
+///
+/// This is the blocking equivalent of [`validation::check_path()`].
+///
+/// # Panics
+///
+/// This will panic if called from within an async runtime or the runtime
+/// can't be created.
+pub fn check_path<C>(base: &Path, path: &str, ctx: &C) -> Checked
+where
+    C: Context + Sync + ?Sized,
+{
+    block_on(validation::check_path(base, path, ctx))
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Unable to start the async runtime")
+        .block_on(future)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+    use codespan::Files;
+
+    #[test]
+    fn validate_without_an_async_runtime() {
+        let server = Server::start(vec![("/", Response::ok(""))]);
+        let mut files = Files::new();
+        let src = format!("[web]({}) [missing](./missing.md)", server.url("/"));
+        let file_id = files.add("index.md", src.clone());
+        let links = crate::scanners::markdown(&src)
+            .map(|(href, span)| Link::new(href, span, file_id));
+
+        let outcomes = validate(
+            Path::new(env!("CARGO_MANIFEST_DIR")),
+            links,
+            &BasicContext::default(),
+        );
+
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert_eq!(outcomes.invalid[0].link.href, "./missing.md");
+    }
+}
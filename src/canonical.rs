@@ -0,0 +1,316 @@
+//! Optional checks for the `rel="canonical"` and `hreflang` alternate links
+//! in HTML pages.
+//!
+//! These links are invisible to readers, so when a generated site gets them
+//! wrong nobody notices until search engines start showing the wrong page.
+//! Besides checking that each one resolves ([`validate()`]), the pages are
+//! compared with each other to make sure they agree ([`check()`]), with any
+//! problems reported as [`Lint`]s.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use codespan::Files;
+//! use linkcheck::{canonical::Page, lints::LintKind};
+//!
+//! let mut files = Files::new();
+//! let en = r#"<link rel="alternate" hreflang="fr" href="/fr/">"#;
+//! let fr = r#"<link rel="canonical" href="https://example.com/fr/">"#;
+//!
+//! let pages = vec![
+//!     Page::parse(
+//!         "https://example.com/en/".parse().unwrap(),
+//!         en,
+//!         files.add("en.html", en),
+//!     ),
+//!     Page::parse(
+//!         "https://example.com/fr/".parse().unwrap(),
+//!         fr,
+//!         files.add("fr.html", fr),
+//!     ),
+//! ];
+//!
+//! let outcomes = linkcheck::canonical::check(&pages);
+//!
+//! // the French page should have a hreflang link back to the English one
+//! assert_eq!(outcomes.lints.len(), 1);
+//! assert_eq!(outcomes.lints[0].kind, LintKind::MissingReturnLink);
+//! ```
+
+use crate::{
+    lints::{Lint, LintKind},
+    scanners::html::{decode_entities, tags},
+    validation::{Context, Outcomes},
+    Link,
+};
+use codespan::FileId;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+use url::Url;
+
+/// The canonical and alternate links from a single HTML page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// The URL this page is served from.
+    pub url: Url,
+    /// Every `<link rel="canonical">` on the page (there should only be one).
+    pub canonical: Vec<Link>,
+    /// Every `<link rel="alternate" hreflang="...">` on the page.
+    pub alternates: Vec<Alternate>,
+}
+
+/// A `<link rel="alternate">` pointing at a translation of the page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alternate {
+    /// The `hreflang` language (e.g. `fr` or `x-default`).
+    pub language: String,
+    /// The link to the translated page.
+    pub link: Link,
+}
+
+impl Page {
+    /// Find the canonical and alternate links in a HTML document which is
+    /// served from `url`.
+    pub fn parse(url: Url, src: &str, file: FileId) -> Self {
+        let mut canonical = Vec::new();
+        let mut alternates = Vec::new();
+
+        for tag in tags(src).into_iter().filter(|tag| tag.name == "link") {
+            let attribute = |name: &str| {
+                tag.attributes
+                    .iter()
+                    .find(|attr| attr.name == name)
+                    .and_then(|attr| Some((attr.value_in(src)?, attr.value?)))
+            };
+            let rel = match attribute("rel") {
+                Some((rel, _)) => rel.to_ascii_lowercase(),
+                None => continue,
+            };
+            let (href, span) = match attribute("href") {
+                Some(href) => href,
+                None => continue,
+            };
+            let link = Link::new(decode_entities(href), span, file);
+            let mut rels = rel.split_ascii_whitespace();
+
+            if rels.clone().any(|r| r == "canonical") {
+                canonical.push(link);
+            } else if rels.any(|r| r == "alternate") {
+                if let Some((language, _)) = attribute("hreflang") {
+                    alternates.push(Alternate {
+                        language: language.to_ascii_lowercase(),
+                        link,
+                    });
+                }
+            }
+        }
+
+        Page {
+            url,
+            canonical,
+            alternates,
+        }
+    }
+
+    /// Every canonical and alternate [`Link`] on the page.
+    pub fn links(&self) -> impl Iterator<Item = &Link> + '_ {
+        self.canonical
+            .iter()
+            .chain(self.alternates.iter().map(|alt| &alt.link))
+    }
+
+    /// Resolve a [`Link`] from this page to an absolute [`Url`] (without its
+    /// fragment).
+    fn resolve(&self, link: &Link) -> Option<Url> {
+        let mut url = self.url.join(&link.href).ok()?;
+        url.set_fragment(None);
+        Some(url)
+    }
+}
+
+/// Make sure each page's canonical and alternate links agree with the pages
+/// they point to.
+///
+/// Only links between the pages passed in are compared, so a page linking to
+/// a site we know nothing about isn't flagged. The returned [`Outcomes`] only
+/// contain [`Outcomes::lints`].
+pub fn check(pages: &[Page]) -> Outcomes {
+    let by_url: HashMap<Url, &Page> = pages
+        .iter()
+        .map(|page| (without_fragment(&page.url), page))
+        .collect();
+    let mut outcomes = Outcomes::empty();
+    let mut push = |link: &Link, kind| {
+        outcomes.lints.push(Lint {
+            link: link.clone(),
+            kind,
+        })
+    };
+
+    for page in pages {
+        for extra in page.canonical.iter().skip(1) {
+            push(extra, LintKind::MultipleCanonicals);
+        }
+
+        if let Some(link) = page.canonical.first() {
+            let target = page.resolve(link);
+            let chained = target
+                .as_ref()
+                .and_then(|t| by_url.get(t))
+                .and_then(|t| Some((t.resolve(t.canonical.first()?)?, t)));
+
+            if let Some((canonical, target)) = chained {
+                if canonical != without_fragment(&target.url) {
+                    push(link, LintKind::CanonicalChain(canonical.to_string()));
+                }
+            }
+        }
+
+        let mut languages: HashMap<&str, Option<Url>> = HashMap::new();
+        let this_page = identities(page);
+
+        for alternate in &page.alternates {
+            let target = page.resolve(&alternate.link);
+
+            match languages.get(alternate.language.as_str()) {
+                Some(previous) if *previous != target => push(
+                    &alternate.link,
+                    LintKind::ConflictingHreflang(alternate.language.clone()),
+                ),
+                Some(_) => {},
+                None => {
+                    languages.insert(&alternate.language, target.clone());
+                },
+            }
+
+            let other = match target.as_ref().and_then(|t| by_url.get(t)) {
+                Some(other) => other,
+                None => continue,
+            };
+            if this_page.contains(&without_fragment(&other.url)) {
+                continue;
+            }
+
+            let links_back = other.alternates.iter().any(|alt| {
+                other
+                    .resolve(&alt.link)
+                    .is_some_and(|url| this_page.contains(&url))
+            });
+            if !links_back {
+                push(&alternate.link, LintKind::MissingReturnLink);
+            }
+        }
+    }
+
+    outcomes
+}
+
+/// Check that every canonical and alternate link resolves, then [`check()`]
+/// that they are consistent.
+///
+/// Relative links are resolved against [`Page::url`] first, so the
+/// [`Link::href`]s in the [`Outcomes`] are the URLs which were checked.
+pub async fn validate<C>(pages: &[Page], ctx: &C) -> Outcomes
+where
+    C: Context + Sync + ?Sized,
+{
+    let links: Vec<Link> = pages
+        .iter()
+        .flat_map(|page| {
+            page.links()
+                .map(move |link| match page.url.join(&link.href) {
+                    Ok(url) => Link::new(url.to_string(), link.span, link.file),
+                    Err(_) => link.clone(),
+                })
+        })
+        .collect();
+
+    let mut outcomes = crate::validate(Path::new("."), links, ctx).await;
+    outcomes.merge(check(pages));
+
+    outcomes
+}
+
+/// The URLs a page may be referred to by.
+fn identities(page: &Page) -> HashSet<Url> {
+    let mut urls = HashSet::new();
+    urls.insert(without_fragment(&page.url));
+    urls.extend(page.canonical.iter().filter_map(|link| page.resolve(link)));
+    urls
+}
+
+fn without_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codespan::Files;
+
+    #[test]
+    fn detect_inconsistent_alternates() {
+        let en = r#"
+            <link rel="canonical" href="https://example.com/en/">
+            <link rel="canonical" href="https://example.com/en/index.html">
+            <link rel="alternate" hreflang="en" href="/en/">
+            <link rel="alternate" hreflang="fr" href="/fr/">
+            <link rel="alternate" hreflang="fr" href="/fr-ca/">
+            <link rel="alternate" hreflang="ja" href="/ja/">
+        "#;
+        let fr = r#"
+            <link rel="alternate" hreflang="en" href="../en/">
+            <link rel="alternate" hreflang="ja" href="../ja/">
+        "#;
+        let ja = r#"
+            <link rel="canonical" href="/ja/old/">
+            <link rel="alternate" hreflang="fr" href="/fr/">
+        "#;
+        let ja_old = r#"<link rel="canonical" href="/ja/">"#;
+        let mut files = Files::new();
+        let base = Url::parse("https://example.com/").unwrap();
+        let pages: Vec<_> =
+            vec![("en/", en), ("fr/", fr), ("ja/", ja), ("ja/old/", ja_old)]
+                .into_iter()
+                .map(|(path, src)| {
+                    let file = files.add(path, src);
+                    Page::parse(base.join(path).unwrap(), src, file)
+                })
+                .collect();
+
+        let got: Vec<_> = check(&pages)
+            .lints
+            .into_iter()
+            .map(|lint| (lint.link.href, lint.kind))
+            .collect();
+
+        let should_be = vec![
+            (
+                String::from("https://example.com/en/index.html"),
+                LintKind::MultipleCanonicals,
+            ),
+            (
+                String::from("/fr-ca/"),
+                LintKind::ConflictingHreflang(String::from("fr")),
+            ),
+            (String::from("/ja/"), LintKind::MissingReturnLink),
+            (
+                String::from("/ja/old/"),
+                LintKind::CanonicalChain(String::from(
+                    "https://example.com/ja/",
+                )),
+            ),
+            (
+                String::from("/ja/"),
+                LintKind::CanonicalChain(String::from(
+                    "https://example.com/ja/old/",
+                )),
+            ),
+        ];
+        assert_eq!(got, should_be);
+    }
+}
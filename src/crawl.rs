@@ -0,0 +1,396 @@
+//! A spider for checking a deployed website.
+//!
+//! Starting from one or more seed URLs, the [`Crawler`] will fetch each page,
+//! extract its links with [`crate::scanners::html()`], validate them, then
+//! recursively do the same for any pages on the same origin.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use codespan::Files;
+//! use linkcheck::{crawl::Crawler, BasicContext};
+//!
+//! # #[tokio::main] async fn main() {
+//! let seed = "https://docs.example.com/".parse().unwrap();
+//! let crawler = Crawler::new(vec![seed]).set_max_depth(3).set_max_pages(500);
+//!
+//! let mut files = Files::new();
+//! let ctx = BasicContext::default();
+//! let outcomes = crawler.crawl(&mut files, &ctx).await;
+//!
+//! for invalid in &outcomes.invalid {
+//!     let page = files.name(invalid.link.file);
+//!     println!("{:?} links to {}", page, invalid.link.href);
+//! }
+//! # }
+//! ```
+
+use crate::{
+    validation::{self, Context, Outcomes},
+    Link,
+};
+use codespan::{Files, Span};
+use reqwest::{header::CONTENT_TYPE, Response};
+use std::{
+    collections::{HashSet, VecDeque},
+    convert::TryFrom,
+    path::Path,
+};
+use url::Url;
+
+/// A breadth-first crawler which only follows links to pages on the same
+/// origin as one of its seeds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crawler {
+    seeds: Vec<Url>,
+    max_depth: usize,
+    max_pages: usize,
+}
+
+impl Crawler {
+    /// The default for [`Crawler::max_depth()`].
+    pub const DEFAULT_MAX_DEPTH: usize = 5;
+    /// The default for [`Crawler::max_pages()`].
+    pub const DEFAULT_MAX_PAGES: usize = 1000;
+
+    /// Create a new [`Crawler`] which will start from the provided URLs.
+    pub fn new<I>(seeds: I) -> Self
+    where
+        I: IntoIterator<Item = Url>,
+    {
+        Crawler {
+            seeds: seeds.into_iter().collect(),
+            max_depth: Crawler::DEFAULT_MAX_DEPTH,
+            max_pages: Crawler::DEFAULT_MAX_PAGES,
+        }
+    }
+
+    /// The URLs crawling will start from.
+    pub fn seeds(&self) -> &[Url] { &self.seeds }
+
+    /// How many links away from a seed may a page be before we stop
+    /// following its links? The seeds themselves are at depth `0`.
+    pub fn max_depth(&self) -> usize { self.max_depth }
+
+    /// Set the [`Crawler::max_depth()`].
+    pub fn set_max_depth(self, max_depth: usize) -> Self {
+        Crawler { max_depth, ..self }
+    }
+
+    /// The maximum number of pages which will be fetched and scanned.
+    pub fn max_pages(&self) -> usize { self.max_pages }
+
+    /// Set the [`Crawler::max_pages()`].
+    pub fn set_max_pages(self, max_pages: usize) -> Self {
+        Crawler { max_pages, ..self }
+    }
+
+    /// Crawl the site, validating every link found along the way.
+    ///
+    /// Each page that gets scanned is added to `files` (named after its URL),
+    /// and every [`Link`] in the returned [`Outcomes`] has been resolved
+    /// against the URL of the page it was found on. HTTPS pages are also
+    /// checked for [`crate::lints::mixed_content()`].
+    pub async fn crawl<C>(&self, files: &mut Files<String>, ctx: &C) -> Outcomes
+    where
+        C: Context + Sync + ?Sized,
+    {
+        let origins: HashSet<_> =
+            self.seeds.iter().map(|seed| seed.origin()).collect();
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<(Url, usize)> = VecDeque::new();
+        let mut outcomes = Outcomes::empty();
+        let mut pages_fetched = 0;
+
+        for seed in &self.seeds {
+            let seed = without_fragment(seed);
+            if visited.insert(seed.clone()) {
+                queue.push_back((seed, 0));
+            }
+        }
+
+        while let Some((page, depth)) = queue.pop_front() {
+            if pages_fetched >= self.max_pages {
+                log::debug!(
+                    "Reached the page budget ({}), not crawling any further",
+                    self.max_pages
+                );
+                break;
+            }
+            pages_fetched += 1;
+
+            // relative links are resolved against where we ended up (e.g.
+            // "/docs" redirecting to "/docs/"), not what we asked for
+            let (page, body) = match fetch_html(&page, ctx).await {
+                Some(fetched) => fetched,
+                None => continue,
+            };
+            visited.insert(without_fragment(&page));
+
+            let file = files.add(page.as_str(), body);
+            let src = files.source(file);
+            let base = base_url(&page, src);
+            let links: Vec<Link> = crate::scanners::html(src)
+                .filter_map(|(href, span)| {
+                    // the <base> tag's own href is relative to the page
+                    let base = match &base {
+                        Some((base, base_span)) if *base_span != span => base,
+                        _ => &page,
+                    };
+
+                    match base.join(&href) {
+                        Ok(url) => Some(Link::new(url.to_string(), span, file)),
+                        Err(e) => {
+                            log::debug!(
+                                "Unable to resolve \"{}\" relative to \
+                                 \"{}\": {}",
+                                href,
+                                ctx.redact(base),
+                                e
+                            );
+                            None
+                        },
+                    }
+                })
+                .collect();
+
+            if depth < self.max_depth {
+                for link in &links {
+                    if let Ok(url) = Url::parse(&link.href) {
+                        let url = without_fragment(&url);
+                        if origins.contains(&url.origin())
+                            && visited.insert(url.clone())
+                        {
+                            queue.push_back((url, depth + 1));
+                        }
+                    }
+                }
+            }
+
+            outcomes.merge(crate::lints::mixed_content(
+                &page,
+                files.source(file),
+                file,
+            ));
+
+            // all the links are absolute URLs now, so the directory is never
+            // used
+            let page_outcomes =
+                validation::validate(Path::new("."), links, ctx).await;
+            outcomes.merge(page_outcomes);
+        }
+
+        outcomes
+    }
+}
+
+fn without_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+/// The URL from a page's `<base href="...">` (resolved against the page),
+/// and where the `href` is, if it has one.
+fn base_url(page: &Url, src: &str) -> Option<(Url, Span)> {
+    let tags = crate::scanners::html::tags(src);
+    let href = tags
+        .iter()
+        .filter(|tag| tag.name == "base")
+        .flat_map(|tag| &tag.attributes)
+        .find(|attr| attr.name == "href")?;
+    let value = crate::scanners::html::decode_entities(href.value_in(src)?);
+
+    match page.join(value.trim()) {
+        Ok(base) => Some((base, href.value?)),
+        Err(_) => None,
+    }
+}
+
+/// Download a page, returning the URL it ended up at (after following any
+/// redirects) and its body, if it is HTML.
+async fn fetch_html<C>(url: &Url, ctx: &C) -> Option<(Url, String)>
+where
+    C: Context + ?Sized,
+{
+    let redacted = ctx.redact(url);
+    log::debug!("Crawling \"{}\"", redacted);
+
+    let response = ctx
+        .client()
+        .get(url.clone())
+        .headers(ctx.url_specific_headers(url))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+
+    let response = match response {
+        Ok(r) => r,
+        Err(e) => {
+            log::warn!("Unable to crawl \"{}\": {}", redacted, e.without_url());
+            return None;
+        },
+    };
+    let final_url = response.url().clone();
+
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.starts_with("text/html"))
+        .unwrap_or(false);
+
+    if !is_html {
+        log::debug!("Not scanning \"{}\" because it isn't HTML", redacted);
+        return None;
+    }
+
+    let max_size = ctx.safety_limits().and_then(|l| l.max_response_size());
+
+    match read_body(response, max_size).await {
+        Ok(Some(body)) => Some((final_url, body)),
+        Ok(None) => {
+            log::warn!("Not scanning \"{}\" because it is too big", redacted);
+            None
+        },
+        Err(e) => {
+            log::warn!(
+                "Unable to read the body of \"{}\": {}",
+                redacted,
+                e.without_url()
+            );
+            None
+        },
+    }
+}
+
+/// Read a response's body, returning `None` if it is bigger than `max_size`
+/// bytes.
+async fn read_body(
+    response: Response,
+    max_size: Option<u64>,
+) -> Result<Option<String>, reqwest::Error> {
+    let max_size = match max_size {
+        Some(max_size) => max_size,
+        None => return response.text().await.map(Some),
+    };
+
+    if response.content_length().is_some_and(|len| len > max_size) {
+        return Ok(None);
+    }
+
+    let budget = usize::try_from(max_size).unwrap_or(usize::MAX);
+    let body = validation::read_body(response, budget).await?;
+
+    if body.truncated {
+        Ok(None)
+    } else {
+        Ok(Some(body.text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+
+    #[tokio::test]
+    async fn crawl_a_small_site() {
+        let server = Server::start(vec![
+            (
+                "/",
+                Response::html(
+                    r#"<a href="/a.html">A</a> <a href="b.html#top">B</a>"#,
+                ),
+            ),
+            (
+                "/a.html",
+                Response::html(r#"<a href="missing.html">broken</a>"#),
+            ),
+            ("/b.html", Response::html(r#"<img src="deep/c.html">"#)),
+            ("/deep/c.html", Response::html(r#"<a href="/">home</a>"#)),
+        ]);
+        let crawler = Crawler::new(vec![server.url("/")]).set_max_depth(1);
+        let mut files = Files::new();
+        let ctx = BasicContext::default();
+
+        let outcomes = crawler.crawl(&mut files, &ctx).await;
+
+        let invalid: Vec<_> = outcomes
+            .invalid
+            .iter()
+            .map(|i| i.link.href.clone())
+            .collect();
+        assert_eq!(invalid, vec![server.url("/missing.html").to_string()]);
+        assert_eq!(outcomes.valid.len(), 3);
+        // deep/c.html is at depth 2 so it gets validated, but never scanned
+        let crawled: Vec<_> = server
+            .requests()
+            .into_iter()
+            .filter(|r| r.method == "GET")
+            .map(|r| r.path)
+            .collect();
+        assert_eq!(crawled, vec!["/", "/a.html", "/b.html"]);
+    }
+
+    #[tokio::test]
+    async fn resolve_links_against_where_the_page_ended_up() {
+        let server = Server::start(vec![
+            ("/docs", Response::redirect("/docs/")),
+            (
+                "/docs/",
+                Response::html(r#"<a href="./a.html">A</a> <a href="b">B</a>"#),
+            ),
+            ("/docs/a.html", Response::html(r#"<base href="/api/">"#)),
+            (
+                "/api/",
+                Response::html(r#"<base href="v2/"><a href="c">C</a>"#),
+            ),
+            ("/api/v2/", Response::ok("")),
+            ("/api/v2/c", Response::ok("")),
+            ("/docs/b", Response::ok("")),
+        ]);
+        let seeds = vec![server.url("/docs"), server.url("/api/")];
+        let crawler = Crawler::new(seeds);
+        let mut files = Files::new();
+        let ctx = BasicContext::default();
+
+        let outcomes = crawler.crawl(&mut files, &ctx).await;
+
+        assert!(outcomes.invalid.is_empty(), "{:?}", outcomes.invalid);
+        let mut valid: Vec<_> =
+            outcomes.valid.iter().map(|l| l.href.clone()).collect();
+        valid.sort();
+        assert_eq!(
+            valid,
+            vec![
+                server.url("/api/").to_string(),
+                server.url("/api/v2/").to_string(),
+                server.url("/api/v2/c").to_string(),
+                server.url("/docs/a.html").to_string(),
+                server.url("/docs/b").to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn respect_the_page_budget() {
+        let server = Server::start(vec![
+            ("/", Response::html(r#"<a href="/a.html">A</a>"#)),
+            ("/a.html", Response::html(r#"<a href="/b.html">B</a>"#)),
+        ]);
+        let crawler = Crawler::new(vec![server.url("/")]).set_max_pages(1);
+        let mut files = Files::new();
+
+        let ctx = BasicContext::default();
+
+        let outcomes = crawler.crawl(&mut files, &ctx).await;
+
+        assert_eq!(outcomes.valid.len(), 1);
+        let page = files.name(outcomes.valid[0].file);
+        assert_eq!(page, server.url("/").as_str());
+    }
+}
@@ -0,0 +1,244 @@
+//! Checking the links in a set of documents as they will be seen once the
+//! site has been deployed.
+//!
+//! Instead of looking for files on disk, relative and root-relative links
+//! are resolved against the URL each document will be served from, then
+//! checked over HTTP.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use codespan::Files;
+//! use linkcheck::{deployed::Deployment, site::Site, BasicContext};
+//!
+//! # #[tokio::main] async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut files = Files::new();
+//! let documents = Site::new("./book/src").scan(&mut files)?;
+//!
+//! // "./book/src/guide/intro.md" is served as
+//! // "https://docs.example.com/guide/intro.html"
+//! let deployment =
+//!     Deployment::new("./book/src", "https://docs.example.com/".parse()?);
+//!
+//! let ctx = BasicContext::default();
+//! let outcomes =
+//!     linkcheck::deployed::validate(&documents, &deployment, &ctx).await;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    site::{self, Document},
+    validation::{Context, Outcomes},
+    Category, Link,
+};
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+use url::Url;
+
+/// Where a directory of documents gets deployed to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deployment {
+    root: PathBuf,
+    base_url: Url,
+    extensions: HashMap<String, String>,
+}
+
+impl Deployment {
+    /// Create a new [`Deployment`], where the documents in `root` are served
+    /// from `base_url`.
+    ///
+    /// By default, `*.md` files are assumed to be rendered to `*.html`.
+    pub fn new<P: Into<PathBuf>>(root: P, mut base_url: Url) -> Self {
+        // make sure joining treats the base as a directory
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+
+        let mut extensions = HashMap::new();
+        extensions.insert(String::from("md"), String::from("html"));
+
+        Deployment {
+            root: root.into(),
+            base_url,
+            extensions,
+        }
+    }
+
+    /// The directory containing the source documents.
+    pub fn root(&self) -> &Path { &self.root }
+
+    /// The URL [`Deployment::root()`] is served from.
+    pub fn base_url(&self) -> &Url { &self.base_url }
+
+    /// Files with the `source` extension are deployed with the `deployed`
+    /// extension instead (e.g. `md` to `html`).
+    pub fn set_extension<S, D>(mut self, source: S, deployed: D) -> Self
+    where
+        S: Into<String>,
+        D: Into<String>,
+    {
+        self.extensions
+            .insert(source.into().to_lowercase(), deployed.into());
+        self
+    }
+
+    /// Figure out which URL a document will be served from, returning `None`
+    /// if it isn't inside [`Deployment::root()`].
+    pub fn url_for(&self, path: &Path) -> Option<Url> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let mut segments = Vec::new();
+
+        for component in relative.components() {
+            match component {
+                Component::Normal(segment) => {
+                    segments.push(segment.to_string_lossy().into_owned())
+                },
+                Component::CurDir => {},
+                _ => return None,
+            }
+        }
+
+        if let Some(last) = segments.last_mut() {
+            *last = self.rename(last);
+        }
+
+        self.base_url.join(&segments.join("/")).ok()
+    }
+
+    /// Resolve a [`Link`] from a document to the URL a reader would end up
+    /// at.
+    ///
+    /// Links which don't point at a local file (e.g. web links) and links
+    /// from documents outside [`Deployment::root()`] give `None`.
+    pub fn resolve(&self, link: &Link, document: &Path) -> Option<Url> {
+        let page = self.url_for(document)?;
+
+        match link.category()? {
+            Category::CurrentFile { .. } => page.join(&link.href).ok(),
+            Category::FileSystem { .. } => {
+                let (path, rest) = match link.href.find(['?', '#']) {
+                    Some(index) => link.href.split_at(index),
+                    None => (link.href.as_str(), ""),
+                };
+                let path = match path.rsplit_once('/') {
+                    Some((directory, name)) => {
+                        format!("{}/{}", directory, self.rename(name))
+                    },
+                    None => self.rename(path),
+                };
+                let href = format!("{}{}", path, rest);
+
+                match href.strip_prefix('/') {
+                    Some(root_relative) => {
+                        self.base_url.join(root_relative).ok()
+                    },
+                    None => page.join(&href).ok(),
+                }
+            },
+            _ => None,
+        }
+    }
+
+    fn rename(&self, name: &str) -> String {
+        let path = Path::new(name);
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+
+        match extension.and_then(|ext| self.extensions.get(&ext)) {
+            Some(deployed) => {
+                path.with_extension(deployed).to_string_lossy().into_owned()
+            },
+            None => name.to_string(),
+        }
+    }
+}
+
+/// Validate the [`Link`]s in a set of [`Document`]s over HTTP, as if they
+/// had already been deployed.
+///
+/// Links to local files are rewritten to absolute URLs using
+/// [`Deployment::resolve()`], so the [`Link::href`]s in the [`Outcomes`] are
+/// the URLs which were actually checked.
+pub async fn validate<C>(
+    documents: &[Document],
+    deployment: &Deployment,
+    ctx: &C,
+) -> Outcomes
+where
+    C: Context + Sync + ?Sized,
+{
+    let rewritten: Vec<Document> = documents
+        .iter()
+        .map(|doc| Document {
+            links: doc
+                .links
+                .iter()
+                .map(|link| match deployment.resolve(link, &doc.path) {
+                    Some(url) => {
+                        Link::new(url.to_string(), link.span, link.file)
+                    },
+                    None => link.clone(),
+                })
+                .collect(),
+            ..doc.clone()
+        })
+        .collect();
+
+    site::validate(&rewritten, ctx).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+    use codespan::{FileId, Files};
+
+    #[tokio::test]
+    async fn check_links_against_the_deployed_site() {
+        let server = Server::start(vec![
+            ("/docs/index.html", Response::ok("")),
+            ("/docs/guide/intro.html", Response::ok("")),
+        ]);
+        let deployment = Deployment::new("/book", server.url("/docs"));
+        let mut files = Files::new();
+        let file: FileId = files.add("intro.md", "");
+        let hrefs =
+            ["../index.md", "/guide/intro.md#top", "missing.md", "#top"];
+        let document = Document {
+            file,
+            path: PathBuf::from("/book/guide/intro.md"),
+            links: hrefs
+                .iter()
+                .map(|href| Link::new(*href, Default::default(), file))
+                .collect(),
+        };
+
+        let outcomes =
+            validate(&[document], &deployment, &BasicContext::default()).await;
+
+        let mut valid: Vec<_> =
+            outcomes.valid.iter().map(|l| l.href.clone()).collect();
+        valid.sort();
+        assert_eq!(
+            valid,
+            vec![
+                server.url("/docs/guide/intro.html#top").to_string(),
+                server.url("/docs/guide/intro.html#top").to_string(),
+                server.url("/docs/index.html").to_string(),
+            ]
+        );
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert_eq!(
+            outcomes.invalid[0].link.href,
+            server.url("/docs/guide/missing.html").to_string()
+        );
+    }
+}
@@ -0,0 +1,592 @@
+//! An in-process DNS cache, so link farms with lots of hosts don't spend most
+//! of their time waiting on DNS.
+//!
+//! The [`DnsCache`] can be used as the DNS resolver for a [`reqwest::Client`],
+//! and when a [`Context`] provides one (see [`Context::dns_cache()`]) every
+//! unique host is resolved up front, before any requests are sent.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use linkcheck::dns::DnsCache;
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main] async fn main() {
+//! let dns = DnsCache::new();
+//! let client = reqwest::Client::builder()
+//!     .dns_resolver(Arc::new(dns.clone()))
+//!     .build()
+//!     .unwrap();
+//!
+//! // look up hosts ahead of time
+//! dns.prefetch(vec!["example.com", "docs.rs"]).await;
+//!
+//! // and this request doesn't need to wait for DNS
+//! let response = client.get("https://example.com/").send().await;
+//! # }
+//! ```
+//!
+//! Dual-stack hosts with broken IPv6 (or IPv4) can make a working link look
+//! dead, so the [`DnsCache`] can also be told which address family to
+//! prefer, either for every host or for particular domains (see
+//! [`IpPreference`]).
+//!
+//! By default hosts are looked up using the operating system's resolver, but
+//! any [`Resolve`] implementation can be plugged in instead. This can be done
+//! for particular domains too, so intranet links can use internal DNS while
+//! everything else goes through public DNS (see [`DnsCache::route()`]). With
+//! the `hickory-dns` feature, [`Nameservers`] sends queries to specific DNS
+//! servers.
+//!
+//! [`Context`]: crate::validation::Context
+//! [`Context::dns_cache()`]: crate::validation::Context::dns_cache
+
+use futures::{
+    future::{BoxFuture, Shared},
+    FutureExt, StreamExt,
+};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Formatter},
+    io,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// How many hosts [`DnsCache::prefetch()`] will look up at a time.
+const PREFETCH_CONCURRENCY: usize = 32;
+
+/// Which addresses to connect to when a host has both IPv4 and IPv6
+/// addresses.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub enum IpPreference {
+    /// Use every address, letting the client race both families ("happy
+    /// eyeballs").
+    #[default]
+    Race,
+    /// Only connect over IPv4, unless the host has no IPv4 addresses.
+    PreferIpv4,
+    /// Only connect over IPv6, unless the host has no IPv6 addresses.
+    PreferIpv6,
+}
+
+impl IpPreference {
+    /// Pick the addresses to connect to.
+    pub fn apply(self, addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        let preferred: fn(&SocketAddr) -> bool = match self {
+            IpPreference::Race => return addrs,
+            IpPreference::PreferIpv4 => SocketAddr::is_ipv4,
+            IpPreference::PreferIpv6 => SocketAddr::is_ipv6,
+        };
+
+        if addrs.iter().any(preferred) {
+            addrs.into_iter().filter(preferred).collect()
+        } else {
+            addrs
+        }
+    }
+}
+
+/// A cache of DNS lookups which can be shared between threads.
+///
+/// Concurrent lookups for the same host share a single query, and failed
+/// lookups are remembered for a little while (see
+/// [`DnsCache::negative_ttl()`]), so a dead domain only costs us one lookup
+/// no matter how many links point to it.
+#[derive(Debug, Clone)]
+pub struct DnsCache {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+    in_flight: Arc<Mutex<HashMap<String, Lookup>>>,
+    preferences: Arc<Mutex<Preferences>>,
+    resolvers: Resolvers,
+    ttl: Duration,
+    negative_ttl: Duration,
+}
+
+/// A lookup which is still in progress, shared by everyone waiting on it.
+type Lookup = Shared<BoxFuture<'static, Result<Vec<SocketAddr>, String>>>;
+
+/// The [`Resolve`]rs used instead of the operating system's resolver.
+#[derive(Default, Clone)]
+struct Resolvers {
+    default: Option<Arc<dyn Resolve>>,
+    domains: HashMap<String, Arc<dyn Resolve>>,
+}
+
+impl Debug for Resolvers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resolvers")
+            .field("default", &self.default.is_some())
+            .field("domains", &self.domains.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Preferences {
+    default: IpPreference,
+    domains: HashMap<String, IpPreference>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    resolved_at: Instant,
+    result: Result<Vec<SocketAddr>, String>,
+}
+
+impl DnsCache {
+    /// How long failed lookups are remembered for by default.
+    pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(30);
+    /// How long answers are remembered for by default.
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(5 * 60);
+
+    /// Create a new, empty [`DnsCache`].
+    pub fn new() -> Self {
+        DnsCache {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            preferences: Arc::new(Mutex::new(Preferences::default())),
+            resolvers: Resolvers::default(),
+            ttl: DnsCache::DEFAULT_TTL,
+            negative_ttl: DnsCache::DEFAULT_NEGATIVE_TTL,
+        }
+    }
+
+    /// How long an answer is remembered for.
+    pub fn ttl(&self) -> Duration { self.ttl }
+
+    /// Set the [`DnsCache::ttl()`].
+    pub fn set_ttl(self, ttl: Duration) -> Self { DnsCache { ttl, ..self } }
+
+    /// How long a failed lookup is remembered for.
+    ///
+    /// This is much shorter than the [`DnsCache::ttl()`], so a temporary
+    /// failure (e.g. a flaky network) doesn't break every link to that host
+    /// for the rest of the run.
+    pub fn negative_ttl(&self) -> Duration { self.negative_ttl }
+
+    /// Set the [`DnsCache::negative_ttl()`].
+    pub fn set_negative_ttl(self, negative_ttl: Duration) -> Self {
+        DnsCache {
+            negative_ttl,
+            ..self
+        }
+    }
+
+    /// Look hosts up using this [`Resolve`]r instead of the operating
+    /// system's resolver.
+    ///
+    /// Like [`DnsCache::set_ttl()`], this only affects the [`DnsCache`] it is
+    /// called on, so it should be done before giving the [`DnsCache`] to a
+    /// [`reqwest::Client`].
+    pub fn set_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: Resolve + 'static,
+    {
+        self.resolvers.default = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Look up a domain and its subdomains using this [`Resolve`]r,
+    /// overriding the one passed to [`DnsCache::set_resolver()`].
+    pub fn route<R>(mut self, domain: &str, resolver: R) -> Self
+    where
+        R: Resolve + 'static,
+    {
+        self.resolvers
+            .domains
+            .insert(domain.to_ascii_lowercase(), Arc::new(resolver));
+        self
+    }
+
+    /// Set the [`IpPreference`] used for every host.
+    ///
+    /// Unlike [`DnsCache::set_ttl()`], this is shared with every clone of the
+    /// [`DnsCache`] (e.g. the one given to a [`reqwest::Client`]).
+    pub fn prefer(&self, preference: IpPreference) {
+        self.preferences().default = preference;
+    }
+
+    /// Set the [`IpPreference`] for a domain and its subdomains, overriding
+    /// the one passed to [`DnsCache::prefer()`].
+    pub fn prefer_for_domain(&self, domain: &str, preference: IpPreference) {
+        self.preferences()
+            .domains
+            .insert(domain.to_ascii_lowercase(), preference);
+    }
+
+    /// The [`IpPreference`] for a host, using the most specific domain it
+    /// belongs to.
+    pub fn ip_preference(&self, host: &str) -> IpPreference {
+        let preferences = self.preferences();
+
+        most_specific(&preferences.domains, host)
+            .copied()
+            .unwrap_or(preferences.default)
+    }
+
+    fn preferences(&self) -> MutexGuard<'_, Preferences> {
+        self.preferences.lock().expect("Mutex was poisoned")
+    }
+
+    /// Get the addresses for a host, if it was resolved recently.
+    pub fn cached(&self, host: &str) -> Option<io::Result<Vec<SocketAddr>>> {
+        let entries = self.entries.lock().expect("Mutex was poisoned");
+        let entry = entries.get(&host.to_ascii_lowercase())?;
+        let ttl = match entry.result {
+            Ok(_) => self.ttl,
+            Err(_) => self.negative_ttl,
+        };
+
+        if entry.resolved_at.elapsed() >= ttl {
+            return None;
+        }
+
+        Some(
+            entry
+                .result
+                .clone()
+                .map_err(|msg| io::Error::new(io::ErrorKind::NotFound, msg)),
+        )
+    }
+
+    /// Look up a host's addresses, using the cache if possible.
+    pub async fn resolve(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        if let Some(cached) = self.cached(host) {
+            log::trace!("Using the cached DNS entry for \"{}\"", host);
+            return cached;
+        }
+
+        self.shared_lookup(&host.to_ascii_lowercase())
+            .await
+            .map_err(|msg| io::Error::new(io::ErrorKind::NotFound, msg))
+    }
+
+    /// Get the [`Lookup`] for a host, starting one if nobody else is already
+    /// looking it up.
+    fn shared_lookup(&self, host: &str) -> Lookup {
+        let mut in_flight = self.in_flight.lock().expect("Mutex was poisoned");
+        if let Some(lookup) = in_flight.get(host) {
+            log::trace!("Waiting for the lookup of \"{}\" to finish", host);
+            return lookup.clone();
+        }
+
+        log::debug!("Resolving \"{}\"", host);
+        let resolver = most_specific(&self.resolvers.domains, host)
+            .or(self.resolvers.default.as_ref())
+            .cloned();
+        let entries = Arc::clone(&self.entries);
+        let in_flight_lookups = Arc::clone(&self.in_flight);
+        let name = host.to_string();
+
+        let lookup = async move {
+            let result = match resolver {
+                Some(resolver) => lookup_with(resolver.as_ref(), &name).await,
+                None => lookup(&name).await,
+            }
+            .map_err(|e| e.to_string());
+
+            let entry = Entry {
+                resolved_at: Instant::now(),
+                result: result.clone(),
+            };
+            entries
+                .lock()
+                .expect("Mutex was poisoned")
+                .insert(name.clone(), entry);
+            in_flight_lookups
+                .lock()
+                .expect("Mutex was poisoned")
+                .remove(&name);
+
+            result
+        }
+        .boxed()
+        .shared();
+
+        in_flight.insert(host.to_string(), lookup.clone());
+        lookup
+    }
+
+    /// Resolve several hosts concurrently, skipping duplicates and any which
+    /// are already cached.
+    pub async fn prefetch<I, S>(&self, hosts: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let hosts: HashSet<String> = hosts
+            .into_iter()
+            .map(|host| host.as_ref().to_ascii_lowercase())
+            .filter(|host| host.parse::<std::net::IpAddr>().is_err())
+            .filter(|host| self.cached(host).is_none())
+            .collect();
+
+        if hosts.is_empty() {
+            return;
+        }
+        log::debug!("Resolving {} hosts ahead of time", hosts.len());
+
+        futures::stream::iter(hosts)
+            .for_each_concurrent(PREFETCH_CONCURRENCY, |host| async move {
+                if let Err(e) = self.resolve(&host).await {
+                    log::debug!("Unable to resolve \"{}\": {}", host, e);
+                }
+            })
+            .await;
+    }
+}
+
+impl Default for DnsCache {
+    fn default() -> Self { DnsCache::new() }
+}
+
+impl Resolve for DnsCache {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = self.clone();
+
+        Box::pin(async move {
+            let addrs = DnsCache::resolve(&cache, name.as_str()).await?;
+            let addrs = cache.ip_preference(name.as_str()).apply(addrs);
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// A [`Resolve`]r which sends queries to specific DNS servers, for use with
+/// [`DnsCache::set_resolver()`] or [`DnsCache::route()`].
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::dns::{DnsCache, Nameservers};
+///
+/// let intranet = Nameservers::new(&["10.0.0.53:53".parse().unwrap()]);
+/// let public = Nameservers::new(&["1.1.1.1:53".parse().unwrap()]);
+///
+/// let dns = DnsCache::new()
+///     .set_resolver(public)
+///     .route("corp.example.com", intranet);
+/// ```
+#[cfg(feature = "hickory-dns")]
+#[derive(Clone)]
+pub struct Nameservers(hickory_resolver::TokioAsyncResolver);
+
+#[cfg(feature = "hickory-dns")]
+impl Nameservers {
+    /// Create a [`Nameservers`] which queries these servers over UDP and
+    /// TCP.
+    pub fn new(servers: &[SocketAddr]) -> Self {
+        use hickory_resolver::config::{
+            NameServerConfig, Protocol, ResolverConfig, ResolverOpts,
+        };
+
+        let name_servers: Vec<_> = servers
+            .iter()
+            .flat_map(|&addr| {
+                vec![
+                    NameServerConfig::new(addr, Protocol::Udp),
+                    NameServerConfig::new(addr, Protocol::Tcp),
+                ]
+            })
+            .collect();
+        let config = ResolverConfig::from_parts(None, Vec::new(), name_servers);
+
+        Nameservers(hickory_resolver::TokioAsyncResolver::tokio(
+            config,
+            ResolverOpts::default(),
+        ))
+    }
+}
+
+#[cfg(feature = "hickory-dns")]
+impl Debug for Nameservers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Nameservers").finish()
+    }
+}
+
+#[cfg(feature = "hickory-dns")]
+impl Resolve for Nameservers {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.0.clone();
+
+        Box::pin(async move {
+            let lookup = resolver.lookup_ip(name.as_str()).await?;
+            let addrs: Addrs =
+                Box::new(lookup.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+/// Find the value for the most specific domain a host belongs to.
+fn most_specific<'a, T>(
+    domains: &'a HashMap<String, T>,
+    host: &str,
+) -> Option<&'a T> {
+    let host = host.to_ascii_lowercase();
+    let mut domain = host.as_str();
+
+    loop {
+        if let Some(value) = domains.get(domain) {
+            return Some(value);
+        }
+        domain = domain.split_once('.')?.1;
+    }
+}
+
+/// Do a lookup using a custom [`Resolve`]r.
+async fn lookup_with(
+    resolver: &dyn Resolve,
+    host: &str,
+) -> io::Result<Vec<SocketAddr>> {
+    let name = Name::from_str(host)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    match resolver.resolve(name).await {
+        Ok(addrs) => Ok(addrs.collect()),
+        Err(e) => Err(io::Error::other(e)),
+    }
+}
+
+/// Do a lookup using the operating system's resolver.
+async fn lookup(host: &str) -> io::Result<Vec<SocketAddr>> {
+    let addrs = tokio::net::lookup_host((host, 0)).await?;
+    Ok(addrs.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{Response, Server};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn requests_use_the_cached_answers() {
+        let server = Server::start(vec![("/", Response::ok(""))]);
+        let mut url = server.url("/");
+        url.set_host(Some("localhost")).unwrap();
+        let dns = DnsCache::new();
+        let client = reqwest::Client::builder()
+            .dns_resolver(Arc::new(dns.clone()))
+            .build()
+            .unwrap();
+
+        dns.prefetch(vec!["LOCALHOST", "localhost", "127.0.0.1"])
+            .await;
+
+        let addrs = dns.cached("localhost").unwrap().unwrap();
+        assert!(addrs.iter().any(|addr| addr.ip().is_loopback()));
+        assert_eq!(dns.entries.lock().unwrap().len(), 1);
+        let response = client.get(url).send().await.unwrap();
+        assert!(response.status().is_success());
+    }
+
+    #[tokio::test]
+    async fn route_domains_to_their_own_resolver() {
+        let intranet: SocketAddr = "10.0.0.1:0".parse().unwrap();
+        let public: SocketAddr = "93.184.216.34:0".parse().unwrap();
+        let dns = DnsCache::new()
+            .set_resolver(Fixed(public))
+            .route("corp.example.com", Fixed(intranet));
+
+        let got = dns.resolve("wiki.CORP.example.com").await.unwrap();
+        assert_eq!(got, vec![intranet]);
+        let got = dns.resolve("example.com").await.unwrap();
+        assert_eq!(got, vec![public]);
+    }
+
+    /// A [`Resolve`]r which always gives back the same address.
+    struct Fixed(SocketAddr);
+
+    impl Resolve for Fixed {
+        fn resolve(&self, _name: Name) -> Resolving {
+            let addrs: Addrs = Box::new(std::iter::once(self.0));
+            Box::pin(futures::future::ready(Ok(addrs)))
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_lookups_share_a_query() {
+        let resolver = Counting::default();
+        let dns = DnsCache::new().set_resolver(resolver.clone());
+
+        let (first, second) = futures::join!(
+            dns.resolve("example.com"),
+            dns.resolve("example.com")
+        );
+
+        assert_eq!(first.unwrap(), second.unwrap());
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 1);
+        assert!(dns.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failures_are_only_remembered_briefly() {
+        let resolver = Counting {
+            fail: true,
+            ..Default::default()
+        };
+        let dns = DnsCache::new().set_resolver(resolver.clone());
+
+        assert!(dns.resolve("example.com").await.is_err());
+        assert!(dns.cached("example.com").unwrap().is_err());
+        assert!(dns.resolve("example.com").await.is_err());
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 1);
+
+        let dns = dns.set_negative_ttl(Duration::ZERO);
+        assert!(dns.cached("example.com").is_none());
+        assert!(dns.resolve("example.com").await.is_err());
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 2);
+    }
+
+    /// A [`Resolve`]r which counts how many times it was asked, and takes a
+    /// moment to answer.
+    #[derive(Default, Clone)]
+    struct Counting {
+        calls: Arc<AtomicUsize>,
+        fail: bool,
+    }
+
+    impl Resolve for Counting {
+        fn resolve(&self, _name: Name) -> Resolving {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let fail = self.fail;
+
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                if fail {
+                    return Err("NXDOMAIN".into());
+                }
+                let addr = SocketAddr::from(([10, 0, 0, 1], 0));
+                let addrs: Addrs = Box::new(std::iter::once(addr));
+                Ok(addrs)
+            })
+        }
+    }
+
+    #[test]
+    fn prefer_an_address_family_per_domain() {
+        let v4: SocketAddr = "93.184.216.34:0".parse().unwrap();
+        let v6: SocketAddr = "[2606:2800:220:1::]:0".parse().unwrap();
+        let dns = DnsCache::new();
+        dns.prefer(IpPreference::PreferIpv6);
+        dns.prefer_for_domain("Example.com", IpPreference::PreferIpv4);
+
+        let preference = dns.clone().ip_preference("docs.example.com");
+        assert_eq!(preference, IpPreference::PreferIpv4);
+        assert_eq!(preference.apply(vec![v6, v4]), vec![v4]);
+        assert_eq!(preference.apply(vec![v6]), vec![v6]);
+
+        let preference = dns.ip_preference("notexample.com");
+        assert_eq!(preference, IpPreference::PreferIpv6);
+        assert_eq!(IpPreference::Race.apply(vec![v6, v4]), vec![v6, v4]);
+    }
+}
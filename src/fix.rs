@@ -0,0 +1,339 @@
+//! Automatically fixing broken links.
+//!
+//! While validating, some broken (or redirected) links can be matched with a
+//! [`Suggestion`] for what they probably should have been. These end up in
+//! [`Outcomes::suggestions`], and the [`Fixer`] uses each [`Link::span`] to
+//! apply them to the original source text.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use codespan::Files;
+//! use linkcheck::{
+//!     fix::{Fixer, Suggestion, SuggestionKind},
+//!     Link,
+//! };
+//!
+//! let mut files = Files::new();
+//! let src = "See [the docs](http://example.com/docs).";
+//! let file_id = files.add("README.md", src);
+//!
+//! let (href, span) = linkcheck::scanners::markdown(src).next().unwrap();
+//! let suggestion = Suggestion {
+//!     link: Link::new(href, span, file_id),
+//!     replacement: String::from("https://example.com/docs/"),
+//!     kind: SuggestionKind::Redirect,
+//! };
+//!
+//! // nothing gets written to disk unless you ask for it
+//! let patches = Fixer::new().fix(&[suggestion], &files).unwrap();
+//!
+//! assert_eq!(
+//!     patches.patches[0].fixed,
+//!     "See [the docs](https://example.com/docs/)."
+//! );
+//! ```
+//!
+//! [`Outcomes::suggestions`]: crate::validation::Outcomes::suggestions
+
+use crate::Link;
+use codespan::{FileId, Files, Span};
+use std::{collections::BTreeMap, io, path::PathBuf};
+
+/// A possible fix for a [`Link`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Suggestion<F = FileId> {
+    /// The [`Link`] to be fixed.
+    pub link: Link<F>,
+    /// What the link's `href` should be replaced with.
+    pub replacement: String,
+    /// Why this fix was suggested.
+    pub kind: SuggestionKind,
+}
+
+/// The reason a [`Suggestion`] was made.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum SuggestionKind {
+    /// The server redirected us somewhere else.
+    Redirect,
+    /// The file doesn't exist, but there is one with a different case or
+    /// extension.
+    FileName,
+    /// The issue or pull request was moved to another repository.
+    Transferred,
+    /// The link points to an archived copy of a page which is still online.
+    LiveOriginal,
+    /// The anchor doesn't exist, but there is one with a similar name.
+    Anchor,
+    /// The URL contains spaces or non-ASCII characters which need to be
+    /// percent-encoded.
+    PercentEncoding,
+    /// The link points somewhere which has moved (see [`crate::migration`]).
+    Migrated,
+}
+
+/// Applies [`Suggestion`]s to the original source text.
+///
+/// By default nothing is written to disk, you need to explicitly opt in with
+/// [`Fixer::set_in_place()`].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Fixer {
+    in_place: bool,
+}
+
+impl Fixer {
+    /// Create a new [`Fixer`].
+    pub fn new() -> Self { Fixer::default() }
+
+    /// Will [`Fixer::fix()`] overwrite the original files?
+    pub fn in_place(&self) -> bool { self.in_place }
+
+    /// Set [`Fixer::in_place()`].
+    pub fn set_in_place(self, in_place: bool) -> Self { Fixer { in_place } }
+
+    /// Work out the edits needed to apply every [`Suggestion`], returning the
+    /// fixed text for each file (and saving it to disk if
+    /// [`Fixer::in_place()`] is set).
+    ///
+    /// Each file is written to the path it was given when added to `files`.
+    /// A [`Suggestion`] is skipped when the [`Link::href`] can't be found
+    /// within its [`Link::span`], or it overlaps an earlier edit.
+    pub fn fix<S>(
+        &self,
+        suggestions: &[Suggestion],
+        files: &Files<S>,
+    ) -> io::Result<PatchSet>
+    where
+        S: AsRef<str>,
+    {
+        let mut edits: BTreeMap<FileId, Vec<Edit>> = BTreeMap::new();
+
+        for suggestion in suggestions {
+            let src = files.source(suggestion.link.file).as_ref();
+
+            match Edit::for_suggestion(src, suggestion) {
+                Some(edit) => {
+                    edits.entry(suggestion.link.file).or_default().push(edit)
+                },
+                None => log::warn!(
+                    "Unable to find \"{}\" in the source text, skipping it",
+                    suggestion.link.href
+                ),
+            }
+        }
+
+        let mut patches = Vec::new();
+
+        for (file, edits) in edits {
+            let patch = Patch::new(file, files, edits);
+
+            if self.in_place {
+                log::info!("Fixing \"{}\"", patch.path.display());
+                std::fs::write(&patch.path, &patch.fixed)?;
+            }
+
+            patches.push(patch);
+        }
+
+        Ok(PatchSet { patches })
+    }
+}
+
+/// The changes made by [`Fixer::fix()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchSet {
+    /// The changes for each file, in order of [`FileId`].
+    pub patches: Vec<Patch>,
+}
+
+impl PatchSet {
+    /// Were there no changes at all?
+    pub fn is_empty(&self) -> bool { self.patches.is_empty() }
+}
+
+/// The changes made to a single file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    /// The file being changed.
+    pub file: FileId,
+    /// The file's name, as it was added to [`Files`].
+    pub path: PathBuf,
+    /// Every edit which was applied, in the order they appear in the file.
+    pub edits: Vec<Edit>,
+    /// The file's text after applying the edits.
+    pub fixed: String,
+}
+
+impl Patch {
+    fn new<S: AsRef<str>>(
+        file: FileId,
+        files: &Files<S>,
+        mut edits: Vec<Edit>,
+    ) -> Self {
+        let src = files.source(file).as_ref();
+        edits.sort_by_key(|edit| (edit.span.start(), edit.span.end()));
+
+        let mut fixed = String::with_capacity(src.len());
+        let mut cursor = 0;
+        let mut applied = Vec::new();
+
+        for edit in edits {
+            let start = edit.span.start().to_usize();
+            if start < cursor {
+                log::warn!(
+                    "Not replacing \"{}\" because it overlaps another edit",
+                    edit.original
+                );
+                continue;
+            }
+
+            fixed.push_str(&src[cursor..start]);
+            fixed.push_str(&edit.replacement);
+            cursor = edit.span.end().to_usize();
+            applied.push(edit);
+        }
+        fixed.push_str(&src[cursor..]);
+
+        Patch {
+            file,
+            path: PathBuf::from(files.name(file)),
+            edits: applied,
+            fixed,
+        }
+    }
+}
+
+/// Replace the text at a [`Span`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    /// Where the original text is.
+    pub span: Span,
+    /// The text being replaced.
+    pub original: String,
+    /// The new text.
+    pub replacement: String,
+}
+
+impl Edit {
+    fn for_suggestion(src: &str, suggestion: &Suggestion) -> Option<Edit> {
+        let link = &suggestion.link;
+        let start = link.span.start().to_usize();
+        let end = link.span.end().to_usize();
+
+        // the span may cover the entire link (e.g. "[text](href)" in
+        // markdown), so look for the href itself
+        let offset = src.get(start..end)?.rfind(&link.href)?;
+        let href_start = start + offset;
+        let href_end = href_start + link.href.len();
+
+        Some(Edit {
+            span: Span::new(href_start as u32, href_end as u32),
+            original: link.href.clone(),
+            replacement: suggestion.replacement.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+
+    #[tokio::test]
+    async fn validation_suggests_fixes() {
+        let server = Server::start(vec![
+            ("/old", Response::redirect("/new")),
+            ("/new", Response::ok("")),
+        ]);
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join("Chapter1.md"), "").unwrap();
+        let mut files = Files::new();
+        let src = format!(
+            "[a](chapter1.md#intro) [b](./chapter1.html) [c]({})",
+            server.url("/old#top")
+        );
+        let file = files.add("index.md", src.clone());
+        let links = crate::scanners::markdown(&src)
+            .map(|(href, span)| Link::new(href, span, file));
+
+        let outcomes =
+            crate::validate(temp.path(), links, &BasicContext::default()).await;
+
+        let mut got: Vec<_> = outcomes
+            .suggestions
+            .iter()
+            .map(|s| (s.link.href.clone(), s.replacement.clone(), s.kind))
+            .collect();
+        got.sort();
+        let should_be = vec![
+            (
+                String::from("./chapter1.html"),
+                String::from("./Chapter1.md"),
+                SuggestionKind::FileName,
+            ),
+            (
+                String::from("chapter1.md#intro"),
+                String::from("Chapter1.md#intro"),
+                SuggestionKind::FileName,
+            ),
+            (
+                server.url("/old#top").to_string(),
+                server.url("/new#top").to_string(),
+                SuggestionKind::Redirect,
+            ),
+        ];
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn apply_suggestions_to_the_source() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("index.md");
+        let src = "[a](Chapter1.MD) and [b](http://x.com/) and [c](missing)";
+        std::fs::write(&path, src).unwrap();
+        let mut files = Files::new();
+        let file = files.add(&path, src.to_string());
+        let links: Vec<_> = crate::scanners::markdown(src)
+            .map(|(href, span)| Link::new(href, span, file))
+            .collect();
+        let suggestions = vec![
+            Suggestion {
+                link: links[1].clone(),
+                replacement: String::from("https://x.com/"),
+                kind: SuggestionKind::Redirect,
+            },
+            Suggestion {
+                link: links[0].clone(),
+                replacement: String::from("chapter1.md"),
+                kind: SuggestionKind::FileName,
+            },
+            Suggestion {
+                link: Link::new("not-in-the-text", links[2].span, file),
+                replacement: String::from("nope"),
+                kind: SuggestionKind::FileName,
+            },
+        ];
+        let should_be =
+            "[a](chapter1.md) and [b](https://x.com/) and [c](missing)";
+
+        let dry_run = Fixer::new().fix(&suggestions, &files).unwrap();
+
+        assert_eq!(dry_run.patches.len(), 1);
+        assert_eq!(dry_run.patches[0].edits.len(), 2);
+        assert_eq!(dry_run.patches[0].fixed, should_be);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), src);
+
+        Fixer::new()
+            .set_in_place(true)
+            .fix(&suggestions, &files)
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), should_be);
+    }
+}
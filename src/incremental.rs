@@ -0,0 +1,261 @@
+//! Incremental validation, only re-scanning documents which have changed since
+//! the last run.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use codespan::Files;
+//! use linkcheck::{incremental::State, site::Site, BasicContext};
+//! use std::fs::File;
+//!
+//! # #[tokio::main] async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // load the state from the previous run (if there was one)
+//! let mut state: State = match File::open("linkcheck-state.json") {
+//!     Ok(f) => serde_json::from_reader(f)?,
+//!     Err(_) => State::new(),
+//! };
+//!
+//! let site = Site::new("./src").include("**/*.md")?;
+//! let mut files = Files::new();
+//! let ctx = BasicContext::default();
+//! let snapshot = state.validate(&site, &mut files, &ctx).await?;
+//!
+//! println!("{} broken links", snapshot.invalid().count());
+//!
+//! // and save it for next time
+//! serde_json::to_writer(File::create("linkcheck-state.json")?, &state)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    site::{self, Document, Site},
+    validation::{file_name, Context, Snapshot, SnapshotEntry, Status},
+    Category, Link,
+};
+use codespan::{Files, Span};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    io,
+    path::PathBuf,
+    time::SystemTime,
+};
+
+/// What we remember about each document between runs.
+///
+/// # Note
+///
+/// Content hashes are calculated using the standard library's
+/// [`DefaultHasher`], which isn't guaranteed to be stable across Rust
+/// releases. At worst, this means upgrading your compiler will cause a full
+/// re-check.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct State {
+    files: HashMap<PathBuf, FileRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+struct FileRecord {
+    hash: u64,
+    checked_at: SystemTime,
+    links: Vec<LinkRecord>,
+}
+
+impl FileRecord {
+    fn is_fresh(&self, hash: u64, timeout: std::time::Duration) -> bool {
+        self.hash == hash
+            && self
+                .checked_at
+                .elapsed()
+                .map(|elapsed| elapsed < timeout)
+                .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+struct LinkRecord {
+    href: String,
+    span: Span,
+    status: Status,
+    reason: Option<String>,
+}
+
+impl State {
+    /// Create a new, empty [`State`].
+    pub fn new() -> Self { State::default() }
+
+    /// Forget everything, forcing the next run to re-check every document.
+    pub fn clear(&mut self) { self.files.clear(); }
+
+    /// Validate all the documents in a [`Site`], reusing results from the
+    /// previous run where possible.
+    ///
+    /// When a document's contents haven't changed, we skip scanning it and
+    /// reuse the results for its web links, provided they are younger than
+    /// [`Context::cache_timeout()`]. Links to local files are always
+    /// re-checked because the files they point to may have been moved or
+    /// deleted in the meantime.
+    pub async fn validate<C>(
+        &mut self,
+        site: &Site,
+        files: &mut Files<String>,
+        ctx: &C,
+    ) -> io::Result<Snapshot>
+    where
+        C: Context + Sync + ?Sized,
+    {
+        let mut documents = Vec::new();
+        let mut reused = Vec::new();
+        let mut records = HashMap::new();
+
+        for path in site.paths()? {
+            let scanner = match Site::scanner_for(&path) {
+                Some(scanner) => scanner,
+                None => continue,
+            };
+            let src = std::fs::read_to_string(&path)?;
+            let hash = content_hash(&src);
+            let file = files.add(&path, src);
+
+            let (links, checked_at) = match self.files.remove(&path) {
+                Some(record) if record.is_fresh(hash, ctx.cache_timeout()) => {
+                    log::debug!(
+                        "\"{}\" hasn't changed, reusing its web results",
+                        path.display()
+                    );
+                    let mut links = Vec::new();
+
+                    for previous in record.links {
+                        let link =
+                            Link::new(previous.href, previous.span, file);
+                        if let Some(Category::Url(_)) =
+                            ctx.categorise(&link.href)
+                        {
+                            reused.push(SnapshotEntry {
+                                file: file_name(files, file),
+                                href: link.href,
+                                span: link.span,
+                                status: previous.status,
+                                reason: previous.reason,
+                                source: None,
+                            });
+                        } else {
+                            links.push(link);
+                        }
+                    }
+
+                    (links, record.checked_at)
+                },
+                _ => {
+                    log::debug!("Scanning \"{}\"", path.display());
+                    let links = scanner(files.source(file))
+                        .into_iter()
+                        .map(|(href, span)| Link::new(href, span, file))
+                        .collect();
+
+                    (links, SystemTime::now())
+                },
+            };
+
+            records.insert(file, (path.clone(), hash, checked_at));
+            documents.push(Document { file, path, links });
+        }
+
+        let outcomes = site::validate(&documents, ctx).await;
+        let mut snapshot = outcomes.snapshot(files);
+        snapshot.entries.extend(reused);
+
+        self.files = records
+            .into_iter()
+            .map(|(file, (path, hash, checked_at))| {
+                let name = file_name(files, file);
+                let links = snapshot
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.file == name)
+                    .map(|entry| LinkRecord {
+                        href: entry.href.clone(),
+                        span: entry.span,
+                        status: entry.status,
+                        reason: entry.reason.clone(),
+                    })
+                    .collect();
+
+                let record = FileRecord {
+                    hash,
+                    checked_at,
+                    links,
+                };
+                (path, record)
+            })
+            .collect();
+
+        Ok(snapshot)
+    }
+}
+
+fn content_hash(src: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    src.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+
+    #[tokio::test]
+    async fn only_rescan_changed_documents() {
+        let server = Server::start(vec![
+            ("/a", Response::ok("")),
+            ("/b", Response::ok("")),
+            ("/b?v=2", Response::ok("")),
+        ]);
+        let temp = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(temp.path()).unwrap();
+        let unchanged = root.join("unchanged.md");
+        let changed = root.join("changed.md");
+        let target = root.join("target.md");
+        std::fs::write(&target, "").unwrap();
+        let src = format!("[web]({}) [local](target.md)", server.url("/a"));
+        std::fs::write(&unchanged, src).unwrap();
+        std::fs::write(&changed, format!("[web]({})", server.url("/b")))
+            .unwrap();
+        let site = Site::new(&root);
+        let mut state = State::new();
+
+        // note: we use a fresh context each time so its cache isn't reused
+        let ctx = BasicContext::default();
+        let first = state.validate(&site, &mut Files::new(), &ctx).await;
+        assert_eq!(first.unwrap().invalid().count(), 0);
+        assert_eq!(server.requests().len(), 2);
+
+        // edit one file and delete the local link target
+        let src = format!("[web]({})", server.url("/b?v=2"));
+        std::fs::write(&changed, src).unwrap();
+        std::fs::remove_file(&target).unwrap();
+        let ctx = BasicContext::default();
+        let second = state
+            .validate(&site, &mut Files::new(), &ctx)
+            .await
+            .unwrap();
+
+        // only the changed document's web link was checked again
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].path, "/b?v=2");
+        // but local links are always re-checked
+        let invalid: Vec<_> =
+            second.invalid().map(|entry| &entry.href).collect();
+        assert_eq!(invalid, vec!["target.md"]);
+        assert_eq!(second.entries.len(), 3);
+    }
+}
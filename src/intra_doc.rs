@@ -0,0 +1,281 @@
+//! Checking Rust-style intra-doc links (e.g. ``[`Vec`]`` or
+//! `[module::func]`) against an index of known items.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use codespan::Files;
+//! use linkcheck::{
+//!     intra_doc::{self, SymbolIndex},
+//!     Link,
+//! };
+//!
+//! let src = "Use [`Widget::new()`] to create a [`Widget`], not [`Gadget`].";
+//! let mut files = Files::new();
+//! let file_id = files.add("lib.rs", src);
+//!
+//! let mut index = SymbolIndex::new();
+//! index.insert("my_crate::Widget");
+//! index.insert("my_crate::Widget::new");
+//!
+//! let links = intra_doc::scan(src)
+//!     .map(|(item, span)| Link::new(item, span, file_id));
+//! let outcomes = intra_doc::validate(links, &index);
+//!
+//! assert_eq!(outcomes.valid.len(), 2);
+//! assert_eq!(outcomes.invalid.len(), 1);
+//! assert_eq!(outcomes.invalid[0].link.href, "Gadget");
+//! ```
+
+use crate::{
+    scanners::markdown_with_broken_link_callback,
+    validation::{InvalidLink, Outcomes, Reason},
+    Link,
+};
+use codespan::Span;
+use pulldown_cmark::{BrokenLink, CowStr};
+use std::collections::HashSet;
+
+/// Disambiguators which may be prepended to an item's path (e.g.
+/// `struct@Foo`).
+const DISAMBIGUATORS: &[&str] = &[
+    "struct",
+    "enum",
+    "trait",
+    "union",
+    "module",
+    "mod",
+    "const",
+    "constant",
+    "fn",
+    "function",
+    "method",
+    "derive",
+    "type",
+    "value",
+    "macro",
+    "prim",
+    "primitive",
+    "static",
+    "field",
+    "variant",
+    "tyalias",
+];
+
+/// Extract intra-doc links from the markdown in a doc-comment.
+///
+/// This picks up explicit links whose destination is a Rust path (e.g.
+/// `[text](std::vec::Vec)`) as well as shortcut links with no definition
+/// (e.g. ``[`Vec`]``). Each item is normalised by removing backticks,
+/// disambiguators (`struct@`), generics, and trailing `()` or `!`.
+pub fn scan(src: &str) -> impl Iterator<Item = (String, Span)> + '_ {
+    let mut on_broken_link = |broken: BrokenLink<'_>| {
+        normalise(broken.reference)
+            .map(|item| (CowStr::from(item), CowStr::from("")))
+    };
+
+    markdown_with_broken_link_callback(src, Some(&mut on_broken_link))
+        .filter_map(|(href, span)| normalise(&href).map(|item| (item, span)))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Turn something like ``"`struct@Vec<T>`"`` into `"Vec"`, returning `None`
+/// if it doesn't look like a Rust path.
+fn normalise(raw: &str) -> Option<String> {
+    let mut item = raw.trim().trim_matches('`');
+
+    if let Some(at) = item.find('@') {
+        if DISAMBIGUATORS.contains(&&item[..at]) {
+            item = &item[at + 1..];
+        }
+    }
+
+    let item = item
+        .trim_end_matches("()")
+        .trim_end_matches('!')
+        .split('<')
+        .next()
+        .unwrap_or_default();
+
+    if is_path(item) {
+        Some(item.to_string())
+    } else {
+        None
+    }
+}
+
+fn is_path(item: &str) -> bool {
+    !item.is_empty()
+        && item.split("::").all(|segment| {
+            let mut chars = segment.chars();
+            matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+                && chars.all(|c| c.is_alphanumeric() || c == '_')
+        })
+}
+
+/// A set of known item paths (e.g. `my_crate::module::Type`) that intra-doc
+/// links can be resolved against.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SymbolIndex {
+    paths: HashSet<String>,
+}
+
+impl SymbolIndex {
+    /// Create an empty [`SymbolIndex`].
+    pub fn new() -> Self { SymbolIndex::default() }
+
+    /// Add an item's fully qualified path to the index.
+    pub fn insert<S: Into<String>>(&mut self, path: S) {
+        self.paths.insert(path.into());
+    }
+
+    /// Build an index from the output of `rustdoc --output-format json`,
+    /// using every item in its `paths` table.
+    #[cfg(feature = "serde-1")]
+    pub fn from_rustdoc_json<R: std::io::Read>(
+        reader: R,
+    ) -> Result<Self, serde_json::Error> {
+        #[derive(serde::Deserialize)]
+        struct Crate {
+            paths: std::collections::HashMap<String, ItemSummary>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct ItemSummary {
+            path: Vec<String>,
+        }
+
+        let krate: Crate = serde_json::from_reader(reader)?;
+
+        Ok(krate
+            .paths
+            .into_values()
+            .map(|summary| summary.path.join("::"))
+            .collect())
+    }
+
+    /// Does an item resolve to something in this index?
+    ///
+    /// We don't know which scope an intra-doc link is written in, so an item
+    /// resolves if it is either a full path in the index or a `::`-separated
+    /// suffix of one (e.g. `Vec` and `vec::Vec` both match `std::vec::Vec`).
+    /// Leading `crate::`, `self::`, `super::`, and `Self::` segments are
+    /// ignored.
+    pub fn resolves(&self, item: &str) -> bool {
+        let mut item = item;
+        while let Some(rest) = ["crate::", "self::", "super::", "Self::"]
+            .iter()
+            .find_map(|prefix| item.strip_prefix(prefix))
+        {
+            item = rest;
+        }
+
+        self.paths.iter().any(|path| {
+            path == item
+                || (path.ends_with(item)
+                    && path[..path.len() - item.len()].ends_with("::"))
+        })
+    }
+}
+
+impl<S: Into<String>> Extend<S> for SymbolIndex {
+    fn extend<T: IntoIterator<Item = S>>(&mut self, iter: T) {
+        self.paths.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+impl<S: Into<String>> std::iter::FromIterator<S> for SymbolIndex {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        let mut index = SymbolIndex::new();
+        index.extend(iter);
+        index
+    }
+}
+
+/// Check that every intra-doc [`Link`] resolves to an item in the
+/// [`SymbolIndex`], reporting the ones that don't with
+/// [`Reason::UnresolvedItem`].
+pub fn validate<L>(links: L, index: &SymbolIndex) -> Outcomes
+where
+    L: IntoIterator<Item = Link>,
+{
+    let mut outcomes = Outcomes::empty();
+
+    for link in links {
+        if index.resolves(&link.href) {
+            outcomes.valid.push(link);
+        } else {
+            let reason = Reason::UnresolvedItem(link.href.clone());
+            outcomes.invalid.push(InvalidLink { link, reason });
+        }
+    }
+
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_intra_doc_links() {
+        let src = "See [`Vec`], [the module](std::collections), \
+                   [`struct@Foo<T>`], [`bar()`], [`println!`], \
+                   [a website](https://example.com/) and [not a link].\n\n\
+                   [not a link]: ./README.md";
+
+        let got: Vec<_> = scan(src).map(|(item, _)| item).collect();
+
+        assert_eq!(
+            got,
+            vec!["Vec", "std::collections", "Foo", "bar", "println"]
+        );
+    }
+
+    #[test]
+    fn resolve_items_against_the_index() {
+        let index: SymbolIndex = vec!["std::vec::Vec", "my_crate::Widget::new"]
+            .into_iter()
+            .collect();
+        let inputs = vec![
+            ("Vec", true),
+            ("vec::Vec", true),
+            ("std::vec::Vec", true),
+            ("crate::Widget::new", true),
+            ("Self::new", true),
+            ("ec::Vec", false),
+            ("HashMap", false),
+        ];
+
+        for (item, should_be) in inputs {
+            assert_eq!(index.resolves(item), should_be, "{}", item);
+        }
+    }
+
+    #[cfg(feature = "serde-1")]
+    #[test]
+    fn load_rustdoc_json() {
+        let json = r#"{
+            "root": "0:0",
+            "paths": {
+                "0:3": {
+                    "crate_id": 0,
+                    "path": ["my_crate", "Widget"],
+                    "kind": "struct"
+                },
+                "1:7": {
+                    "crate_id": 1,
+                    "path": ["std", "vec", "Vec"],
+                    "kind": "struct"
+                }
+            }
+        }"#;
+
+        let index = SymbolIndex::from_rustdoc_json(json.as_bytes()).unwrap();
+
+        assert!(index.resolves("Widget"));
+        assert!(index.resolves("Vec"));
+        assert!(!index.resolves("Gadget"));
+    }
+}
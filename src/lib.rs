@@ -60,6 +60,27 @@
 //!
 //! * **serde-1** - Adds `Serialize` and `Deserialize` implementations for use
 //!   with `serde`
+//! * **html-report** - Generate standalone HTML reports (see
+//!   [`report::html()`])
+//! * **blocking** - A synchronous API which hides the async runtime (see the
+//!   `blocking` module)
+//! * **tower** - Use the web checker as a `tower::Service` (see
+//!   `validation::WebChecker`)
+//! * **notify** - Watch a directory and re-check links as files change (see the
+//!   `watch` module)
+//! * **forges** - Check links to GitHub and GitLab issues using their APIs (see
+//!   `validation::ForgeApi`)
+//! * **container-images** - Check `docker://` links to container images against
+//!   their registry (see `validation::ImageRef`)
+//! * **socks** - Send requests through a SOCKS5 proxy (see
+//!   `validation::ProxySettings::with_socks5()`)
+//! * **hickory-dns** - Look hosts up using specific DNS servers (see
+//!   `dns::Nameservers`)
+//! * **metrics** - Report counters and request durations through the `metrics`
+//!   facade (see the `metrics` module)
+//! * **http3** - Check links over HTTP/3 (see
+//!   `validation::BasicContext::with_http3()`). Like `reqwest`'s HTTP/3
+//!   support, this also needs `RUSTFLAGS="--cfg reqwest_unstable"`
 
 #![forbid(unsafe_code)]
 #![deny(
@@ -72,66 +93,186 @@
 #[macro_use]
 extern crate pretty_assertions;
 
+pub mod anchors;
+pub mod backlinks;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod canonical;
+pub mod crawl;
+pub mod deployed;
+pub mod dns;
+pub mod fix;
+pub mod incremental;
+pub mod intra_doc;
+pub mod lints;
+pub mod lsp;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod migration;
+pub mod report;
 pub mod scanners;
+pub mod site;
+pub mod toc;
+pub mod translations;
 pub mod validation;
+#[cfg(feature = "notify")]
+pub mod watch;
 
-pub use validation::{validate, BasicContext};
+mod location;
+#[cfg(test)]
+mod test_utils;
+
+pub use location::Location;
+pub use validation::{check_path, check_url, validate, BasicContext};
 
-use codespan::{FileId, Span};
+use codespan::{FileId, Files, Span};
 use http::uri::PathAndQuery;
-use std::path::PathBuf;
+use std::{borrow::Cow, path::PathBuf};
 use url::Url;
 
+/// The different kinds of [`Link`], which determines how each one is
+/// validated.
+///
+/// Callers can override how links are categorised with
+/// [`validation::Context::categorise()`].
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum Category {
+#[non_exhaustive]
+pub enum Category {
     /// A local file.
     FileSystem {
+        /// The path to the file.
         path: PathBuf,
+        /// The query string (e.g. `highlight=x` in `page.md?highlight=x`),
+        /// if any.
+        ///
+        /// This isn't used when looking for the file, but is kept around
+        /// for custom validators and hooks.
+        query: Option<String>,
+        /// The section of the file being linked to, if any.
         fragment: Option<String>,
     },
     /// A link to somewhere else in the current document.
-    CurrentFile { fragment: String },
+    CurrentFile {
+        /// The section being linked to.
+        fragment: String,
+    },
     /// A URL for something on the web.
     Url(Url),
+    /// A URL without a scheme (e.g. `//example.com/page`), which uses the
+    /// same scheme as the page it is on.
+    ///
+    /// These are checked as web links using the
+    /// [`validation::Context::default_scheme()`].
+    ProtocolRelative(String),
     /// A `mailto:` link.
     MailTo(String),
+    /// A `tel:` link to a phone number.
+    Tel(String),
+    /// A `javascript:` link.
+    JavaScript(String),
+    /// A URI which doesn't point to something we can fetch, because it has
+    /// no host or hierarchical path (e.g. `urn:isbn:0451450523` or
+    /// `did:example:123`).
+    ///
+    /// These are checked with [`validation::Context::check_opaque_uri()`].
+    Opaque(Url),
+    /// A link which doesn't go anywhere (e.g. `href=""`, whitespace, or a
+    /// bare `#`).
+    Empty,
 }
 
 impl Category {
-    fn categorise(src: &str) -> Option<Self> {
-        if src.is_empty() {
-            return None;
+    /// Figure out what kind of link `src` is, returning `None` when it isn't
+    /// something we know how to check.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use linkcheck::Category;
+    /// use std::path::PathBuf;
+    ///
+    /// assert_eq!(
+    ///     Category::categorise("../README.md#license"),
+    ///     Some(Category::FileSystem {
+    ///         path: PathBuf::from("../README.md"),
+    ///         query: None,
+    ///         fragment: Some(String::from("license")),
+    ///     })
+    /// );
+    /// assert_eq!(
+    ///     Category::categorise("page.md?highlight=x#section"),
+    ///     Some(Category::FileSystem {
+    ///         path: PathBuf::from("page.md"),
+    ///         query: Some(String::from("highlight=x")),
+    ///         fragment: Some(String::from("section")),
+    ///     })
+    /// );
+    /// assert!(matches!(
+    ///     Category::categorise("https://example.com/"),
+    ///     Some(Category::Url(_))
+    /// ));
+    /// ```
+    pub fn categorise(src: &str) -> Option<Self> {
+        let trimmed = src.trim();
+        if trimmed.is_empty() || trimmed == "#" {
+            return Some(Category::Empty);
         }
 
-        let mailto_prefix = "mailto:";
-        if src.starts_with(mailto_prefix) {
-            let address = &src[mailto_prefix.len()..];
+        if let Some(address) = strip_scheme(src, "mailto:") {
             return Some(Category::MailTo(address.to_string()));
         }
 
-        if let Ok(url) = src.parse() {
+        if let Some(number) = strip_scheme(src, "tel:") {
+            return Some(Category::Tel(number.to_string()));
+        }
+
+        if let Some(script) = strip_scheme(src, "javascript:") {
+            return Some(Category::JavaScript(script.to_string()));
+        }
+
+        if let Ok(url) = src.parse::<Url>() {
+            if url.cannot_be_a_base() {
+                return Some(Category::Opaque(url));
+            }
             return Some(Category::Url(url));
         }
 
-        if src.starts_with("#") {
+        if is_protocol_relative(src) {
+            return Some(Category::ProtocolRelative(src.to_string()));
+        }
+
+        if let Some(fragment) = src.strip_prefix('#') {
             return Some(Category::CurrentFile {
-                fragment: String::from(&src[1..]),
+                fragment: String::from(fragment),
             });
         }
 
-        let (path, fragment) = match src.find("#") {
-            Some(hash) => {
-                let (path, rest) = src.split_at(hash);
-                (path, Some(String::from(&rest[1..])))
-            },
-            None => (src, None),
-        };
+        // everything after the first "#" is the fragment (even if it
+        // contains a "?" or another "#"), and the query sits between the
+        // path and the fragment
+        let (rest, fragment) = split_off(src, '#');
+        let (path, query) = split_off(rest, '?');
 
         // as a sanity check we use the http crate's PathAndQuery type to make
-        // sure the path is decoded correctly
-        if let Ok(path_and_query) = path.parse::<PathAndQuery>() {
+        // sure the path is decoded correctly. Spaces aren't allowed in a URL,
+        // but are common in file names (e.g. "<./my file.md>" in markdown)
+        let has_spaces = path.contains(' ');
+        let encoded = if has_spaces {
+            Cow::Owned(path.replace(' ', "%20"))
+        } else {
+            Cow::Borrowed(path)
+        };
+
+        if let Ok(path_and_query) = encoded.parse::<PathAndQuery>() {
+            let path = if has_spaces {
+                PathBuf::from(path_and_query.path().replace("%20", " "))
+            } else {
+                PathBuf::from(path_and_query.path())
+            };
+
             return Some(Category::FileSystem {
-                path: PathBuf::from(path_and_query.path()),
+                path,
+                query,
                 fragment,
             });
         }
@@ -140,30 +281,195 @@ impl Category {
     }
 }
 
+/// Split `src` at the first `separator`, treating an empty remainder (e.g.
+/// the trailing `?` in `page.md?`) as missing.
+fn split_off(src: &str, separator: char) -> (&str, Option<String>) {
+    match src.split_once(separator) {
+        Some((head, "")) => (head, None),
+        Some((head, tail)) => (head, Some(String::from(tail))),
+        None => (src, None),
+    }
+}
+
+/// Strip a URL scheme from the start of `src`, ignoring case.
+fn strip_scheme<'a>(src: &'a str, scheme: &str) -> Option<&'a str> {
+    let prefix = src.get(..scheme.len())?;
+
+    if prefix.eq_ignore_ascii_case(scheme) {
+        Some(&src[scheme.len()..])
+    } else {
+        None
+    }
+}
+
+/// Does this look like `//example.com/page`?
+fn is_protocol_relative(src: &str) -> bool {
+    match src.strip_prefix("//") {
+        Some(rest) => {
+            !rest.starts_with('/')
+                && Url::parse(&format!("https://{}", rest))
+                    .is_ok_and(|url| url.has_host())
+        },
+        None => false,
+    }
+}
+
 /// A link to some other resource.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Links normally belong to a document in [`codespan::Files`], but tools
+/// with their own source maps can use any other type (e.g. a path or an
+/// integer) to identify where a link came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
-pub struct Link {
+pub struct Link<F = FileId> {
     /// The link itself.
     pub href: String,
     /// Where the [`Link`] lies in its source text.
     pub span: Span,
     /// Which document does this [`Link`] belong to?
-    pub file: FileId,
+    pub file: F,
+    /// The link's title (e.g. `[text](href "title")` in markdown), if it has
+    /// one.
+    #[cfg_attr(
+        feature = "serde-1",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub title: Option<String>,
+    /// The label for a reference-style link (e.g. `docs` in
+    /// `[text][docs]`).
+    ///
+    /// For these links the [`Link::span`] points to the `href` in the link
+    /// definition (e.g. `[docs]: href`) rather than where it was used.
+    #[cfg_attr(
+        feature = "serde-1",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub reference_label: Option<String>,
+    /// How the link was written, if the scanner knows.
+    #[cfg_attr(
+        feature = "serde-1",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub kind: Option<LinkKind>,
+    /// The text around the link, if the scanner captured it (see
+    /// [`scanners::context()`]).
+    #[cfg_attr(
+        feature = "serde-1",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub context: Option<LinkContext>,
 }
 
-impl Link {
+impl<F> Link<F> {
     /// Create a new [`Link`].
-    pub fn new<S: Into<String>>(href: S, span: Span, file: FileId) -> Self {
+    pub fn new<S: Into<String>>(href: S, span: Span, file: F) -> Self {
         Link {
             href: href.into(),
             span,
             file,
+            title: None,
+            reference_label: None,
+            kind: None,
+            context: None,
+        }
+    }
+
+    /// Set the [`Link::title`].
+    pub fn set_title<S: Into<String>>(self, title: S) -> Self {
+        Link {
+            title: Some(title.into()),
+            ..self
+        }
+    }
+
+    /// Set the [`Link::reference_label`].
+    pub fn set_reference_label<S: Into<String>>(self, label: S) -> Self {
+        Link {
+            reference_label: Some(label.into()),
+            ..self
+        }
+    }
+
+    /// Set the [`Link::kind`].
+    pub fn set_kind(self, kind: LinkKind) -> Self {
+        Link {
+            kind: Some(kind),
+            ..self
+        }
+    }
+
+    /// Set the [`Link::context`].
+    pub fn set_context(self, context: LinkContext) -> Self {
+        Link {
+            context: Some(context),
+            ..self
         }
     }
 
-    fn category(&self) -> Option<Category> { Category::categorise(&self.href) }
+    /// Use [`Category::categorise()`] to find out what kind of link this is.
+    pub fn category(&self) -> Option<Category> {
+        Category::categorise(&self.href)
+    }
+
+    /// Swap out the [`Link::file`], keeping everything else.
+    pub fn map_file<G, M>(self, map: M) -> Link<G>
+    where
+        M: FnOnce(F) -> G,
+    {
+        Link {
+            href: self.href,
+            span: self.span,
+            file: map(self.file),
+            title: self.title,
+            reference_label: self.reference_label,
+            kind: self.kind,
+            context: self.context,
+        }
+    }
+}
+
+impl Link {
+    /// Find out where this [`Link`] is in its document.
+    pub fn location<S>(&self, files: &Files<S>) -> Option<Location>
+    where
+        S: AsRef<str>,
+    {
+        Location::new(self.file, self.span, files)
+    }
+}
+
+/// The different ways a [`Link`] can be written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum LinkKind {
+    /// A markdown link with its destination inline (e.g. `[text](href)`).
+    Inline,
+    /// A reference-style markdown link (e.g. `[text][label]` or `[label]`).
+    Reference,
+    /// A markdown autolink (e.g. `<https://example.com/>`).
+    Autolink,
+    /// A markdown image (e.g. `![alt](src)`).
+    Image,
+    /// An attribute on a HTML element (e.g. `<a href="...">`).
+    Html,
+    /// A URL found in plain text.
+    PlainText,
+    /// A clickable element or included file in a diagram's source (e.g.
+    /// Graphviz or PlantUML).
+    Diagram,
+}
+
+/// The text surrounding a [`Link`], so reports can show where it is without
+/// going back to the source.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkContext {
+    /// The line containing the link, shortened if it is too long.
+    pub snippet: String,
+    /// The heading the link appears under, if there is one.
+    pub heading: Option<String>,
 }
 
 #[cfg(test)]
@@ -183,6 +489,7 @@ mod tests {
                 "README.md",
                 Some(Category::FileSystem {
                     path: PathBuf::from("README.md"),
+                    query: None,
                     fragment: None,
                 }),
             ),
@@ -190,6 +497,7 @@ mod tests {
                 "./README.md",
                 Some(Category::FileSystem {
                     path: PathBuf::from("./README.md"),
+                    query: None,
                     fragment: None,
                 }),
             ),
@@ -197,13 +505,93 @@ mod tests {
                 "./README.md#license",
                 Some(Category::FileSystem {
                     path: PathBuf::from("./README.md"),
+                    query: None,
                     fragment: Some(String::from("license")),
                 }),
             ),
+            (
+                "page.md?highlight=x#section",
+                Some(Category::FileSystem {
+                    path: PathBuf::from("page.md"),
+                    query: Some(String::from("highlight=x")),
+                    fragment: Some(String::from("section")),
+                }),
+            ),
+            (
+                "page.md#a?b#c",
+                Some(Category::FileSystem {
+                    path: PathBuf::from("page.md"),
+                    query: None,
+                    fragment: Some(String::from("a?b#c")),
+                }),
+            ),
+            (
+                "page.md?#",
+                Some(Category::FileSystem {
+                    path: PathBuf::from("page.md"),
+                    query: None,
+                    fragment: None,
+                }),
+            ),
+            (
+                "file.md?x#y",
+                Some(Category::FileSystem {
+                    path: PathBuf::from("file.md"),
+                    query: Some(String::from("x")),
+                    fragment: Some(String::from("y")),
+                }),
+            ),
+            (
+                "file.md#",
+                Some(Category::FileSystem {
+                    path: PathBuf::from("file.md"),
+                    query: None,
+                    fragment: None,
+                }),
+            ),
+            (
+                "why%3F.md?x#y",
+                Some(Category::FileSystem {
+                    path: PathBuf::from("why%3F.md"),
+                    query: Some(String::from("x")),
+                    fragment: Some(String::from("y")),
+                }),
+            ),
             (
                 "mailto:michael@example.com",
                 Some(Category::MailTo(String::from("michael@example.com"))),
             ),
+            (
+                "//example.com/page",
+                Some(Category::ProtocolRelative(String::from(
+                    "//example.com/page",
+                ))),
+            ),
+            (
+                "./my file.md#intro",
+                Some(Category::FileSystem {
+                    path: PathBuf::from("./my file.md"),
+                    query: None,
+                    fragment: Some(String::from("intro")),
+                }),
+            ),
+            ("", Some(Category::Empty)),
+            (" \t", Some(Category::Empty)),
+            ("#", Some(Category::Empty)),
+            (
+                "TEL:+1-201-555-0123",
+                Some(Category::Tel(String::from("+1-201-555-0123"))),
+            ),
+            (
+                "javascript:void(0)",
+                Some(Category::JavaScript(String::from("void(0)"))),
+            ),
+            (
+                "urn:isbn:0451450523",
+                Some(Category::Opaque(
+                    Url::parse("urn:isbn:0451450523").unwrap(),
+                )),
+            ),
         ];
 
         for (src, should_be) in inputs {
@@ -0,0 +1,727 @@
+//! An opt-in pass which flags links whose text is unhelpful for readers,
+//! particularly those using screen readers.
+//!
+//! Lints don't make a link broken, so they are reported separately in
+//! [`Outcomes::lints`]. Other optional checks (e.g. [`crate::canonical`])
+//! report their findings the same way.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use codespan::Files;
+//! use linkcheck::{lints::LintKind, Link};
+//!
+//! let mut files = Files::new();
+//! let src = "For more, [click here](https://example.com/).";
+//! let file = files.add("README.md", src);
+//! let links: Vec<_> = linkcheck::scanners::markdown(src)
+//!     .map(|(href, span)| Link::new(href, span, file))
+//!     .collect();
+//!
+//! let outcomes = linkcheck::lints::lint(&links, &files);
+//!
+//! assert_eq!(
+//!     outcomes.lints[0].kind,
+//!     LintKind::VagueText(String::from("click here"))
+//! );
+//! ```
+//!
+//! [`Outcomes::lints`]: crate::validation::Outcomes::lints
+
+use crate::{
+    scanners::html::{decode_entities, tags},
+    validation::{self, Outcomes},
+    Category, Link,
+};
+use codespan::{FileId, Files, Span};
+use pulldown_cmark::{BrokenLink, CowStr, Event, Options, Parser, Tag};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display, Formatter},
+    path::{Component, Path, PathBuf},
+};
+use url::Url;
+
+/// Link text which doesn't say anything about where the link goes.
+const VAGUE_TEXT: &[&str] = &[
+    "click here",
+    "click",
+    "here",
+    "link",
+    "this link",
+    "this",
+    "this page",
+    "more",
+    "read more",
+    "learn more",
+];
+
+/// HTML elements which load a sub-resource, and the attribute with its URL.
+const ASSET_ATTRIBUTES: &[(&str, &str)] = &[
+    ("img", "src"),
+    ("script", "src"),
+    ("iframe", "src"),
+    ("audio", "src"),
+    ("video", "src"),
+    ("video", "poster"),
+    ("source", "src"),
+    ("track", "src"),
+    ("embed", "src"),
+    ("object", "data"),
+];
+
+/// The `rel` values for a `<link>` which loads a sub-resource.
+const ASSET_RELS: &[&str] =
+    &["stylesheet", "icon", "preload", "modulepreload", "manifest"];
+
+/// A problem with a link which doesn't necessarily mean it is broken.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lint<F = FileId> {
+    /// The offending [`Link`].
+    pub link: Link<F>,
+    /// What is wrong with it.
+    pub kind: LintKind,
+}
+
+/// The different things a [`Lint`] can flag.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum LintKind {
+    /// The link (or image alt text) is empty.
+    EmptyText,
+    /// The URL itself is used as the link text.
+    UrlAsText,
+    /// The link goes to the same place as the link right before it.
+    DuplicateAdjacent,
+    /// The text doesn't describe where the link goes (e.g. "click here").
+    VagueText(String),
+    /// A page has more than one `rel="canonical"` link.
+    MultipleCanonicals,
+    /// The canonical page says a different page (the URL) is canonical.
+    CanonicalChain(String),
+    /// The same `hreflang` language (e.g. `fr`) points to different pages.
+    ConflictingHreflang(String),
+    /// The alternate page doesn't have a `hreflang` link back to this one.
+    MissingReturnLink,
+    /// A page served over HTTPS loads an asset (e.g. an image or script) over
+    /// plain HTTP.
+    MixedContent,
+    /// The anchor (e.g. a heading's slug) is used more than once in the same
+    /// document (see [`crate::anchors::AnchorDb::lint_duplicates()`]).
+    DuplicateAnchor(String),
+    /// A HTTPS link ends up being redirected to the URL over plain HTTP.
+    ProtocolDowngrade(String),
+    /// The link goes to the page it is on.
+    SelfReference,
+    /// The URL contains a username or password, which anyone reading the
+    /// documentation can see.
+    EmbeddedCredentials,
+    /// An entry in the table of contents goes to a heading which doesn't
+    /// exist (see [`crate::toc::check()`]).
+    StaleTocEntry,
+    /// The heading (its title) isn't in the table of contents.
+    MissingTocEntry(String),
+    /// The link is an absolute path, which only works on machines with the
+    /// same directory layout. The relative equivalent is attached.
+    AbsolutePath(String),
+}
+
+impl Display for LintKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LintKind::EmptyText => write!(f, "The link text is empty"),
+            LintKind::UrlAsText => write!(f, "The URL is used as link text"),
+            LintKind::DuplicateAdjacent => {
+                write!(f, "The previous link goes to the same place")
+            },
+            LintKind::VagueText(text) => {
+                write!(f, "\"{}\" doesn't describe where the link goes", text)
+            },
+            LintKind::MultipleCanonicals => {
+                write!(f, "The page has more than one canonical URL")
+            },
+            LintKind::CanonicalChain(url) => {
+                write!(f, "The canonical page says \"{}\" is canonical", url)
+            },
+            LintKind::ConflictingHreflang(language) => write!(
+                f,
+                "There are multiple alternates for the \"{}\" language",
+                language
+            ),
+            LintKind::MissingReturnLink => {
+                write!(f, "The alternate page doesn't link back to this one")
+            },
+            LintKind::MixedContent => {
+                write!(f, "The asset is loaded over HTTP on a HTTPS page")
+            },
+            LintKind::DuplicateAnchor(anchor) => {
+                write!(f, "The \"#{}\" anchor is used more than once", anchor)
+            },
+            LintKind::ProtocolDowngrade(url) => {
+                write!(f, "The HTTPS link redirects to \"{}\"", url)
+            },
+            LintKind::SelfReference => {
+                write!(f, "The link goes to the page it is on")
+            },
+            LintKind::EmbeddedCredentials => {
+                write!(f, "The URL contains a username or password")
+            },
+            LintKind::StaleTocEntry => {
+                write!(f, "The table of contents entry has no heading")
+            },
+            LintKind::MissingTocEntry(title) => write!(
+                f,
+                "The \"{}\" heading isn't in the table of contents",
+                title
+            ),
+            LintKind::AbsolutePath(relative) => {
+                write!(f, "The path is absolute, use \"{}\" instead", relative)
+            },
+        }
+    }
+}
+
+/// Check the text of each [`Link`], returning [`Outcomes`] which only
+/// contain [`Outcomes::lints`] so they can be merged with the results from
+/// validation.
+///
+/// Link text can only be found for markdown links, which are matched up with
+/// the whole link (e.g. `[text](href)`) using
+/// [`crate::scanners::markdown_links()`]. Duplicate links are checked for
+/// every kind of link.
+pub fn lint<S: AsRef<str>>(links: &[Link], files: &Files<S>) -> Outcomes {
+    let mut by_file: BTreeMap<FileId, Vec<&Link>> = BTreeMap::new();
+    for link in links {
+        by_file.entry(link.file).or_default().push(link);
+    }
+
+    let mut outcomes = Outcomes::empty();
+
+    for (file, links) in by_file {
+        let src = files.source(file).as_ref();
+        let elements: HashMap<Span, Span> =
+            crate::scanners::markdown_links(src, None)
+                .map(|link| (link.span, link.element))
+                .collect();
+        let mut links: Vec<_> = links
+            .into_iter()
+            .map(|link| {
+                let element = elements.get(&link.span).copied();
+                (link, element.unwrap_or(link.span))
+            })
+            .collect();
+        links.sort_by_key(|(_, element)| (element.start(), element.end()));
+
+        for (i, &(link, element)) in links.iter().enumerate() {
+            let mut push = |kind| {
+                outcomes.lints.push(Lint {
+                    link: (*link).clone(),
+                    kind,
+                })
+            };
+
+            if let Some(text) = link_text(src, element) {
+                let trimmed = text.trim();
+
+                if trimmed.is_empty() {
+                    push(LintKind::EmptyText);
+                } else if looks_like_a_url(trimmed, &link.href) {
+                    push(LintKind::UrlAsText);
+                } else if is_vague(trimmed) {
+                    push(LintKind::VagueText(trimmed.to_string()));
+                }
+            }
+
+            if i > 0 && is_duplicate(src, links[i - 1], (link, element)) {
+                push(LintKind::DuplicateAdjacent);
+            }
+        }
+    }
+
+    outcomes
+}
+
+/// Flag images, scripts, stylesheets, and other assets loaded over plain HTTP
+/// by a HTML page which is served over HTTPS.
+///
+/// Browsers block (or warn about) these "mixed content" assets, so they can
+/// be broken for readers even though they resolve successfully. Nothing is
+/// flagged when `page` isn't a HTTPS URL. The returned [`Outcomes`] only
+/// contain [`Outcomes::lints`].
+///
+/// # Examples
+///
+/// ```rust
+/// use codespan::Files;
+/// use linkcheck::lints::LintKind;
+///
+/// let src = r#"<img src="http://example.com/logo.png">"#;
+/// let file = Files::new().add("index.html", src);
+/// let page = "https://example.com/".parse().unwrap();
+///
+/// let outcomes = linkcheck::lints::mixed_content(&page, src, file);
+///
+/// assert_eq!(outcomes.lints[0].kind, LintKind::MixedContent);
+/// ```
+pub fn mixed_content(page: &Url, src: &str, file: FileId) -> Outcomes {
+    let mut outcomes = Outcomes::empty();
+
+    if page.scheme() != "https" {
+        return outcomes;
+    }
+
+    for tag in tags(src) {
+        let is_asset_link = tag.name == "link"
+            && tag.attributes.iter().any(|attr| {
+                attr.name == "rel"
+                    && attr.value_in(src).is_some_and(|rel| {
+                        rel.split_ascii_whitespace().any(|rel| {
+                            ASSET_RELS.contains(&rel.to_lowercase().as_str())
+                        })
+                    })
+            });
+
+        for attr in &tag.attributes {
+            let is_asset = (is_asset_link && attr.name == "href")
+                || ASSET_ATTRIBUTES.contains(&(&tag.name, &attr.name));
+            let (value, span) = match (attr.value_in(src), attr.value) {
+                (Some(value), Some(span)) if is_asset => (value, span),
+                _ => continue,
+            };
+            let href = decode_entities(value);
+
+            if href
+                .trim_start()
+                .to_ascii_lowercase()
+                .starts_with("http://")
+            {
+                outcomes.lints.push(Lint {
+                    link: Link::new(href, span, file),
+                    kind: LintKind::MixedContent,
+                });
+            }
+        }
+    }
+
+    outcomes
+}
+
+/// Flag links which go to the page or file they appear on, which usually
+/// means the template generating the documentation has a bug.
+///
+/// Links with a fragment (e.g. `#installation`) are assumed to be
+/// intentional. Each file's name in `files` is used as its location, so
+/// pages from the [`crate::crawl::Crawler`] (named by their URL) are handled
+/// too. The returned [`Outcomes`] only contain [`Outcomes::lints`].
+///
+/// # Examples
+///
+/// ```rust
+/// use codespan::Files;
+/// use linkcheck::{lints::LintKind, Link};
+///
+/// let mut files = Files::new();
+/// let file = files.add("docs/guide.md", "");
+/// let links = vec![
+///     Link::new("../docs/guide.md", Default::default(), file),
+///     Link::new("guide.md#setup", Default::default(), file),
+/// ];
+///
+/// let outcomes = linkcheck::lints::self_references(&links, &files);
+///
+/// assert_eq!(outcomes.lints.len(), 1);
+/// assert_eq!(outcomes.lints[0].kind, LintKind::SelfReference);
+/// ```
+pub fn self_references<S>(links: &[Link], files: &Files<S>) -> Outcomes
+where
+    S: AsRef<str>,
+{
+    let mut outcomes = Outcomes::empty();
+
+    for link in links {
+        let name = files.name(link.file).to_string_lossy();
+
+        let is_self_reference = match Url::parse(&name) {
+            Ok(page) if !page.cannot_be_a_base() => {
+                page_links_to_itself(&page, &link.href)
+            },
+            _ => file_links_to_itself(Path::new(name.as_ref()), &link.href),
+        };
+
+        if is_self_reference {
+            outcomes.lints.push(Lint {
+                link: link.clone(),
+                kind: LintKind::SelfReference,
+            });
+        }
+    }
+
+    outcomes
+}
+
+/// Flag links to local files which use an absolute path (e.g.
+/// `/docs/guide.md`), suggesting the relative path to use instead.
+///
+/// These are flagged even when they resolve, because they depend on where
+/// the [`validation::Options::root_directory()`] (passed in as `root`) is,
+/// or on the layout of the author's machine. When there is no root, the
+/// link is treated as a path on disk. The returned [`Outcomes`] only
+/// contain [`Outcomes::lints`].
+///
+/// # Examples
+///
+/// ```rust
+/// use codespan::Files;
+/// use linkcheck::{lints::LintKind, Link};
+/// use std::path::Path;
+///
+/// let mut files = Files::new();
+/// let file = files.add("book/src/guide/setup.md", "");
+/// let links = vec![
+///     Link::new("/reference/cli.md#flags", Default::default(), file),
+///     Link::new("../reference/cli.md", Default::default(), file),
+/// ];
+///
+/// let root = Path::new("book/src");
+/// let outcomes = linkcheck::lints::absolute_paths(&links, &files, Some(root));
+///
+/// assert_eq!(outcomes.lints.len(), 1);
+/// assert_eq!(
+///     outcomes.lints[0].kind,
+///     LintKind::AbsolutePath(String::from("../reference/cli.md#flags"))
+/// );
+/// ```
+pub fn absolute_paths<S>(
+    links: &[Link],
+    files: &Files<S>,
+    root: Option<&Path>,
+) -> Outcomes
+where
+    S: AsRef<str>,
+{
+    let mut outcomes = Outcomes::empty();
+
+    for link in links {
+        let (path, query, fragment) = match link.category() {
+            Some(Category::FileSystem {
+                path,
+                query,
+                fragment,
+            }) if path.has_root() => (path, query, fragment),
+            _ => continue,
+        };
+
+        let name = PathBuf::from(files.name(link.file));
+        let directory = name.parent().unwrap_or_else(|| Path::new(""));
+        let target = match root {
+            Some(root) => root.join(path.strip_prefix("/").unwrap_or(&path)),
+            None => path,
+        };
+
+        let mut relative = relative_path(directory, &target);
+        if let Some(query) = query {
+            relative.push('?');
+            relative.push_str(&query);
+        }
+        if let Some(fragment) = fragment {
+            relative.push('#');
+            relative.push_str(&fragment);
+        }
+
+        outcomes.lints.push(Lint {
+            link: link.clone(),
+            kind: LintKind::AbsolutePath(relative),
+        });
+    }
+
+    outcomes
+}
+
+/// Lexically work out the path to `target` from `directory`, using `/` as
+/// the separator.
+fn relative_path(directory: &Path, target: &Path) -> String {
+    let both_absolute = directory.is_absolute() && target.is_absolute();
+    let both_relative = directory.is_relative() && target.is_relative();
+    let (directory, target) = if both_absolute || both_relative {
+        (clean(directory), clean(target))
+    } else {
+        // one of them is relative to the current directory
+        let absolute = |path: &Path| {
+            std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+        };
+        (clean(&absolute(directory)), clean(&absolute(target)))
+    };
+
+    let from: Vec<_> = directory.components().collect();
+    let to: Vec<_> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let parents = std::iter::repeat_n(String::from(".."), from.len() - common);
+    let rest = to[common..]
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned());
+    let segments: Vec<_> = parents.chain(rest).collect();
+
+    if segments.is_empty() {
+        String::from(".")
+    } else {
+        segments.join("/")
+    }
+}
+
+fn page_links_to_itself(page: &Url, href: &str) -> bool {
+    match page.join(href) {
+        Ok(target) if target.fragment().is_none() => {
+            let mut page = page.clone();
+            page.set_fragment(None);
+            target == page
+        },
+        _ => false,
+    }
+}
+
+fn file_links_to_itself(file: &Path, href: &str) -> bool {
+    let path = match Category::categorise(href) {
+        Some(Category::FileSystem {
+            path,
+            fragment: None,
+            ..
+        }) if !path.has_root() => path,
+        _ => return false,
+    };
+    let directory = file.parent().unwrap_or_else(|| Path::new(""));
+    let target = clean(&directory.join(path));
+    let file = clean(file);
+
+    // a link to the directory goes to its index page
+    target == file
+        || (target == clean(directory)
+            && file
+                .file_name()
+                .is_some_and(|name| name == validation::Options::DEFAULT_FILE))
+}
+
+/// Lexically remove any `.` and `..` components from a path.
+fn clean(path: &Path) -> PathBuf {
+    let mut cleaned = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir if cleaned.file_name().is_some() => {
+                cleaned.pop();
+            },
+            other => cleaned.push(other),
+        }
+    }
+
+    cleaned
+}
+
+/// Find the text for a markdown link by re-parsing just that link.
+fn link_text(src: &str, element: Span) -> Option<String> {
+    let span = element.start().to_usize()..element.end().to_usize();
+    let snippet = src.get(span)?;
+
+    if !snippet.starts_with(['[', '!', '<']) {
+        // probably HTML or plain text, where the span only covers the URL
+        return None;
+    }
+
+    // pretend reference-style links are defined
+    let mut callback =
+        |_: BrokenLink<'_>| Some((CowStr::from(""), CowStr::from("")));
+    let mut events = Parser::new_with_broken_link_callback(
+        snippet,
+        Options::empty(),
+        Some(&mut callback),
+    )
+    .skip_while(|event| matches!(event, Event::Start(Tag::Paragraph)));
+
+    match events.next()? {
+        Event::Start(Tag::Link(..)) | Event::Start(Tag::Image(..)) => {},
+        _ => return None,
+    }
+
+    let mut text = String::new();
+    let mut depth = 1;
+
+    for event in events {
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(text);
+                }
+            },
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            _ => {},
+        }
+    }
+
+    None
+}
+
+fn looks_like_a_url(text: &str, href: &str) -> bool {
+    text == href
+        || text.starts_with("http://")
+        || text.starts_with("https://")
+        || text.starts_with("www.")
+}
+
+fn is_vague(text: &str) -> bool {
+    let normalised = text
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase();
+
+    VAGUE_TEXT.contains(&normalised.as_str())
+}
+
+/// Two links are duplicates when they go to the same place and there is
+/// only whitespace between them.
+fn is_duplicate(
+    src: &str,
+    (previous, previous_element): (&Link, Span),
+    (link, element): (&Link, Span),
+) -> bool {
+    let between =
+        src.get(previous_element.end().to_usize()..element.start().to_usize());
+
+    previous.href == link.href
+        && between.is_some_and(|text| text.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_unhelpful_link_text() {
+        let src = r#"
+[](a.md) and ![](b.png) and <https://example.com/> and
+[https://x.com/](https://x.com/) and [Read More...](c.md) but
+[the guide](d.md) [the guide again](d.md) and [`Options`][opts] are fine.
+<a href="e.html">here</a>
+"#;
+        let mut files = Files::new();
+        let file = files.add("index.md", src);
+        let mut links: Vec<_> = crate::scanners::markdown(src)
+            .map(|(href, span)| Link::new(href, span, file))
+            .collect();
+        links.extend(
+            crate::scanners::html(src)
+                .map(|(href, span)| Link::new(href, span, file)),
+        );
+
+        let got: Vec<_> = lint(&links, &files)
+            .lints
+            .into_iter()
+            .map(|lint| (lint.link.href, lint.kind))
+            .collect();
+
+        let should_be = vec![
+            (String::from("a.md"), LintKind::EmptyText),
+            (String::from("b.png"), LintKind::EmptyText),
+            (String::from("https://example.com/"), LintKind::UrlAsText),
+            (String::from("https://x.com/"), LintKind::UrlAsText),
+            (
+                String::from("c.md"),
+                LintKind::VagueText(String::from("Read More...")),
+            ),
+            (String::from("d.md"), LintKind::DuplicateAdjacent),
+        ];
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn flag_insecure_assets_on_secure_pages() {
+        let src = r#"
+<link rel="stylesheet" href="http://cdn.example.com/style.css">
+<link rel="alternate" href="http://example.com/feed.xml">
+<script src="HTTP://cdn.example.com/app.js"></script>
+<img src="https://example.com/ok.png"> <img src="//example.com/ok.png">
+<a href="http://example.com/">Plain links are fine</a>
+<video poster="http://example.com/poster.jpg"></video>
+"#;
+        let file = Files::new().add("index.html", src);
+        let page = Url::parse("https://example.com/").unwrap();
+
+        let got: Vec<_> = mixed_content(&page, src, file)
+            .lints
+            .into_iter()
+            .map(|lint| lint.link.href)
+            .collect();
+
+        assert_eq!(
+            got,
+            vec![
+                "http://cdn.example.com/style.css",
+                "HTTP://cdn.example.com/app.js",
+                "http://example.com/poster.jpg",
+            ]
+        );
+        let insecure_page = Url::parse("http://example.com/").unwrap();
+        let outcomes = mixed_content(&insecure_page, src, file);
+        assert!(outcomes.lints.is_empty());
+    }
+
+    #[test]
+    fn flag_links_to_the_current_page() {
+        let mut files = Files::new();
+        let index = files.add("book/index.html", "");
+        let page = files.add("https://example.com/docs/page.html", "");
+        let link = |href: &str, file| Link::new(href, Span::default(), file);
+        let links = vec![
+            link("./", index),
+            link("index.html#top", index),
+            link("../book/other.html", index),
+            link("page.html", page),
+            link("/docs/page.html?tab=2", page),
+            link("#intro", page),
+        ];
+
+        let got: Vec<_> = self_references(&links, &files)
+            .lints
+            .into_iter()
+            .map(|lint| lint.link.href)
+            .collect();
+
+        assert_eq!(got, vec!["./", "page.html"]);
+    }
+
+    #[test]
+    fn suggest_relative_paths_for_absolute_links() {
+        let mut files = Files::new();
+        let file = files.add("docs/guide/intro.md", "");
+        let link = |href: &str| Link::new(href, Span::default(), file);
+        let links = vec![
+            link("/guide/setup.md?tab=2#linux"),
+            link("/index.md"),
+            link("/guide/"),
+            link("setup.md"),
+            link("https://example.com/"),
+        ];
+
+        let root = Path::new("docs");
+
+        let got: Vec<_> = absolute_paths(&links, &files, Some(root))
+            .lints
+            .into_iter()
+            .map(|lint| lint.kind)
+            .collect();
+
+        assert_eq!(
+            got,
+            vec![
+                LintKind::AbsolutePath(String::from("setup.md?tab=2#linux")),
+                LintKind::AbsolutePath(String::from("../index.md")),
+                LintKind::AbsolutePath(String::from(".")),
+            ]
+        );
+        let without_root = absolute_paths(&links[1..2], &files, None);
+        assert_eq!(without_root.lints.len(), 1);
+    }
+}
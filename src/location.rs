@@ -0,0 +1,64 @@
+use codespan::{FileId, Files, Span};
+use std::{
+    fmt::{self, Display, Formatter},
+    path::PathBuf,
+};
+
+/// A human-friendly description of where something (typically a
+/// [`crate::Link`]) can be found.
+///
+/// # Examples
+///
+/// ```rust
+/// use codespan::{Files, Span};
+/// use linkcheck::Location;
+///
+/// let mut files = Files::new();
+/// let file_id = files.add("index.md", "# Title\n\nSee [here](./other.md).");
+/// let span = Span::new(13, 31);
+///
+/// let location = Location::new(file_id, span, &files).unwrap();
+///
+/// assert_eq!(location.line, 3);
+/// assert_eq!(location.column, 5);
+/// assert_eq!(location.to_string(), "index.md:3:5");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    /// The name of the source file, as it was added to [`Files`].
+    pub path: PathBuf,
+    /// The byte span.
+    pub span: Span,
+    /// The (1-based) line number the span starts on.
+    pub line: usize,
+    /// The (1-based) column the span starts at, counted in `char`s.
+    pub column: usize,
+}
+
+impl Location {
+    /// Look up the [`Location`] of a [`Span`] in one of the documents in a
+    /// set of [`Files`].
+    ///
+    /// This will return `None` if the [`Span`] doesn't lie within the file.
+    pub fn new<S: AsRef<str>>(
+        file: FileId,
+        span: Span,
+        files: &Files<S>,
+    ) -> Option<Self> {
+        let start = files.location(file, span.start()).ok()?;
+
+        Some(Location {
+            path: PathBuf::from(files.name(file)),
+            span,
+            line: start.line.to_usize() + 1,
+            column: start.column.to_usize() + 1,
+        })
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
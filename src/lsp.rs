@@ -0,0 +1,563 @@
+//! Helpers for language servers which want to report broken links as
+//! diagnostics.
+//!
+//! Language servers work with [`Position`]s (a zero-based line number and a
+//! column measured in UTF-16 code units) instead of byte offsets, and tend to
+//! re-check a single document at a time as the user types. The [`Workspace`]
+//! keeps track of every open document so that when one changes, you know
+//! which documents need their diagnostics refreshed.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use linkcheck::{lsp::Workspace, BasicContext};
+//!
+//! # #[tokio::main] async fn main() {
+//! let ctx = BasicContext::default();
+//! let mut workspace = Workspace::new();
+//!
+//! // the user opened (or edited) a document
+//! let stale = workspace.set_document("/book/intro.md", "[next](next.md)");
+//!
+//! for file in stale {
+//!     workspace.validate(file, &ctx).await;
+//!     let outcomes = workspace.outcomes(file).unwrap();
+//!     let src = workspace.files().source(file);
+//!
+//!     for invalid in &outcomes.invalid {
+//!         let range = linkcheck::lsp::span_to_range(src, invalid.link.span);
+//!         println!("{:?}: {}", range, invalid.reason);
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::{
+    site::{self, Document, Site},
+    validation::{Context, Outcomes},
+    Category, Link,
+};
+use codespan::{FileId, Files, Span};
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+/// A location in a text document, as used by the Language Server Protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// The zero-based line number.
+    pub line: u32,
+    /// The zero-based offset into the line, in UTF-16 code units.
+    pub character: u32,
+}
+
+/// The text between two [`Position`]s.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Range {
+    /// Where the range starts.
+    pub start: Position,
+    /// Where the range ends (exclusive).
+    pub end: Position,
+}
+
+/// Convert a byte offset into a [`Position`].
+///
+/// Offsets past the end of `src` are clamped to the end of the text, and
+/// offsets in the middle of a character are moved back to its start.
+pub fn position(src: &str, offset: usize) -> Position {
+    let offset = char_boundary(src, offset);
+    let before = &src[..offset];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// Convert a [`Position`] back into a byte offset.
+///
+/// As per the Language Server Protocol, a `character` past the end of the
+/// line is treated as the end of the line. Returns `None` if the line doesn't
+/// exist.
+pub fn offset(src: &str, position: Position) -> Option<usize> {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        line_start += src[line_start..].find('\n')? + 1;
+    }
+
+    let line = &src[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    let mut remaining = position.character as usize;
+    for (i, c) in line.char_indices() {
+        if remaining < c.len_utf16() {
+            return Some(line_start + i);
+        }
+        remaining -= c.len_utf16();
+    }
+
+    Some(line_start + line.len())
+}
+
+/// Convert a [`Span`] into a [`Range`].
+pub fn span_to_range(src: &str, span: Span) -> Range {
+    Range {
+        start: position(src, span.start().to_usize()),
+        end: position(src, span.end().to_usize()),
+    }
+}
+
+/// Convert a [`Range`] into a [`Span`], returning `None` if either end is
+/// on a line that doesn't exist.
+pub fn range_to_span(src: &str, range: Range) -> Option<Span> {
+    let start = offset(src, range.start)?;
+    let end = offset(src, range.end)?;
+
+    Some(Span::new(start as u32, end as u32))
+}
+
+/// Convert a byte offset into the number of UTF-16 code units before it.
+///
+/// Offsets are clamped and moved to a character boundary the same way as
+/// [`position()`].
+pub fn utf16_offset(src: &str, offset: usize) -> usize {
+    let offset = char_boundary(src, offset);
+    src[..offset].encode_utf16().count()
+}
+
+/// Convert an offset in UTF-16 code units back into a byte offset, returning
+/// `None` if it is past the end of `src`.
+///
+/// An offset in the middle of a surrogate pair is moved back to the start of
+/// the character.
+pub fn byte_offset(src: &str, utf16_offset: usize) -> Option<usize> {
+    let mut remaining = utf16_offset;
+
+    for (i, c) in src.char_indices() {
+        if remaining < c.len_utf16() {
+            return Some(i);
+        }
+        remaining -= c.len_utf16();
+    }
+
+    if remaining == 0 {
+        Some(src.len())
+    } else {
+        None
+    }
+}
+
+/// A pre-computed table of where each line starts, for when you need to
+/// convert lots of offsets in the same document.
+///
+/// This gives the same answers as [`position()`] and [`offset()`] without
+/// re-scanning the text from the start every time.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::lsp::{LineIndex, Position};
+///
+/// let src = "# 😀\n[link](./missing.md)\n";
+/// let index = LineIndex::new(src);
+///
+/// let position = index.position(src.find("./missing").unwrap());
+/// assert_eq!(position, Position { line: 1, character: 7 });
+/// assert_eq!(index.offset(Position { line: 0, character: 4 }), Some(6));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineIndex<'a> {
+    src: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Index the lines in some text.
+    pub fn new(src: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        LineIndex { src, line_starts }
+    }
+
+    /// The text being indexed.
+    pub fn source(&self) -> &'a str { self.src }
+
+    /// The number of lines in the text.
+    pub fn line_count(&self) -> usize { self.line_starts.len() }
+
+    /// Convert a byte offset into a [`Position`] (see [`position()`]).
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = char_boundary(self.src, offset);
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+
+        Position {
+            line: line as u32,
+            character: self.src[line_start..offset].encode_utf16().count()
+                as u32,
+        }
+    }
+
+    /// Convert a [`Position`] back into a byte offset (see [`offset()`]).
+    pub fn offset(&self, position: Position) -> Option<usize> {
+        let line = position.line as usize;
+        let start = *self.line_starts.get(line)?;
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map(|next| next - 1)
+            .unwrap_or(self.src.len());
+
+        let text = &self.src[start..end];
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        let character = position.character as usize;
+
+        Some(start + byte_offset(text, character).unwrap_or(text.len()))
+    }
+
+    /// Convert a [`Span`] into a [`Range`].
+    pub fn span_to_range(&self, span: Span) -> Range {
+        Range {
+            start: self.position(span.start().to_usize()),
+            end: self.position(span.end().to_usize()),
+        }
+    }
+
+    /// Convert a [`Range`] into a [`Span`], returning `None` if either end is
+    /// on a line that doesn't exist.
+    pub fn range_to_span(&self, range: Range) -> Option<Span> {
+        let start = self.offset(range.start)?;
+        let end = self.offset(range.end)?;
+
+        Some(Span::new(start as u32, end as u32))
+    }
+}
+
+fn char_boundary(src: &str, offset: usize) -> usize {
+    let mut offset = offset.min(src.len());
+    while !src.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    offset
+}
+
+/// Validate the [`Link`]s from a single file, resolving relative links from
+/// the directory that file is in (according to its name in `files`).
+pub async fn validate_file<S, L, C>(
+    files: &Files<S>,
+    file: FileId,
+    links: L,
+    ctx: &C,
+) -> Outcomes
+where
+    S: AsRef<str>,
+    L: IntoIterator<Item = Link>,
+    C: Context + Sync + ?Sized,
+{
+    let document = Document {
+        file,
+        path: PathBuf::from(files.name(file)),
+        links: links.into_iter().collect(),
+    };
+
+    site::validate(std::slice::from_ref(&document), ctx).await
+}
+
+/// The set of documents a language server has open, and the results of the
+/// last time each one was validated.
+///
+/// Results are cached until the document (or a document it links to)
+/// changes.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    files: Files<String>,
+    documents: HashMap<PathBuf, Document>,
+    outcomes: HashMap<FileId, Outcomes>,
+}
+
+impl Workspace {
+    /// Create a new, empty [`Workspace`].
+    pub fn new() -> Self { Workspace::default() }
+
+    /// The source for every document in the [`Workspace`].
+    pub fn files(&self) -> &Files<String> { &self.files }
+
+    /// Look up the [`FileId`] for an open document.
+    pub fn file_id(&self, path: &Path) -> Option<FileId> {
+        self.documents.get(path).map(|doc| doc.file)
+    }
+
+    /// Add a document or replace its text, returning every document whose
+    /// diagnostics are now out of date (including this one).
+    ///
+    /// Links are extracted based on the document's extension (see
+    /// [`Site::scanner_for()`]), defaulting to markdown.
+    pub fn set_document<P, S>(&mut self, path: P, text: S) -> Vec<FileId>
+    where
+        P: Into<PathBuf>,
+        S: Into<String>,
+    {
+        let path = path.into();
+        let text = text.into();
+
+        let file = match self.documents.get(&path) {
+            Some(doc) => {
+                self.files.update(doc.file, text);
+                doc.file
+            },
+            None => self.files.add(&path, text),
+        };
+
+        let scanner = Site::scanner_for(&path).unwrap_or(markdown);
+        let links = scanner(self.files.source(file))
+            .into_iter()
+            .map(|(href, span)| Link::new(href, span, file))
+            .collect();
+        self.documents
+            .insert(path.clone(), Document { file, path, links });
+
+        self.invalidate(file)
+    }
+
+    /// Forget about a document, returning the documents whose diagnostics
+    /// are now out of date because they link to it.
+    pub fn remove_document(&mut self, path: &Path) -> Vec<FileId> {
+        let file = match self.documents.get(path) {
+            Some(doc) => doc.file,
+            None => return Vec::new(),
+        };
+
+        let mut stale = self.invalidate(file);
+        stale.retain(|&f| f != file);
+        self.documents.remove(path);
+        self.outcomes.remove(&file);
+
+        stale
+    }
+
+    /// The results from the last time a document was validated, if they are
+    /// still up to date.
+    pub fn outcomes(&self, file: FileId) -> Option<&Outcomes> {
+        self.outcomes.get(&file)
+    }
+
+    /// Get the results of validating a document, only re-checking its links
+    /// if something has changed since last time.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the [`FileId`] didn't come from this
+    /// [`Workspace`].
+    pub async fn validate<C>(&mut self, file: FileId, ctx: &C) -> &Outcomes
+    where
+        C: Context + Sync + ?Sized,
+    {
+        if !self.outcomes.contains_key(&file) {
+            let document = self
+                .documents
+                .values()
+                .find(|doc| doc.file == file)
+                .expect("The file should be part of this workspace");
+            let outcomes =
+                site::validate(std::slice::from_ref(document), ctx).await;
+            self.outcomes.insert(file, outcomes);
+        }
+
+        &self.outcomes[&file]
+    }
+
+    /// Drop the cached results for a document and any documents linking to
+    /// it.
+    fn invalidate(&mut self, file: FileId) -> Vec<FileId> {
+        let target = match self.documents.values().find(|d| d.file == file) {
+            Some(doc) => without_extension(&doc.path),
+            None => return Vec::new(),
+        };
+
+        let mut stale: Vec<FileId> = self
+            .documents
+            .values()
+            .filter(|doc| {
+                doc.file == file
+                    || doc.links.iter().any(|link| {
+                        links_to(link, doc.directory()).as_ref()
+                            == Some(&target)
+                    })
+            })
+            .map(|doc| doc.file)
+            .collect();
+        stale.sort();
+
+        for file in &stale {
+            self.outcomes.remove(file);
+        }
+
+        stale
+    }
+}
+
+fn markdown(src: &str) -> Vec<(String, Span)> {
+    crate::scanners::markdown(src)
+        .map(|(href, span)| (href.into_owned(), span))
+        .collect()
+}
+
+/// Work out which file a link points to (ignoring its extension) without
+/// touching the filesystem, so alternate extensions (e.g. `*.md` and
+/// `*.html`) are treated as the same document.
+fn links_to(link: &Link, directory: &Path) -> Option<PathBuf> {
+    match link.category()? {
+        Category::FileSystem { path, .. } if !path.has_root() => {
+            Some(without_extension(&directory.join(path)))
+        },
+        _ => None,
+    }
+}
+
+fn without_extension(path: &Path) -> PathBuf {
+    let mut normalised = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                normalised.pop();
+            },
+            other => normalised.push(other),
+        }
+    }
+
+    normalised.set_extension("");
+    normalised
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_positions_through_multi_byte_text() {
+        let src = "# Héllo\r\n\n😀 [x](./y.md)\n";
+        let inputs = vec![
+            (
+                0,
+                Position {
+                    line: 0,
+                    character: 0,
+                },
+            ),
+            (
+                5,
+                Position {
+                    line: 0,
+                    character: 4,
+                },
+            ),
+            (
+                11,
+                Position {
+                    line: 2,
+                    character: 0,
+                },
+            ),
+            // the emoji is 4 bytes, but 2 UTF-16 code units
+            (
+                15,
+                Position {
+                    line: 2,
+                    character: 2,
+                },
+            ),
+            (
+                src.len(),
+                Position {
+                    line: 3,
+                    character: 0,
+                },
+            ),
+        ];
+
+        for (byte_offset, should_be) in inputs {
+            let got = position(src, byte_offset);
+            assert_eq!(got, should_be, "{}", byte_offset);
+            assert_eq!(offset(src, got), Some(byte_offset));
+        }
+
+        let past_the_end = Position {
+            line: 0,
+            character: 100,
+        };
+        assert_eq!(offset(src, past_the_end), Some(8));
+        assert_eq!(
+            offset(
+                src,
+                Position {
+                    line: 4,
+                    character: 0
+                }
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn line_index_agrees_with_the_slow_conversions() {
+        let src = "👩‍👩‍👧 family\r\nnaïve [x](./ÿ.md)\n\n日本語 [y](z.md)";
+        let index = LineIndex::new(src);
+
+        for i in 0..=src.len() + 2 {
+            let got = index.position(i);
+            assert_eq!(got, position(src, i), "{}", i);
+            assert_eq!(index.offset(got), offset(src, got));
+
+            let utf16 = utf16_offset(src, i);
+            let expected = char_boundary(src, i);
+            assert_eq!(byte_offset(src, utf16), Some(expected));
+        }
+
+        let end_of_first_line = Position {
+            line: 0,
+            character: 100,
+        };
+        assert_eq!(index.offset(end_of_first_line), Some(25));
+        assert_eq!(
+            index.offset(Position {
+                line: 4,
+                character: 0
+            }),
+            None
+        );
+        assert_eq!(byte_offset(src, src.encode_utf16().count() + 1), None);
+        // the first emoji is a surrogate pair, so "1" is half-way through it
+        assert_eq!(byte_offset(src, 1), Some(0));
+    }
+
+    #[test]
+    fn changing_a_document_invalidates_its_backlinks() {
+        let mut workspace = Workspace::new();
+        workspace.set_document("/book/index.md", "[a](./a.html)");
+        workspace.set_document("/book/a.md", "[home](index.md)");
+        workspace.set_document("/book/b.md", "no links");
+        let index = workspace.file_id(Path::new("/book/index.md")).unwrap();
+        let a = workspace.file_id(Path::new("/book/a.md")).unwrap();
+        let b = workspace.file_id(Path::new("/book/b.md")).unwrap();
+
+        let stale = workspace.set_document("/book/a.md", "[home](./)");
+
+        assert_eq!(stale, vec![index, a]);
+        let index_path = Path::new("/book/index.md");
+        assert_eq!(workspace.remove_document(index_path), Vec::new());
+        assert_eq!(workspace.set_document("/book/b.md", "[a](a.md)"), vec![b]);
+    }
+}
@@ -0,0 +1,249 @@
+//! Counters and histograms reported through the [`metrics`] facade, so
+//! services which embed `linkcheck` get dashboards without any extra
+//! plumbing.
+//!
+//! Nothing is recorded until the application installs a recorder (e.g. from
+//! `metrics-exporter-prometheus`). Call [`describe()`] once at start-up to
+//! give each metric a description and unit.
+//!
+//! | Name                                   | Kind      | Labels             |
+//! | -------------------------------------- | --------- | ------------------ |
+//! | [`LINKS_CHECKED`]                      | counter   | `status`           |
+//! | [`FAILURES`]                           | counter   | `status`, `reason` |
+//! | [`CACHE_HITS`] / [`CACHE_MISSES`]      | counter   |                    |
+//! | [`REQUEST_DURATION`]                   | histogram | `method`, `status` |
+//!
+//! The `status` of a link is one of `valid`, `invalid`, `ignored`,
+//! `unknown_category`, `unchecked`, `suppressed`, or `unverifiable`, and a
+//! failure's `reason` is the name of the [`Reason`] variant (e.g.
+//! `HostNotFound`). A request's `status` is its HTTP status code, or `error`
+//! when there was no response.
+
+use crate::validation::{Outcome, Reason};
+use http::Method;
+use metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+use reqwest::Response;
+use std::time::SystemTime;
+
+/// How many links were checked, by their `status`.
+pub const LINKS_CHECKED: &str = "linkcheck_links_checked_total";
+/// How many links were broken (or couldn't be checked), by their `status`
+/// and `reason`.
+pub const FAILURES: &str = "linkcheck_failures_total";
+/// How many web links were answered by the cache.
+pub const CACHE_HITS: &str = "linkcheck_cache_hits_total";
+/// How many web links weren't in the cache (or had expired).
+pub const CACHE_MISSES: &str = "linkcheck_cache_misses_total";
+/// How long each HTTP request took, in seconds.
+pub const REQUEST_DURATION: &str = "linkcheck_request_duration_seconds";
+
+/// Describe every metric to the installed recorder.
+pub fn describe() {
+    describe_counter!(LINKS_CHECKED, Unit::Count, "Links which were checked");
+    describe_counter!(
+        FAILURES,
+        Unit::Count,
+        "Links which were broken or couldn't be checked"
+    );
+    describe_counter!(CACHE_HITS, Unit::Count, "Web links found in the cache");
+    describe_counter!(
+        CACHE_MISSES,
+        Unit::Count,
+        "Web links which weren't in the cache"
+    );
+    describe_histogram!(
+        REQUEST_DURATION,
+        Unit::Seconds,
+        "How long each HTTP request took"
+    );
+}
+
+/// Count a link once we know what happened to it.
+pub(crate) fn link_checked<F>(outcome: &Outcome<F>) {
+    let (status, reason) = status(outcome);
+
+    counter!(LINKS_CHECKED, "status" => status).increment(1);
+    if let Some(reason) = reason {
+        counter!(FAILURES, "status" => status, "reason" => variant(reason))
+            .increment(1);
+    }
+}
+
+/// Count a lookup in the [`crate::validation::Context::cache()`].
+pub(crate) fn cache_lookup(hit: bool) {
+    if hit {
+        counter!(CACHE_HITS).increment(1);
+    } else {
+        counter!(CACHE_MISSES).increment(1);
+    }
+}
+
+/// Record how long a request which was sent at `sent` took.
+pub(crate) fn request_finished(
+    sent: SystemTime,
+    method: &Method,
+    result: &Result<Response, reqwest::Error>,
+) {
+    let status = match result {
+        Ok(response) => response.status().as_str().to_string(),
+        Err(e) => match e.status() {
+            Some(status) => status.as_str().to_string(),
+            None => String::from("error"),
+        },
+    };
+    let duration = sent.elapsed().unwrap_or_default();
+
+    histogram!(
+        REQUEST_DURATION,
+        "method" => method.to_string(),
+        "status" => status,
+    )
+    .record(duration.as_secs_f64());
+}
+
+fn status<F>(outcome: &Outcome<F>) -> (&'static str, Option<&Reason>) {
+    match outcome {
+        Outcome::Valid(_) => ("valid", None),
+        Outcome::Invalid(invalid) => ("invalid", Some(&invalid.reason)),
+        Outcome::Ignored(_) => ("ignored", None),
+        Outcome::UnknownCategory(_) => ("unknown_category", None),
+        Outcome::Unchecked(_) => ("unchecked", None),
+        Outcome::Suppressed(invalid) => ("suppressed", Some(&invalid.reason)),
+        Outcome::Unverifiable(invalid) => {
+            ("unverifiable", Some(&invalid.reason))
+        },
+        Outcome::ExpectedFailure(invalid) => {
+            ("expected_failure", Some(&invalid.reason))
+        },
+        Outcome::Timed(outcome, _)
+        | Outcome::Suggested(outcome, _)
+        | Outcome::Candidates(outcome, _)
+        | Outcome::Attempted(outcome, _)
+        | Outcome::Linted(outcome, _) => status(outcome),
+    }
+}
+
+/// The name of a [`Reason`]'s variant (e.g. `HostNotFound`), which (unlike
+/// its message) is safe to use as a label.
+fn variant(reason: &Reason) -> String {
+    format!("{:?}", reason)
+        .chars()
+        .take_while(|c| c.is_alphanumeric())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        validation::validate,
+        BasicContext, Link,
+    };
+    use metrics::{
+        Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName,
+        Metadata, Recorder, SharedString as Text,
+    };
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+
+    /// Keeps every value that was recorded, keyed by `name{label=value}`.
+    #[derive(Debug, Default)]
+    struct TestRecorder(Arc<Mutex<Vec<(String, f64)>>>);
+
+    impl TestRecorder {
+        fn total(&self, key: &str) -> f64 {
+            let values = self.0.lock().unwrap();
+            values
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v)
+                .sum()
+        }
+
+        fn handle(&self, key: &Key) -> Arc<Handle> {
+            let labels: Vec<_> = key
+                .labels()
+                .map(|l| format!("{}={}", l.key(), l.value()))
+                .collect();
+            let key = if labels.is_empty() {
+                key.name().to_string()
+            } else {
+                format!("{}{{{}}}", key.name(), labels.join(","))
+            };
+            Arc::new(Handle(key, Arc::clone(&self.0)))
+        }
+    }
+
+    struct Handle(String, Arc<Mutex<Vec<(String, f64)>>>);
+
+    impl CounterFn for Handle {
+        fn increment(&self, value: u64) {
+            self.1.lock().unwrap().push((self.0.clone(), value as f64));
+        }
+
+        fn absolute(&self, _value: u64) { unimplemented!() }
+    }
+
+    impl HistogramFn for Handle {
+        fn record(&self, value: f64) {
+            self.1.lock().unwrap().push((self.0.clone(), value));
+        }
+    }
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: Text) {}
+
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: Text) {}
+
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: Text) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.handle(key))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::from_arc(self.handle(key))
+        }
+    }
+
+    #[test]
+    fn count_links_failures_and_cache_hits() {
+        let server = Server::start(vec![("/ok", Response::ok(""))]);
+        let hrefs = [
+            server.url("/ok").to_string(),
+            server.url("/ok").to_string(),
+            String::from("missing.md"),
+        ];
+        let links: Vec<_> = hrefs
+            .iter()
+            .map(|href| Link::new(href.as_str(), Default::default(), ()))
+            .collect();
+        let ctx = BasicContext::default();
+        let recorder = TestRecorder::default();
+
+        metrics::with_local_recorder(&recorder, || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(validate(Path::new("."), links, &ctx))
+        });
+
+        let valid = format!("{}{{status=valid}}", LINKS_CHECKED);
+        let missing = format!("{}{{status=invalid,reason=Io}}", FAILURES);
+        let requests =
+            format!("{}{{method=HEAD,status=200}}", REQUEST_DURATION);
+        assert_eq!(recorder.total(&valid), 2.0);
+        assert_eq!(recorder.total(&missing), 1.0);
+        assert_eq!(recorder.total(CACHE_HITS), 1.0);
+        assert_eq!(recorder.total(CACHE_MISSES), 1.0);
+        assert!(recorder.total(&requests) > 0.0);
+    }
+}
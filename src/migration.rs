@@ -0,0 +1,194 @@
+//! Finding links which need to change when documentation moves somewhere
+//! else (e.g. to a new domain).
+//!
+//! A [`Migration`] maps old URL prefixes to new ones. Every link matching an
+//! old prefix is reported as a [`Suggestion`] with
+//! [`SuggestionKind::Migrated`], so the usual [`Fixer`] can rewrite them, and
+//! [`Migration::verify()`] makes sure the new targets actually exist.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use codespan::Files;
+//! use linkcheck::{migration::Migration, Link};
+//!
+//! let mut files = Files::new();
+//! let src = "See [the guide](http://old.example.com/docs/guide.html#setup).";
+//! let file_id = files.add("README.md", src);
+//! let links: Vec<_> = linkcheck::scanners::markdown(src)
+//!     .map(|(href, span)| Link::new(href, span, file_id))
+//!     .collect();
+//!
+//! let migration = Migration::new().map(
+//!     "https://old.example.com/docs/".parse().unwrap(),
+//!     "https://docs.example.com/".parse().unwrap(),
+//! );
+//! let rewrites = migration.report(&links);
+//!
+//! assert_eq!(
+//!     rewrites[0].replacement,
+//!     "https://docs.example.com/guide.html#setup"
+//! );
+//! ```
+//!
+//! [`Fixer`]: crate::fix::Fixer
+
+use crate::{
+    fix::{Suggestion, SuggestionKind},
+    validation::{self, Context, Outcomes},
+    Link,
+};
+use std::{hash::Hash, path::Path};
+use url::{Position, Url};
+
+/// A mapping from old URL prefixes to new ones.
+///
+/// Prefixes only match whole path segments, so `https://example.com/docs`
+/// matches `https://example.com/docs/page` but not
+/// `https://example.com/docs-old/`. The `http` and `https` schemes are
+/// treated as the same, and the longest matching prefix wins.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Migration {
+    prefixes: Vec<(Url, Url)>,
+}
+
+impl Migration {
+    /// Create an empty [`Migration`].
+    pub fn new() -> Self { Migration::default() }
+
+    /// Links starting with the `old` prefix should start with `new` instead.
+    pub fn map(mut self, old: Url, new: Url) -> Self {
+        self.prefixes.push((old, new));
+        self
+    }
+
+    /// The `(old, new)` prefixes, in the order they were added.
+    pub fn prefixes(&self) -> &[(Url, Url)] { &self.prefixes }
+
+    /// Where a [`Url`] has moved to, if it matches one of the old prefixes.
+    pub fn rewrite(&self, url: &Url) -> Option<String> {
+        let (_, rest, new) = self
+            .prefixes
+            .iter()
+            .filter_map(|(old, new)| Some((old, remainder(old, url)?, new)))
+            .max_by_key(|(old, _, _)| old.path().len())?;
+        let new = new[..Position::AfterPath].trim_end_matches('/');
+
+        Some(format!("{}{}", new, rest))
+    }
+
+    /// Find every [`Link`] which points at one of the old prefixes, and what
+    /// it should be changed to.
+    pub fn report<F: Clone>(&self, links: &[Link<F>]) -> Vec<Suggestion<F>> {
+        links
+            .iter()
+            .filter_map(|link| {
+                let url = Url::parse(&link.href).ok()?;
+
+                Some(Suggestion {
+                    link: link.clone(),
+                    replacement: self.rewrite(&url)?,
+                    kind: SuggestionKind::Migrated,
+                })
+            })
+            .collect()
+    }
+
+    /// Check the new targets for links found by [`Migration::report()`].
+    ///
+    /// The [`Link`]s in the returned [`Outcomes`] have their `href` replaced,
+    /// but keep the original span.
+    pub async fn verify<F, C>(
+        &self,
+        rewrites: &[Suggestion<F>],
+        current_directory: &Path,
+        ctx: &C,
+    ) -> Outcomes<F>
+    where
+        F: Clone + Eq + Hash + Send,
+        C: Context + Sync + ?Sized,
+    {
+        let links = rewrites.iter().map(|rewrite| Link {
+            href: rewrite.replacement.clone(),
+            ..rewrite.link.clone()
+        });
+
+        validation::validate(current_directory, links, ctx).await
+    }
+}
+
+/// The part of `url` after the `old` prefix (its remaining path, query, and
+/// fragment), if it matches.
+fn remainder<'a>(old: &Url, url: &'a Url) -> Option<&'a str> {
+    let web = |scheme: &str| scheme == "http" || scheme == "https";
+    let same_scheme = old.scheme() == url.scheme()
+        || (web(old.scheme()) && web(url.scheme()));
+
+    if !same_scheme || old.host() != url.host() || old.port() != url.port() {
+        return None;
+    }
+
+    let prefix = old.path().trim_end_matches('/');
+    let rest = url[Position::BeforePath..].strip_prefix(prefix)?;
+
+    if rest.is_empty() || rest.starts_with(['/', '?', '#']) {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+
+    #[tokio::test]
+    async fn report_and_verify_links_to_the_old_site() {
+        let server = Server::start(vec![("/v2/guide.html", Response::ok(""))]);
+        let migration = Migration::new()
+            .map(
+                "https://old.example.com/".parse().unwrap(),
+                "https://unused.example.com/".parse().unwrap(),
+            )
+            .map(
+                "https://old.example.com/docs".parse().unwrap(),
+                server.url("/v2/"),
+            );
+        let links: Vec<_> = vec![
+            "http://old.example.com/docs/guide.html",
+            "https://old.example.com/docs/missing.html?q=1#top",
+            "https://old.example.com/docs-old/page.html",
+            "https://other.example.com/docs/guide.html",
+            "./README.md",
+        ]
+        .into_iter()
+        .map(|href| Link::new(href, Default::default(), ()))
+        .collect();
+
+        let rewrites = migration.report(&links);
+
+        let got: Vec<_> =
+            rewrites.iter().map(|r| r.replacement.as_str()).collect();
+        assert_eq!(
+            got,
+            vec![
+                server.url("/v2/guide.html").to_string(),
+                server.url("/v2/missing.html?q=1#top").to_string(),
+                String::from("https://unused.example.com/docs-old/page.html"),
+            ]
+        );
+        let outcomes = migration
+            .verify(&rewrites[..2], Path::new("."), &BasicContext::default())
+            .await;
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert_eq!(
+            outcomes.invalid[0].link.href,
+            server.url("/v2/missing.html?q=1#top").as_str()
+        );
+    }
+}
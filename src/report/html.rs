@@ -0,0 +1,294 @@
+use crate::{
+    validation::{Outcomes, Status},
+    Link,
+};
+use codespan::Files;
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fmt::{self, Display, Formatter},
+    io::{self, Write},
+};
+
+/// Generate a standalone HTML report from a set of [`Outcomes`].
+///
+/// Links are grouped by the file they came from, with each file getting a
+/// table listing every link, where it is, its status, the reason it failed
+/// (if any), and how long it took to check. Tables can be sorted by clicking
+/// on a column header.
+///
+/// # Examples
+///
+/// ```rust
+/// use codespan::{Files, Span};
+/// use linkcheck::{validation::Outcomes, Link};
+///
+/// let mut files = Files::new();
+/// let file_id = files.add("index.md", "[home](https://example.com/)");
+/// let outcomes = Outcomes {
+///     valid: vec![Link::new("https://example.com/", Span::new(0, 28), file_id)],
+///     ..Default::default()
+/// };
+///
+/// let report = linkcheck::report::html(&outcomes, &files);
+///
+/// assert!(report.contains("index.md"));
+/// assert!(report.contains("https://example.com/"));
+/// ```
+pub fn html<S: AsRef<str>>(outcomes: &Outcomes, files: &Files<S>) -> String {
+    let mut buffer = Vec::new();
+    write_html(outcomes, files, &mut buffer)
+        .expect("Writing to an in-memory buffer should never fail");
+
+    String::from_utf8(buffer).expect("The report is always valid UTF-8")
+}
+
+/// Write a standalone HTML report to some [`Write`]r.
+///
+/// See [`html()`] for more.
+pub fn write_html<S, W>(
+    outcomes: &Outcomes,
+    files: &Files<S>,
+    mut writer: W,
+) -> io::Result<()>
+where
+    S: AsRef<str>,
+    W: Write,
+{
+    let rows = rows(outcomes);
+    let mut by_file: BTreeMap<&OsStr, Vec<&Row<'_>>> = BTreeMap::new();
+    for row in &rows {
+        by_file
+            .entry(files.name(row.link.file))
+            .or_default()
+            .push(row);
+    }
+
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(writer, "<html>")?;
+    writeln!(writer, "<head>")?;
+    writeln!(writer, "<meta charset=\"utf-8\">")?;
+    writeln!(writer, "<title>Link Check Report</title>")?;
+    writeln!(writer, "<style>{}</style>", STYLE)?;
+    writeln!(writer, "</head>")?;
+    writeln!(writer, "<body>")?;
+    writeln!(writer, "<h1>Link Check Report</h1>")?;
+    writeln!(
+        writer,
+        "<p>{} valid, {} invalid, {} ignored, {} unknown, {} unchecked, {} \
+         known broken, {} unverifiable, {} expected failures</p>",
+        outcomes.valid.len(),
+        outcomes.invalid.len(),
+        outcomes.ignored.len(),
+        outcomes.unknown_category.len(),
+        outcomes.unchecked.len(),
+        outcomes.suppressed.len(),
+        outcomes.unverifiable.len(),
+        outcomes.expected_failures.len(),
+    )?;
+
+    for (name, mut rows) in by_file {
+        rows.sort_by_key(|row| row.link.span.start());
+
+        writeln!(writer, "<h2>{}</h2>", Escaped(&name.to_string_lossy()))?;
+        writeln!(writer, "<table class=\"sortable\">")?;
+        writeln!(
+            writer,
+            "<thead><tr><th>Link</th><th>Line</th><th>Status</th><th>Reason</th><th>Duration (ms)</th></tr></thead>"
+        )?;
+        writeln!(writer, "<tbody>")?;
+
+        for row in rows {
+            let line = row
+                .link
+                .location(files)
+                .map(|location| location.line.to_string())
+                .unwrap_or_default();
+            let duration = outcomes
+                .durations
+                .get(row.link)
+                .map(|d| d.as_millis().to_string())
+                .unwrap_or_default();
+
+            writeln!(
+                writer,
+                "<tr class=\"{status}\"><td>{href}</td><td>{line}</td><td>{status}</td><td>{reason}</td><td>{duration}</td></tr>",
+                href = Escaped(&row.link.href),
+                line = line,
+                status = status_name(row.status),
+                reason = Escaped(row.reason.as_deref().unwrap_or_default()),
+                duration = duration,
+            )?;
+        }
+
+        writeln!(writer, "</tbody>")?;
+        writeln!(writer, "</table>")?;
+    }
+
+    writeln!(writer, "<script>{}</script>", SCRIPT)?;
+    writeln!(writer, "</body>")?;
+    writeln!(writer, "</html>")?;
+
+    Ok(())
+}
+
+struct Row<'a> {
+    link: &'a Link,
+    status: Status,
+    reason: Option<String>,
+}
+
+fn rows(outcomes: &Outcomes) -> Vec<Row<'_>> {
+    let mut rows = Vec::new();
+
+    rows.extend(outcomes.valid.iter().map(|link| Row {
+        link,
+        status: Status::Valid,
+        reason: None,
+    }));
+    rows.extend(outcomes.invalid.iter().map(|invalid| Row {
+        link: &invalid.link,
+        status: Status::Invalid,
+        reason: Some(invalid.reason.to_string()),
+    }));
+    rows.extend(outcomes.ignored.iter().map(|link| Row {
+        link,
+        status: Status::Ignored,
+        reason: None,
+    }));
+    rows.extend(outcomes.unknown_category.iter().map(|link| Row {
+        link,
+        status: Status::UnknownCategory,
+        reason: None,
+    }));
+    rows.extend(outcomes.unchecked.iter().map(|link| Row {
+        link,
+        status: Status::Unchecked,
+        reason: None,
+    }));
+    rows.extend(outcomes.suppressed.iter().map(|suppressed| Row {
+        link: &suppressed.link,
+        status: Status::Suppressed,
+        reason: Some(suppressed.reason.to_string()),
+    }));
+    rows.extend(outcomes.unverifiable.iter().map(|unverifiable| Row {
+        link: &unverifiable.link,
+        status: Status::Unverifiable,
+        reason: Some(unverifiable.reason.to_string()),
+    }));
+    rows.extend(outcomes.expected_failures.iter().map(|expected| Row {
+        link: &expected.link,
+        status: Status::ExpectedFailure,
+        reason: Some(expected.reason.to_string()),
+    }));
+
+    rows
+}
+
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::Valid => "valid",
+        Status::Invalid => "invalid",
+        Status::Ignored => "ignored",
+        Status::UnknownCategory => "unknown",
+        Status::Unchecked => "unchecked",
+        Status::Suppressed => "suppressed",
+        Status::Unverifiable => "unverifiable",
+        Status::ExpectedFailure => "expected-failure",
+    }
+}
+
+/// Escape text so it can be embedded in HTML.
+struct Escaped<'a>(&'a str);
+
+impl Display for Escaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '&' => f.write_str("&amp;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&#39;")?,
+                other => write!(f, "{}", other)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { border: 1px solid #ddd; padding: 0.3em 0.6em; text-align: left; }
+th { cursor: pointer; background: #f4f4f4; }
+tr.valid td:nth-child(3) { color: #2a7d2a; }
+tr.invalid td:nth-child(3) { color: #c0392b; font-weight: bold; }
+tr.ignored td:nth-child(3), tr.unknown td:nth-child(3),
+tr.unchecked td:nth-child(3) { color: #888; }
+tr.suppressed td:nth-child(3) { color: #b9770e; }
+tr.unverifiable td:nth-child(3) { color: #7f8c8d; }
+tr.expected-failure td:nth-child(3) { color: #888; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable th").forEach(function(th, column) {
+  th.addEventListener("click", function() {
+    var tbody = th.closest("table").querySelector("tbody");
+    var ascending = th.dataset.order !== "asc";
+    th.dataset.order = ascending ? "asc" : "desc";
+    var rows = Array.from(tbody.querySelectorAll("tr"));
+    rows.sort(function(a, b) {
+      var x = a.children[column].textContent;
+      var y = b.children[column].textContent;
+      var cmp = (x !== "" && y !== "" && !isNaN(x) && !isNaN(y))
+        ? Number(x) - Number(y)
+        : x.localeCompare(y);
+      return ascending ? cmp : -cmp;
+    });
+    rows.forEach(function(row) { tbody.appendChild(row); });
+  });
+});
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{InvalidLink, Reason};
+    use codespan::Span;
+    use std::time::Duration;
+
+    #[test]
+    fn links_are_grouped_by_file_and_escaped() {
+        let mut files = Files::new();
+        let first = files.add("first.md", "[a](<b>)\n[c](./missing.md)");
+        let second = files.add("second.md", "[x](https://example.com/)");
+        let missing = Link::new("./missing.md", Span::new(9, 26), first);
+        let mut outcomes = Outcomes {
+            valid: vec![
+                Link::new("<b>", Span::new(0, 8), first),
+                Link::new("https://example.com/", Span::new(0, 25), second),
+            ],
+            invalid: vec![InvalidLink {
+                link: missing.clone(),
+                reason: Reason::Io(io::ErrorKind::NotFound.into()),
+            }],
+            ..Default::default()
+        };
+        outcomes
+            .durations
+            .insert(missing, Duration::from_millis(42));
+
+        let got = html(&outcomes, &files);
+
+        let first_heading = got.find("<h2>first.md</h2>").unwrap();
+        let second_heading = got.find("<h2>second.md</h2>").unwrap();
+        let missing_row = got.find("./missing.md").unwrap();
+        assert!(first_heading < missing_row && missing_row < second_heading);
+        assert!(got.contains("<td>&lt;b&gt;</td>"));
+        assert!(got.contains(
+            "<tr class=\"invalid\"><td>./missing.md</td><td>2</td><td>invalid</td><td>An OS-level error occurred</td><td>42</td></tr>"
+        ));
+    }
+}
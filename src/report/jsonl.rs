@@ -0,0 +1,121 @@
+use crate::{
+    validation::{statuses, Outcomes, Status},
+    Location,
+};
+use codespan::Files;
+use std::io::{self, Write};
+
+/// What happened to a single [`crate::Link`], written as one line by
+/// [`write_jsonl()`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Event {
+    /// The link's `href`.
+    pub href: String,
+    /// The name of the file the link came from.
+    pub file: String,
+    /// Where the link is, if its span could be found in the file.
+    pub location: Option<Location>,
+    /// What happened to the link.
+    pub status: Status,
+    /// Why the link was broken (or couldn't be checked).
+    pub reason: Option<String>,
+    /// How long it took to check the link, in milliseconds.
+    pub duration_ms: Option<u64>,
+    /// Which run the link was checked by (see [`Outcomes::merge_from()`]).
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Turn [`Outcomes`] into one [`Event`] per [`crate::Link`].
+pub fn events<S>(outcomes: &Outcomes, files: &Files<S>) -> Vec<Event>
+where
+    S: AsRef<str>,
+{
+    statuses(outcomes)
+        .into_iter()
+        .map(|(link, status, reason)| Event {
+            href: link.href.clone(),
+            file: files.name(link.file).to_string_lossy().into_owned(),
+            location: link.location(files),
+            status,
+            reason: reason.map(ToString::to_string),
+            duration_ms: outcomes
+                .durations
+                .get(link)
+                .map(|d| d.as_millis() as u64),
+            source: outcomes.sources.get(link).cloned(),
+        })
+        .collect()
+}
+
+/// Write one JSON object per line (JSON Lines) for each [`crate::Link`] in
+/// the [`Outcomes`].
+///
+/// Each line is written with a single [`Write::write_all()`] call, so the
+/// [`Outcomes`] from [`crate::site::validate_stream()`] can be written as they
+/// arrive and piped into `jq` or a log collector during a long run.
+pub fn write_jsonl<S, W>(
+    outcomes: &Outcomes,
+    files: &Files<S>,
+    mut writer: W,
+) -> io::Result<()>
+where
+    S: AsRef<str>,
+    W: Write,
+{
+    for event in events(outcomes, files) {
+        let mut line = serde_json::to_vec(&event)?;
+        line.push(b'\n');
+        writer.write_all(&line)?;
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        validation::{InvalidLink, Reason},
+        Link,
+    };
+    use codespan::Span;
+    use std::time::Duration;
+
+    #[test]
+    fn one_json_object_per_line() {
+        let mut files = Files::new();
+        let file = files.add("index.md", "[a](a.md)\n[b](./missing.md)");
+        let missing = Link::new("./missing.md", Span::new(14, 26), file);
+        let mut outcomes = Outcomes {
+            valid: vec![Link::new("a.md", Span::new(4, 8), file)],
+            invalid: vec![InvalidLink {
+                link: missing.clone(),
+                reason: Reason::Io(io::ErrorKind::NotFound.into()),
+            }],
+            ..Default::default()
+        };
+        outcomes
+            .durations
+            .insert(missing, Duration::from_millis(42));
+        let mut buffer = Vec::new();
+
+        write_jsonl(&outcomes, &files, &mut buffer).unwrap();
+
+        let got: Vec<Event> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0].status, Status::Valid);
+        assert_eq!(got[1].href, "./missing.md");
+        assert_eq!(got[1].file, "index.md");
+        assert_eq!(got[1].location.as_ref().unwrap().line, 2);
+        assert_eq!(
+            got[1].reason.as_deref(),
+            Some("An OS-level error occurred")
+        );
+        assert_eq!(got[1].duration_ms, Some(42));
+    }
+}
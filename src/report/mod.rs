@@ -0,0 +1,13 @@
+//! Human-friendly reports generated from validation [`Outcomes`].
+//!
+//! [`Outcomes`]: crate::validation::Outcomes
+
+#[cfg(feature = "html-report")]
+mod html;
+#[cfg(feature = "serde-1")]
+mod jsonl;
+
+#[cfg(feature = "html-report")]
+pub use html::{html, write_html};
+#[cfg(feature = "serde-1")]
+pub use jsonl::{events, write_jsonl, Event};
@@ -0,0 +1,118 @@
+use codespan::Span;
+use std::{borrow::Cow, collections::HashMap};
+
+/// Group a scanner's output by `href`, keeping every place it was found.
+///
+/// Links are kept in the order they first appear, and so are their spans.
+///
+/// # Examples
+///
+/// ```rust
+/// # use codespan::Span;
+/// let src = "[a](x.md) [b](y.md) [c](x.md)";
+///
+/// let got = linkcheck::scanners::dedup(linkcheck::scanners::markdown(src));
+///
+/// assert_eq!(
+///     got,
+///     vec![
+///         ("x.md".into(), vec![Span::new(4, 8), Span::new(24, 28)]),
+///         ("y.md".into(), vec![Span::new(14, 18)]),
+///     ]
+/// );
+/// ```
+pub fn dedup<H, I>(links: I) -> Vec<(H, Vec<Span>)>
+where
+    I: IntoIterator<Item = (H, Span)>,
+    H: AsRef<str>,
+{
+    let mut deduped: Vec<(H, Vec<Span>)> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for (href, span) in links {
+        match seen.get(href.as_ref()) {
+            Some(&index) => deduped[index].1.push(span),
+            None => {
+                seen.insert(href.as_ref().to_string(), deduped.len());
+                deduped.push((href, vec![span]));
+            },
+        }
+    }
+
+    deduped
+}
+
+/// Sort a scanner's output by where each link starts (then ends), keeping
+/// links with the same span in their original order.
+pub fn sort_by_span<H, I>(links: I) -> Vec<(H, Span)>
+where
+    I: IntoIterator<Item = (H, Span)>,
+{
+    let mut links: Vec<_> = links.into_iter().collect();
+    links.sort_by_key(|(_, span)| (span.start(), span.end()));
+    links
+}
+
+/// Combine the output of two scanners which were run over the same text,
+/// sorted by span.
+///
+/// A link found by both scanners (i.e. the same `href` at the same span) is
+/// only reported once. The result can be passed to [`merge()`] again to
+/// combine more than two scanners.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::scanners::{markdown, merge, plaintext};
+///
+/// let src = "See [the docs](https://example.com/) or https://example.org/";
+///
+/// let got: Vec<_> = merge(markdown(src), plaintext(src))
+///     .into_iter()
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(got, vec!["https://example.com/", "https://example.org/"]);
+/// ```
+pub fn merge<'a, A, B, HA, HB>(first: A, second: B) -> Vec<(Cow<'a, str>, Span)>
+where
+    A: IntoIterator<Item = (HA, Span)>,
+    B: IntoIterator<Item = (HB, Span)>,
+    HA: Into<Cow<'a, str>>,
+    HB: Into<Cow<'a, str>>,
+{
+    let first = first.into_iter().map(|(href, span)| (href.into(), span));
+    let second = second.into_iter().map(|(href, span)| (href.into(), span));
+
+    let mut merged = sort_by_span(first.chain(second));
+    merged.dedup();
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combine_results_from_several_scanners() {
+        let src = "<a href=\"https://example.com/\">x</a> [y](./y.md) \
+                   [z](https://example.com/)";
+
+        let merged = merge(
+            merge(crate::scanners::markdown(src), crate::scanners::html(src)),
+            crate::scanners::plaintext(src),
+        );
+        let got = dedup(merged);
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    Cow::Borrowed("https://example.com/"),
+                    vec![Span::new(9, 29), Span::new(53, 73)]
+                ),
+                (Cow::Borrowed("./y.md"), vec![Span::new(41, 47)]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use crate::validation::ImageRef;
+use codespan::Span;
+use std::collections::HashSet;
+
+/// Find references to container images in `docker pull` commands,
+/// Dockerfile `FROM` instructions, and `image:` keys (e.g. in a
+/// `docker-compose.yml`).
+///
+/// Each image is returned as a `docker://` URL (see [`ImageRef::to_url()`])
+/// along with the span of the reference as it was written.
+///
+/// # Examples
+///
+/// ```rust
+/// let src = "FROM rust:1.70 AS build\n\
+///            FROM build\n\
+///            $ docker pull ghcr.io/org/app:v2";
+///
+/// let got: Vec<_> = linkcheck::scanners::container_images(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(
+///     got,
+///     vec![
+///         "docker://docker.io/library/rust:1.70",
+///         "docker://ghcr.io/org/app:v2",
+///     ]
+/// );
+/// ```
+pub fn container_images(
+    src: &str,
+) -> impl Iterator<Item = (String, Span)> + '_ {
+    let mut stages = HashSet::new();
+    let mut offset = 0;
+
+    src.split('\n').filter_map(move |line| {
+        let line_start = offset;
+        offset += line.len() + 1;
+
+        let words = words(line);
+        let (start, image) = image_in(&words, &mut stages)?;
+        let image = image.trim_matches(|c| c == '"' || c == '\'');
+        let start = line_start + start + line[start..].find(image)?;
+
+        if image.contains('$') || stages.contains(image) || image == "scratch" {
+            return None;
+        }
+
+        let href = ImageRef::parse(image)?.to_url().to_string();
+        let span = Span::new(start as u32, (start + image.len()) as u32);
+        Some((href, span))
+    })
+}
+
+/// Find the image in a line, remembering the names of any build stages.
+fn image_in<'a>(
+    words: &[(usize, &'a str)],
+    stages: &mut HashSet<String>,
+) -> Option<(usize, &'a str)> {
+    // skip shell prompts and YAML list markers
+    let mut words = words
+        .iter()
+        .copied()
+        .skip_while(|(_, w)| *w == "$" || *w == "-");
+    let (_, first) = words.next()?;
+
+    if first.eq_ignore_ascii_case("FROM") {
+        let mut rest = words.skip_while(|(_, w)| w.starts_with("--"));
+        let image = rest.next()?;
+        if let (Some((_, as_)), Some((_, stage))) = (rest.next(), rest.next()) {
+            if as_.eq_ignore_ascii_case("AS") {
+                stages.insert(stage.to_string());
+            }
+        }
+        Some(image)
+    } else if first == "docker" || first == "podman" {
+        let (_, command) = words.next()?;
+        if command != "pull" {
+            return None;
+        }
+        words.find(|(_, w)| !w.starts_with('-'))
+    } else if first == "image:" {
+        words.next()
+    } else {
+        None
+    }
+}
+
+/// Split a line into words, keeping track of where each one starts.
+fn words(line: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                words.push((s, &line[s..i]));
+                start = None;
+            },
+            (false, None) => start = Some(i),
+            _ => {},
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &line[s..]));
+    }
+
+    words
+}
@@ -0,0 +1,188 @@
+use crate::LinkContext;
+use codespan::Span;
+
+/// How many characters to keep either side of a link when its line is too
+/// long to use as-is.
+const CONTEXT_CHARS: usize = 40;
+
+/// Capture the text around a link and the heading it appears under, so
+/// reports can show where a link is without reloading the source.
+///
+/// The snippet is the line containing the link, trimmed to about
+/// 40 characters either side of it. Markdown (`## Heading`) and HTML
+/// (`<h2>Heading</h2>`) headings are recognised.
+///
+/// # Examples
+///
+/// ```rust
+/// use codespan::Span;
+///
+/// let src = "# Install\n\nGet it from [the site](https://example.com/).";
+/// let start = src.find("https").unwrap();
+/// let span = Span::new(start as u32, (start + 20) as u32);
+///
+/// let context = linkcheck::scanners::context(src, span);
+///
+/// assert_eq!(
+///     context.snippet,
+///     "Get it from [the site](https://example.com/)."
+/// );
+/// assert_eq!(context.heading.as_deref(), Some("Install"));
+/// ```
+pub fn context(src: &str, span: Span) -> LinkContext {
+    let start = clamp(src, span.start().to_usize());
+    let end = clamp(src, span.end().to_usize()).max(start);
+
+    LinkContext {
+        snippet: snippet(src, start, end),
+        heading: heading(&src[..start]),
+    }
+}
+
+/// Make sure an offset is inside `src` and on a character boundary.
+fn clamp(src: &str, offset: usize) -> usize {
+    let mut offset = offset.min(src.len());
+    while !src.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn snippet(src: &str, start: usize, end: usize) -> String {
+    let line_start = src[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = src[end..].find('\n').map(|i| end + i).unwrap_or(src.len());
+
+    let before = src[line_start..start].trim_start();
+    let after = src[end..line_end].trim_end();
+    let (before, cut_before) = last_chars(before, CONTEXT_CHARS);
+    let (after, cut_after) = first_chars(after, CONTEXT_CHARS);
+
+    let mut snippet = String::new();
+    if cut_before {
+        snippet.push('…');
+    }
+    snippet.push_str(before);
+    snippet.push_str(&src[start..end]);
+    snippet.push_str(after);
+    if cut_after {
+        snippet.push('…');
+    }
+
+    snippet
+}
+
+fn first_chars(s: &str, n: usize) -> (&str, bool) {
+    match s.char_indices().nth(n) {
+        Some((i, _)) => (&s[..i], true),
+        None => (s, false),
+    }
+}
+
+fn last_chars(s: &str, n: usize) -> (&str, bool) {
+    match s.char_indices().rev().nth(n) {
+        Some((i, c)) => (&s[i + c.len_utf8()..], true),
+        None => (s, false),
+    }
+}
+
+/// Find the last heading in some text.
+fn heading(text: &str) -> Option<String> {
+    markdown_heading(text)
+        .into_iter()
+        .chain(html_heading(text))
+        .max_by_key(|(offset, _)| *offset)
+        .map(|(_, heading)| heading)
+}
+
+/// The last ATX-style markdown heading (e.g. `## Heading`).
+fn markdown_heading(text: &str) -> Option<(usize, String)> {
+    let mut found = None;
+    let mut offset = 0;
+
+    for line in text.split('\n') {
+        let trimmed = line.trim_start();
+        let hashes = trimmed.len() - trimmed.trim_start_matches('#').len();
+        let rest = &trimmed[hashes..];
+
+        if (1..=6).contains(&hashes)
+            && (rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            let title = rest.trim().trim_end_matches('#').trim();
+            found = Some((offset, title.to_string()));
+        }
+
+        offset += line.len() + 1;
+    }
+
+    found
+}
+
+/// The last HTML heading (e.g. `<h2 id="x">Heading</h2>`), with any nested
+/// tags removed.
+fn html_heading(text: &str) -> Option<(usize, String)> {
+    let lowercase = text.to_ascii_lowercase();
+    let mut search = lowercase.as_str();
+
+    while let Some(tag_start) = search.rfind("<h") {
+        let level = lowercase.as_bytes().get(tag_start + 2).copied();
+        if !matches!(level, Some(b'1'..=b'6')) {
+            search = &lowercase[..tag_start];
+            continue;
+        }
+
+        let open_end = lowercase[tag_start..].find('>')? + tag_start + 1;
+        let close = format!("</h{}", level? as char);
+        let close_start = lowercase[open_end..]
+            .find(&close)
+            .map(|i| open_end + i)
+            .unwrap_or(text.len());
+
+        return Some((tag_start, strip_tags(&text[open_end..close_start])));
+    }
+
+    None
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {},
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span_of(src: &str, needle: &str) -> Span {
+        let start = src.find(needle).unwrap();
+        Span::new(start as u32, (start + needle.len()) as u32)
+    }
+
+    #[test]
+    fn long_lines_are_trimmed_around_the_link() {
+        let src = format!(
+            "<h1>Intro</h1>\n<h2 id=\"x\">The <em>Details</em></h2>\n{} \
+             <a href=\"./page.html\">page</a> {}\n",
+            "word ".repeat(20),
+            "more ".repeat(20),
+        );
+
+        let got = context(&src, span_of(&src, "./page.html"));
+
+        assert_eq!(got.heading.as_deref(), Some("The Details"));
+        assert!(got.snippet.starts_with('…'));
+        assert!(got.snippet.ends_with('…'));
+        assert!(got.snippet.contains("<a href=\"./page.html\">page</a>"));
+        assert_eq!(got.snippet.chars().count(), 2 * CONTEXT_CHARS + 13);
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::Path;
+
+/// The kinds of document which can be scanned for links.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FileKind {
+    /// A markdown document.
+    Markdown,
+    /// An HTML page.
+    Html,
+    /// Plain text, where only bare URLs are links.
+    PlainText,
+    /// A Graphviz diagram.
+    Graphviz,
+    /// A PlantUML diagram.
+    PlantUml,
+    /// A man page (or anything else written in roff).
+    Roff,
+}
+
+impl FileKind {
+    /// Guess a file's kind from its extension.
+    pub fn from_path(path: &Path) -> Option<FileKind> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+
+        match extension.as_str() {
+            "md" | "markdown" => Some(FileKind::Markdown),
+            "html" | "htm" => Some(FileKind::Html),
+            "txt" => Some(FileKind::PlainText),
+            "dot" | "gv" => Some(FileKind::Graphviz),
+            "puml" | "plantuml" | "pu" | "iuml" => Some(FileKind::PlantUml),
+            // man pages are usually named after their section (e.g. "ls.1"),
+            // but numeric extensions are too ambiguous to pick up by default
+            "man" | "roff" | "mdoc" => Some(FileKind::Roff),
+            _ => None,
+        }
+    }
+
+    /// Guess a file's kind by looking at the start of its contents.
+    ///
+    /// This only returns a kind when there is something to go by (e.g. a
+    /// HTML doctype or markdown headings), so binary files, scripts, and
+    /// things like a `Makefile` or `.git/config` are skipped.
+    pub fn sniff(contents: &str) -> Option<FileKind> {
+        let head = truncate(contents, 4096);
+        let start = head.trim_start().to_ascii_lowercase();
+
+        if head.contains('\0') || head.starts_with("#!") {
+            None
+        } else if start.starts_with("<!doctype html")
+            || start.starts_with("<html")
+        {
+            Some(FileKind::Html)
+        } else if start.starts_with("@startuml") {
+            Some(FileKind::PlantUml)
+        } else if is_graphviz(&start) {
+            Some(FileKind::Graphviz)
+        } else if head.lines().any(is_roff_header) {
+            Some(FileKind::Roff)
+        } else if head.lines().any(looks_like_markdown) {
+            Some(FileKind::Markdown)
+        } else {
+            None
+        }
+    }
+}
+
+/// Figure out which kind of document a file is.
+///
+/// The extension is used when there is one (see [`FileKind::from_path()`]).
+/// Files without an extension (e.g. `README` or `CHANGELOG`) are sniffed
+/// instead (see [`FileKind::sniff()`]), while files with an extension we
+/// don't recognise (e.g. source code) are skipped.
+///
+/// Plain text is only detected by extension (e.g. `*.txt`), because a file
+/// without one is just as likely to be a build script or config file.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::scanners::{detect, FileKind};
+/// use std::path::Path;
+///
+/// let readme = "# My Project\n\nSee [the docs](https://example.com/).";
+/// assert_eq!(detect(Path::new("README"), readme), Some(FileKind::Markdown));
+///
+/// let page = "<!DOCTYPE html>\n<html></html>";
+/// assert_eq!(detect(Path::new("404"), page), Some(FileKind::Html));
+///
+/// assert_eq!(detect(Path::new("notes.md"), page), Some(FileKind::Markdown));
+/// assert_eq!(detect(Path::new("main.rs"), readme), None);
+/// ```
+pub fn detect(path: &Path, contents: &str) -> Option<FileKind> {
+    match path.extension() {
+        Some(_) => FileKind::from_path(path),
+        None => FileKind::sniff(contents),
+    }
+}
+
+fn truncate(text: &str, max_len: usize) -> &str {
+    if text.len() <= max_len {
+        return text;
+    }
+
+    let mut end = max_len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+fn is_graphviz(start: &str) -> bool {
+    let start = start.strip_prefix("strict").unwrap_or(start).trim_start();
+    let header = match start.split_once('{') {
+        Some((header, _)) => header,
+        None => return false,
+    };
+
+    let mut words = header.split_whitespace();
+    matches!(words.next(), Some("graph") | Some("digraph"))
+        && words.count() <= 1
+}
+
+fn is_roff_header(line: &str) -> bool {
+    line.starts_with(".TH ") || line.starts_with(".Dd ")
+}
+
+fn looks_like_markdown(line: &str) -> bool {
+    let line = line.trim_end();
+
+    line.starts_with("# ")
+        || line.starts_with("## ")
+        || line.starts_with("```")
+        || (line.starts_with('[') && line.contains("]: "))
+        || line.contains("](")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_files_without_an_extension() {
+        let inputs = [
+            ("<html>\n<a href=\"x\">x</a>\n</html>", Some(FileKind::Html)),
+            ("  <!doctype HTML>\n", Some(FileKind::Html)),
+            ("@startuml\nA -> B\n@enduml\n", Some(FileKind::PlantUml)),
+            ("digraph deps {\n  a -> b;\n}\n", Some(FileKind::Graphviz)),
+            ("strict graph {\n  a -- b;\n}\n", Some(FileKind::Graphviz)),
+            (".TH LS 1\n.SH NAME\n", Some(FileKind::Roff)),
+            ("Changes\n\n[1.0]: https://x.y/\n", Some(FileKind::Markdown)),
+            ("Copyright (c)\nhttps://x.y/\n", None),
+            ("[remote \"origin\"]\n\turl = https://x.y/z.git\n", None),
+            ("#!/bin/sh\necho https://example.com/\n", None),
+            ("\0\0\0", None),
+        ];
+
+        for (src, should_be) in inputs.iter() {
+            let got = detect(Path::new("docs/README"), src);
+            assert_eq!(got, *should_be, "{:?}", src);
+        }
+    }
+}
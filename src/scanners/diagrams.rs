@@ -0,0 +1,198 @@
+use codespan::Span;
+
+/// Graphviz attributes which contain a link.
+const GRAPHVIZ_ATTRIBUTES: &[&str] = &[
+    "URL",
+    "href",
+    "edgeURL",
+    "edgehref",
+    "headURL",
+    "headhref",
+    "labelURL",
+    "labelhref",
+    "tailURL",
+    "tailhref",
+];
+
+/// Find the targets of clickable nodes and edges (e.g. `URL="..."`) in a
+/// Graphviz (`*.dot`) file.
+///
+/// # Examples
+///
+/// ```rust
+/// # use codespan::Span;
+/// let src = r#"digraph { api [URL="api.html"]; api -> db [href=db.html] }"#;
+///
+/// let got: Vec<_> = linkcheck::scanners::graphviz(src).collect();
+///
+/// assert_eq!(
+///     got,
+///     vec![("api.html", Span::new(20, 28)), ("db.html", Span::new(48, 55))]
+/// );
+/// ```
+pub fn graphviz(src: &str) -> impl Iterator<Item = (&str, Span)> + '_ {
+    let mut cursor = 0;
+
+    std::iter::from_fn(move || {
+        while cursor < src.len() {
+            let start = cursor;
+            cursor += 1;
+
+            if !src.is_char_boundary(start)
+                || src[..start].ends_with(is_id_char)
+            {
+                continue;
+            }
+            let rest = match GRAPHVIZ_ATTRIBUTES
+                .iter()
+                .find_map(|name| src[start..].strip_prefix(name))
+            {
+                Some(rest) if !rest.starts_with(is_id_char) => rest,
+                _ => continue,
+            };
+
+            let value = match rest.trim_start().strip_prefix('=') {
+                Some(value) => value,
+                None => continue,
+            };
+            let value_start = src.len() - value.trim_start().len();
+
+            if let Some((href, span)) = attribute_value(src, value_start) {
+                cursor = span.end().to_usize();
+                return Some((href, span));
+            }
+        }
+
+        None
+    })
+}
+
+/// Parse a quoted string or a plain identifier, returning its contents and
+/// their span.
+fn attribute_value(src: &str, start: usize) -> Option<(&str, Span)> {
+    let rest = &src[start..];
+
+    let (start, end) = if let Some(quoted) = rest.strip_prefix('"') {
+        let mut escaped = false;
+        let length = quoted.char_indices().find_map(|(i, c)| {
+            match (escaped, c) {
+                (false, '"') => return Some(i),
+                (false, '\\') => escaped = true,
+                _ => escaped = false,
+            }
+            None
+        })?;
+        (start + 1, start + 1 + length)
+    } else {
+        let length = rest
+            .find(|c: char| {
+                c.is_whitespace() || matches!(c, ',' | ';' | ']' | '"')
+            })
+            .unwrap_or(rest.len());
+        (start, start + length)
+    };
+
+    if start == end {
+        return None;
+    }
+
+    Some((&src[start..end], Span::new(start as u32, end as u32)))
+}
+
+fn is_id_char(c: char) -> bool { c.is_alphanumeric() || c == '_' }
+
+/// Find links (e.g. `[[https://example.com/ label]]`) and `!include`d files
+/// in a PlantUML (`*.puml`) file.
+///
+/// Includes from the standard library (e.g. `!include <C4/C4_Container>`)
+/// and variables aren't reported, and a sub-part (e.g. `!includesub
+/// common.puml!BASIC`) is removed from the path.
+///
+/// # Examples
+///
+/// ```rust
+/// let src = "!include common.puml\n\
+///            !include <C4/C4_Container>\n\
+///            class Api [[https://example.com/api{The API} Docs]]";
+///
+/// let got: Vec<_> = linkcheck::scanners::plantuml(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(got, vec!["common.puml", "https://example.com/api"]);
+/// ```
+pub fn plantuml(src: &str) -> impl Iterator<Item = (&str, Span)> + '_ {
+    let mut offset = 0;
+
+    src.split('\n').flat_map(move |line| {
+        let line_start = offset;
+        offset += line.len() + 1;
+
+        let include = include_path(line).into_iter();
+        let links = line.match_indices("[[").filter_map(move |(i, _)| {
+            let target = &line[i + 2..];
+            let end = target.find(|c: char| {
+                c.is_whitespace() || c == '{' || c == ']' || c == '|'
+            })?;
+            Some((i + 2, &target[..end]))
+        });
+
+        include
+            .chain(links)
+            .filter(|(_, href)| !href.is_empty())
+            .map(move |(start, href)| {
+                let start = line_start + start;
+                let span = Span::new(start as u32, (start + href.len()) as u32);
+                (href, span)
+            })
+    })
+}
+
+/// The path from an `!include` directive, and where it starts in the line.
+fn include_path(line: &str) -> Option<(usize, &str)> {
+    let directive = line.trim_start().strip_prefix("!include")?;
+    let argument = directive.trim_start_matches(is_id_char);
+    if !argument.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let argument = argument.trim_start();
+    if argument.starts_with(['<', '$', '%']) {
+        return None;
+    }
+
+    let start = line.len() - argument.len();
+    let path = argument.trim_end().split('!').next()?;
+    Some((start, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_links_in_diagram_sources() {
+        let dot = r#"digraph G {
+            // myURL="ignored" isn't an attribute
+            a [label="A", URL = "https://example.com/a"];
+            a -> b [tailURL="b.html#tail", headhref=c.svg];
+        }"#;
+        let puml = "@startuml\n  !includesub shared.puml!PART  \n\
+                    !include $THEME\n!includeurl https://example.com/s.puml\n\
+                    A -> B : [[docs/a.md]] and [[ ]]\n@enduml";
+
+        let got: Vec<_> = graphviz(dot).map(|(href, _)| href).collect();
+        let included: Vec<_> = plantuml(puml)
+            .map(|(href, span)| {
+                assert_eq!(&puml[span.start().into()..span.end().into()], href);
+                href
+            })
+            .collect();
+
+        assert_eq!(got, vec!["https://example.com/a", "b.html#tail", "c.svg"]);
+        assert_eq!(
+            included,
+            vec!["shared.puml", "https://example.com/s.puml", "docs/a.md"]
+        );
+    }
+}
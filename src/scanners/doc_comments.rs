@@ -0,0 +1,263 @@
+use codespan::Span;
+
+/// Find `L<...>` links in Perl's Pod.
+///
+/// Only the target is returned, so `L<the docs|https://example.com/>`
+/// gives `https://example.com/`. Targets can also be the name of another
+/// module or section (e.g. `L<Foo::Bar/"SYNOPSIS">`), which can be filtered
+/// out with [`crate::Category::categorise()`] when only URLs are wanted.
+///
+/// # Examples
+///
+/// ```rust
+/// let src = "See L<the docs|https://example.com/>, L<Foo::Bar>, and\n\
+///            L<< C<perlpod> | https://perldoc.perl.org/perlpod >>.";
+///
+/// let got: Vec<_> = linkcheck::scanners::pod(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(
+///     got,
+///     vec![
+///         "https://example.com/",
+///         "Foo::Bar",
+///         "https://perldoc.perl.org/perlpod",
+///     ]
+/// );
+/// ```
+pub fn pod(src: &str) -> impl Iterator<Item = (&str, Span)> + '_ {
+    src.match_indices("L<").filter_map(move |(start, _)| {
+        if src[..start].ends_with(char::is_alphanumeric) {
+            return None;
+        }
+
+        let after = &src[start + 1..];
+        let brackets = after.len() - after.trim_start_matches('<').len();
+        let content_start = start + 1 + brackets;
+
+        let content_end = if brackets == 1 {
+            content_start + single_bracket_content(&src[content_start..])?
+        } else {
+            // "L<< ... >>" needs whitespace on both sides of the content
+            let closing = " ".to_string() + &">".repeat(brackets);
+            content_start + src[content_start..].find(&closing)? + 1
+        };
+
+        let content = &src[content_start..content_end];
+        let target = match content.rfind('|') {
+            Some(bar) => &content[bar + 1..],
+            None => content,
+        };
+        let target = target.trim();
+        if target.is_empty() {
+            return None;
+        }
+
+        let start = content_start + content.rfind(target)?;
+        Some((target, span(start, target)))
+    })
+}
+
+/// The length of a `L<...>`'s content, skipping over nested formatting codes
+/// (e.g. `L<C<Foo>|Foo>`).
+fn single_bracket_content(src: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut previous = ' ';
+
+    for (i, c) in src.char_indices() {
+        match c {
+            '<' if previous.is_ascii_uppercase() => depth += 1,
+            '>' if depth == 0 => return Some(i),
+            '>' => depth -= 1,
+            '\n' if src[..i].ends_with('\n') => return None,
+            _ => {},
+        }
+        previous = c;
+    }
+
+    None
+}
+
+/// Find `{@link ...}`, `{@linkplain ...}`, and `@see` references in Javadoc
+/// comments.
+///
+/// The targets are usually classes and members (e.g. `java.util.List#add`),
+/// although `@see` can also mention a URL. Labels and parameter lists are
+/// removed. Links written as HTML (e.g. `<a href="...">`) can be found with
+/// [`crate::scanners::html()`].
+///
+/// # Examples
+///
+/// ```rust
+/// let src = "/**\n\
+///            * Use {@link java.util.List#add(int, Object) add} instead.\n\
+///            * @see <a href=\"https://example.com/\">the guide</a>\n\
+///            * @see Widget\n\
+///            */";
+///
+/// let got: Vec<_> = linkcheck::scanners::javadoc(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(got, vec!["java.util.List#add", "Widget"]);
+/// ```
+pub fn javadoc(src: &str) -> impl Iterator<Item = (&str, Span)> + '_ {
+    let mut links = inline_tags(src, &["link", "linkplain"], false);
+    links.extend(see_tags(src));
+
+    super::sort_by_span(links)
+        .into_iter()
+        .filter_map(|(target, span)| {
+            let target = target.split('(').next()?;
+            Some((target, self::span(span.start().to_usize(), target)))
+        })
+}
+
+/// Find `{@link ...}` (and `{@linkcode ...}` or `{@linkplain ...}`) and
+/// `@see` references in JSDoc comments.
+///
+/// The targets are either URLs or namepaths (e.g. `Widget#render` or
+/// `module:widgets/button`), with any label removed.
+///
+/// # Examples
+///
+/// ```rust
+/// let src = "/**\n\
+///            * Wraps [the API]{@link https://example.com/api}.\n\
+///            * See {@link module:widgets/button|Button} and\n\
+///            * {@linkcode Widget#render}.\n\
+///            */";
+///
+/// let got: Vec<_> = linkcheck::scanners::jsdoc(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(
+///     got,
+///     vec![
+///         "https://example.com/api",
+///         "module:widgets/button",
+///         "Widget#render",
+///     ]
+/// );
+/// ```
+pub fn jsdoc(src: &str) -> impl Iterator<Item = (&str, Span)> + '_ {
+    let mut links = inline_tags(src, &["link", "linkcode", "linkplain"], true);
+    links.extend(see_tags(src));
+    super::sort_by_span(links).into_iter()
+}
+
+/// Find the targets of inline tags like `{@link target label}`. JSDoc also
+/// lets the label come after a `|`.
+fn inline_tags<'a>(
+    src: &'a str,
+    tags: &[&str],
+    jsdoc: bool,
+) -> Vec<(&'a str, Span)> {
+    let mut links = Vec::new();
+
+    for (start, _) in src.match_indices("{@") {
+        let rest = &src[start + 2..];
+        let tag_length = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        if !tags.contains(&&rest[..tag_length]) {
+            continue;
+        }
+
+        let argument = &rest[tag_length..];
+        let target = argument.trim_start();
+        let target_start = src.len() - target.len();
+        let length = target_length(target, jsdoc);
+
+        if length > 0 {
+            let target = &target[..length];
+            links.push((target, span(target_start, target)));
+        }
+    }
+
+    links
+}
+
+/// Where a target ends, ignoring whitespace inside a parameter list.
+fn target_length(target: &str, jsdoc: bool) -> usize {
+    let mut depth = 0;
+
+    for (i, c) in target.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '}' => return i,
+            '|' if jsdoc => return i,
+            c if c.is_whitespace() && depth <= 0 => return i,
+            _ => {},
+        }
+    }
+
+    target.len()
+}
+
+/// Find the targets of `@see` tags, skipping the ones which are a quoted
+/// string or HTML.
+fn see_tags(src: &str) -> Vec<(&str, Span)> {
+    src.match_indices("@see")
+        .filter_map(|(start, tag)| {
+            let argument = &src[start + tag.len()..];
+            if !argument.starts_with([' ', '\t']) {
+                return None;
+            }
+
+            let target = argument.trim_start_matches([' ', '\t']);
+            if target.starts_with(['"', '<', '{']) {
+                return None;
+            }
+            let target_start = src.len() - target.len();
+
+            let length = target_length(target, false);
+            let target = target[..length].trim_end_matches(['.', ',']);
+            if target.is_empty() {
+                return None;
+            }
+
+            Some((target, span(target_start, target)))
+        })
+        .collect()
+}
+
+fn span(start: usize, text: &str) -> Span {
+    Span::new(start as u32, (start + text.len()) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Make sure each span points at the target, then return the targets.
+    fn targets<'a>(src: &str, links: Vec<(&'a str, Span)>) -> Vec<&'a str> {
+        for (href, span) in &links {
+            assert_eq!(&src[span.start().into()..span.end().into()], *href);
+        }
+
+        links.into_iter().map(|(href, _)| href).collect()
+    }
+
+    #[test]
+    fn find_links_in_doc_comments() {
+        let perl = "L<perlpod/\"Formatting Codes\">, URL<x>, \
+                    L<C<ls>|https://a.example/>";
+        let java = "{@linkplain Map#put(Object, Object) put} {@code List}";
+        let js = "@see {@link Foo} and {@link https://b.example/ B}\n\
+                  @see Bar#baz";
+
+        assert_eq!(
+            targets(perl, pod(perl).collect()),
+            vec!["perlpod/\"Formatting Codes\"", "https://a.example/"]
+        );
+        assert_eq!(targets(java, javadoc(java).collect()), vec!["Map#put"]);
+        assert_eq!(
+            targets(js, jsdoc(js).collect()),
+            vec!["Foo", "https://b.example/", "Bar#baz"]
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use codespan::Span;
+
+/// The attributes which may contain a link.
+const LINK_ATTRIBUTES: &[&str] = &["href", "src"];
+
+/// A scanner which extracts the `href` and `src` attributes from HTML
+/// elements.
+///
+/// The [`Span`] for each link covers just the attribute's value, and HTML
+/// character references (e.g. `&amp;`) are decoded. Comments and the contents
+/// of `<script>` and `<style>` elements are skipped.
+///
+/// # Examples
+///
+/// ```rust
+/// # use codespan::Span;
+/// let src = r#"<p>Go <a href="https://example.com/?a=1&amp;b=2">here</a></p>"#;
+///
+/// let got: Vec<_> = linkcheck::scanners::html(src).collect();
+///
+/// assert_eq!(got.len(), 1);
+/// let (href, span) = &got[0];
+/// assert_eq!(href, "https://example.com/?a=1&b=2");
+/// assert_eq!(*span, Span::new(15, 47));
+/// ```
+pub fn html(src: &str) -> impl Iterator<Item = (String, Span)> + '_ {
+    tags(src).into_iter().flat_map(move |tag| {
+        tag.attributes
+            .into_iter()
+            .filter(|attr| LINK_ATTRIBUTES.contains(&attr.name.as_str()))
+            .filter_map(move |attr| {
+                let span = attr.value?;
+                Some((decode_entities(attr.value_in(src)?), span))
+            })
+    })
+}
+
+/// Find every opening tag in an HTML document.
+///
+/// Comments are skipped, as are the contents of `<script>` and `<style>`
+/// elements.
+pub(crate) fn tags(src: &str) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    let mut cursor = 0;
+
+    while let Some(offset) = src[cursor..].find('<') {
+        let start = cursor + offset;
+        let rest = &src[start..];
+
+        if rest.starts_with("<!--") {
+            cursor = match rest.find("-->") {
+                Some(end) => start + end + 3,
+                None => src.len(),
+            };
+            continue;
+        }
+
+        let tag = match Tag::parse(src, start) {
+            Some(tag) => tag,
+            None => {
+                cursor = start + 1;
+                continue;
+            },
+        };
+
+        cursor = tag.end;
+
+        if tag.name == "script" || tag.name == "style" {
+            let closing = format!("</{}", tag.name);
+            cursor = match src[cursor..].to_ascii_lowercase().find(&closing) {
+                Some(end) => cursor + end,
+                None => src.len(),
+            };
+        }
+
+        if !tag.name.is_empty() {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+/// An opening HTML tag.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Tag {
+    /// The tag's name, in lowercase.
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<Attribute>,
+    /// The span of the entire tag, from `<` to `>`.
+    pub(crate) span: Span,
+    /// The index just after the closing `>`.
+    end: usize,
+}
+
+/// An attribute on a [`Tag`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Attribute {
+    /// The attribute's name, in lowercase.
+    pub(crate) name: String,
+    /// Where the attribute's value is, if it has one.
+    pub(crate) value: Option<Span>,
+}
+
+impl Attribute {
+    /// Get the attribute's raw (undecoded) value.
+    pub(crate) fn value_in<'src>(&self, src: &'src str) -> Option<&'src str> {
+        let span = self.value?;
+        src.get(span.start().to_usize()..span.end().to_usize())
+    }
+}
+
+impl Tag {
+    /// Try to parse the opening tag starting at `src[start]` (which must be a
+    /// `<`).
+    fn parse(src: &str, start: usize) -> Option<Tag> {
+        let bytes = src.as_bytes();
+        let mut i = start + 1;
+
+        if i < bytes.len() && matches!(bytes[i], b'/' | b'!' | b'?') {
+            // closing tags, doctypes, and processing instructions can't
+            // contain links
+            let end = src[i..].find('>').map(|e| i + e + 1)?;
+            return Some(Tag {
+                name: String::new(),
+                attributes: Vec::new(),
+                span: Span::new(start as u32, end as u32),
+                end,
+            });
+        }
+
+        let name_start = i;
+        while i < bytes.len()
+            && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'-')
+        {
+            i += 1;
+        }
+        if i == name_start {
+            return None;
+        }
+        let name = src[name_start..i].to_ascii_lowercase();
+        let mut attributes = Vec::new();
+
+        loop {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+
+            match bytes.get(i) {
+                None => return None,
+                Some(b'>') => break,
+                Some(b'/') => {
+                    i += 1;
+                    continue;
+                },
+                _ => {},
+            }
+
+            let attr_start = i;
+            while i < bytes.len()
+                && !bytes[i].is_ascii_whitespace()
+                && !matches!(bytes[i], b'=' | b'>' | b'/')
+            {
+                i += 1;
+            }
+            let attr_name = src[attr_start..i].to_ascii_lowercase();
+
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+
+            let mut value = None;
+            if bytes.get(i) == Some(&b'=') {
+                i += 1;
+                while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+
+                match bytes.get(i) {
+                    Some(&quote) if quote == b'"' || quote == b'\'' => {
+                        let value_start = i + 1;
+                        let value_end = value_start
+                            + src[value_start..].find(quote as char)?;
+                        value = Some(Span::new(
+                            value_start as u32,
+                            value_end as u32,
+                        ));
+                        i = value_end + 1;
+                    },
+                    _ => {
+                        let value_start = i;
+                        while i < bytes.len()
+                            && !bytes[i].is_ascii_whitespace()
+                            && bytes[i] != b'>'
+                        {
+                            i += 1;
+                        }
+                        value = Some(Span::new(value_start as u32, i as u32));
+                    },
+                }
+            }
+
+            attributes.push(Attribute {
+                name: attr_name,
+                value,
+            });
+        }
+
+        Some(Tag {
+            name,
+            attributes,
+            span: Span::new(start as u32, i as u32 + 1),
+            end: i + 1,
+        })
+    }
+}
+
+/// Decode the handful of HTML character references you'd expect to see in a
+/// URL.
+pub(crate) fn decode_entities(value: &str) -> String {
+    if !value.contains('&') {
+        return value.to_string();
+    }
+
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let replacement = rest.find(';').and_then(|semi| {
+            let entity = &rest[1..semi];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16)
+                        .ok()
+                        .and_then(std::char::from_u32)
+                },
+                _ if entity.starts_with('#') => {
+                    entity[1..].parse().ok().and_then(std::char::from_u32)
+                },
+                _ => None,
+            };
+            c.map(|c| (c, semi + 1))
+        });
+
+        match replacement {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            },
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_links_in_html() {
+        let src = r#"<!DOCTYPE html>
+<html>
+<head>
+  <link rel="stylesheet" href=style.css>
+  <script src='app.js'>var x = "<a href='nope.html'>";</script>
+</head>
+<body>
+  <!-- <a href="commented-out.html"> -->
+  <A HREF = "./page.html#section">Page</A>
+  <img alt="x" src="img/logo.png" />
+  <p data-href="not-a-link.html">text</p>
+</body>
+</html>"#;
+
+        let got: Vec<_> = html(src)
+            .map(|(href, span)| {
+                let raw = &src[span.start().to_usize()..span.end().to_usize()];
+                (href, raw.to_string())
+            })
+            .collect();
+
+        let should_be: Vec<_> =
+            vec!["style.css", "app.js", "./page.html#section", "img/logo.png"]
+                .into_iter()
+                .map(|s| (s.to_string(), s.to_string()))
+                .collect();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn decode_character_references() {
+        let inputs = vec![
+            ("a&amp;b", "a&b"),
+            ("&#x2F;path&#47;", "/path/"),
+            ("no entities", "no entities"),
+            ("dangling & ampersand", "dangling & ampersand"),
+        ];
+
+        for (src, should_be) in inputs {
+            assert_eq!(decode_entities(src), should_be);
+        }
+    }
+}
@@ -1,12 +1,23 @@
-use codespan::Span;
-use pulldown_cmark::{BrokenLink, CowStr, Event, Options, Parser, Tag};
+use crate::{Link, LinkKind};
+use codespan::{FileId, Span};
+use pulldown_cmark::{
+    BrokenLink, CowStr, Event, LinkType, Options, Parser, Tag,
+};
+use std::borrow::Cow;
 
 /// A scanner that uses [`pulldown_cmark`] to extract all links from markdown.
 ///
+/// Links are borrowed from the source text wherever possible, so no memory is
+/// allocated unless a link's destination needed to be unescaped.
+///
+/// The [`Span`] only covers the link's destination. Use [`markdown_links()`]
+/// if you also need the span of the whole element.
+///
 /// # Examples
 ///
 /// ```rust
 /// # use codespan::Span;
+/// # use std::borrow::Cow;
 /// let src = "This is a [link](https://example.com/) and an ![Image](img.png)";
 ///
 /// let got: Vec<_> = linkcheck::scanners::markdown(src).collect();
@@ -14,9 +25,11 @@ use pulldown_cmark::{BrokenLink, CowStr, Event, Options, Parser, Tag};
 /// assert_eq!(got.len(), 2);
 /// let (href, span) = &got[0];
 /// assert_eq!(href, "https://example.com/");
-/// assert_eq!(*span, Span::new(10, 38));
+/// assert_eq!(*span, Span::new(17, 37));
+/// // the href points into the original text
+/// assert!(matches!(href, Cow::Borrowed(_)));
 /// ```
-pub fn markdown(src: &str) -> impl Iterator<Item = (String, Span)> + '_ {
+pub fn markdown(src: &str) -> impl Iterator<Item = (Cow<'_, str>, Span)> + '_ {
     markdown_with_broken_link_callback(src, None)
 }
 
@@ -30,23 +43,249 @@ pub type BrokenLinkCallback<'src> = dyn FnMut(BrokenLink<'_>) -> std::option::Op
 pub fn markdown_with_broken_link_callback<'a>(
     src: &'a str,
     on_broken_link: Option<&'a mut BrokenLinkCallback<'a>>,
-) -> impl Iterator<Item = (String, Span)> + 'a {
+) -> impl Iterator<Item = (Cow<'a, str>, Span)> + 'a {
+    markdown_links(src, on_broken_link).map(|link| (link.href, link.span))
+}
+
+/// A link found by the [`markdown_links()`] scanner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownLink<'a> {
+    /// The link's destination.
+    pub href: Cow<'a, str>,
+    /// Where the destination is in the source text.
+    ///
+    /// For reference-style links this is the destination in the link
+    /// definition (e.g. `[label]: href`). When the destination doesn't appear
+    /// in the source text as-is (e.g. because it contains escapes), this is
+    /// the same as [`MarkdownLink::element`].
+    pub span: Span,
+    /// The whole link (e.g. `[text](href)` or `<href>`).
+    pub element: Span,
+    /// The link's title (e.g. `[text](href "title")`), if it has one.
+    pub title: Option<Cow<'a, str>>,
+    /// The label used by a reference-style link (e.g. `docs` in
+    /// `[text][docs]`, or `text` in `[text][]` and `[text]`).
+    pub reference_label: Option<Cow<'a, str>>,
+    /// How the link was written.
+    pub kind: LinkKind,
+}
+
+impl<'a> MarkdownLink<'a> {
+    /// Convert this into a [`Link`] belonging to a particular file.
+    pub fn into_link(self, file: FileId) -> Link {
+        let mut link =
+            Link::new(self.href, self.span, file).set_kind(self.kind);
+
+        if let Some(title) = self.title {
+            link = link.set_title(title);
+        }
+        if let Some(label) = self.reference_label {
+            link = link.set_reference_label(label);
+        }
+
+        link
+    }
+}
+
+/// Like [`markdown_with_broken_link_callback()`], except the span of each
+/// link's destination and its entire element are both returned.
+///
+/// # Examples
+///
+/// ```rust
+/// # use codespan::Span;
+/// let src = "See [the docs][docs].\n\n[docs]: https://docs.rs/";
+///
+/// let link = linkcheck::scanners::markdown_links(src, None).next().unwrap();
+///
+/// assert_eq!(link.href, "https://docs.rs/");
+/// let (start, end) = (link.element.start(), link.element.end());
+/// assert_eq!(&src[start.to_usize()..end.to_usize()], "[the docs][docs]");
+/// assert_eq!(link.span, Span::new(31, 47));
+/// ```
+pub fn markdown_links<'a>(
+    src: &'a str,
+    on_broken_link: Option<&'a mut BrokenLinkCallback<'a>>,
+) -> impl Iterator<Item = MarkdownLink<'a>> + 'a {
     Parser::new_with_broken_link_callback(
         src,
         Options::ENABLE_FOOTNOTES,
         on_broken_link,
     )
     .into_offset_iter()
-    .filter_map(|(event, range)| match event {
-        Event::Start(Tag::Link(_, dest, _))
-        | Event::Start(Tag::Image(_, dest, _)) => Some((
-            dest.to_string(),
-            Span::new(range.start as u32, range.end as u32),
-        )),
-        _ => None,
+    .filter_map(move |(event, range)| {
+        let (link_type, dest, title, is_image) = match event {
+            Event::Start(Tag::Link(link_type, dest, title)) => {
+                (link_type, dest, title, false)
+            },
+            Event::Start(Tag::Image(link_type, dest, title)) => {
+                (link_type, dest, title, true)
+            },
+            _ => return None,
+        };
+
+        let element = Span::new(range.start as u32, range.end as u32);
+        let span = destination_span(src, &dest).unwrap_or(element);
+        let reference_label = reference_label(&src[range], link_type);
+
+        let kind = if is_image {
+            LinkKind::Image
+        } else if reference_label.is_some() {
+            LinkKind::Reference
+        } else {
+            match link_type {
+                LinkType::Autolink | LinkType::Email => LinkKind::Autolink,
+                _ => LinkKind::Inline,
+            }
+        };
+
+        Some(MarkdownLink {
+            href: into_cow(dest),
+            span,
+            element,
+            title: Some(into_cow(title)).filter(|t| !t.is_empty()),
+            reference_label: reference_label.map(Cow::Borrowed),
+            kind,
+        })
+    })
+}
+
+/// Get the label out of a reference-style link's text (e.g. `[text][label]`).
+fn reference_label(element: &str, link_type: LinkType) -> Option<&str> {
+    let element = element.trim_start_matches('!');
+
+    let label = match link_type {
+        LinkType::Reference | LinkType::ReferenceUnknown => {
+            element.strip_suffix(']')?.rsplit('[').next()?
+        },
+        LinkType::Collapsed
+        | LinkType::CollapsedUnknown
+        | LinkType::Shortcut
+        | LinkType::ShortcutUnknown => {
+            // the trailing "[]" may or may not be part of a collapsed link
+            let element = element.strip_suffix("[]").unwrap_or(element);
+            element.strip_prefix('[')?.strip_suffix(']')?
+        },
+        _ => return None,
+    };
+
+    Some(label)
+}
+
+/// A more forgiving version of [`markdown()`] which also picks up links with
+/// unencoded spaces in their destination (e.g. `[doc](./my file.md)`).
+///
+/// These aren't links according to CommonMark, so they show up as plain text
+/// when rendered, but it is much more helpful to check them than to silently
+/// skip them. Destinations in angle brackets (e.g. `[doc](<./my file.md>)`)
+/// are valid CommonMark and are found by both scanners.
+///
+/// # Examples
+///
+/// ```rust
+/// let src = "Read [the doc](./my file.md) or [the other](<./other file.md>).";
+///
+/// let hrefs: Vec<_> = linkcheck::scanners::markdown_lenient(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(hrefs, vec!["./my file.md", "./other file.md"]);
+/// ```
+pub fn markdown_lenient(
+    src: &str,
+) -> impl Iterator<Item = (Cow<'_, str>, Span)> + '_ {
+    markdown_lenient_links(src).map(|link| (link.href, link.span))
+}
+
+/// Like [`markdown_lenient()`], except each link's metadata is returned as a
+/// [`MarkdownLink`].
+pub fn markdown_lenient_links(
+    src: &str,
+) -> impl Iterator<Item = MarkdownLink<'_>> + '_ {
+    let mut repaired = Vec::new();
+    let mut on_broken_link = |broken: BrokenLink<'_>| {
+        let destination = parenthesised(&src[broken.span.end..])?;
+        repaired.push(broken.span.start);
+        Some((CowStr::Borrowed(destination.trim()), CowStr::Borrowed("")))
+    };
+
+    let links: Vec<_> = markdown_links(src, Some(&mut on_broken_link))
+        .map(|link| MarkdownLink {
+            href: Cow::Owned(link.href.into_owned()),
+            title: link.title.map(|t| Cow::Owned(t.into_owned())),
+            reference_label: link.reference_label.map(|l| Cow::Owned(l.into())),
+            ..link
+        })
+        .collect();
+
+    // the callback doesn't live as long as the source text, so we need to
+    // borrow from the original text again
+    links.into_iter().map(move |mut link| {
+        let range = link.span.start().to_usize()..link.span.end().to_usize();
+
+        if let Some(original) = src.get(range) {
+            if original == link.href {
+                link.href = Cow::Borrowed(original);
+            }
+        }
+
+        let start = link.element.start().to_usize();
+        let start = start + usize::from(src[start..].starts_with('!'));
+        if repaired.contains(&start) {
+            // we made this into a link, so it's not really a reference
+            link.reference_label = None;
+            if link.kind == LinkKind::Reference {
+                link.kind = LinkKind::Inline;
+            }
+        }
+
+        link
     })
 }
 
+/// Get the text inside a pair of parentheses at the start of `text`, as long
+/// as they are on the same line.
+fn parenthesised(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix('(')?;
+    let mut depth = 0;
+
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(&rest[..i]),
+            ')' => depth -= 1,
+            '\n' => return None,
+            _ => {},
+        }
+    }
+
+    None
+}
+
+/// Find where a destination borrowed from the source text came from.
+fn destination_span(src: &str, dest: &CowStr<'_>) -> Option<Span> {
+    let dest = match dest {
+        CowStr::Borrowed(dest) => *dest,
+        _ => return None,
+    };
+
+    let start = (dest.as_ptr() as usize).checked_sub(src.as_ptr() as usize)?;
+    let end = start + dest.len();
+
+    if end <= src.len() {
+        Some(Span::new(start as u32, end as u32))
+    } else {
+        None
+    }
+}
+
+fn into_cow(s: CowStr<'_>) -> Cow<'_, str> {
+    match s {
+        CowStr::Borrowed(s) => Cow::Borrowed(s),
+        other => Cow::Owned(other.into_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,18 +303,69 @@ mod tests {
 [nowhere]: https://dev.null/
         "#;
         let should_be = vec![
-            (String::from("https://example.com"), Span::new(17, 44)),
-            (String::from("https://dev.null/"), Span::new(55, 76)),
-            (String::from("../README.md"), Span::new(82, 102)),
+            (Cow::from("https://example.com"), Span::new(24, 43)),
+            (Cow::from("https://dev.null/"), Span::new(196, 213)),
+            (Cow::from("../README.md"), Span::new(89, 101)),
             (
-                String::from("https://imgur.com/gallery/f28OkrB"),
-                Span::new(130, 183),
+                Cow::from("https://imgur.com/gallery/f28OkrB"),
+                Span::new(149, 182),
             ),
         ];
 
+        let mut on_broken_link = |_: BrokenLink<'_>| None;
         let got: Vec<_> =
-            markdown_with_broken_link_callback(src, Some(&mut |_| None)).collect();
+            markdown_with_broken_link_callback(src, Some(&mut on_broken_link))
+                .collect();
+
+        assert_eq!(got, should_be);
+    }
 
+    #[test]
+    fn lenient_scanning_finds_destinations_with_spaces() {
+        let src = "[a](<./my file.md>) [b](./my file.md) [c](
+./on another line.md) [d](./normal.md)";
+
+        let got: Vec<_> = markdown_lenient(src).collect();
+
+        let should_be = vec![
+            (Cow::from("./my file.md"), Span::new(5, 17)),
+            (Cow::from("./my file.md"), Span::new(24, 36)),
+            (Cow::from("./normal.md"), Span::new(69, 80)),
+        ];
+        assert_eq!(got, should_be);
+        assert!(got.iter().all(|(href, _)| matches!(href, Cow::Borrowed(_))));
+        // the strict scanner only finds the pointy-bracket destination
+        assert_eq!(markdown(src).count(), 2);
+    }
+
+    #[test]
+    fn keep_titles_and_reference_labels() {
+        let src = "[a](./a.md \"Title\") [b][docs] [docs][] [docs]
+<https://x.y/> ![c](./c.png) [d](./my file.md)
+
+[docs]: https://docs.rs/
+";
+
+        let got: Vec<_> = markdown_lenient_links(src)
+            .map(|link| {
+                (
+                    link.kind,
+                    link.title.map(Cow::into_owned),
+                    link.reference_label.map(Cow::into_owned),
+                )
+            })
+            .collect();
+
+        let reference = (LinkKind::Reference, None, Some("docs".to_string()));
+        let should_be = vec![
+            (LinkKind::Inline, Some("Title".to_string()), None),
+            reference.clone(),
+            reference.clone(),
+            reference,
+            (LinkKind::Autolink, None, None),
+            (LinkKind::Image, None, None),
+            (LinkKind::Inline, None, None),
+        ];
         assert_eq!(got, should_be);
     }
 }
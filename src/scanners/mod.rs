@@ -1,10 +1,31 @@
 //! A *scanner* is just a function that which can extract links from a body of
 //! text.
 
+mod combinators;
+#[cfg(feature = "container-images")]
+mod container_images;
+mod context;
+mod detect;
+mod diagrams;
+mod doc_comments;
+pub(crate) mod html;
 mod markdown;
 mod plaintext;
+mod readme;
+mod roff;
 
+pub use combinators::{dedup, merge, sort_by_span};
+#[cfg(feature = "container-images")]
+pub use container_images::container_images;
+pub use context::context;
+pub use detect::{detect, FileKind};
+pub use diagrams::{graphviz, plantuml};
+pub use doc_comments::{javadoc, jsdoc, pod};
+pub use html::html;
 pub use markdown::{
-    markdown, markdown_with_broken_link_callback, BrokenLinkCallback,
+    markdown, markdown_lenient, markdown_lenient_links, markdown_links,
+    markdown_with_broken_link_callback, BrokenLinkCallback, MarkdownLink,
 };
 pub use plaintext::plaintext;
+pub use readme::{badges, compare_links};
+pub use roff::roff;
@@ -0,0 +1,173 @@
+use crate::{scanners::MarkdownLink, LinkKind};
+use codespan::Span;
+use std::{borrow::Cow, collections::BTreeSet};
+use url::Url;
+
+/// Services which only serve badges.
+const BADGE_HOSTS: &[&str] = &[
+    "img.shields.io",
+    "badgen.net",
+    "flat.badgen.net",
+    "badge.fury.io",
+    "travis-ci.org",
+    "travis-ci.com",
+    "deps.rs",
+    "ci.appveyor.com",
+];
+
+/// Find the badges (e.g. from shields.io) in a markdown README, along with
+/// the links wrapped around them.
+///
+/// An image is treated as a badge when it comes from a badge service or its
+/// path mentions a badge (e.g. GitHub Actions' `workflows/ci.yml/badge.svg`).
+/// Reference-style images and links (e.g. `[![CI][ci-badge]][ci]`) are
+/// resolved using their definitions, with the spans pointing at the
+/// definition.
+///
+/// # Examples
+///
+/// ```rust
+/// let src = "[![Crates.io][crates-badge]][crates]\n\
+///            ![Screenshot](screenshot.png)\n\
+///            \n\
+///            [crates-badge]: https://img.shields.io/crates/v/linkcheck.svg\n\
+///            [crates]: https://crates.io/crates/linkcheck\n";
+///
+/// let got: Vec<_> = linkcheck::scanners::badges(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(
+///     got,
+///     vec![
+///         "https://crates.io/crates/linkcheck",
+///         "https://img.shields.io/crates/v/linkcheck.svg",
+///     ]
+/// );
+/// ```
+pub fn badges(src: &str) -> impl Iterator<Item = (Cow<'_, str>, Span)> + '_ {
+    let links: Vec<MarkdownLink<'_>> =
+        super::markdown_links(src, None).collect();
+    let mut badges = BTreeSet::new();
+
+    for (i, image) in links.iter().enumerate() {
+        if image.kind != LinkKind::Image || !is_badge(&image.href) {
+            continue;
+        }
+        badges.insert(i);
+
+        // links always come before the things they wrap
+        let wrapper = links[..i].iter().rposition(|link| {
+            link.kind != LinkKind::Image
+                && link.element.start() <= image.element.start()
+                && image.element.end() <= link.element.end()
+        });
+        badges.extend(wrapper);
+    }
+
+    links
+        .into_iter()
+        .enumerate()
+        .filter(move |(i, _)| badges.contains(i))
+        .map(|(_, link)| (link.href, link.span))
+}
+
+fn is_badge(href: &str) -> bool {
+    let url = match Url::parse(href) {
+        Ok(url) => url,
+        Err(_) => return false,
+    };
+
+    url.host_str()
+        .is_some_and(|host| BADGE_HOSTS.contains(&host))
+        || url
+            .path()
+            .split('/')
+            .any(|segment| segment.starts_with("badge"))
+}
+
+/// Find links to a comparison between two refs on GitHub or GitLab (e.g.
+/// `https://github.com/o/r/compare/v1.0.0...v1.1.0`), the way they
+/// are usually written at the bottom of a `CHANGELOG.md`.
+///
+/// Every URL in the text is looked at, so definitions which are never used
+/// (e.g. an `[Unreleased]` link without a matching heading) are found too.
+/// Use [`crate::validation::ForgeApi`] to make sure both refs exist.
+///
+/// # Examples
+///
+/// ```rust
+/// let src = "## [1.1.0] - 2021-02-03\n\
+///            \n\
+///            - Fixed [#42](https://github.com/o/r/issues/42)\n\
+///            \n\
+///            [Unreleased]: https://github.com/o/r/compare/v1.1.0...HEAD\n\
+///            [1.1.0]: https://github.com/o/r/compare/v1.0.0...v1.1.0\n";
+///
+/// let got: Vec<_> = linkcheck::scanners::compare_links(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(
+///     got,
+///     vec![
+///         "https://github.com/o/r/compare/v1.1.0...HEAD",
+///         "https://github.com/o/r/compare/v1.0.0...v1.1.0",
+///     ]
+/// );
+/// ```
+pub fn compare_links(src: &str) -> impl Iterator<Item = (&str, Span)> + '_ {
+    super::plaintext(src).filter(|(href, _)| is_comparison(href))
+}
+
+fn is_comparison(href: &str) -> bool {
+    let url = match Url::parse(href) {
+        Ok(url) => url,
+        Err(_) => return false,
+    };
+
+    match url.path_segments() {
+        Some(segments) => {
+            let segments: Vec<_> = segments.collect();
+            segments
+                .windows(2)
+                .any(|pair| pair[0] == "compare" && !pair[1].is_empty())
+        },
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_badges_and_comparisons_in_a_readme() {
+        let src = "[![CI](https://ci.rs/o/r/badge.svg)](https://ci.rs/)\n\
+                   [![Docs][docs-badge]][docs] [![Deps][deps-badge]][docs]\n\
+                   [![Demo](demo.gif)](https://example.com/)\n\
+                   See <https://github.com/o/r/compare> and [the diff].\n\
+                   \n\
+                   [docs-badge]: https://docs.rs/r/badge.svg\n\
+                   [deps-badge]: https://deps.rs/repo/github/o/r/status.svg\n\
+                   [docs]: https://docs.rs/r\n\
+                   [the diff]: https://gitlab.com/g/r/-/compare/a...b\n";
+
+        let got: Vec<_> = badges(src).map(|(href, _)| href).collect();
+        let comparisons: Vec<_> =
+            compare_links(src).map(|(href, _)| href).collect();
+
+        assert_eq!(
+            got,
+            vec![
+                "https://ci.rs/",
+                "https://ci.rs/o/r/badge.svg",
+                "https://docs.rs/r",
+                "https://docs.rs/r/badge.svg",
+                "https://docs.rs/r",
+                "https://deps.rs/repo/github/o/r/status.svg",
+            ]
+        );
+        assert_eq!(comparisons, vec!["https://gitlab.com/g/r/-/compare/a...b"]);
+    }
+}
@@ -0,0 +1,147 @@
+use codespan::Span;
+use std::borrow::Cow;
+
+/// Find links in a man page (or any other `roff` source).
+///
+/// This picks up `.UR` (and mdoc's `.Lk`) hyperlinks, `.MT` email addresses
+/// (as `mailto:` links), and bare URLs in the text. Escapes commonly used
+/// inside URLs (e.g. `\-` and `\:`) are removed, so the returned spans may
+/// be longer than the links themselves. Comments are skipped.
+///
+/// # Examples
+///
+/// ```rust
+/// let src = r#".TH FOO 1
+/// .SH SEE ALSO
+/// .UR https://example.com/foo\-docs
+/// The manual
+/// .UE
+/// .MT bugs@example.com
+/// .ME
+/// .\" https://example.com/commented-out
+/// Report bugs at https://example.com/issues.
+/// "#;
+///
+/// let got: Vec<_> = linkcheck::scanners::roff(src)
+///     .map(|(href, _)| href)
+///     .collect();
+///
+/// assert_eq!(
+///     got,
+///     vec![
+///         "https://example.com/foo-docs",
+///         "mailto:bugs@example.com",
+///         "https://example.com/issues",
+///     ]
+/// );
+/// ```
+pub fn roff(src: &str) -> impl Iterator<Item = (Cow<'_, str>, Span)> + '_ {
+    let mut offset = 0;
+
+    src.split('\n').flat_map(move |line| {
+        let line_start = offset;
+        offset += line.len() + 1;
+
+        let links: Vec<_> = match request(line) {
+            Some(("UR", argument)) | Some(("Lk", argument)) => {
+                link_from(line, argument, "").into_iter().collect()
+            },
+            Some(("MT", argument)) => {
+                link_from(line, argument, "mailto:").into_iter().collect()
+            },
+            _ => {
+                let text = line.split("\\\"").next().unwrap_or(line);
+                super::plaintext(text)
+                    .map(|(url, span)| (unescape(url, ""), span))
+                    .collect()
+            },
+        };
+
+        links.into_iter().map(move |(href, span)| {
+            let start = line_start as u32 + span.start().to_usize() as u32;
+            let end = line_start as u32 + span.end().to_usize() as u32;
+            (href, Span::new(start, end))
+        })
+    })
+}
+
+/// Split a control line (e.g. `.UR https://example.com/`) into the macro's
+/// name and its arguments.
+fn request(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(['.', '\''])?.trim_start();
+
+    match rest.split_once(char::is_whitespace) {
+        Some((name, arguments)) => Some((name, arguments.trim())),
+        None => Some((rest, "")),
+    }
+}
+
+/// Turn the first argument to a macro into a link, with a span relative to
+/// the start of the line.
+fn link_from<'a>(
+    line: &'a str,
+    arguments: &'a str,
+    prefix: &str,
+) -> Option<(Cow<'a, str>, Span)> {
+    let argument = arguments.split_whitespace().next()?.trim_matches('"');
+    if argument.is_empty() {
+        return None;
+    }
+
+    let start = line.find(argument)?;
+    let span = Span::new(start as u32, (start + argument.len()) as u32);
+    Some((unescape(argument, prefix), span))
+}
+
+/// Remove the escapes which tend to show up in URLs, adding a `prefix`.
+fn unescape<'a>(text: &'a str, prefix: &str) -> Cow<'a, str> {
+    if prefix.is_empty() && !text.contains('\\') {
+        return Cow::Borrowed(text);
+    }
+
+    let unescaped = text
+        .replace("\\-", "-")
+        .replace("\\(ti", "~")
+        .replace("\\[ti]", "~")
+        .replace("\\:", "")
+        .replace("\\&", "")
+        .replace("\\%", "")
+        .replace("\\e", "\\");
+
+    Cow::Owned(format!("{}{}", prefix, unescaped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_links_in_a_man_page() {
+        let src = ".TH FOO 1\n\
+                   .UR \"https://\\:example.com/\\(tiuser/a\\-b\"\n\
+                   home page\n\
+                   .UE\n\
+                   .Lk https://example.org/ mdoc\n\
+                   \\\" http://example.com/comment\n\
+                   See http://example.net/ for more.\n";
+
+        let got: Vec<_> = roff(src)
+            .map(|(href, span)| {
+                let raw = &src[span.start().into()..span.end().into()];
+                (href.into_owned(), raw)
+            })
+            .collect();
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    String::from("https://example.com/~user/a-b"),
+                    "https://\\:example.com/\\(tiuser/a\\-b",
+                ),
+                (String::from("https://example.org/"), "https://example.org/"),
+                (String::from("http://example.net/"), "http://example.net/"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,507 @@
+//! Walk a directory tree, collecting all the [`Link`]s in every document.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use codespan::Files;
+//! use linkcheck::{site::Site, BasicContext};
+//!
+//! # #[tokio::main] async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut files = Files::new();
+//! let documents = Site::new("./book/src")
+//!     .include("**/*.md")?
+//!     .exclude("drafts/**")?
+//!     .scan(&mut files)?;
+//!
+//! let ctx = BasicContext::default();
+//! let outcomes = linkcheck::site::validate(&documents, &ctx).await;
+//!
+//! for invalid in &outcomes.invalid {
+//!     println!("{:?}", invalid);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    scanners::{FileKind, MarkdownLink},
+    validation::{self, Context, Origin, Outcomes, PlannedCheck},
+    Link, LinkKind,
+};
+use codespan::{FileId, Files, Span};
+use futures::Stream;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// A directory of documents to be scanned for [`Link`]s.
+#[derive(Debug, Clone)]
+pub struct Site {
+    root: PathBuf,
+    include: GlobSetBuilder,
+    exclude: GlobSetBuilder,
+    has_includes: bool,
+    lenient: bool,
+    capture_context: bool,
+}
+
+impl Site {
+    /// Create a new [`Site`] rooted at a particular directory.
+    ///
+    /// By default every file with a known extension (see
+    /// [`Site::scanner_for()`]) will be scanned, as well as files without an
+    /// extension which look like a document (see
+    /// [`crate::scanners::detect()`]). Hidden files and directories (e.g.
+    /// `.git/`) are always skipped.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Site {
+            root: root.into(),
+            include: GlobSetBuilder::new(),
+            exclude: GlobSetBuilder::new(),
+            has_includes: false,
+            lenient: false,
+            capture_context: false,
+        }
+    }
+
+    /// The directory being scanned.
+    pub fn root(&self) -> &Path { &self.root }
+
+    /// Only scan files matching this glob (relative to [`Site::root()`]).
+    ///
+    /// If this is called multiple times, a file will be scanned when it
+    /// matches *any* of the patterns.
+    pub fn include(mut self, pattern: &str) -> Result<Self, globset::Error> {
+        self.include.add(Glob::new(pattern)?);
+        self.has_includes = true;
+        Ok(self)
+    }
+
+    /// Skip any files matching this glob (relative to [`Site::root()`]),
+    /// even if they would otherwise be included.
+    pub fn exclude(mut self, pattern: &str) -> Result<Self, globset::Error> {
+        self.exclude.add(Glob::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Are markdown documents scanned with
+    /// [`crate::scanners::markdown_lenient()`]?
+    pub fn lenient(&self) -> bool { self.lenient }
+
+    /// Set [`Site::lenient()`], so markdown links with unencoded spaces
+    /// (e.g. `[doc](./my file.md)`) are checked instead of skipped.
+    pub fn set_lenient(self, lenient: bool) -> Self { Site { lenient, ..self } }
+
+    /// Does each [`Link`] get a [`Link::context`] (see
+    /// [`crate::scanners::context()`])?
+    pub fn capture_context(&self) -> bool { self.capture_context }
+
+    /// Set [`Site::capture_context()`].
+    pub fn set_capture_context(self, capture_context: bool) -> Self {
+        Site {
+            capture_context,
+            ..self
+        }
+    }
+
+    /// Walk the directory tree, adding each document to `files` and
+    /// extracting its [`Link`]s.
+    ///
+    /// Files which aren't valid UTF-8 are logged and skipped.
+    pub fn scan(&self, files: &mut Files<String>) -> io::Result<Vec<Document>> {
+        let mut documents = Vec::new();
+
+        for path in self.paths()? {
+            let src = match std::fs::read_to_string(&path) {
+                Ok(src) => src,
+                // one bad file shouldn't stop us from checking the rest
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    log::warn!(
+                        "Skipping \"{}\" because it isn't valid UTF-8",
+                        path.display()
+                    );
+                    continue;
+                },
+                Err(e) => return Err(e),
+            };
+            let kind = match crate::scanners::detect(&path, &src) {
+                Some(kind) => kind,
+                None => continue,
+            };
+
+            log::debug!("Scanning \"{}\" as {:?}", path.display(), kind);
+            let file = files.add(&path, src);
+            let src = files.source(file);
+            let links = if kind == FileKind::Markdown {
+                self.scan_markdown(src, file)
+            } else {
+                let link_kind = match kind {
+                    FileKind::PlainText | FileKind::Roff => LinkKind::PlainText,
+                    FileKind::Graphviz | FileKind::PlantUml => {
+                        LinkKind::Diagram
+                    },
+                    _ => LinkKind::Html,
+                };
+
+                Site::scanner_for_kind(kind)(src)
+                    .into_iter()
+                    .map(|(href, span)| {
+                        let link =
+                            Link::new(href, span, file).set_kind(link_kind);
+                        self.with_context(link, src, span)
+                    })
+                    .collect()
+            };
+
+            documents.push(Document { file, path, links });
+        }
+
+        Ok(documents)
+    }
+
+    /// Walk the directory tree, finding every file that would be scanned by
+    /// [`Site::scan()`].
+    pub fn paths(&self) -> io::Result<Vec<PathBuf>> {
+        let include = self.include.build().map_err(invalid_input)?;
+        let exclude = self.exclude.build().map_err(invalid_input)?;
+        let mut paths = Vec::new();
+
+        let walker = WalkDir::new(&self.root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry));
+
+        for entry in walker {
+            let entry = entry?;
+
+            if entry.file_type().is_file()
+                && self.is_selected(entry.path(), &include, &exclude)
+                && is_scannable(entry.path())
+            {
+                paths.push(entry.into_path());
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Pick a scanner based on a file's extension (see
+    /// [`FileKind::from_path()`]).
+    pub fn scanner_for(path: &Path) -> Option<Scanner> {
+        FileKind::from_path(path).map(Site::scanner_for_kind)
+    }
+
+    /// The scanner used for a particular [`FileKind`] (e.g. one found using
+    /// [`crate::scanners::detect()`]).
+    pub fn scanner_for_kind(kind: FileKind) -> Scanner {
+        match kind {
+            FileKind::Markdown => scan_markdown,
+            FileKind::Html => scan_html,
+            FileKind::PlainText => scan_plaintext,
+            FileKind::Graphviz => scan_graphviz,
+            FileKind::PlantUml => scan_plantuml,
+            FileKind::Roff => scan_roff,
+        }
+    }
+
+    /// Scan a markdown document, keeping each link's title, reference label,
+    /// and kind.
+    fn scan_markdown(&self, src: &str, file: FileId) -> Vec<Link> {
+        let to_link = |link: MarkdownLink<'_>| {
+            // the element is where the link was used, even for references
+            let element = link.element;
+            self.with_context(link.into_link(file), src, element)
+        };
+
+        if self.lenient {
+            crate::scanners::markdown_lenient_links(src)
+                .map(to_link)
+                .collect()
+        } else {
+            crate::scanners::markdown_links(src, None)
+                .map(to_link)
+                .collect()
+        }
+    }
+
+    fn with_context(&self, link: Link, src: &str, span: Span) -> Link {
+        if self.capture_context {
+            link.set_context(crate::scanners::context(src, span))
+        } else {
+            link
+        }
+    }
+
+    fn is_selected(
+        &self,
+        path: &Path,
+        include: &GlobSet,
+        exclude: &GlobSet,
+    ) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+
+        (!self.has_includes || include.is_match(relative))
+            && !exclude.is_match(relative)
+    }
+}
+
+/// A function which extracts links from some text.
+pub type Scanner = fn(&str) -> Vec<(String, codespan::Span)>;
+
+/// Hidden files and directories, which includes version control metadata
+/// like `.git/` and `.hg/`.
+fn is_hidden(entry: &walkdir::DirEntry) -> bool {
+    entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// Could [`crate::scanners::detect()`] find a scanner for this file?
+///
+/// Files without an extension need to be sniffed, so we only read the first
+/// few kilobytes instead of the whole file.
+fn is_scannable(path: &Path) -> bool {
+    if path.extension().is_some() {
+        return FileKind::from_path(path).is_some();
+    }
+
+    let mut head = Vec::new();
+    let read =
+        File::open(path).and_then(|f| f.take(4096).read_to_end(&mut head));
+    if read.is_err() {
+        return false;
+    }
+    let head = match std::str::from_utf8(&head) {
+        Ok(head) => head,
+        // we may have cut a character in half
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or_default()
+        },
+        Err(_) => return false,
+    };
+
+    FileKind::sniff(head).is_some()
+}
+
+fn scan_markdown(src: &str) -> Vec<(String, codespan::Span)> {
+    crate::scanners::markdown(src)
+        .map(|(href, span)| (href.into_owned(), span))
+        .collect()
+}
+
+fn scan_html(src: &str) -> Vec<(String, codespan::Span)> {
+    crate::scanners::html(src).collect()
+}
+
+fn scan_plaintext(src: &str) -> Vec<(String, codespan::Span)> {
+    crate::scanners::plaintext(src)
+        .map(|(href, span)| (href.to_string(), span))
+        .collect()
+}
+
+fn scan_graphviz(src: &str) -> Vec<(String, codespan::Span)> {
+    crate::scanners::graphviz(src)
+        .map(|(href, span)| (href.to_string(), span))
+        .collect()
+}
+
+fn scan_plantuml(src: &str) -> Vec<(String, codespan::Span)> {
+    crate::scanners::plantuml(src)
+        .map(|(href, span)| (href.to_string(), span))
+        .collect()
+}
+
+fn scan_roff(src: &str) -> Vec<(String, codespan::Span)> {
+    crate::scanners::roff(src)
+        .map(|(href, span)| (href.into_owned(), span))
+        .collect()
+}
+
+fn invalid_input(e: globset::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
+
+/// A single file found by [`Site::scan()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    /// The file's ID in [`Files`].
+    pub file: FileId,
+    /// Where the file is on disk.
+    pub path: PathBuf,
+    /// All the [`Link`]s in this file.
+    pub links: Vec<Link>,
+}
+
+impl Document {
+    /// The directory relative links in this [`Document`] are resolved from.
+    pub fn directory(&self) -> &Path {
+        self.path.parent().unwrap_or_else(|| Path::new("."))
+    }
+}
+
+/// Validate the [`Link`]s in a set of [`Document`]s, resolving relative links
+/// from the directory each [`Document`] lives in.
+pub async fn validate<C>(documents: &[Document], ctx: &C) -> Outcomes
+where
+    C: Context + Sync + ?Sized,
+{
+    validation::validate_many(links(documents), ctx).await
+}
+
+/// Like [`validate()`], except the [`Outcomes`] for each [`Link`] are
+/// yielded as soon as it has been checked (see
+/// [`validation::validate_stream()`]).
+pub fn validate_stream<'a, C>(
+    documents: &'a [Document],
+    ctx: &'a C,
+) -> impl Stream<Item = Outcomes> + 'a
+where
+    C: Context + ?Sized,
+{
+    validation::validate_stream_many(
+        futures::stream::iter(links(documents)),
+        ctx,
+    )
+}
+
+/// Work out how the [`Link`]s in a set of [`Document`]s would be checked,
+/// without checking them (see [`validation::plan()`]).
+pub fn plan<C>(documents: &[Document], ctx: &C) -> Vec<PlannedCheck>
+where
+    C: Context + ?Sized,
+{
+    links(documents)
+        .map(|(link, origin)| validation::plan_from(link, origin, ctx))
+        .collect()
+}
+
+fn links(
+    documents: &[Document],
+) -> impl Iterator<Item = (Link, Origin<'_>)> + '_ {
+    documents.iter().flat_map(|doc| {
+        let origin = Origin {
+            directory: doc.directory(),
+            document: Some(&doc.path),
+        };
+        doc.links.iter().map(move |link| (link.clone(), origin))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{anchors::AnchorDb, validation::Reason, BasicContext};
+
+    fn write<P: AsRef<Path>>(path: P, contents: &str) {
+        let path = path.as_ref();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[tokio::test]
+    async fn scan_and_validate_a_directory_tree() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(temp.path()).unwrap();
+        write(root.join("index.md"), "[chapter](chapter/index.md)");
+        write(
+            root.join("chapter/index.md"),
+            "[up](../index.md) [](nope.md)",
+        );
+        write(root.join("drafts/wip.md"), "[x](missing.md)");
+        write(root.join("notes.txt"), "see https://example.com/");
+        write(root.join("image.png"), "");
+        let mut files = Files::new();
+
+        let documents = Site::new(&root)
+            .include("**/*.md")
+            .unwrap()
+            .exclude("drafts/**")
+            .unwrap()
+            .scan(&mut files)
+            .unwrap();
+
+        let paths: Vec<_> = documents.iter().map(|d| d.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![root.join("chapter/index.md"), root.join("index.md")]
+        );
+        let outcomes = validate(&documents, &BasicContext::default()).await;
+        assert_eq!(outcomes.valid.len(), 2);
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert_eq!(outcomes.invalid[0].link.href, "nope.md");
+    }
+
+    #[tokio::test]
+    async fn check_links_to_sections_in_the_same_document() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(temp.path()).unwrap();
+        write(
+            root.join("index.md"),
+            "# Usage\n\n[ok](#usage) [typo](#usgae) [x](#something-else)",
+        );
+        let mut files = Files::new();
+        let documents = Site::new(&root).scan(&mut files).unwrap();
+        let mut ctx = BasicContext::default();
+        ctx.anchors = Some(AnchorDb::from_documents(&documents, &files));
+
+        let outcomes = validate(&documents, &ctx).await;
+
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.valid[0].href, "#usage");
+        let mut reasons: Vec<_> = outcomes
+            .invalid
+            .iter()
+            .map(|invalid| match &invalid.reason {
+                Reason::MissingAnchor(fragment, closest) => {
+                    (fragment.as_str(), closest.as_deref())
+                },
+                other => panic!("Unexpected reason: {}", other),
+            })
+            .collect();
+        reasons.sort();
+        assert_eq!(
+            reasons,
+            vec![("something-else", None), ("usgae", Some("usage"))]
+        );
+        assert_eq!(outcomes.suggestions.len(), 1);
+        assert_eq!(outcomes.suggestions[0].replacement, "#usage");
+    }
+
+    #[test]
+    fn skip_files_which_are_not_utf8() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(temp.path()).unwrap();
+        write(root.join("good.md"), "[link](other.md)");
+        // valid for the first few kilobytes, so it isn't caught by sniffing
+        let mut bad = vec![b'a'; 5000];
+        bad.push(0xff);
+        std::fs::write(root.join("bad.md"), bad).unwrap();
+        let mut files = Files::new();
+
+        let documents = Site::new(&root).scan(&mut files).unwrap();
+
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].path, root.join("good.md"));
+    }
+
+    #[test]
+    fn only_scan_things_which_look_like_documents() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(temp.path()).unwrap();
+        write(root.join("README"), "# Project\n\n[docs](docs/index.md)");
+        write(root.join("Makefile"), "deploy:\n\tcurl https://x.y/build\n");
+        write(root.join(".git/config"), "[remote]\n\turl = https://x.y/z");
+        write(root.join(".git/HEAD.md"), "# ref\n");
+        write(root.join("docs/.hidden.md"), "# draft\n");
+        write(root.join("docs/index.md"), "# Docs\n");
+
+        let paths = Site::new(&root).paths().unwrap();
+
+        assert_eq!(
+            paths,
+            vec![root.join("README"), root.join("docs/index.md")]
+        );
+    }
+}
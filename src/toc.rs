@@ -0,0 +1,469 @@
+//! Checking (and regenerating) the hand-written table of contents at the top
+//! of a markdown document.
+//!
+//! A table of contents is the first list whose items are all links to a
+//! heading in the same document (e.g. `- [Usage](#usage)`). Headings get
+//! renamed and sections get added, so [`check()`] makes sure each entry goes
+//! to a heading which exists and that every heading after the table of
+//! contents has an entry. [`update()`] creates an [`Edit`] which replaces
+//! the table of contents with one generated from the headings.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use codespan::Files;
+//! use linkcheck::{
+//!     lints::LintKind,
+//!     toc::{self, TocOptions},
+//! };
+//!
+//! let src = concat!(
+//!     "# Project\n\n",
+//!     "- [Install](#install)\n- [Usage](#usage)\n\n",
+//!     "## Installation\n\n## Usage\n",
+//! );
+//! let mut files = Files::new();
+//! let file = files.add("README.md", src);
+//!
+//! let outcomes = toc::check(src, file, &TocOptions::default());
+//!
+//! assert_eq!(outcomes.lints.len(), 2);
+//! assert_eq!(outcomes.lints[0].kind, LintKind::StaleTocEntry);
+//! assert_eq!(
+//!     outcomes.lints[1].kind,
+//!     LintKind::MissingTocEntry(String::from("Installation"))
+//! );
+//!
+//! let edit = toc::update(src, &TocOptions::default()).unwrap();
+//! assert_eq!(
+//!     edit.replacement,
+//!     "- [Installation](#installation)\n- [Usage](#usage)"
+//! );
+//! ```
+
+use crate::{
+    anchors::slugify,
+    fix::{Edit, Suggestion, SuggestionKind},
+    lints::{Lint, LintKind},
+    validation::Outcomes,
+    Link,
+};
+use codespan::{FileId, Span};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Which headings go in a table of contents, and how it is written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TocOptions {
+    /// The smallest heading level to include (the default skips the
+    /// document's `#` title).
+    pub min_level: u32,
+    /// The largest heading level to include.
+    pub max_level: u32,
+    /// Write numbered (ordered) lists instead of bullet points.
+    pub numbered: bool,
+}
+
+impl TocOptions {
+    fn includes(&self, heading: &Heading) -> bool {
+        self.min_level <= heading.level && heading.level <= self.max_level
+    }
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        TocOptions {
+            min_level: 2,
+            max_level: 3,
+            numbered: false,
+        }
+    }
+}
+
+/// A heading in a markdown document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// The heading's level (e.g. `2` for `## Usage`).
+    pub level: u32,
+    /// The heading's text, without any formatting.
+    pub title: String,
+    /// The anchor used to link to the heading, including the `-1`, `-2`,
+    /// ... suffix given to duplicates.
+    pub anchor: String,
+    /// Where the heading is in the document.
+    pub span: Span,
+}
+
+/// Find every heading in a markdown document.
+pub fn headings(src: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut slug_counts: HashMap<String, usize> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    let events = Parser::new_ext(src, Options::all()).into_offset_iter();
+
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::Heading(_)) => current = Some(String::new()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(title) = current.as_mut() {
+                    title.push_str(&text);
+                }
+            },
+            Event::End(Tag::Heading(level)) => {
+                let title = current.take().unwrap_or_default();
+                let slug = slugify(&title);
+                let count = slug_counts.entry(slug.clone()).or_insert(0);
+                let anchor = match *count {
+                    0 => slug,
+                    n => format!("{}-{}", slug, n),
+                };
+                *count += 1;
+
+                let span = src[range.clone()].trim_end();
+                headings.push(Heading {
+                    level,
+                    title: title.trim().to_string(),
+                    anchor,
+                    span: Span::new(
+                        range.start as u32,
+                        (range.start + span.len()) as u32,
+                    ),
+                });
+            },
+            _ => {},
+        }
+    }
+
+    headings
+}
+
+/// A hand-written table of contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableOfContents {
+    /// The whole list, from the start of the first entry to the end of the
+    /// last one.
+    pub span: Span,
+    /// Each item in the list.
+    pub entries: Vec<TocEntry>,
+}
+
+/// An item in a [`TableOfContents`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// The link's text.
+    pub title: String,
+    /// The anchor being linked to (without the `#`).
+    pub anchor: String,
+    /// Where the link's `href` (e.g. `#usage`) is.
+    pub span: Span,
+    /// How deeply the item is nested, starting at `0`.
+    pub depth: usize,
+}
+
+impl TableOfContents {
+    /// Find the first list in a markdown document which has at least two
+    /// items and only contains links to anchors.
+    pub fn find(src: &str) -> Option<TableOfContents> {
+        let mut in_code_block = false;
+        let mut current: Vec<(usize, &str)> = Vec::new();
+        let mut offset = 0;
+
+        for line in src.split('\n') {
+            let line_start = offset;
+            offset += line.len() + 1;
+
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code_block = !in_code_block;
+            }
+
+            if !in_code_block && entry(line).is_some() {
+                current.push((line_start, line));
+            } else if !trimmed.is_empty() || in_code_block {
+                if current.len() >= 2 {
+                    break;
+                }
+                current.clear();
+            }
+        }
+
+        if current.len() < 2 {
+            return None;
+        }
+
+        let indents: BTreeSet<usize> = current
+            .iter()
+            .map(|(_, line)| line.len() - line.trim_start().len())
+            .collect();
+        let indents: Vec<_> = indents.into_iter().collect();
+
+        let entries = current
+            .iter()
+            .filter_map(|&(line_start, line)| {
+                let (indent, title, href_start, anchor) = entry(line)?;
+                let start = line_start + href_start;
+
+                Some(TocEntry {
+                    title: title.to_string(),
+                    anchor: anchor.to_string(),
+                    span: Span::new(
+                        start as u32,
+                        (start + anchor.len() + 1) as u32,
+                    ),
+                    depth: indents.binary_search(&indent).ok()?,
+                })
+            })
+            .collect();
+
+        let (first_start, _) = current[0];
+        let (last_start, last) = current[current.len() - 1];
+
+        Some(TableOfContents {
+            span: Span::new(
+                first_start as u32,
+                (last_start + last.trim_end().len()) as u32,
+            ),
+            entries,
+        })
+    }
+}
+
+/// Parse a line like `  - [Usage](#usage)`, returning its indent, the link
+/// text, where the `#` is, and the anchor.
+fn entry(line: &str) -> Option<(usize, &str, usize, &str)> {
+    let item = line.trim_start();
+    let indent = line.len() - item.len();
+
+    let rest = match item.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let number = item.trim_start_matches(|c: char| c.is_ascii_digit());
+            if number.len() == item.len() {
+                return None;
+            }
+            number.strip_prefix(['.', ')'])?
+        },
+    };
+    if !rest.starts_with(' ') {
+        return None;
+    }
+
+    let link = rest.trim_start().strip_prefix('[')?;
+    let (title, target) = link.split_once("](#")?;
+    let (anchor, after) = target.split_once(')')?;
+    if anchor.is_empty() || anchor.contains(char::is_whitespace) {
+        return None;
+    }
+    if !after.trim().is_empty() {
+        return None;
+    }
+
+    let href_start = line.len() - target.len() - 1;
+    Some((indent, title, href_start, anchor))
+}
+
+/// Make sure each entry in the document's table of contents goes to a
+/// heading and every heading after the table of contents has an entry.
+///
+/// Problems are reported as [`Lint`]s, and when a broken entry's text
+/// matches a heading the new anchor is suggested. The returned [`Outcomes`]
+/// are empty when there is no table of contents.
+pub fn check(src: &str, file: FileId, options: &TocOptions) -> Outcomes {
+    let mut outcomes = Outcomes::empty();
+    let toc = match TableOfContents::find(src) {
+        Some(toc) => toc,
+        None => return outcomes,
+    };
+    let headings = headings(src);
+    let anchors: HashSet<&str> =
+        headings.iter().map(|h| h.anchor.as_str()).collect();
+
+    for entry in &toc.entries {
+        if anchors.contains(entry.anchor.as_str()) {
+            continue;
+        }
+
+        let link = Link::new(format!("#{}", entry.anchor), entry.span, file);
+        let slug = slugify(&entry.title);
+        let renamed = headings.iter().find(|h| {
+            h.title.eq_ignore_ascii_case(entry.title.trim()) || h.anchor == slug
+        });
+
+        if let Some(heading) = renamed {
+            outcomes.suggestions.push(Suggestion {
+                link: link.clone(),
+                replacement: format!("#{}", heading.anchor),
+                kind: SuggestionKind::Anchor,
+            });
+        }
+        outcomes.lints.push(Lint {
+            link,
+            kind: LintKind::StaleTocEntry,
+        });
+    }
+
+    let listed: HashSet<&str> =
+        toc.entries.iter().map(|e| e.anchor.as_str()).collect();
+    let missing = headings.iter().filter(|h| {
+        h.span.start() > toc.span.end()
+            && options.includes(h)
+            && !listed.contains(h.anchor.as_str())
+    });
+
+    for heading in missing {
+        outcomes.lints.push(Lint {
+            link: Link::new(format!("#{}", heading.anchor), heading.span, file),
+            kind: LintKind::MissingTocEntry(heading.title.clone()),
+        });
+    }
+
+    outcomes
+}
+
+/// Generate a table of contents from a document's headings.
+///
+/// When the document already has a table of contents, only the headings
+/// after it are included.
+pub fn generate(src: &str, options: &TocOptions) -> String {
+    let after = TableOfContents::find(src)
+        .map(|toc| toc.span.end())
+        .unwrap_or_default();
+    let headings = headings(src);
+    let headings = headings
+        .iter()
+        .filter(|h| h.span.start() >= after && options.includes(h));
+
+    let mut lines = Vec::new();
+    // the number for the next item at each depth
+    let mut numbers: Vec<usize> = Vec::new();
+
+    for heading in headings {
+        // don't skip a level when a document does (e.g. "##" then "####")
+        let depth =
+            ((heading.level - options.min_level) as usize).min(numbers.len());
+        numbers.truncate(depth + 1);
+        if numbers.len() <= depth {
+            numbers.push(1);
+        }
+
+        let (indent, marker) = if options.numbered {
+            (4 * depth, format!("{}.", numbers[depth]))
+        } else {
+            (2 * depth, String::from("-"))
+        };
+        numbers[depth] += 1;
+
+        let title = heading.title.replace('[', "\\[").replace(']', "\\]");
+        lines.push(format!(
+            "{:indent$}{} [{}](#{})",
+            "",
+            marker,
+            title,
+            heading.anchor,
+            indent = indent
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// Replace the document's table of contents with one generated from its
+/// headings, or `None` if there is no table of contents or it is already
+/// up to date.
+pub fn update(src: &str, options: &TocOptions) -> Option<Edit> {
+    let toc = TableOfContents::find(src)?;
+    let start = toc.span.start().to_usize();
+    let end = toc.span.end().to_usize();
+    let original = &src[start..end];
+    let replacement = generate(src, options);
+
+    if original == replacement {
+        None
+    } else {
+        Some(Edit {
+            span: toc.span,
+            original: original.to_string(),
+            replacement,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codespan::Files;
+
+    const SRC: &str = "# Title
+
+Contents:
+
+1. [Intro](#intro)
+   1. [Set Up](#set-up)
+2. [Usage](#usage)
+
+```md
+- [Not](#a-toc)
+- [In a](#code-block)
+```
+
+## Intro
+
+### Setting Up
+
+#### Details
+
+## Intro
+
+## Usage
+";
+
+    #[test]
+    fn find_and_check_the_table_of_contents() {
+        let mut files = Files::new();
+        let file = files.add("README.md", SRC);
+
+        let toc = TableOfContents::find(SRC).unwrap();
+        let outcomes = check(SRC, file, &TocOptions::default());
+
+        let depths: Vec<_> = toc.entries.iter().map(|e| e.depth).collect();
+        assert_eq!(depths, vec![0, 1, 0]);
+        let span = toc.entries[1].span;
+        assert_eq!(&SRC[span.start().into()..span.end().into()], "#set-up");
+        let kinds: Vec<_> = outcomes.lints.iter().map(|l| &l.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                &LintKind::StaleTocEntry,
+                &LintKind::MissingTocEntry(String::from("Setting Up")),
+                &LintKind::MissingTocEntry(String::from("Intro")),
+            ]
+        );
+        assert_eq!(outcomes.lints[2].link.href, "#intro-1");
+        assert!(outcomes.suggestions.is_empty());
+    }
+
+    #[test]
+    fn generate_a_numbered_table_of_contents() {
+        let options = TocOptions {
+            max_level: 4,
+            numbered: true,
+            ..Default::default()
+        };
+
+        let edit = update(SRC, &options).unwrap();
+
+        assert_eq!(
+            edit.replacement,
+            "1. [Intro](#intro)
+    1. [Setting Up](#setting-up)
+        1. [Details](#details)
+2. [Intro](#intro-1)
+3. [Usage](#usage)"
+        );
+        assert_eq!(
+            edit.original,
+            "1. [Intro](#intro)\n   1. [Set Up](#set-up)\n2. [Usage](#usage)"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Checking translated documentation which lives in parallel directory
+//! trees (e.g. `docs/en/`, `docs/fr/`, and `docs/ja/`).
+//!
+//! Each localized document's relative links must resolve within its own
+//! language's tree. When a link is broken in a translation but works from the
+//! same place in the source language, the page it points to probably hasn't
+//! been translated yet, and it is reported as [`Reason::Untranslated`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use codespan::Files;
+//! use linkcheck::{site::Site, translations::Translations, BasicContext};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut files = Files::new();
+//! let documents = Site::new("./docs").scan(&mut files)?;
+//!
+//! let translations = Translations::new("./docs", "en");
+//! let outcomes = translations.check(&documents, &BasicContext::default());
+//!
+//! for invalid in &outcomes.invalid {
+//!     println!("{}: {}", invalid.link.href, invalid.reason);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    site::Document,
+    validation::{resolve_link, Context, InvalidLink, Outcomes, Reason},
+    Category,
+};
+use std::path::{Component, Path, PathBuf};
+
+/// A directory where each sub-directory contains the same documents in a
+/// different language.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translations {
+    root: PathBuf,
+    source_language: String,
+}
+
+impl Translations {
+    /// Create a new [`Translations`], where `root` contains one directory
+    /// per language and `source_language` is the directory everything is
+    /// translated from.
+    pub fn new<P, S>(root: P, source_language: S) -> Self
+    where
+        P: Into<PathBuf>,
+        S: Into<String>,
+    {
+        Translations {
+            root: root.into(),
+            source_language: source_language.into(),
+        }
+    }
+
+    /// The directory containing each language's tree.
+    pub fn root(&self) -> &Path { &self.root }
+
+    /// The language documents are translated from.
+    pub fn source_language(&self) -> &str { &self.source_language }
+
+    /// Which language's tree a file is in, if any.
+    pub fn language_of<'p>(&self, path: &'p Path) -> Option<&'p str> {
+        match path.strip_prefix(&self.root).ok()?.components().next()? {
+            Component::Normal(language) => language.to_str(),
+            _ => None,
+        }
+    }
+
+    /// Check the relative links in every translated [`Document`].
+    ///
+    /// Documents in the source language (or outside
+    /// [`Translations::root()`]) are skipped, as are links which aren't
+    /// relative paths, so this is meant to be used alongside the normal
+    /// [`crate::site::validate()`].
+    pub fn check<C>(&self, documents: &[Document], ctx: &C) -> Outcomes
+    where
+        C: Context + ?Sized,
+    {
+        let mut outcomes = Outcomes::empty();
+
+        for doc in documents {
+            let language = match self.language_of(&doc.path) {
+                Some(language) if language != self.source_language => language,
+                _ => continue,
+            };
+
+            let options = match ctx
+                .filesystem_options()
+                .clone()
+                .with_root_directory(self.root.join(language))
+            {
+                Ok(options) => options,
+                Err(e) => {
+                    log::warn!(
+                        "Unable to use \"{}\" as the root directory: {}",
+                        self.root.join(language).display(),
+                        e
+                    );
+                    continue;
+                },
+            };
+
+            for link in &doc.links {
+                let path = match ctx.categorise(&link.href) {
+                    Some(Category::FileSystem { path, .. })
+                        if !path.has_root() =>
+                    {
+                        path
+                    },
+                    _ => continue,
+                };
+
+                match resolve_link(doc.directory(), &path, &options) {
+                    Ok(_) => outcomes.valid.push(link.clone()),
+                    Err(reason) => {
+                        let reason = match self.in_source_language(
+                            doc.directory(),
+                            language,
+                            &path,
+                            ctx,
+                        ) {
+                            Some(source) if reason.file_not_found() => {
+                                Reason::Untranslated(source)
+                            },
+                            _ => reason,
+                        };
+
+                        outcomes.invalid.push(InvalidLink {
+                            link: link.clone(),
+                            reason,
+                        });
+                    },
+                }
+            }
+        }
+
+        outcomes
+    }
+
+    /// Try to resolve a link from the equivalent directory in the source
+    /// language's tree.
+    fn in_source_language<C>(
+        &self,
+        directory: &Path,
+        language: &str,
+        link: &Path,
+        ctx: &C,
+    ) -> Option<PathBuf>
+    where
+        C: Context + ?Sized,
+    {
+        let tree = self.root.join(language);
+        let relative = directory.strip_prefix(tree).ok()?;
+        let source_root = self.root.join(&self.source_language);
+        let options = ctx
+            .filesystem_options()
+            .clone()
+            .with_root_directory(&source_root)
+            .ok()?;
+
+        resolve_link(&source_root.join(relative), link, &options).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BasicContext, Link};
+    use codespan::Files;
+
+    #[test]
+    fn links_to_untranslated_pages() {
+        let temp = tempfile::tempdir().unwrap();
+        for path in &[
+            "en/index.md",
+            "en/setup.md",
+            "en/guide/a.md",
+            "fr/index.md",
+            "fr/guide/a.md",
+        ] {
+            let path = temp.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let mut files = Files::new();
+        let file = files.add("fr/index.md", "");
+        let hrefs = [
+            "guide/a.md",
+            "setup.md",
+            "../en/setup.md",
+            "missing.md",
+            "https://example.com/",
+        ];
+        let document = Document {
+            file,
+            path: temp.path().join("fr").join("index.md"),
+            links: hrefs
+                .iter()
+                .map(|href| Link::new(*href, Default::default(), file))
+                .collect(),
+        };
+        let source = Document {
+            path: temp.path().join("en").join("index.md"),
+            ..document.clone()
+        };
+        let translations = Translations::new(temp.path(), "en");
+
+        let outcomes =
+            translations.check(&[document, source], &BasicContext::default());
+
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.valid[0].href, "guide/a.md");
+        let got: Vec<_> = outcomes
+            .invalid
+            .iter()
+            .map(|invalid| (invalid.link.href.as_str(), &invalid.reason))
+            .collect();
+        assert_eq!(got.len(), 3);
+        assert!(matches!(got[0], ("setup.md", Reason::Untranslated(_))));
+        assert!(matches!(
+            got[1],
+            ("../en/setup.md", Reason::TraversesParentDirectories)
+        ));
+        assert!(got[2].0 == "missing.md" && got[2].1.file_not_found());
+    }
+}
@@ -0,0 +1,215 @@
+use crate::validation::{web, Context, Reason};
+use http::StatusCode;
+use reqwest::Url;
+use std::time::Instant;
+
+/// Check links to snapshots on the Internet Archive's Wayback Machine (e.g.
+/// `https://web.archive.org/web/20200101000000/https://example.com/`).
+///
+/// The Wayback Machine happily redirects to the closest snapshot it has, so
+/// a normal request can't tell whether the page was ever archived. Instead,
+/// we ask the CDX API whether there is a snapshot matching the link's
+/// timestamp.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::WaybackMachine;
+/// use url::Url;
+///
+/// let wayback = WaybackMachine::new().set_check_original(true);
+///
+/// let url = Url::parse(
+///     "https://web.archive.org/web/2020/https://example.com/page",
+/// )
+/// .unwrap();
+/// let page = wayback.archived_page(&url).unwrap();
+///
+/// assert_eq!(page.timestamp, "2020");
+/// assert_eq!(page.original, "https://example.com/page");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaybackMachine {
+    cdx: Url,
+    check_original: bool,
+}
+
+impl WaybackMachine {
+    /// Create a [`WaybackMachine`] which uses the Internet Archive's CDX API.
+    pub fn new() -> Self { WaybackMachine::default() }
+
+    /// The CDX API used to look up snapshots.
+    pub fn cdx(&self) -> &Url { &self.cdx }
+
+    /// Set [`WaybackMachine::cdx()`].
+    pub fn set_cdx(self, cdx: Url) -> Self { WaybackMachine { cdx, ..self } }
+
+    /// Should we also check whether the archived page is still online?
+    ///
+    /// When it is, the archive link is unnecessary and a
+    /// [`SuggestionKind::LiveOriginal`] suggestion is made.
+    ///
+    /// [`SuggestionKind::LiveOriginal`]: crate::fix::SuggestionKind
+    pub fn check_original(&self) -> bool { self.check_original }
+
+    /// Set [`WaybackMachine::check_original()`].
+    pub fn set_check_original(self, check_original: bool) -> Self {
+        WaybackMachine {
+            check_original,
+            ..self
+        }
+    }
+
+    /// Figure out which archived page a URL points to, if any.
+    pub fn archived_page(&self, url: &Url) -> Option<ArchivedPage> {
+        if url.host_str()? != "web.archive.org" {
+            return None;
+        }
+
+        let rest = url.as_str().split_once("/web/")?.1;
+        let (timestamp, original) = rest.split_once('/')?;
+        // the timestamp may have a modifier (e.g. "20200101id_")
+        let digits = timestamp.trim_end_matches(|c: char| !c.is_ascii_digit());
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        Some(ArchivedPage {
+            timestamp: digits.to_string(),
+            original: original.to_string(),
+        })
+    }
+
+    /// Make sure the snapshot exists, returning the original URL if it is
+    /// still online and [`WaybackMachine::check_original()`] is set.
+    pub(crate) async fn check<C>(
+        &self,
+        page: &ArchivedPage,
+        ctx: &C,
+    ) -> Result<Option<String>, Reason>
+    where
+        C: Context + ?Sized,
+    {
+        let mut query = self.cdx.clone();
+        query
+            .query_pairs_mut()
+            .append_pair("url", &page.original)
+            .append_pair("from", &page.timestamp)
+            .append_pair("to", &page.timestamp)
+            .append_pair("limit", "1");
+
+        let mut request = ctx.client().get(query);
+        if let Some(deadline) = ctx.deadline() {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            request = request.timeout(timeout);
+        }
+
+        log::debug!(
+            "Looking for a snapshot of {}",
+            super::redact::redacted(&page.original, ctx)
+        );
+        let response = request.send().await?;
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(Reason::RateLimited(String::from("web.archive.org")));
+        }
+
+        let body = response.error_for_status()?.text().await?;
+        if body.trim().is_empty() {
+            return Err(Reason::MissingSnapshot(
+                page.original.clone(),
+                page.timestamp.clone(),
+            ));
+        }
+
+        if !self.check_original {
+            return Ok(None);
+        }
+
+        match Url::parse(&page.original) {
+            Ok(original) if web::check_web(&original, ctx).await.is_ok() => {
+                log::debug!(
+                    "\"{}\" is still online, the archive link isn't needed",
+                    ctx.redact(&original)
+                );
+                Ok(Some(page.original.clone()))
+            },
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Default for WaybackMachine {
+    fn default() -> Self {
+        WaybackMachine {
+            cdx: Url::parse("https://web.archive.org/cdx/search/cdx").unwrap(),
+            check_original: false,
+        }
+    }
+}
+
+/// A page archived on the Wayback Machine.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArchivedPage {
+    /// When the snapshot was taken, as `YYYYMMDDhhmmss` (or a prefix of
+    /// it).
+    pub timestamp: String,
+    /// The URL which was archived.
+    pub original: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+
+    #[tokio::test]
+    async fn look_up_snapshots_with_the_cdx_api() {
+        let site = Server::start(vec![("/page", Response::ok("still here"))]);
+        let original = site.url("/page");
+        let encoded: String =
+            url::form_urlencoded::byte_serialize(original.as_str().as_bytes())
+                .collect();
+        let query = |timestamp: &str| {
+            format!(
+                "/cdx?url={}&from={}&to={}&limit=1",
+                encoded, timestamp, timestamp
+            )
+        };
+        let (found, missing) = (query("2020"), query("1999"));
+        let server = Server::start(vec![
+            (found.as_str(), Response::ok("com,example)/ 2020...")),
+            (missing.as_str(), Response::ok("")),
+        ]);
+        let wayback = WaybackMachine::new().set_cdx(server.url("/cdx"));
+        let ctx = BasicContext::default();
+
+        let archive = |timestamp: &str| {
+            let url = format!(
+                "https://web.archive.org/web/{}/{}",
+                timestamp, original
+            );
+            WaybackMachine::new()
+                .archived_page(&url.parse().unwrap())
+                .unwrap()
+        };
+
+        let page = archive("2020");
+        assert_eq!(wayback.check(&page, &ctx).await.unwrap(), None);
+
+        let wayback = wayback.set_check_original(true);
+        assert_eq!(
+            wayback.check(&page, &ctx).await.unwrap().as_deref(),
+            Some(original.as_str())
+        );
+
+        let page = archive("1999id_");
+        assert_eq!(page.timestamp, "1999");
+        let got = wayback.check(&page, &ctx).await;
+        assert!(matches!(got, Err(Reason::MissingSnapshot(..))));
+    }
+}
@@ -0,0 +1,125 @@
+use http::Method;
+use reqwest::{Response, Url};
+use std::{
+    fmt::{self, Display, Formatter},
+    time::{Duration, SystemTime},
+};
+
+/// A request which was sent while checking a link.
+///
+/// These are only recorded when [`Context::record_attempts()`] is enabled,
+/// and end up in [`Outcomes::attempts`].
+///
+/// [`Context::record_attempts()`]: crate::validation::Context::record_attempts
+/// [`Outcomes::attempts`]: crate::validation::Outcomes::attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    /// When the request was sent.
+    pub timestamp: SystemTime,
+    /// The HTTP method used.
+    pub method: Method,
+    /// The URL the request was sent to.
+    pub url: Url,
+    /// The status code, if we got a response.
+    pub status: Option<u16>,
+    /// Why the request failed, if we didn't get a response.
+    pub error: Option<String>,
+    /// How long we waited before sending the request, if it was a retry.
+    pub backoff: Option<Duration>,
+}
+
+impl Display for Attempt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.url)?;
+
+        if let Some(backoff) = self.backoff {
+            write!(f, " (after {:?})", backoff)?;
+        }
+
+        match (self.status, &self.error) {
+            (Some(status), _) => write!(f, " -> {}", status),
+            (None, Some(error)) => write!(f, " -> {}", error),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Somewhere to keep track of the [`Attempt`]s made for a single link.
+#[derive(Debug, Default)]
+pub(crate) struct Attempts {
+    enabled: bool,
+    attempts: Vec<Attempt>,
+}
+
+impl Attempts {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Attempts {
+            enabled,
+            attempts: Vec::new(),
+        }
+    }
+
+    /// Record the result of a request which was sent at `timestamp`.
+    pub(crate) fn record(
+        &mut self,
+        timestamp: SystemTime,
+        method: Method,
+        url: &Url,
+        result: &Result<Response, reqwest::Error>,
+    ) {
+        #[cfg(feature = "metrics")]
+        crate::metrics::request_finished(timestamp, &method, result);
+
+        if !self.enabled {
+            return;
+        }
+
+        let (status, error) = match result {
+            Ok(response) => (Some(response.status().as_u16()), None),
+            Err(e) => (e.status().map(|s| s.as_u16()), Some(e.to_string())),
+        };
+
+        self.attempts.push(Attempt {
+            timestamp,
+            method,
+            url: url.clone(),
+            status,
+            error,
+            backoff: None,
+        });
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<Attempt> { self.attempts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        validation::validate,
+        BasicContext, Link,
+    };
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn record_each_request_when_asked() {
+        let server = Server::start(vec![("/forbidden", Response::status(403))]);
+        let href = server.url("/forbidden").to_string();
+        let link = || Link::new(href.clone(), Default::default(), "index.md");
+        let mut ctx = BasicContext::default();
+
+        let outcomes = validate(Path::new("."), vec![link()], &ctx).await;
+        assert!(outcomes.attempts.is_empty());
+
+        ctx.record_attempts = true;
+        let outcomes = validate(Path::new("."), vec![link()], &ctx).await;
+
+        // the HEAD, then a GET to see whether it was a challenge page
+        let attempts = &outcomes.attempts[&link()];
+        let methods: Vec<_> = attempts.iter().map(|a| &a.method).collect();
+        assert_eq!(methods, vec![Method::HEAD, Method::GET]);
+        assert_eq!(attempts[0].status, Some(403));
+        assert!(attempts[0].to_string().ends_with("-> 403"));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{validation::Outcomes, Link};
+use codespan::{FileId, Files};
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+    mem,
+};
+
+/// Links which were already broken when `linkcheck` was adopted, so they can
+/// be fixed incrementally instead of all at once.
+///
+/// Unlike a [`crate::validation::KnownBroken`] list, which is written by hand,
+/// a [`Baseline`] is generated from the [`Outcomes`] of a run (see
+/// [`Baseline::new()`]) and is meant to be saved next to the documents (e.g.
+/// as JSON) and committed to version control. Each entry is identified by a
+/// fingerprint of the file it was found in and its `href`, so a broken link
+/// stays in the baseline when lines are added or removed around it.
+///
+/// Use [`Outcomes::apply_baseline()`] to accept the links in a [`Baseline`].
+///
+/// # Examples
+///
+/// ```rust
+/// use codespan::{Files, Span};
+/// use linkcheck::{
+///     validation::{Baseline, InvalidLink, Outcomes, Reason},
+///     Link,
+/// };
+///
+/// let mut files = Files::new();
+/// let file = files.add("README.md", "[old](./old.md)");
+/// let broken = || InvalidLink {
+///     link: Link::new("./old.md", Span::new(6, 14), file),
+///     reason: Reason::Io(std::io::ErrorKind::NotFound.into()),
+/// };
+/// let first_run = Outcomes {
+///     invalid: vec![broken()],
+///     ..Default::default()
+/// };
+/// let baseline = Baseline::new(&first_run, &files);
+///
+/// let mut second_run = Outcomes {
+///     invalid: vec![broken()],
+///     ..Default::default()
+/// };
+/// let stale = second_run.apply_baseline(&baseline, &files);
+///
+/// assert!(stale.is_empty());
+/// assert!(second_run.invalid.is_empty());
+/// assert_eq!(second_run.suppressed.len(), 1);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Baseline {
+    /// The broken links which should be accepted.
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    /// Record every broken link in a set of [`Outcomes`].
+    pub fn new<S>(outcomes: &Outcomes, files: &Files<S>) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let mut seen = HashSet::new();
+        let entries = outcomes
+            .invalid
+            .iter()
+            .map(|invalid| BaselineEntry {
+                reason: invalid.reason.to_string(),
+                ..BaselineEntry::new(&invalid.link, files)
+            })
+            .filter(|entry| seen.insert(entry.fingerprint.clone()))
+            .collect();
+
+        Baseline { entries }
+    }
+
+    /// Does the baseline accept this [`Link`] being broken?
+    pub fn contains<S>(&self, link: &Link, files: &Files<S>) -> bool
+    where
+        S: AsRef<str>,
+    {
+        let fingerprint = BaselineEntry::new(link, files).fingerprint;
+        self.entries
+            .iter()
+            .any(|entry| entry.fingerprint == fingerprint)
+    }
+
+    /// Find the entries which don't match any broken (or suppressed) link in
+    /// the [`Outcomes`], usually because the link was fixed or removed.
+    ///
+    /// Stale entries should be deleted, otherwise the link could break again
+    /// without anyone noticing.
+    pub fn stale<S>(
+        &self,
+        outcomes: &Outcomes,
+        files: &Files<S>,
+    ) -> Vec<&BaselineEntry>
+    where
+        S: AsRef<str>,
+    {
+        let broken: HashSet<String> = outcomes
+            .invalid
+            .iter()
+            .chain(&outcomes.suppressed)
+            .map(|invalid| BaselineEntry::new(&invalid.link, files).fingerprint)
+            .collect();
+
+        self.entries
+            .iter()
+            .filter(|entry| !broken.contains(&entry.fingerprint))
+            .collect()
+    }
+}
+
+/// A single broken link in a [`Baseline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct BaselineEntry {
+    /// The name of the file the link was found in, using `/` as the path
+    /// separator.
+    pub file: String,
+    /// The link's `href`.
+    pub href: String,
+    /// Why the link was broken when the baseline was recorded.
+    pub reason: String,
+    /// A stable hash of the [`BaselineEntry::file`] and
+    /// [`BaselineEntry::href`], used to match links against the baseline.
+    pub fingerprint: String,
+}
+
+impl BaselineEntry {
+    fn new<S>(link: &Link, files: &Files<S>) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let file = file_name(files, link.file);
+        let fingerprint = fingerprint(&file, &link.href);
+
+        BaselineEntry {
+            file,
+            href: link.href.clone(),
+            reason: String::new(),
+            fingerprint,
+        }
+    }
+}
+
+impl Display for BaselineEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.file, self.href, self.reason)
+    }
+}
+
+impl Outcomes {
+    /// Accept the broken links in a [`Baseline`], moving them to
+    /// [`Outcomes::suppressed`], and return the baseline's stale entries (see
+    /// [`Baseline::stale()`]).
+    pub fn apply_baseline<'b, S>(
+        &mut self,
+        baseline: &'b Baseline,
+        files: &Files<S>,
+    ) -> Vec<&'b BaselineEntry>
+    where
+        S: AsRef<str>,
+    {
+        let (accepted, invalid) = mem::take(&mut self.invalid)
+            .into_iter()
+            .partition(|invalid| baseline.contains(&invalid.link, files));
+        self.invalid = invalid;
+        self.suppressed.extend::<Vec<_>>(accepted);
+
+        baseline.stale(self, files)
+    }
+}
+
+/// The name of the file a [`Link`] was found in, using `/` as the path
+/// separator so it is the same on every platform.
+pub(crate) fn file_name<S>(files: &Files<S>, file: FileId) -> String
+where
+    S: AsRef<str>,
+{
+    files.name(file).to_string_lossy().replace('\\', "/")
+}
+
+/// A 64-bit FNV-1a hash, which (unlike the standard library's hashers) is
+/// guaranteed to give the same result on every platform and Rust release.
+fn fingerprint(file: &str, href: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for byte in file.bytes().chain(Some(0)).chain(href.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{InvalidLink, Reason};
+    use codespan::Span;
+    use std::io;
+
+    fn invalid(href: &str, file: codespan::FileId) -> InvalidLink {
+        InvalidLink {
+            link: Link::new(href, Span::new(0, 0), file),
+            reason: Reason::Io(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    #[test]
+    fn accept_old_breakages_and_find_stale_entries() {
+        let mut files = Files::new();
+        let index = files.add("docs/index.md", "");
+        let other = files.add("docs/other.md", "");
+        let before = Outcomes {
+            invalid: vec![
+                invalid("./old.md", index),
+                invalid("./old.md", index),
+                invalid("./fixed.md", index),
+            ],
+            ..Default::default()
+        };
+        let baseline = Baseline::new(&before, &files);
+        let mut after = Outcomes {
+            invalid: vec![
+                invalid("./old.md", index),
+                invalid("./new.md", index),
+                invalid("./old.md", other),
+            ],
+            ..Default::default()
+        };
+
+        let stale = after.apply_baseline(&baseline, &files);
+
+        assert_eq!(baseline.entries.len(), 2);
+        assert_eq!(baseline.entries[0].file, "docs/index.md");
+        assert_eq!(baseline.entries[0].fingerprint, "8130c38fe0a2ae1b");
+        let still_broken: Vec<_> =
+            after.invalid.iter().map(|i| &i.link.href).collect();
+        assert_eq!(still_broken, vec!["./new.md", "./old.md"]);
+        assert_eq!(after.suppressed.len(), 1);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(
+            stale[0].to_string(),
+            "docs/index.md: ./fixed.md (An OS-level error occurred)"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use encoding_rs::{Encoding, UTF_8};
+use http::{header::CONTENT_TYPE, HeaderMap};
+use reqwest::Response;
+
+/// The part of a response's body which was downloaded by [`read_body()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Body {
+    /// The decoded text.
+    pub text: String,
+    /// The name of the encoding used to decode the body (e.g. `UTF-8`).
+    pub encoding: &'static str,
+    /// Did the body go past the budget, meaning we only have the start of
+    /// it?
+    pub truncated: bool,
+}
+
+impl Body {
+    /// The default for [`Context::body_budget()`].
+    ///
+    /// [`Context::body_budget()`]: crate::validation::Context::body_budget
+    pub const DEFAULT_BUDGET: usize = 1024 * 1024;
+}
+
+/// Download at most `budget` bytes of a response's body and decode it.
+///
+/// The encoding comes from a byte order mark or the `charset` in the
+/// `Content-Type` header, falling back to UTF-8. Anything which can't be
+/// decoded (e.g. because the body is actually binary) is replaced with
+/// `U+FFFD`.
+pub async fn read_body(
+    mut response: Response,
+    budget: usize,
+) -> Result<Body, reqwest::Error> {
+    let declared = declared_encoding(response.headers());
+    let mut bytes = Vec::new();
+    let mut truncated = false;
+
+    while let Some(chunk) = response.chunk().await? {
+        let remaining = budget - bytes.len();
+
+        if chunk.len() > remaining {
+            bytes.extend_from_slice(&chunk[..remaining]);
+            truncated = true;
+            break;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(decode(&bytes, declared, truncated))
+}
+
+/// The encoding named by the `charset` parameter in a `Content-Type`
+/// header.
+pub(crate) fn declared_encoding(
+    headers: &HeaderMap,
+) -> Option<&'static Encoding> {
+    let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        let label = value.trim().trim_matches('"');
+        Encoding::for_label(label.as_bytes())
+    })
+}
+
+/// Decode some bytes, preferring a byte order mark over the `declared`
+/// encoding.
+///
+/// When the bytes were `truncated`, a multi-byte character which was cut in
+/// half is dropped instead of being replaced with `U+FFFD`.
+pub(crate) fn decode(
+    bytes: &[u8],
+    declared: Option<&'static Encoding>,
+    truncated: bool,
+) -> Body {
+    let (encoding, bom_length) = Encoding::for_bom(bytes)
+        .unwrap_or_else(|| (declared.unwrap_or(UTF_8), 0));
+    let bytes = &bytes[bom_length..];
+
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let capacity = decoder
+        .max_utf8_buffer_length(bytes.len())
+        .unwrap_or(bytes.len());
+    let mut text = String::with_capacity(capacity);
+    let _ = decoder.decode_to_string(bytes, &mut text, !truncated);
+
+    Body {
+        text,
+        encoding: encoding.name(),
+        truncated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{Response, Server};
+
+    #[tokio::test]
+    async fn only_download_the_budget_and_honour_the_charset() {
+        let latin1 = Response::ok("caf\u{e9}")
+            .with_header("Content-Type", "text/html; charset=\"ISO-8859-1\"");
+        let server = Server::start(vec![
+            ("/huge", Response::ok(&"x".repeat(10_000))),
+            ("/latin1", latin1),
+        ]);
+        let client = reqwest::Client::new();
+
+        let response = client.get(server.url("/huge")).send().await.unwrap();
+        let body = read_body(response, 100).await.unwrap();
+        assert_eq!(body.text.len(), 100);
+        assert!(body.truncated);
+
+        // the test server sends UTF-8, so each byte is decoded on its own
+        let response = client.get(server.url("/latin1")).send().await.unwrap();
+        let body = read_body(response, 100).await.unwrap();
+        assert_eq!(body.encoding, "windows-1252");
+        assert_eq!(body.text, "caf\u{c3}\u{a9}");
+
+        // a character cut in half by the budget is dropped
+        let got = decode(&"café".as_bytes()[..4], None, true);
+        assert_eq!(got.text, "caf");
+        let latin1 = Encoding::for_label(b"latin1");
+        let got = decode(b"\xEF\xBB\xBFhi", latin1, false);
+        assert_eq!((got.text.as_str(), got.encoding), ("hi", "UTF-8"));
+    }
+}
@@ -0,0 +1,246 @@
+use crate::validation::{read_body, Attempts, Context};
+use http::{HeaderMap, Method, StatusCode};
+use reqwest::{Response, Url};
+use std::time::SystemTime;
+
+/// The most of a response body to look at when a rule needs to match the
+/// body. Challenge pages put their markers near the top.
+const BODY_PREFIX: usize = 16 * 1024;
+
+/// Rules for recognising responses from servers which block automated
+/// clients (e.g. LinkedIn's `999` status or a Cloudflare challenge page).
+///
+/// These servers respond with an error no matter whether the page exists, so
+/// links matching a rule are reported as unverifiable instead of broken.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::{ResponseRule, ResponseRules};
+///
+/// let rules = ResponseRules::defaults().with_rule(
+///     ResponseRule::for_domain("example.com")
+///         .with_status(403)
+///         .with_body("Access denied"),
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ResponseRules {
+    rules: Vec<ResponseRule>,
+}
+
+impl ResponseRules {
+    /// Create an empty set of [`ResponseRules`].
+    pub fn new() -> Self { ResponseRules::default() }
+
+    /// Rules for well-known bot blocking, including LinkedIn and Cloudflare
+    /// challenge pages.
+    pub fn defaults() -> Self {
+        let linkedin =
+            ResponseRule::for_domain("linkedin.com").with_status(999);
+
+        ResponseRules::new()
+            .with_rule(linkedin)
+            .with_rule(ResponseRule::any_domain().with_header("cf-mitigated"))
+            .with_rule(
+                ResponseRule::any_domain()
+                    .with_status(403)
+                    .with_body("<title>Just a moment...</title>"),
+            )
+            .with_rule(
+                ResponseRule::any_domain()
+                    .with_status(503)
+                    .with_body("<title>Just a moment...</title>"),
+            )
+    }
+
+    /// Add another rule.
+    pub fn with_rule(mut self, rule: ResponseRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// The rules being used.
+    pub fn rules(&self) -> &[ResponseRule] { &self.rules }
+
+    /// Was this response sent because the server doesn't like automated
+    /// clients?
+    ///
+    /// Rules which look at the body need a `GET` request, because the
+    /// response being checked is normally from a `HEAD`.
+    pub(crate) async fn is_blocked<C>(
+        &self,
+        url: &Url,
+        response: &Response,
+        ctx: &C,
+        attempts: &mut Attempts,
+    ) -> bool
+    where
+        C: Context + ?Sized,
+    {
+        let status = response.status();
+        let candidates: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|rule| {
+                rule.matches_response(url, status, response.headers())
+            })
+            .collect();
+
+        if candidates.iter().any(|rule| rule.body_contains.is_none()) {
+            return true;
+        }
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let request = ctx
+            .client()
+            .get(url.clone())
+            .headers(ctx.url_specific_headers(url));
+        let sent = SystemTime::now();
+        let result = request.send().await;
+        attempts.record(sent, Method::GET, url, &result);
+
+        let budget = ctx.body_budget().min(BODY_PREFIX);
+        let body = match result {
+            Ok(response) => read_body(response, budget).await,
+            Err(e) => Err(e),
+        };
+        let body = match body {
+            Ok(body) => body.text,
+            Err(_) => return false,
+        };
+
+        candidates.iter().any(|rule| {
+            rule.body_contains
+                .as_deref()
+                .is_some_and(|needle| body.contains(needle))
+        })
+    }
+}
+
+/// A single rule in a set of [`ResponseRules`].
+///
+/// A rule matches when everything it specifies matches.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ResponseRule {
+    domain: Option<String>,
+    status: Option<u16>,
+    header: Option<String>,
+    body_contains: Option<String>,
+}
+
+impl ResponseRule {
+    /// A rule which applies to every domain.
+    pub fn any_domain() -> Self { ResponseRule::default() }
+
+    /// A rule which applies to a domain and its subdomains.
+    pub fn for_domain(domain: &str) -> Self {
+        ResponseRule {
+            domain: Some(domain.trim_end_matches('.').to_ascii_lowercase()),
+            ..ResponseRule::default()
+        }
+    }
+
+    /// Only match responses with this status code.
+    pub fn with_status(self, status: u16) -> Self {
+        ResponseRule {
+            status: Some(status),
+            ..self
+        }
+    }
+
+    /// Only match responses which have this header.
+    pub fn with_header(self, name: &str) -> Self {
+        ResponseRule {
+            header: Some(name.to_ascii_lowercase()),
+            ..self
+        }
+    }
+
+    /// Only match responses whose body contains this text.
+    pub fn with_body(self, text: &str) -> Self {
+        ResponseRule {
+            body_contains: Some(text.to_string()),
+            ..self
+        }
+    }
+
+    /// The domain this rule applies to (`None` means every domain).
+    pub fn domain(&self) -> Option<&str> { self.domain.as_deref() }
+
+    /// The status code this rule matches.
+    pub fn status(&self) -> Option<u16> { self.status }
+
+    /// The header this rule looks for.
+    pub fn header(&self) -> Option<&str> { self.header.as_deref() }
+
+    /// The text this rule looks for in the body.
+    pub fn body_contains(&self) -> Option<&str> {
+        self.body_contains.as_deref()
+    }
+
+    /// Does everything except the body match?
+    fn matches_response(
+        &self,
+        url: &Url,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> bool {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let domain_matches = self.domain.as_deref().is_none_or(|domain| {
+            host == domain || host.ends_with(&format!(".{}", domain))
+        });
+
+        domain_matches
+            && self.status.is_none_or(|s| s == status.as_u16())
+            && self
+                .header
+                .as_deref()
+                .is_none_or(|name| headers.contains_key(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{Response, Server},
+        validation::{check_web, validate, Reason},
+        BasicContext, Link,
+    };
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn challenge_pages_are_not_broken_links() {
+        let challenge = "<html><title>Just a moment...</title></html>";
+        let server = Server::start(vec![
+            (
+                "/challenge",
+                Response {
+                    status: 403,
+                    ..Response::ok(challenge)
+                },
+            ),
+            ("/forbidden", Response::status(403)),
+            (
+                "/mitigated",
+                Response::status(403).with_header("cf-mitigated", "challenge"),
+            ),
+        ]);
+        let ctx = BasicContext::default();
+
+        let got = check_web(&server.url("/challenge"), &ctx).await;
+        assert!(matches!(got, Err(Reason::BlockedByServer(_, 403))));
+        let got = check_web(&server.url("/mitigated"), &ctx).await;
+        assert!(got.unwrap_err().blocked_by_server());
+        let got = check_web(&server.url("/forbidden"), &ctx).await;
+        assert!(matches!(got, Err(Reason::Web(_))));
+
+        let href = server.url("/challenge").to_string();
+        let links = vec![Link::new(href, Default::default(), "index.md")];
+        let outcomes = validate(Path::new("."), links, &ctx).await;
+        assert!(outcomes.invalid.is_empty());
+        assert_eq!(outcomes.unverifiable.len(), 1);
+    }
+}
@@ -1,5 +1,8 @@
+use crate::validation::{normalise_url, TrailingSlash};
+use http::HeaderMap;
 use std::{
-    collections::HashMap,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
     time::{Duration, SystemTime},
 };
 use url::Url;
@@ -15,6 +18,69 @@ impl Cache {
     /// Create a new, empty [`Cache`].
     pub fn new() -> Self { Cache::default() }
 
+    /// The key used to store the result for a [`Url`] which was requested
+    /// with some extra `headers` (see [`Context::cache_key()`]).
+    ///
+    /// This is the [`normalise_url()`]d URL, with a hash of the headers as
+    /// its fragment. That way the result for a page which needs an auth
+    /// token won't be used when checking it without one, and the headers
+    /// themselves never end up in the [`Cache`].
+    ///
+    /// [`Context::cache_key()`]: crate::validation::Context::cache_key
+    pub fn key(
+        url: &Url,
+        headers: &HeaderMap,
+        trailing_slash: TrailingSlash,
+    ) -> Url {
+        let mut key = normalise_url(url, trailing_slash);
+
+        if !headers.is_empty() {
+            let mut pairs: Vec<_> = headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes()))
+                .collect();
+            pairs.sort();
+
+            let mut hasher = DefaultHasher::new();
+            pairs.hash(&mut hasher);
+            key.set_fragment(Some(&format!(
+                "headers-{:016x}",
+                hasher.finish()
+            )));
+        }
+
+        key
+    }
+
+    /// Like [`Cache::key()`], but for a `url` with secrets in it (e.g. an
+    /// access token in the query string) which were hidden in `redacted`.
+    ///
+    /// The key is made from the `redacted` URL, with a hash of the original
+    /// as its fragment. That way URLs which only differ by their secrets get
+    /// their own entries, without the secrets ending up in the [`Cache`].
+    pub fn redacted_key(
+        url: &Url,
+        redacted: &Url,
+        headers: &HeaderMap,
+        trailing_slash: TrailingSlash,
+    ) -> Url {
+        let mut key = Cache::key(redacted, headers, trailing_slash);
+
+        if url != redacted {
+            let mut hasher = DefaultHasher::new();
+            key.fragment().hash(&mut hasher);
+            normalise_url(url, trailing_slash)
+                .as_str()
+                .hash(&mut hasher);
+            key.set_fragment(Some(&format!(
+                "secrets-{:016x}",
+                hasher.finish()
+            )));
+        }
+
+        key
+    }
+
     /// Lookup a particular [`CacheEntry`].
     pub fn lookup(&self, url: &Url) -> Option<&CacheEntry> {
         self.entries.get(url)
@@ -28,16 +94,24 @@ impl Cache {
     /// Ask the [`Cache`] whether a particular [`Url`] is still okay (i.e.
     /// [`CacheEntry::valid`] is `true`).
     pub fn url_is_still_valid(&self, url: &Url, timeout: Duration) -> bool {
-        if let Some(entry) = self.lookup(url) {
-            if entry.valid {
-                if let Ok(time_since_check_was_done) = entry.timestamp.elapsed()
-                {
-                    return time_since_check_was_done < timeout;
-                }
-            }
-        }
+        self.still_valid(url, timeout).is_some()
+    }
+
+    /// Get the [`CacheEntry`] for a [`Url`] if it is still okay (see
+    /// [`Cache::url_is_still_valid()`]).
+    pub fn still_valid(
+        &self,
+        url: &Url,
+        timeout: Duration,
+    ) -> Option<&CacheEntry> {
+        let entry = self.lookup(url).filter(|entry| entry.valid)?;
+        let time_since_check_was_done = entry.timestamp.elapsed().ok()?;
 
-        false
+        if time_since_check_was_done < timeout {
+            Some(entry)
+        } else {
+            None
+        }
     }
 
     /// Iterate over all known [`CacheEntries`][CacheEntry], regardless of
@@ -58,7 +132,7 @@ impl Extend<(Url, CacheEntry)> for Cache {
 
 /// A timestamped boolean used by the [`Cache`] to keep track of the last time
 /// a web [`crate::Link`] was checked.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
 pub struct CacheEntry {
     /// When the [`CacheEntry`] was created.
@@ -66,11 +140,49 @@ pub struct CacheEntry {
     /// Did we find a valid resource the last time this [`crate::Link`] was
     /// checked?
     pub valid: bool,
+    /// Where the server redirected us the last time this [`crate::Link`] was
+    /// checked (with any secrets redacted), so links answered by the cache
+    /// still get redirect suggestions and lints.
+    #[cfg_attr(feature = "serde-1", serde(default))]
+    pub redirected: Option<Url>,
 }
 
 impl CacheEntry {
     /// Create a new [`CacheEntry`].
     pub const fn new(timestamp: SystemTime, valid: bool) -> Self {
-        CacheEntry { timestamp, valid }
+        CacheEntry {
+            timestamp,
+            valid,
+            redirected: None,
+        }
+    }
+
+    /// Set [`CacheEntry::redirected`].
+    pub fn set_redirected(self, redirected: Option<Url>) -> Self {
+        CacheEntry { redirected, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::{HeaderValue, AUTHORIZATION, USER_AGENT};
+
+    #[test]
+    fn keys_depend_on_the_extra_headers() {
+        let url = Url::parse("https://example.com/private#section").unwrap();
+        let key = |headers: &[(_, &'static str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in headers {
+                map.insert(name, HeaderValue::from_static(value));
+            }
+            Cache::key(&url, &map, TrailingSlash::Keep)
+        };
+
+        assert_eq!(key(&[]).as_str(), "https://example.com/private");
+        let alice = key(&[(AUTHORIZATION, "alice"), (USER_AGENT, "me")]);
+        assert_eq!(alice, key(&[(USER_AGENT, "me"), (AUTHORIZATION, "alice")]));
+        assert_ne!(alice, key(&[(USER_AGENT, "me"), (AUTHORIZATION, "bob")]));
+        assert!(!alice.as_str().contains("alice"));
     }
 }
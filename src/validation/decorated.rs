@@ -0,0 +1,330 @@
+#[cfg(feature = "forges")]
+use crate::validation::ForgeApi;
+use crate::{
+    anchors::AnchorDb,
+    dns::DnsCache,
+    validation::{
+        Cache, CheckLevel, Context, DomainPolicy, ExpectedFailures,
+        KnownBroken, Options, PackageRegistries, Reason, ResponseRules,
+        SafetyLimits, TrailingSlash, WaybackMachine,
+    },
+    Category, Link,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use reqwest::{header::HeaderMap, Client, Url};
+use std::{
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Tweak a single behaviour of an existing [`Context`] without
+/// re-implementing the whole trait.
+///
+/// This is implemented for every [`Context`].
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{
+///     validation::{Context, ContextExt},
+///     BasicContext, Link,
+/// };
+/// use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer xyz"));
+///
+/// let ctx = BasicContext::default()
+///     .with_ignore(["https://localhost*", "*.invalid/*"])?
+///     .with_headers(headers);
+///
+/// let link = Link::new("https://localhost:8000/", Default::default(), ());
+/// assert!(ctx.should_ignore(&link));
+/// let url = "https://example.com/".parse().unwrap();
+/// assert!(ctx.url_specific_headers(&url).contains_key(AUTHORIZATION));
+/// # Ok::<(), globset::Error>(())
+/// ```
+pub trait ContextExt: Context + Sized {
+    /// Skip links with an `href` matching any of these globs, as well as the
+    /// links this [`Context`] already skips.
+    fn with_ignore<I, S>(
+        self,
+        patterns: I,
+    ) -> Result<Decorated<Self>, globset::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Decorated::new(self).with_ignore(patterns)
+    }
+
+    /// Send these headers with every web request, on top of the
+    /// [`Context::url_specific_headers()`].
+    fn with_headers(self, headers: HeaderMap) -> Decorated<Self> {
+        Decorated::new(self).with_headers(headers)
+    }
+
+    /// Use this [`Cache`] instead of the [`Context::cache()`].
+    fn with_cache(self, cache: Cache) -> Decorated<Self> {
+        Decorated::new(self).with_cache(cache)
+    }
+
+    /// Check this many links at a time, with at most `per_host` of them on
+    /// the same host (see [`Context::concurrency()`] and
+    /// [`Context::concurrency_per_host()`]).
+    fn with_concurrency(
+        self,
+        concurrency: usize,
+        per_host: usize,
+    ) -> Decorated<Self> {
+        Decorated::new(self).with_concurrency(concurrency, per_host)
+    }
+}
+
+impl<C: Context> ContextExt for C {}
+
+/// A [`Context`] which forwards everything to another [`Context`], except
+/// for the behaviour changed using [`ContextExt`].
+#[derive(Debug)]
+pub struct Decorated<C> {
+    inner: C,
+    ignore: Option<GlobSet>,
+    headers: HeaderMap,
+    cache: Option<Mutex<Cache>>,
+    concurrency: Option<(usize, usize)>,
+}
+
+impl<C> Decorated<C> {
+    /// Wrap a [`Context`] without changing anything.
+    pub fn new(inner: C) -> Self {
+        Decorated {
+            inner,
+            ignore: None,
+            headers: HeaderMap::new(),
+            cache: None,
+            concurrency: None,
+        }
+    }
+
+    /// Skip links with an `href` matching any of these globs (see
+    /// [`ContextExt::with_ignore()`]).
+    ///
+    /// Calling this again replaces the patterns.
+    pub fn with_ignore<I, S>(self, patterns: I) -> Result<Self, globset::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern.as_ref())?);
+        }
+
+        Ok(Decorated {
+            ignore: Some(builder.build()?),
+            ..self
+        })
+    }
+
+    /// Send these headers with every web request (see
+    /// [`ContextExt::with_headers()`]).
+    ///
+    /// Calling this again adds to the headers.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Use this [`Cache`] instead of the wrapped [`Context::cache()`].
+    pub fn with_cache(self, cache: Cache) -> Self {
+        Decorated {
+            cache: Some(Mutex::new(cache)),
+            ..self
+        }
+    }
+
+    /// Check this many links at a time, with at most `per_host` of them on
+    /// the same host (see [`ContextExt::with_concurrency()`]).
+    pub fn with_concurrency(self, concurrency: usize, per_host: usize) -> Self {
+        Decorated {
+            concurrency: Some((concurrency, per_host)),
+            ..self
+        }
+    }
+
+    /// The wrapped [`Context`].
+    pub fn inner(&self) -> &C { &self.inner }
+
+    /// Unwrap the [`Context`], along with the [`Cache`] passed to
+    /// [`Decorated::with_cache()`] (if there was one).
+    pub fn into_inner(self) -> (C, Option<Cache>) {
+        let cache = self
+            .cache
+            .map(|cache| cache.into_inner().expect("Mutex was poisoned"));
+        (self.inner, cache)
+    }
+}
+
+impl<C: Context> Context for Decorated<C> {
+    fn client(&self) -> &Client { self.inner.client() }
+
+    fn filesystem_options(&self) -> &Options { self.inner.filesystem_options() }
+
+    fn url_specific_headers(&self, url: &Url) -> HeaderMap {
+        let mut headers = self.inner.url_specific_headers(url);
+        headers.extend(self.headers.clone());
+        headers
+    }
+
+    fn cache(&self) -> Option<MutexGuard<'_, Cache>> {
+        match &self.cache {
+            Some(cache) => Some(cache.lock().expect("Mutex was poisoned")),
+            None => self.inner.cache(),
+        }
+    }
+
+    fn cache_key(&self, url: &Url) -> Url {
+        if self.headers.is_empty() {
+            self.inner.cache_key(url)
+        } else {
+            Cache::redacted_key(
+                url,
+                &self.redact(url),
+                &self.url_specific_headers(url),
+                self.trailing_slash(),
+            )
+        }
+    }
+
+    fn redact(&self, url: &Url) -> Url { self.inner.redact(url) }
+
+    fn concurrency(&self) -> usize {
+        match self.concurrency {
+            Some((concurrency, _)) => concurrency,
+            None => self.inner.concurrency(),
+        }
+    }
+
+    fn concurrency_per_host(&self) -> usize {
+        match self.concurrency {
+            Some((_, per_host)) => per_host,
+            None => self.inner.concurrency_per_host(),
+        }
+    }
+
+    fn cache_timeout(&self) -> Duration { self.inner.cache_timeout() }
+
+    fn should_ignore<F>(&self, link: &Link<F>) -> bool {
+        self.ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.is_match(&link.href))
+            || self.inner.should_ignore(link)
+    }
+
+    fn categorise(&self, href: &str) -> Option<Category> {
+        self.inner.categorise(href)
+    }
+
+    fn default_scheme(&self) -> &str { self.inner.default_scheme() }
+
+    fn trailing_slash(&self) -> TrailingSlash { self.inner.trailing_slash() }
+
+    fn deadline(&self) -> Option<Instant> { self.inner.deadline() }
+
+    fn anchor_db(&self) -> Option<&AnchorDb> { self.inner.anchor_db() }
+
+    fn dns_cache(&self) -> Option<&DnsCache> { self.inner.dns_cache() }
+
+    fn known_broken(&self) -> Option<&KnownBroken> { self.inner.known_broken() }
+
+    fn expected_failures(&self) -> Option<&ExpectedFailures> {
+        self.inner.expected_failures()
+    }
+
+    fn domain_policy(&self) -> Option<&DomainPolicy> {
+        self.inner.domain_policy()
+    }
+
+    fn safety_limits(&self) -> Option<&SafetyLimits> {
+        self.inner.safety_limits()
+    }
+
+    fn response_rules(&self) -> Option<&ResponseRules> {
+        self.inner.response_rules()
+    }
+
+    fn wayback_machine(&self) -> Option<&WaybackMachine> {
+        self.inner.wayback_machine()
+    }
+
+    fn package_registries(&self) -> Option<&PackageRegistries> {
+        self.inner.package_registries()
+    }
+
+    #[cfg(feature = "forges")]
+    fn forge_api(&self) -> Option<&ForgeApi> { self.inner.forge_api() }
+
+    fn record_attempts(&self) -> bool { self.inner.record_attempts() }
+
+    fn body_budget(&self) -> usize { self.inner.body_budget() }
+
+    fn send_credentials(&self, url: &Url) -> bool {
+        self.inner.send_credentials(url)
+    }
+
+    fn check_level(&self, category: &Category) -> CheckLevel {
+        self.inner.check_level(category)
+    }
+
+    fn check_opaque_uri(&self, uri: &Url) -> Option<Result<(), Reason>> {
+        self.inner.check_opaque_uri(uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        validation::validate,
+        BasicContext,
+    };
+    use reqwest::header::HeaderValue;
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn only_the_decorated_behaviour_changes() {
+        let server = Server::start(vec![("/", Response::ok(""))]);
+        let url = server.url("/").to_string();
+        let links = vec![
+            Link::new(url.as_str(), Default::default(), ()),
+            Link::new("https://example.invalid/", Default::default(), ()),
+        ];
+        let mut headers = HeaderMap::new();
+        headers.insert("x-token", HeaderValue::from_static("secret"));
+        let mut inner = BasicContext::default();
+        inner.record_attempts = true;
+        let ctx = inner
+            .with_ignore(["*.invalid/*"])
+            .unwrap()
+            .with_headers(headers)
+            .with_cache(Cache::new())
+            .with_concurrency(4, 2);
+
+        let got = validate(Path::new("."), links, &ctx).await;
+
+        assert_eq!(got.valid.len(), 1);
+        assert_eq!(got.ignored.len(), 1);
+        assert!(ctx.record_attempts());
+        assert_eq!((ctx.concurrency(), ctx.concurrency_per_host()), (4, 2));
+        let sent_token = server.requests().iter().all(|request| {
+            request.headers.iter().any(|(name, value)| {
+                name.eq_ignore_ascii_case("x-token") && value == "secret"
+            })
+        });
+        assert!(sent_token);
+        let (inner, cache) = ctx.into_inner();
+        assert_eq!(cache.unwrap().iter().count(), 1);
+        assert_eq!(inner.cache().unwrap().iter().count(), 0);
+    }
+}
@@ -0,0 +1,295 @@
+use crate::{
+    validation::{baseline::file_name, InvalidLink, Outcomes, Reason},
+    Link,
+};
+use codespan::{Files, Span};
+use std::collections::HashSet;
+
+/// A serializable record of the [`Outcomes`] from a previous run.
+///
+/// Unlike [`Outcomes`], a [`Snapshot`] only keeps the textual form of each
+/// [`crate::validation::Reason`] and refers to files by name instead of by
+/// [`codespan::FileId`], so it can be saved to disk (e.g. as a baseline
+/// committed to version control) and loaded again later.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    /// Every [`Link`] that was checked, and what happened to it.
+    pub entries: Vec<SnapshotEntry>,
+}
+
+impl Snapshot {
+    /// Record what happened to every [`Link`] in a set of [`Outcomes`].
+    pub fn new<S>(outcomes: &Outcomes, files: &Files<S>) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let entries = statuses(outcomes)
+            .into_iter()
+            .map(|(link, status, reason)| SnapshotEntry {
+                file: file_name(files, link.file),
+                href: link.href.clone(),
+                span: link.span,
+                status,
+                reason: reason.map(ToString::to_string),
+                source: outcomes.sources.get(link).cloned(),
+            })
+            .collect();
+
+        Snapshot { entries }
+    }
+
+    /// Iterate over all the [`Link`]s which were broken in this run.
+    pub fn invalid(&self) -> impl Iterator<Item = &SnapshotEntry> + '_ {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == Status::Invalid)
+    }
+}
+
+/// Every [`Link`] in the [`Outcomes`], what happened to it, and why it
+/// failed (if it did).
+pub(crate) fn statuses(
+    outcomes: &Outcomes,
+) -> Vec<(&Link, Status, Option<&Reason>)> {
+    let Outcomes {
+        valid,
+        invalid,
+        ignored,
+        unknown_category,
+        unchecked,
+        suppressed,
+        unverifiable,
+        expected_failures,
+        durations: _,
+        candidates: _,
+        attempts: _,
+        suggestions: _,
+        lints: _,
+        sources: _,
+    } = outcomes;
+
+    fn links(
+        links: &[Link],
+        status: Status,
+    ) -> impl Iterator<Item = (&Link, Status, Option<&Reason>)> {
+        links.iter().map(move |link| (link, status, None))
+    }
+    fn failures(
+        failures: &[InvalidLink],
+        status: Status,
+    ) -> impl Iterator<Item = (&Link, Status, Option<&Reason>)> {
+        failures
+            .iter()
+            .map(move |invalid| (&invalid.link, status, Some(&invalid.reason)))
+    }
+
+    let mut entries = Vec::new();
+    entries.extend(links(valid, Status::Valid));
+    entries.extend(failures(invalid, Status::Invalid));
+    entries.extend(links(ignored, Status::Ignored));
+    entries.extend(links(unknown_category, Status::UnknownCategory));
+    entries.extend(links(unchecked, Status::Unchecked));
+    entries.extend(failures(suppressed, Status::Suppressed));
+    entries.extend(failures(unverifiable, Status::Unverifiable));
+    entries.extend(failures(expected_failures, Status::ExpectedFailure));
+
+    entries
+}
+
+/// A single [`Link`] in a [`Snapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotEntry {
+    /// The name of the file the link was found in, using `/` as the path
+    /// separator (like [`crate::validation::BaselineEntry::file`]).
+    pub file: String,
+    /// The link's `href`.
+    pub href: String,
+    /// Where the link was in its file.
+    pub span: Span,
+    /// What was the result?
+    pub status: Status,
+    /// A human-readable explanation of why the [`Link`] was invalid.
+    pub reason: Option<String>,
+    /// Which run the [`Link`] was checked by (see
+    /// [`Outcomes::merge_from()`]).
+    #[cfg_attr(feature = "serde-1", serde(default))]
+    pub source: Option<String>,
+}
+
+/// The status of a [`Link`] recorded in a [`Snapshot`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Status {
+    /// The [`Link`] was valid.
+    Valid,
+    /// The [`Link`] was broken.
+    Invalid,
+    /// The [`Link`] was explicitly ignored.
+    Ignored,
+    /// We weren't able to identify a suitable validator for the [`Link`].
+    UnknownCategory,
+    /// We ran out of time before the [`Link`] could be checked.
+    Unchecked,
+    /// The [`Link`] was broken, but is on the known-broken list.
+    Suppressed,
+    /// We couldn't tell whether the [`Link`] works.
+    Unverifiable,
+    /// The [`Link`] was broken on purpose (see
+    /// [`crate::validation::Context::expected_failures()`]).
+    ExpectedFailure,
+}
+
+/// The difference between two runs, as produced by [`Outcomes::diff()`].
+///
+/// Links are matched up by the file they are in and their [`Link::href`]
+/// (like a [`crate::validation::Baseline`]), so moving a broken link around
+/// within a document won't make it show up as newly broken.
+#[derive(Debug)]
+pub struct Diff<'a> {
+    /// Links which are broken now, but weren't broken previously.
+    pub newly_broken: Vec<&'a InvalidLink>,
+    /// Links which were broken previously and are still broken.
+    pub still_broken: Vec<&'a InvalidLink>,
+    /// Links which were broken previously, but are valid now.
+    ///
+    /// A link which is still failing (e.g. it is now suppressed or
+    /// unverifiable) or wasn't checked this time doesn't count as fixed.
+    pub newly_fixed: Vec<&'a SnapshotEntry>,
+}
+
+impl<'a> Diff<'a> {
+    /// Compare the current [`Outcomes`] against a [`Snapshot`] from a
+    /// previous run.
+    pub fn new<S>(
+        current: &'a Outcomes,
+        previous: &'a Snapshot,
+        files: &Files<S>,
+    ) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let key = |invalid: &InvalidLink| {
+            (
+                file_name(files, invalid.link.file),
+                invalid.link.href.clone(),
+            )
+        };
+        let previously_broken: HashSet<(&str, &str)> = previous
+            .invalid()
+            .map(|entry| (entry.file.as_str(), entry.href.as_str()))
+            .collect();
+        let currently_valid: HashSet<(String, String)> = current
+            .valid
+            .iter()
+            .map(|link| (file_name(files, link.file), link.href.clone()))
+            .collect();
+
+        let (still_broken, newly_broken) =
+            current.invalid.iter().partition(|invalid| {
+                let (file, href) = key(invalid);
+                previously_broken.contains(&(file.as_str(), href.as_str()))
+            });
+        let newly_fixed = previous
+            .invalid()
+            .filter(|entry| {
+                let key = (entry.file.clone(), entry.href.clone());
+                currently_valid.contains(&key)
+            })
+            .collect();
+
+        Diff {
+            newly_broken,
+            still_broken,
+            newly_fixed,
+        }
+    }
+
+    /// Have any links broken since the previous run?
+    pub fn has_regressions(&self) -> bool { !self.newly_broken.is_empty() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::Reason;
+    use codespan::{FileId, Files, Span};
+    use std::io;
+
+    fn link(href: &str, file: FileId) -> Link {
+        Link::new(href, Span::new(0, 0), file)
+    }
+
+    fn invalid(href: &str, file: FileId) -> InvalidLink {
+        InvalidLink {
+            link: link(href, file),
+            reason: Reason::Io(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    #[test]
+    fn compare_against_a_previous_run() {
+        let mut files = Files::new();
+        let file = files.add("index.md", "");
+        let other = files.add("other.md", "");
+        let previous = Outcomes {
+            valid: vec![link("./regressed.md", file)],
+            invalid: vec![
+                invalid("./fixed.md", file),
+                invalid("./old.md", file),
+            ],
+            ..Default::default()
+        };
+        let previous = Snapshot::new(&previous, &files);
+        let current = Outcomes {
+            valid: vec![link("./fixed.md", file)],
+            invalid: vec![
+                invalid("./regressed.md", file),
+                invalid("./old.md", file),
+                invalid("./old.md", other),
+            ],
+            ..Default::default()
+        };
+
+        let diff = current.diff(&previous, &files);
+
+        let newly_broken: Vec<_> =
+            diff.newly_broken.iter().map(|i| &i.link.href).collect();
+        assert_eq!(newly_broken, vec!["./regressed.md", "./old.md"]);
+        assert_eq!(diff.newly_broken[1].link.file, other);
+        let still_broken: Vec<_> =
+            diff.still_broken.iter().map(|i| &i.link.href).collect();
+        assert_eq!(still_broken, vec!["./old.md"]);
+        let newly_fixed: Vec<_> =
+            diff.newly_fixed.iter().map(|e| &e.href).collect();
+        assert_eq!(newly_fixed, vec!["./fixed.md"]);
+        assert!(diff.has_regressions());
+        assert_eq!(previous.entries[1].file, "index.md");
+        assert_eq!(
+            previous.entries[1].reason.as_deref(),
+            Some("An OS-level error occurred")
+        );
+    }
+
+    #[test]
+    fn suppressed_links_are_not_fixed() {
+        let mut files = Files::new();
+        let file = files.add("index.md", "");
+        let previous = Outcomes {
+            invalid: vec![invalid("./flaky.md", file)],
+            ..Default::default()
+        };
+        let previous = Snapshot::new(&previous, &files);
+        let current = Outcomes {
+            suppressed: vec![invalid("./flaky.md", file)],
+            ..Default::default()
+        };
+
+        let diff = current.diff(&previous, &files);
+
+        assert!(diff.newly_fixed.is_empty());
+        assert!(diff.newly_broken.is_empty());
+    }
+}
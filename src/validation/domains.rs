@@ -0,0 +1,163 @@
+use crate::validation::Reason;
+use url::Url;
+
+/// Rules about which domains web links are allowed to point to.
+///
+/// A domain also covers its subdomains, so blocking `internal.example.com`
+/// blocks `wiki.internal.example.com` too. Blocked domains always fail, even
+/// when they are also on the allowlist.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::{DomainPolicy, Unlisted};
+///
+/// // no links to internal hostnames in public docs
+/// let policy = DomainPolicy::new().block("corp.example.com");
+/// assert!(policy.is_blocked("wiki.corp.example.com"));
+/// assert!(policy.is_allowed("docs.rs"));
+///
+/// // only check links to our own sites
+/// let policy = DomainPolicy::new()
+///     .allow("example.com")
+///     .set_unlisted(Unlisted::Fail);
+/// assert!(policy.is_allowed("www.example.com"));
+/// assert!(!policy.is_allowed("example.org"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde-1", serde(default))]
+pub struct DomainPolicy {
+    allowed: Vec<String>,
+    blocked: Vec<String>,
+    unlisted: Unlisted,
+}
+
+impl DomainPolicy {
+    /// Create a [`DomainPolicy`] which allows every domain.
+    pub fn new() -> Self { DomainPolicy::default() }
+
+    /// Add a domain to the allowlist.
+    ///
+    /// Once there is an allowlist, links to any other domain are handled
+    /// according to [`DomainPolicy::unlisted()`].
+    pub fn allow(mut self, domain: &str) -> Self {
+        self.allowed.push(normalise_domain(domain));
+        self
+    }
+
+    /// Forbid links to a domain.
+    pub fn block(mut self, domain: &str) -> Self {
+        self.blocked.push(normalise_domain(domain));
+        self
+    }
+
+    /// What happens to links which aren't on the allowlist?
+    pub fn unlisted(&self) -> Unlisted { self.unlisted }
+
+    /// Set [`DomainPolicy::unlisted()`].
+    pub fn set_unlisted(self, unlisted: Unlisted) -> Self {
+        DomainPolicy { unlisted, ..self }
+    }
+
+    /// The domains on the allowlist.
+    pub fn allowed(&self) -> &[String] { &self.allowed }
+
+    /// The domains which are forbidden.
+    pub fn blocked(&self) -> &[String] { &self.blocked }
+
+    /// Is this host (or one of its parent domains) blocked?
+    pub fn is_blocked(&self, host: &str) -> bool {
+        let host = normalise_domain(host);
+        self.blocked.iter().any(|domain| covers(domain, &host))
+    }
+
+    /// Is this host on the allowlist (or is there no allowlist)?
+    pub fn is_allowed(&self, host: &str) -> bool {
+        let host = normalise_domain(host);
+        self.allowed.is_empty()
+            || self.allowed.iter().any(|domain| covers(domain, &host))
+    }
+
+    /// Decide what to do with a web link.
+    ///
+    /// Returns `Ok(true)` if the link should be checked, `Ok(false)` if it
+    /// should be ignored, and an error if it breaks one of the rules.
+    pub(crate) fn check(&self, url: &Url) -> Result<bool, Reason> {
+        let host = match url.host_str() {
+            Some(host) => host,
+            None => return Ok(true),
+        };
+
+        if self.is_blocked(host) {
+            Err(Reason::BlockedDomain(host.to_string()))
+        } else if self.is_allowed(host) {
+            Ok(true)
+        } else {
+            match self.unlisted {
+                Unlisted::Ignore => Ok(false),
+                Unlisted::Fail => {
+                    Err(Reason::DomainNotAllowed(host.to_string()))
+                },
+            }
+        }
+    }
+}
+
+/// What to do with web links to domains which aren't on a
+/// [`DomainPolicy`]'s allowlist.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Unlisted {
+    /// Skip the link, reporting it in [`Outcomes::ignored`].
+    ///
+    /// [`Outcomes::ignored`]: crate::validation::Outcomes::ignored
+    #[default]
+    Ignore,
+    /// Report the link as broken.
+    Fail,
+}
+
+fn normalise_domain(domain: &str) -> String {
+    domain.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Is `host` the same as `domain` or one of its subdomains?
+fn covers(domain: &str, host: &str) -> bool {
+    match host.strip_suffix(domain) {
+        Some("") => true,
+        Some(prefix) => prefix.ends_with('.'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subdomains_are_covered_by_their_parents() {
+        let policy = DomainPolicy::new()
+            .allow("Example.com.")
+            .block("internal.example.com")
+            .set_unlisted(Unlisted::Fail);
+        let inputs = vec![
+            ("https://example.com/", Some(true)),
+            ("https://docs.EXAMPLE.com/", Some(true)),
+            ("https://notexample.com/", None),
+            ("https://internal.example.com/", None),
+            ("https://a.internal.example.com/", None),
+            ("file:///etc/passwd", Some(true)),
+        ];
+
+        for (url, should_be) in inputs {
+            let url = Url::parse(url).unwrap();
+            let got = policy.check(&url).ok();
+            assert_eq!(got, should_be, "{}", url);
+        }
+
+        let ignore = policy.set_unlisted(Unlisted::Ignore);
+        let url = Url::parse("https://example.org/").unwrap();
+        assert_eq!(ignore.check(&url).ok(), Some(false));
+    }
+}
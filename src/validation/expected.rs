@@ -0,0 +1,132 @@
+use crate::Link;
+use url::Url;
+
+/// Links which are *supposed* to be broken, like the made-up URLs used as
+/// examples in documentation about error handling.
+///
+/// A link is expected to fail when its host is one of the
+/// [`ExpectedFailures::domains()`] (or a subdomain of one), or when its title
+/// contains the [`ExpectedFailures::marker()`] (e.g.
+/// `[dead link](https://example.org/gone "linkcheck:expect-failure")`).
+///
+/// These links are still checked. If they are broken they end up in
+/// [`Outcomes::expected_failures`] instead of [`Outcomes::invalid`], but a
+/// link which works is reported as invalid with
+/// [`Reason::UnexpectedlyValid`] so stale examples get noticed.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{validation::ExpectedFailures, Link};
+///
+/// let expected = ExpectedFailures::new().add_domain("dead.example.com");
+/// let link = |href: &str| Link::new(href, Default::default(), ());
+///
+/// let reserved = link("https://this-domain-does-not-exist.example/");
+/// assert!(expected.expects_failure(&reserved));
+/// assert!(expected.expects_failure(&link("https://docs.dead.example.com/")));
+///
+/// let mut annotated = link("./missing.md");
+/// annotated.title = Some(String::from("linkcheck:expect-failure"));
+/// assert!(expected.expects_failure(&annotated));
+///
+/// assert!(!expected.expects_failure(&link("https://example.com/")));
+/// ```
+///
+/// [`Outcomes::expected_failures`]: crate::validation::Outcomes::expected_failures
+/// [`Outcomes::invalid`]: crate::validation::Outcomes::invalid
+/// [`Reason::UnexpectedlyValid`]: crate::validation::Reason::UnexpectedlyValid
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpectedFailures {
+    domains: Vec<String>,
+    marker: Option<String>,
+}
+
+impl ExpectedFailures {
+    /// The default [`ExpectedFailures::marker()`].
+    pub const DEFAULT_MARKER: &'static str = "linkcheck:expect-failure";
+    /// The top-level domains which are reserved for documentation and
+    /// testing, and will never resolve ([RFC 2606]).
+    ///
+    /// [RFC 2606]: https://www.rfc-editor.org/rfc/rfc2606
+    pub const RESERVED_DOMAINS: &'static [&'static str] =
+        &["example", "invalid", "test"];
+
+    /// Expect links to the [`ExpectedFailures::RESERVED_DOMAINS`] and links
+    /// with the [`ExpectedFailures::DEFAULT_MARKER`] in their title to fail.
+    pub fn new() -> Self {
+        ExpectedFailures {
+            domains: ExpectedFailures::RESERVED_DOMAINS
+                .iter()
+                .map(|domain| domain.to_string())
+                .collect(),
+            marker: Some(ExpectedFailures::DEFAULT_MARKER.to_string()),
+        }
+    }
+
+    /// An empty list, which doesn't expect anything to fail.
+    pub fn none() -> Self {
+        ExpectedFailures {
+            domains: Vec::new(),
+            marker: None,
+        }
+    }
+
+    /// Also expect links to this domain (and its subdomains) to fail.
+    pub fn add_domain<S: Into<String>>(mut self, domain: S) -> Self {
+        let domain = domain.into();
+        self.domains
+            .push(domain.trim_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    /// Use a different annotation in the link's title, or `None` to only go
+    /// by the domain.
+    pub fn set_marker<S: Into<String>>(self, marker: Option<S>) -> Self {
+        ExpectedFailures {
+            marker: marker.map(Into::into),
+            ..self
+        }
+    }
+
+    /// The domains whose links are expected to fail.
+    pub fn domains(&self) -> impl Iterator<Item = &str> + '_ {
+        self.domains.iter().map(String::as_str)
+    }
+
+    /// The text which marks a link as an expected failure when it appears
+    /// in the link's title.
+    pub fn marker(&self) -> Option<&str> { self.marker.as_deref() }
+
+    /// Is this [`Link`] expected to be broken?
+    pub fn expects_failure<F>(&self, link: &Link<F>) -> bool {
+        let annotated = match (&self.marker, &link.title) {
+            (Some(marker), Some(title)) => title.contains(marker.as_str()),
+            _ => false,
+        };
+
+        annotated || self.is_expected_domain(&link.href)
+    }
+
+    fn is_expected_domain(&self, href: &str) -> bool {
+        let url = match Url::parse(href.trim()) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+        let host = match url.host_str() {
+            Some(host) => host.trim_end_matches('.').to_ascii_lowercase(),
+            None => return false,
+        };
+
+        self.domains.iter().any(|domain| {
+            host == *domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|rest| rest.ends_with('.'))
+        })
+    }
+}
+
+impl Default for ExpectedFailures {
+    fn default() -> Self { ExpectedFailures::new() }
+}
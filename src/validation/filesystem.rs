@@ -1,12 +1,18 @@
-use crate::validation::{Context, Reason};
+use crate::validation::{Context, Reason, UrlMapping};
 use std::{
+    borrow::Cow,
     collections::HashMap,
     ffi::{OsStr, OsString},
     fmt::{self, Debug, Formatter},
     io,
     path::{Component, Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
+use unicode_normalization::UnicodeNormalization;
+use walkdir::WalkDir;
+
+/// The most files [`find_moved_file()`] will report.
+const MAX_CANDIDATES: usize = 5;
 
 /// Try to resolve a link relative to the current directory.
 ///
@@ -51,20 +57,32 @@ pub fn resolve_link(
     link: &Path,
     options: &Options,
 ) -> Result<PathBuf, Reason> {
-    let joined = options.join(current_directory, link)?;
+    resolve_link_with(current_directory, link, options, &Directories::default())
+}
 
-    let candidates = options.possible_names(joined);
+/// [`resolve_link()`], reusing the canonical form of directories we've seen
+/// before.
+pub(crate) fn resolve_link_with(
+    current_directory: &Path,
+    link: &Path,
+    options: &Options,
+    directories: &Directories,
+) -> Result<PathBuf, Reason> {
+    for form in normalisation_forms(link) {
+        let joined = options.join(current_directory, &form)?;
 
-    for candidate in candidates {
-        log::trace!(
-            "Checking if \"{}\" points to \"{}\"",
-            link.display(),
-            candidate.display(),
-        );
+        for candidate in options.possible_names(joined) {
+            log::trace!(
+                "Checking if \"{}\" points to \"{}\"",
+                link.display(),
+                candidate.display(),
+            );
 
-        if let Ok(canonical) = options.canonicalize(&candidate) {
-            options.sanity_check(&canonical)?;
-            return Ok(canonical);
+            if let Ok(canonical) = options.canonicalize(&candidate, directories)
+            {
+                options.sanity_check(&canonical)?;
+                return Ok(canonical);
+            }
         }
     }
 
@@ -72,17 +90,166 @@ pub fn resolve_link(
     Err(Reason::Io(io::ErrorKind::NotFound.into()))
 }
 
+/// The link as written, followed by its NFC and NFD forms if they are
+/// different.
+///
+/// Some filesystems (notably on macOS) store file names decomposed, while
+/// links are usually typed composed, so `café.md` might not match on the
+/// first try.
+pub(crate) fn normalisation_forms(link: &Path) -> Vec<Cow<'_, Path>> {
+    let mut forms = vec![Cow::Borrowed(link)];
+
+    let text = match link.to_str() {
+        Some(text) if !text.is_ascii() => text,
+        _ => return forms,
+    };
+
+    for normalised in &[text.nfc().collect::<String>(), text.nfd().collect()] {
+        if forms
+            .iter()
+            .all(|form| form.as_os_str() != normalised.as_str())
+        {
+            forms.push(Cow::Owned(PathBuf::from(normalised)));
+        }
+    }
+
+    forms
+}
+
+/// The canonical form of each directory links have pointed into, so a
+/// directory only gets canonicalized once no matter how many links point
+/// into it.
+///
+/// It also remembers every file under the [`Options::root_directory()`] by
+/// name, so [`find_moved_file()`] only walks the directory tree once.
+#[derive(Debug, Default)]
+pub(crate) struct Directories {
+    canonical: Mutex<HashMap<PathBuf, Option<PathBuf>>>,
+    files_by_name: Mutex<Option<HashMap<OsString, Vec<PathBuf>>>>,
+}
+
+impl Directories {
+    fn canonicalize(&self, directory: &Path) -> io::Result<PathBuf> {
+        let cached = self
+            .canonical
+            .lock()
+            .expect("Mutex was poisoned")
+            .get(directory)
+            .cloned();
+
+        let canonical = match cached {
+            Some(canonical) => canonical,
+            None => {
+                // Note: don't hold the lock while touching the filesystem
+                let canonical = dunce::canonicalize(directory).ok();
+                self.canonical
+                    .lock()
+                    .expect("Mutex was poisoned")
+                    .insert(directory.to_path_buf(), canonical.clone());
+                canonical
+            },
+        };
+
+        canonical.ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    /// Find the files under `root` with this name.
+    fn files_named(&self, root: &Path, name: &OsStr) -> Vec<PathBuf> {
+        // Note: we hold the lock while walking the tree so it only happens
+        // once, even when lots of links are broken
+        let mut files_by_name =
+            self.files_by_name.lock().expect("Mutex was poisoned");
+
+        files_by_name
+            .get_or_insert_with(|| files_by_name_under(root))
+            .get(name)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Walk the directory tree, grouping the files in it by name (skipping
+/// hidden files and directories).
+fn files_by_name_under(root: &Path) -> HashMap<OsString, Vec<PathBuf>> {
+    let is_hidden = |entry: &walkdir::DirEntry| {
+        entry.depth() > 0
+            && entry.file_name().to_string_lossy().starts_with('.')
+    };
+    let mut files: HashMap<OsString, Vec<PathBuf>> = HashMap::new();
+
+    let entries = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| !is_hidden(entry))
+        .flatten()
+        .filter(|entry| entry.file_type().is_file());
+
+    for entry in entries {
+        files
+            .entry(entry.file_name().to_os_string())
+            .or_default()
+            .push(entry.into_path());
+    }
+
+    files
+}
+
 /// Check whether a [`Path`] points to a valid file on disk.
 ///
 /// If a fragment specifier is provided, this function will scan through the
 /// linked document and check that the file contains the corresponding anchor
-/// (e.g. markdown heading or HTML `id`).
+/// (e.g. markdown heading or HTML `id`). When the linked document isn't in
+/// the [`crate::anchors::AnchorDb`], its alternate extensions are tried (e.g.
+/// the anchors for `other.html` come from `other.md`).
 pub fn check_filesystem<C>(
     current_directory: &Path,
     path: &Path,
     fragment: Option<&str>,
     ctx: &C,
 ) -> Result<(), Reason>
+where
+    C: Context + ?Sized,
+{
+    let directories = Directories::default();
+    check_filesystem_with(current_directory, path, fragment, ctx, &directories)
+}
+
+/// Look for an anchor in a document using the [`Context::anchor_db()`],
+/// returning `None` if the document isn't in the database.
+pub(crate) fn check_fragment<C>(
+    document: &Path,
+    fragment: &str,
+    ctx: &C,
+) -> Option<Result<(), Reason>>
+where
+    C: Context + ?Sized,
+{
+    let db = ctx.anchor_db()?;
+    // the file we found may not be the one with the anchors (e.g. a link to
+    // "other.html" when only "other.md" was added to the database)
+    let source = ctx
+        .filesystem_options()
+        .anchor_sources(document)
+        .into_iter()
+        .find(|candidate| db.anchors(candidate).is_some())?;
+
+    if db.contains(&source, fragment)? {
+        Some(Ok(()))
+    } else {
+        let closest = db.closest(&source, fragment).map(String::from);
+        Some(Err(Reason::MissingAnchor(fragment.to_string(), closest)))
+    }
+}
+
+/// [`check_filesystem()`], reusing the canonical form of directories we've
+/// seen before.
+pub(crate) fn check_filesystem_with<C>(
+    current_directory: &Path,
+    path: &Path,
+    fragment: Option<&str>,
+    ctx: &C,
+    directories: &Directories,
+) -> Result<(), Reason>
 where
     C: Context + ?Sized,
 {
@@ -93,7 +260,8 @@ where
     );
 
     let options = ctx.filesystem_options();
-    let resolved_location = resolve_link(current_directory, path, options)?;
+    let resolved_location =
+        resolve_link_with(current_directory, path, options, directories)?;
 
     log::debug!(
         "\"{}\" resolved to \"{}\"",
@@ -102,12 +270,14 @@ where
     );
 
     if let Some(fragment) = fragment {
-        // TODO: detect the file type and check the fragment exists
-        log::warn!(
-            "Not checking that the \"{}\" section exists in \"{}\" because fragment resolution isn't implemented",
-            fragment,
-            resolved_location.display(),
-        );
+        match check_fragment(&resolved_location, fragment, ctx) {
+            Some(result) => result?,
+            None => log::warn!(
+                "Not checking that the \"{}\" section exists in \"{}\" because it isn't in the anchor database",
+                fragment,
+                resolved_location.display(),
+            ),
+        }
     }
 
     if let Err(reason) =
@@ -124,6 +294,86 @@ where
     Ok(())
 }
 
+/// When a link points to a file that doesn't exist, look for one in the same
+/// directory whose name only differs by case, or which has a different
+/// extension (as long as there is only one).
+pub(crate) fn suggest_file_name(
+    current_directory: &Path,
+    link: &Path,
+    options: &Options,
+) -> Option<String> {
+    let joined = options.join(current_directory, link).ok()?;
+    let wanted = joined.file_name()?.to_str()?;
+    let wanted_stem = joined.file_stem()?.to_str()?;
+    let mut same_stem = Vec::new();
+
+    for entry in std::fs::read_dir(joined.parent()?).ok()?.flatten() {
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        let stem = Path::new(&name).file_stem().and_then(OsStr::to_str);
+
+        if name.eq_ignore_ascii_case(wanted) {
+            same_stem = vec![name];
+            break;
+        } else if stem.is_some_and(|s| s.eq_ignore_ascii_case(wanted_stem)) {
+            same_stem.push(name);
+        }
+    }
+
+    if same_stem.len() != 1 {
+        return None;
+    }
+    let name = same_stem.remove(0);
+
+    // make sure the suggestion would actually be valid
+    resolve_link(current_directory, &link.with_file_name(&name), options)
+        .ok()?;
+    log::debug!("Suggesting \"{}\" instead of \"{}\"", name, wanted);
+
+    Some(name)
+}
+
+/// When a link points to a file that doesn't exist, look for files with the
+/// same name elsewhere (e.g. because a chapter was moved to another
+/// directory).
+///
+/// Only the [`Options::root_directory()`] is searched, so nothing is
+/// suggested when there isn't one.
+pub(crate) fn find_moved_file(
+    current_directory: &Path,
+    link: &Path,
+    options: &Options,
+    directories: &Directories,
+) -> Vec<PathBuf> {
+    let root = match options.root_directory() {
+        Some(root) => root,
+        None => return Vec::new(),
+    };
+    let joined = match options.join(current_directory, link) {
+        Ok(joined) => joined,
+        Err(_) => return Vec::new(),
+    };
+    let wanted = match joined.file_name() {
+        Some(name) => name,
+        None => return Vec::new(),
+    };
+
+    let mut candidates = directories.files_named(root, wanted);
+    candidates.truncate(MAX_CANDIDATES);
+
+    if !candidates.is_empty() {
+        log::debug!(
+            "\"{}\" doesn't exist, but there are files with the same name at {:?}",
+            joined.display(),
+            candidates
+        );
+    }
+
+    candidates
+}
+
 /// Options to be used with [`resolve_link()`].
 #[derive(Clone)]
 #[cfg_attr(
@@ -138,10 +388,14 @@ pub struct Options {
     // Note: the key is normalised to lowercase to make sure extensions are
     // case insensitive
     alternate_extensions: HashMap<String, Vec<OsString>>,
+    url_mappings: Vec<UrlMapping>,
     #[serde(skip, default = "nop_custom_validation")]
-    custom_validation: Arc<dyn Fn(&Path, Option<&str>) -> Result<(), Reason>>,
+    custom_validation: CustomValidation,
 }
 
+type CustomValidation =
+    Arc<dyn Fn(&Path, Option<&str>) -> Result<(), Reason> + Send + Sync>;
+
 impl Options {
     /// The name used by [`Options::default_file()`].
     pub const DEFAULT_FILE: &'static str = "index.html";
@@ -151,8 +405,7 @@ impl Options {
     pub fn default_alternate_extensions(
     ) -> impl IntoIterator<Item = (OsString, impl IntoIterator<Item = OsString>)>
     {
-        const MAPPING: &'static [(&'static str, &'static [&'static str])] =
-            &[("md", &["html"])];
+        const MAPPING: &[(&str, &[&str])] = &[("md", &["html"])];
 
         MAPPING.iter().map(|(ext, alts)| {
             (OsString::from(ext), alts.iter().map(OsString::from))
@@ -170,17 +423,18 @@ impl Options {
                 .map(|(key, values)| {
                     (
                         key.to_string_lossy().to_lowercase(),
-                        values.into_iter().map(Into::into).collect(),
+                        values.into_iter().collect(),
                     )
                 })
                 .collect(),
+            url_mappings: Vec::new(),
             custom_validation: nop_custom_validation(),
         }
     }
 
     /// Get the root directory, if one was provided.
     pub fn root_directory(&self) -> Option<&Path> {
-        self.root_directory.as_ref().map(|p| &**p)
+        self.root_directory.as_deref()
     }
 
     /// Set the [`Options::root_directory()`], automatically converting to its
@@ -254,11 +508,29 @@ impl Options {
         }
     }
 
+    /// URLs which should be checked against local files instead of going to
+    /// the web (see [`UrlMapping`]).
+    pub fn url_mappings(&self) -> &[UrlMapping] { &self.url_mappings }
+
+    /// Set the [`Options::url_mappings()`].
+    pub fn set_url_mappings<I>(self, url_mappings: I) -> Self
+    where
+        I: IntoIterator<Item = UrlMapping>,
+    {
+        Options {
+            url_mappings: url_mappings.into_iter().collect(),
+            ..self
+        }
+    }
+
     /// Set a function which will be executed after a link is resolved, allowing
     /// you to apply custom business logic.
     pub fn set_custom_validation<F>(self, custom_validation: F) -> Self
     where
-        F: Fn(&Path, Option<&str>) -> Result<(), Reason> + 'static,
+        F: Fn(&Path, Option<&str>) -> Result<(), Reason>
+            + Send
+            + Sync
+            + 'static,
     {
         let custom_validation = Arc::new(custom_validation);
         Options {
@@ -267,7 +539,7 @@ impl Options {
         }
     }
 
-    fn join(
+    pub(crate) fn join(
         &self,
         current_dir: &Path,
         second: &Path,
@@ -312,8 +584,23 @@ impl Options {
     /// other filesystem quirks.
     ///
     /// This will fail if the item doesn't exist.
-    fn canonicalize(&self, path: &Path) -> Result<PathBuf, Reason> {
-        let mut canonical = dunce::canonicalize(path)?;
+    fn canonicalize(
+        &self,
+        path: &Path,
+        directories: &Directories,
+    ) -> Result<PathBuf, Reason> {
+        let mut canonical = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+                let candidate = directories.canonicalize(parent)?.join(name);
+
+                if std::fs::symlink_metadata(&candidate)?.is_symlink() {
+                    dunce::canonicalize(candidate)?
+                } else {
+                    candidate
+                }
+            },
+            _ => dunce::canonicalize(path)?,
+        };
 
         if canonical.is_dir() {
             log::trace!(
@@ -357,7 +644,7 @@ impl Options {
     /// sometimes the file being linked to may be usable with another extension
     /// (e.g. in mdbook, markdown files can be linked to with the HTML
     /// extension).
-    fn possible_names(
+    pub(crate) fn possible_names(
         &self,
         original: PathBuf,
     ) -> impl IntoIterator<Item = PathBuf> {
@@ -382,6 +669,47 @@ impl Options {
         names
     }
 
+    /// The files which might hold the anchors for `resolved`, in the order
+    /// they should be tried.
+    ///
+    /// This is `resolved` followed by its [`Options::alternate_extensions()`]
+    /// and then any extensions which use its extension as an alternate, so
+    /// a link to `other.html#section` can be checked against the anchors in
+    /// `other.md` (and vice versa).
+    pub(crate) fn anchor_sources(&self, resolved: &Path) -> Vec<PathBuf> {
+        let mut sources: Vec<PathBuf> = self
+            .possible_names(resolved.to_path_buf())
+            .into_iter()
+            .collect();
+
+        let extension = match resolved.extension() {
+            Some(ext) => ext.to_string_lossy().to_lowercase(),
+            None => return sources,
+        };
+
+        // sorted so the order doesn't depend on the HashMap
+        let mut originals: Vec<&String> = self
+            .alternate_extensions
+            .iter()
+            .filter(|(_, alternatives)| {
+                alternatives.iter().any(|alt| {
+                    alt.to_string_lossy().eq_ignore_ascii_case(&extension)
+                })
+            })
+            .map(|(original, _)| original)
+            .collect();
+        originals.sort();
+
+        for original in originals {
+            let source = resolved.with_extension(original);
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+
+        sources
+    }
+
     fn run_custom_validation(
         &self,
         resolved_path: &Path,
@@ -391,10 +719,7 @@ impl Options {
     }
 }
 
-fn nop_custom_validation(
-) -> Arc<dyn Fn(&Path, Option<&str>) -> Result<(), Reason>> {
-    Arc::new(|_, _| Ok(()))
-}
+fn nop_custom_validation() -> CustomValidation { Arc::new(|_, _| Ok(())) }
 
 impl Default for Options {
     fn default() -> Self { Options::new() }
@@ -407,6 +732,7 @@ impl Debug for Options {
             default_file,
             links_may_traverse_the_root_directory,
             alternate_extensions,
+            url_mappings,
             custom_validation: _,
         } = self;
 
@@ -418,6 +744,7 @@ impl Debug for Options {
                 links_may_traverse_the_root_directory,
             )
             .field("alternate_extensions", alternate_extensions)
+            .field("url_mappings", url_mappings)
             .finish()
     }
 }
@@ -429,6 +756,7 @@ impl PartialEq for Options {
             default_file,
             links_may_traverse_the_root_directory,
             alternate_extensions,
+            url_mappings,
             custom_validation: _,
         } = self;
 
@@ -437,12 +765,13 @@ impl PartialEq for Options {
             && links_may_traverse_the_root_directory
                 == &other.links_may_traverse_the_root_directory
             && alternate_extensions == &other.alternate_extensions
+            && url_mappings == &other.url_mappings
     }
 }
 
 fn remove_absolute_components(
     path: &Path,
-) -> impl Iterator<Item = Component> + '_ {
+) -> impl Iterator<Item = Component<'_>> + '_ {
     path.components()
         .skip_while(|c| matches!(c, Component::Prefix(_) | Component::RootDir))
 }
@@ -450,7 +779,11 @@ fn remove_absolute_components(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::BasicContext;
+    use crate::{
+        anchors::AnchorDb,
+        validation::{KnownBroken, Outcomes, SafetyLimits},
+        BasicContext,
+    };
     use std::sync::atomic::{AtomicBool, Ordering};
 
     fn validation_dir() -> PathBuf {
@@ -624,6 +957,47 @@ mod tests {
         assert_eq!(got, temp.join("index.html"));
     }
 
+    #[test]
+    fn anchors_come_from_the_source_of_a_rendered_file() {
+        init_logging();
+        let temp = tempfile::tempdir().unwrap();
+        let temp = dunce::canonicalize(temp.path()).unwrap();
+        touch("other.md", &[&temp]);
+        touch("other.html", &[&temp]);
+        let mut ctx = BasicContext::default();
+        let mut anchors = AnchorDb::new();
+        anchors.insert_markdown(temp.join("other.md"), "# Getting Started");
+        ctx.anchors = Some(anchors);
+        let link = Path::new("other.html");
+
+        let check = |fragment| check_filesystem(&temp, link, fragment, &ctx);
+
+        let found = check(Some("getting-started"));
+        let typo = check(Some("getting-start"));
+
+        assert!(found.is_ok());
+        match typo {
+            Err(Reason::MissingAnchor(_, closest)) => {
+                assert_eq!(closest.as_deref(), Some("getting-started"))
+            },
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn link_to_a_file_with_a_decomposed_name() {
+        init_logging();
+        let temp = tempfile::tempdir().unwrap();
+        let temp = dunce::canonicalize(temp.path()).unwrap();
+        touch("cafe\u{301}.md", &[&temp]);
+        let options = Options::default();
+
+        let got =
+            resolve_link(&temp, Path::new("caf\u{e9}.md"), &options).unwrap();
+
+        assert_eq!(got, temp.join("cafe\u{301}.md"));
+    }
+
     #[test]
     fn join_paths() {
         init_logging();
@@ -652,4 +1026,75 @@ mod tests {
             assert_eq!(got, *should_be);
         }
     }
+
+    #[test]
+    fn check_lots_of_local_links_in_parallel() {
+        init_logging();
+        let temp = tempfile::tempdir().unwrap();
+        let temp = dunce::canonicalize(temp.path()).unwrap();
+        let chapter = temp.join("chapter");
+        touch("index.md", &[&temp, &chapter]);
+        let ctx = BasicContext::default();
+        let file = codespan::Files::new().add("index.md", "");
+        let hrefs = ["chapter/index.md", "./chapter/../index.md", "nope.md"];
+        let link = |href| crate::Link::new(href, Default::default(), file);
+        let mut links = Vec::new();
+        for _ in 0..100 {
+            for href in &hrefs {
+                links.push((link(*href), temp.as_path()));
+            }
+        }
+        let web = link("https://example.com/");
+        links.push((web.clone(), temp.as_path()));
+
+        let (outcomes, remaining) =
+            crate::validation::validate_local(links, &ctx);
+
+        assert_eq!(outcomes.valid.len(), 200);
+        assert_eq!(outcomes.invalid.len(), 100);
+        assert!(outcomes.invalid.iter().all(|i| i.reason.file_not_found()));
+        assert_eq!(remaining, vec![(web, temp.as_path())]);
+    }
+
+    #[tokio::test]
+    async fn check_local_links_the_same_way_as_validate() {
+        let temp = tempfile::tempdir().unwrap();
+        let temp = dunce::canonicalize(temp.path()).unwrap();
+        touch("index.md", &[&temp]);
+        let mut ctx = BasicContext::with_safety_limits(
+            SafetyLimits::new().set_max_url_length(Some(20)),
+        );
+        ctx.known_broken =
+            Some(KnownBroken::new().add("snoozed.md", None).unwrap());
+        let long = "./././././././index.md";
+        let hrefs = ["index.md", "nope.md", "snoozed.md", long];
+        let links: Vec<_> = hrefs
+            .iter()
+            .map(|href| crate::Link::new(*href, Default::default(), ()))
+            .collect();
+
+        let (local, remaining) = crate::validation::validate_local(
+            links.iter().map(|link| (link.clone(), temp.as_path())),
+            &ctx,
+        );
+        let all = crate::validation::validate(&temp, links, &ctx).await;
+
+        assert!(remaining.is_empty());
+        fn sorted<'a, I: Iterator<Item = &'a String>>(hrefs: I) -> Vec<String> {
+            let mut hrefs: Vec<_> = hrefs.cloned().collect();
+            hrefs.sort();
+            hrefs
+        }
+        let summary = |outcomes: &Outcomes<()>| {
+            (
+                sorted(outcomes.valid.iter().map(|l| &l.href)),
+                sorted(outcomes.invalid.iter().map(|i| &i.link.href)),
+                sorted(outcomes.suppressed.iter().map(|i| &i.link.href)),
+                outcomes.durations.len(),
+            )
+        };
+        assert_eq!(summary(&local), summary(&all));
+        assert_eq!(local.invalid.len(), 2);
+        assert_eq!(local.suppressed.len(), 1);
+    }
 }
@@ -0,0 +1,803 @@
+use crate::validation::Reason;
+use http::StatusCode;
+use reqwest::{Client, RequestBuilder, Url};
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    time::Instant,
+};
+
+/// Check links to GitHub and GitLab issues, pull requests, and files using
+/// each forge's API.
+///
+/// A plain request to an issue's web page can't tell the difference between
+/// an issue which doesn't exist, a private repository, and being rate
+/// limited. The API can, and it also tells us when an issue was transferred
+/// to another repository.
+///
+/// Links to a file in a repository (e.g. `blob/main/src/lib.rs#L42`) are
+/// checked against the raw file, so we can make sure the lines being linked
+/// to actually exist.
+///
+/// Comparison links (e.g. `compare/v1.0.0...v1.1.0`, as seen in most
+/// changelogs) are checked by asking the API to compare the two refs. The web
+/// page is happy to render an empty comparison when a tag was never pushed.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::ForgeApi;
+/// use url::Url;
+///
+/// let api = ForgeApi::new()
+///     .set_github_token("ghp_xxx")
+///     .add_gitlab_host("gitlab.example.com");
+///
+/// let url = Url::parse("https://github.com/rust-lang/rust/issues/1").unwrap();
+/// let issue = api.issue(&url).unwrap();
+/// assert_eq!(issue.to_string(), "rust-lang/rust#1");
+///
+/// let url =
+///     Url::parse("https://gitlab.example.com/group/app/-/merge_requests/7")
+///         .unwrap();
+/// assert_eq!(api.issue(&url).unwrap().to_string(), "group/app!7");
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct ForgeApi {
+    github_api: Url,
+    github_raw: Url,
+    github_token: Option<String>,
+    gitlab_hosts: Vec<String>,
+    gitlab_token: Option<String>,
+}
+
+impl ForgeApi {
+    /// Create a [`ForgeApi`] for `github.com` and `gitlab.com`, without any
+    /// tokens.
+    pub fn new() -> Self { ForgeApi::default() }
+
+    /// Send this token when using the GitHub API, which gives a much higher
+    /// rate limit and access to private repositories.
+    pub fn set_github_token<S: Into<String>>(self, token: S) -> Self {
+        ForgeApi {
+            github_token: Some(token.into()),
+            ..self
+        }
+    }
+
+    /// Use a different GitHub API (e.g. for GitHub Enterprise).
+    pub fn set_github_api(self, github_api: Url) -> Self {
+        ForgeApi { github_api, ..self }
+    }
+
+    /// The GitHub API being used.
+    pub fn github_api(&self) -> &Url { &self.github_api }
+
+    /// Download raw files from somewhere other than
+    /// `raw.githubusercontent.com`.
+    pub fn set_github_raw(self, github_raw: Url) -> Self {
+        ForgeApi { github_raw, ..self }
+    }
+
+    /// Where raw files are downloaded from on GitHub.
+    pub fn github_raw(&self) -> &Url { &self.github_raw }
+
+    /// Send this token when using the GitLab API.
+    pub fn set_gitlab_token<S: Into<String>>(self, token: S) -> Self {
+        ForgeApi {
+            gitlab_token: Some(token.into()),
+            ..self
+        }
+    }
+
+    /// Treat links to another host as a (self-hosted) GitLab instance.
+    pub fn add_gitlab_host(mut self, host: &str) -> Self {
+        self.gitlab_hosts.push(host.to_ascii_lowercase());
+        self
+    }
+
+    /// The hosts which are treated as GitLab instances.
+    pub fn gitlab_hosts(&self) -> &[String] { &self.gitlab_hosts }
+
+    /// Figure out which issue or pull request a URL points to, if any.
+    pub fn issue(&self, url: &Url) -> Option<Issue> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let segments: Vec<_> = url.path_segments()?.collect();
+
+        if host == "github.com" || host == "www.github.com" {
+            github_issue(&segments)
+        } else if self.gitlab_hosts.contains(&host) {
+            gitlab_issue(&segments)
+        } else {
+            None
+        }
+    }
+
+    /// Figure out which file in a repository a URL points to, if any.
+    pub fn file(&self, url: &Url) -> Option<RepoFile> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let segments: Vec<_> = url.path_segments()?.collect();
+
+        let (forge, project, path) = match segments.as_slice() {
+            [owner, repo, "blob", path @ ..]
+                if host == "github.com" || host == "www.github.com" =>
+            {
+                (Forge::GitHub, format!("{}/{}", owner, repo), path)
+            },
+            _ if self.gitlab_hosts.contains(&host) => {
+                let dash = segments.iter().position(|s| *s == "-")?;
+                match &segments[dash..] {
+                    ["-", "blob", path @ ..] if dash > 0 => {
+                        (Forge::GitLab, segments[..dash].join("/"), path)
+                    },
+                    _ => return None,
+                }
+            },
+            _ => return None,
+        };
+
+        // we need at least the ref and a file name
+        if path.len() < 2 || path.iter().any(|s| s.is_empty()) {
+            return None;
+        }
+
+        Some(RepoFile {
+            forge,
+            project,
+            path: path.join("/"),
+            lines: url.fragment().and_then(line_range),
+        })
+    }
+
+    /// Figure out which refs a comparison URL (e.g.
+    /// `owner/repo/compare/v1.0.0...v1.1.0`) is between, if any.
+    ///
+    /// Both the three-dot and two-dot forms are understood, as are GitHub's
+    /// single-ref comparisons against the default branch (e.g.
+    /// `compare/feature`), heads in a fork (e.g. `main...user:feature`), and
+    /// a trailing `.diff` or `.patch`. Git doesn't allow `..` in a ref's name,
+    /// so the refs can always be told apart.
+    pub fn comparison(&self, url: &Url) -> Option<Comparison> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let segments: Vec<_> = url.path_segments()?.collect();
+
+        let (forge, project, refs) = match segments.as_slice() {
+            [owner, repo, "compare", refs @ ..]
+                if host == "github.com" || host == "www.github.com" =>
+            {
+                (Forge::GitHub, format!("{}/{}", owner, repo), refs)
+            },
+            _ if self.gitlab_hosts.contains(&host) => {
+                let dash = segments.iter().position(|s| *s == "-")?;
+                match &segments[dash..] {
+                    ["-", "compare", refs @ ..] if dash > 0 => {
+                        (Forge::GitLab, segments[..dash].join("/"), refs)
+                    },
+                    _ => return None,
+                }
+            },
+            _ => return None,
+        };
+
+        let refs = refs.join("/");
+        let refs = refs
+            .strip_suffix(".diff")
+            .or_else(|| refs.strip_suffix(".patch"))
+            .unwrap_or(&refs);
+        let (base, head) =
+            match refs.split_once("...").or_else(|| refs.split_once("..")) {
+                Some((base, head)) => (Some(base.to_string()), head),
+                // GitLab always needs both refs
+                None if forge == Forge::GitLab => return None,
+                None => (None, refs),
+            };
+
+        if head.is_empty() || base.as_deref() == Some("") {
+            return None;
+        }
+
+        Some(Comparison {
+            forge,
+            project,
+            base,
+            head: head.to_string(),
+        })
+    }
+
+    /// Ask the forge to compare the two refs, which only works when both of
+    /// them exist.
+    pub(crate) async fn check_comparison(
+        &self,
+        client: &Client,
+        url: &Url,
+        comparison: &Comparison,
+        deadline: Option<Instant>,
+    ) -> Result<(), Reason> {
+        // on GitHub, a single ref is compared with the default branch
+        let base = comparison.base.as_deref().unwrap_or("HEAD");
+
+        let mut request = match comparison.forge {
+            Forge::GitHub => {
+                let endpoint = format!(
+                    "repos/{}/compare/{}...{}",
+                    comparison.project, base, comparison.head
+                );
+                let mut request = client
+                    .get(self.github_api.join(&endpoint).map_err(invalid_url)?)
+                    .header("Accept", "application/vnd.github+json");
+                if let Some(token) = &self.github_token {
+                    request = request.bearer_auth(token);
+                }
+                request
+            },
+            Forge::GitLab => {
+                let project: String = url::form_urlencoded::byte_serialize(
+                    comparison.project.as_bytes(),
+                )
+                .collect();
+                let endpoint = format!(
+                    "{}/api/v4/projects/{}/repository/compare",
+                    url.origin().ascii_serialization(),
+                    project
+                );
+                let endpoint = Url::parse_with_params(
+                    &endpoint,
+                    &[("from", base), ("to", &comparison.head)],
+                )
+                .map_err(invalid_url)?;
+                let mut request = client.get(endpoint);
+                if let Some(token) = &self.gitlab_token {
+                    request = request.header("PRIVATE-TOKEN", token);
+                }
+                request
+            },
+        };
+
+        if let Some(deadline) = deadline {
+            request = request
+                .timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        log::debug!("Asking the {} API about {}", comparison.forge, comparison);
+        let response = request.send().await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Reason::MissingRef(comparison.to_string()));
+        }
+        if let Some(reason) = rate_limited(&response) {
+            return Err(reason);
+        }
+
+        response.error_for_status()?;
+        Ok(())
+    }
+
+    /// Download the raw file to make sure it exists and has the lines being
+    /// linked to.
+    pub(crate) async fn check_file(
+        &self,
+        client: &Client,
+        url: &Url,
+        file: &RepoFile,
+        deadline: Option<Instant>,
+    ) -> Result<(), Reason> {
+        let raw = match file.forge {
+            Forge::GitHub => self
+                .github_raw
+                .join(&format!("{}/{}", file.project, file.path)),
+            Forge::GitLab => Url::parse(&format!(
+                "{}/{}/-/raw/{}",
+                url.origin().ascii_serialization(),
+                file.project,
+                file.path
+            )),
+        };
+        let mut request = client.get(raw.map_err(invalid_url)?);
+
+        match (file.forge, &self.github_token, &self.gitlab_token) {
+            (Forge::GitHub, Some(token), _) => {
+                request = request.bearer_auth(token)
+            },
+            (Forge::GitLab, _, Some(token)) => {
+                request = request.header("PRIVATE-TOKEN", token)
+            },
+            _ => {},
+        }
+        if let Some(deadline) = deadline {
+            request = request
+                .timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        log::debug!("Downloading {} from {}", file, file.forge);
+        let response = request.send().await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => {
+                return Err(Reason::MissingRepositoryFile(file.to_string()))
+            },
+            StatusCode::TOO_MANY_REQUESTS => {
+                let host = response.url().host_str().unwrap_or_default();
+                return Err(Reason::RateLimited(host.to_string()));
+            },
+            _ => {},
+        }
+
+        let body = response.error_for_status()?.bytes().await?;
+
+        match file.lines {
+            Some((_, last)) => {
+                let line_count = count_lines(&body);
+                if last > line_count {
+                    Err(Reason::LineOutOfBounds(last, line_count))
+                } else {
+                    Ok(())
+                }
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Ask the forge whether an issue exists, returning its new location if
+    /// it was transferred.
+    pub(crate) async fn check(
+        &self,
+        client: &Client,
+        url: &Url,
+        issue: &Issue,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Url>, Reason> {
+        let mut request = match issue.forge {
+            Forge::GitHub => self.github_request(client, issue)?,
+            Forge::GitLab => self.gitlab_request(client, url, issue)?,
+        };
+
+        if let Some(deadline) = deadline {
+            request = request
+                .timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        log::debug!("Asking the {} API about {}", issue.forge, issue);
+        let response = request.send().await?;
+        let status = response.status();
+
+        if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+            return Err(Reason::IssueNotFound(issue.to_string()));
+        }
+        if let Some(reason) = rate_limited(&response) {
+            return Err(reason);
+        }
+
+        let body = response.error_for_status()?.bytes().await?;
+        let body: serde_json::Value = match serde_json::from_slice(&body) {
+            Ok(body) => body,
+            Err(e) => {
+                log::warn!("Unable to parse the response for {}: {}", issue, e);
+                return Ok(None);
+            },
+        };
+
+        if !body["moved_to_id"].is_null() {
+            log::warn!("{} was moved to another project", issue);
+        }
+
+        let web_url = body["html_url"]
+            .as_str()
+            .or_else(|| body["web_url"].as_str())
+            .and_then(|web_url| Url::parse(web_url).ok());
+
+        match web_url {
+            Some(web_url) if self.was_transferred(issue, &web_url) => {
+                log::debug!("{} was transferred to \"{}\"", issue, web_url);
+                Ok(Some(web_url))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn github_request(
+        &self,
+        client: &Client,
+        issue: &Issue,
+    ) -> Result<RequestBuilder, Reason> {
+        let endpoint = format!(
+            "repos/{}/issues/{}",
+            issue.project.trim_end_matches('/'),
+            issue.number
+        );
+        let mut request = client
+            .get(self.github_api.join(&endpoint).map_err(invalid_url)?)
+            .header("Accept", "application/vnd.github+json");
+
+        if let Some(token) = &self.github_token {
+            request = request.bearer_auth(token);
+        }
+
+        Ok(request)
+    }
+
+    fn gitlab_request(
+        &self,
+        client: &Client,
+        url: &Url,
+        issue: &Issue,
+    ) -> Result<RequestBuilder, Reason> {
+        let kind = match issue.kind {
+            IssueKind::Issue => "issues",
+            IssueKind::PullRequest => "merge_requests",
+        };
+        let project: String =
+            url::form_urlencoded::byte_serialize(issue.project.as_bytes())
+                .collect();
+        let endpoint = format!(
+            "{}/api/v4/projects/{}/{}/{}",
+            url.origin().ascii_serialization(),
+            project,
+            kind,
+            issue.number
+        );
+        let endpoint = Url::parse(&endpoint).map_err(invalid_url)?;
+        let mut request = client.get(endpoint);
+
+        if let Some(token) = &self.gitlab_token {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+
+        Ok(request)
+    }
+
+    fn was_transferred(&self, issue: &Issue, web_url: &Url) -> bool {
+        match self.issue(web_url) {
+            Some(actual) => {
+                !actual.project.eq_ignore_ascii_case(&issue.project)
+                    || actual.number != issue.number
+            },
+            None => false,
+        }
+    }
+}
+
+impl Default for ForgeApi {
+    fn default() -> Self {
+        ForgeApi {
+            github_api: Url::parse("https://api.github.com/").unwrap(),
+            github_raw: Url::parse("https://raw.githubusercontent.com/")
+                .unwrap(),
+            github_token: None,
+            gitlab_hosts: vec![String::from("gitlab.com")],
+            gitlab_token: None,
+        }
+    }
+}
+
+impl Debug for ForgeApi {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // don't leak the tokens into logs
+        let redacted = |token: &Option<String>| token.as_ref().map(|_| "***");
+
+        f.debug_struct("ForgeApi")
+            .field("github_api", &self.github_api)
+            .field("github_raw", &self.github_raw)
+            .field("github_token", &redacted(&self.github_token))
+            .field("gitlab_hosts", &self.gitlab_hosts)
+            .field("gitlab_token", &redacted(&self.gitlab_token))
+            .finish()
+    }
+}
+
+/// An issue or pull request on a code forge.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Issue {
+    /// Where the issue lives.
+    pub forge: Forge,
+    /// The project's path (e.g. `rust-lang/rust`).
+    pub project: String,
+    /// The issue number.
+    pub number: u64,
+    /// Is this an issue or a pull request?
+    pub kind: IssueKind,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let separator = match (self.forge, self.kind) {
+            (Forge::GitLab, IssueKind::PullRequest) => '!',
+            _ => '#',
+        };
+
+        write!(f, "{}{}{}", self.project, separator, self.number)
+    }
+}
+
+/// A file in a repository on a code forge.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RepoFile {
+    /// Where the repository lives.
+    pub forge: Forge,
+    /// The project's path (e.g. `rust-lang/rust`).
+    pub project: String,
+    /// The ref (branch, tag, or commit) followed by the file's path within
+    /// the repository (e.g. `main/src/lib.rs`).
+    ///
+    /// Branch names may contain slashes, so there's no way to tell where the
+    /// ref stops and the path starts without asking the forge.
+    pub path: String,
+    /// The (one-based, inclusive) lines being linked to, if any.
+    pub lines: Option<(usize, usize)>,
+}
+
+impl Display for RepoFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.project, self.path)?;
+
+        match self.lines {
+            Some((first, last)) if first == last => write!(f, "#L{}", first),
+            Some((first, last)) => write!(f, "#L{}-L{}", first, last),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A comparison between two refs on a code forge (e.g.
+/// `rust-lang/rust/compare/1.50.0...1.51.0`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Comparison {
+    /// Where the repository lives.
+    pub forge: Forge,
+    /// The project's path (e.g. `rust-lang/rust`).
+    pub project: String,
+    /// The ref being compared against, or `None` for the default branch.
+    pub base: Option<String>,
+    /// The ref being compared, which may be in a fork (e.g. `user:feature`).
+    pub head: String,
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.base {
+            Some(base) => {
+                write!(f, "{}/compare/{}...{}", self.project, base, self.head)
+            },
+            None => write!(f, "{}/compare/{}", self.project, self.head),
+        }
+    }
+}
+
+/// The code forges known to [`ForgeApi`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Forge {
+    /// GitHub.
+    GitHub,
+    /// GitLab (including self-hosted instances).
+    GitLab,
+}
+
+impl Display for Forge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Forge::GitHub => write!(f, "GitHub"),
+            Forge::GitLab => write!(f, "GitLab"),
+        }
+    }
+}
+
+/// Whether an [`Issue`] is an issue or a pull (merge) request.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IssueKind {
+    /// An issue.
+    Issue,
+    /// A pull request (called a merge request on GitLab).
+    PullRequest,
+}
+
+/// `owner/repo/issues/42` or `owner/repo/pull/42/files`.
+fn github_issue(segments: &[&str]) -> Option<Issue> {
+    match segments {
+        [owner, repo, kind, number, ..] => {
+            let kind = match *kind {
+                "issues" => IssueKind::Issue,
+                "pull" => IssueKind::PullRequest,
+                _ => return None,
+            };
+
+            Some(Issue {
+                forge: Forge::GitHub,
+                project: format!("{}/{}", owner, repo),
+                number: number.parse().ok()?,
+                kind,
+            })
+        },
+        _ => None,
+    }
+}
+
+/// `group/subgroup/project/-/issues/42` or
+/// `group/project/-/merge_requests/42/diffs`.
+fn gitlab_issue(segments: &[&str]) -> Option<Issue> {
+    let dash = segments.iter().position(|s| *s == "-")?;
+    let (project, rest) = segments.split_at(dash);
+
+    let kind = match *rest.get(1)? {
+        "issues" => IssueKind::Issue,
+        "merge_requests" => IssueKind::PullRequest,
+        _ => return None,
+    };
+
+    if project.is_empty() {
+        return None;
+    }
+
+    Some(Issue {
+        forge: Forge::GitLab,
+        project: project.join("/"),
+        number: rest.get(2)?.parse().ok()?,
+        kind,
+    })
+}
+
+/// Parse a line anchor like `L42`, `L10-L20` (GitHub), or `L10-20`
+/// (GitLab).
+fn line_range(fragment: &str) -> Option<(usize, usize)> {
+    let range = fragment.strip_prefix('L')?;
+    let (first, last) = match range.split_once('-') {
+        Some((first, last)) => (first, last.trim_start_matches('L')),
+        None => (range, range),
+    };
+    let first: usize = first.parse().ok()?;
+    let last: usize = last.parse().ok()?;
+
+    Some((first.min(last), first.max(last)))
+}
+
+fn count_lines(text: &[u8]) -> usize {
+    let newlines = text.iter().filter(|b| **b == b'\n').count();
+
+    match text.last() {
+        Some(b'\n') | None => newlines,
+        Some(_) => newlines + 1,
+    }
+}
+
+/// Did the forge refuse to answer because we sent too many requests?
+fn rate_limited(response: &reqwest::Response) -> Option<Reason> {
+    let status = response.status();
+    let out_of_requests = response
+        .headers()
+        .get("x-ratelimit-remaining")
+        .is_some_and(|remaining| remaining == "0");
+
+    if status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && out_of_requests)
+    {
+        let host = response.url().host_str().unwrap_or_default();
+        Some(Reason::RateLimited(host.to_string()))
+    } else {
+        None
+    }
+}
+
+fn invalid_url(e: url::ParseError) -> Reason {
+    Reason::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{Response, Server};
+
+    #[tokio::test]
+    async fn ask_the_github_api_about_issues() {
+        let moved = r#"{"html_url": "https://github.com/new/repo/issues/3"}"#;
+        let server = Server::start(vec![
+            (
+                "/repos/owner/repo/issues/1",
+                Response::ok(
+                    r#"{"html_url": "https://github.com/owner/repo/pull/1"}"#,
+                ),
+            ),
+            ("/repos/owner/repo/issues/2", Response::status(404)),
+            ("/repos/owner/repo/issues/3", Response::ok(moved)),
+            (
+                "/repos/owner/repo/issues/4",
+                Response::status(403).with_header("X-RateLimit-Remaining", "0"),
+            ),
+        ]);
+        let api = ForgeApi::new()
+            .set_github_api(server.url("/"))
+            .set_github_token("secret");
+        let client = Client::new();
+
+        let check = |n: u64| {
+            let url = format!("https://github.com/owner/repo/issues/{}", n);
+            let url = Url::parse(&url).unwrap();
+            let issue = api.issue(&url).unwrap();
+            let api = api.clone();
+            let client = client.clone();
+            async move { api.check(&client, &url, &issue, None).await }
+        };
+
+        assert_eq!(check(1).await.unwrap(), None);
+        assert!(matches!(check(2).await, Err(Reason::IssueNotFound(_))));
+        assert_eq!(
+            check(3).await.unwrap().unwrap().as_str(),
+            "https://github.com/new/repo/issues/3"
+        );
+        assert!(matches!(check(4).await, Err(Reason::RateLimited(_))));
+
+        let requests = server.requests();
+        assert!(requests[0].headers.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case("authorization")
+                && value == "Bearer secret"
+        }));
+    }
+
+    #[tokio::test]
+    async fn line_anchors_must_be_inside_the_raw_file() {
+        let server = Server::start(vec![
+            ("/owner/repo/main/src/lib.rs", Response::ok("a\nb\nc\n")),
+            ("/group/app/-/raw/v1.0/README.md", Response::ok("# App")),
+        ]);
+        let api = ForgeApi::new()
+            .set_github_raw(server.url("/"))
+            .add_gitlab_host("127.0.0.1");
+        let client = Client::new();
+        let gitlab = server.url("/group/app/-/blob/v1.0/README.md");
+        let inputs = vec![
+            ("https://github.com/owner/repo/blob/main/src/lib.rs", true),
+            (
+                "https://github.com/owner/repo/blob/main/src/lib.rs#L2-L3",
+                true,
+            ),
+            (
+                "https://github.com/owner/repo/blob/main/src/lib.rs#L4",
+                false,
+            ),
+            ("https://github.com/owner/repo/blob/main/src/main.rs", false),
+            (gitlab.as_str(), true),
+        ];
+
+        for (url, should_be) in inputs {
+            let url = Url::parse(url).unwrap();
+            let file = api.file(&url).unwrap();
+            let got = api.check_file(&client, &url, &file, None).await;
+            assert_eq!(got.is_ok(), should_be, "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn both_sides_of_a_comparison_must_exist() {
+        let server = Server::start(vec![
+            (
+                "/repos/owner/repo/compare/v1.0.0...v1.1.0",
+                Response::ok("{}"),
+            ),
+            (
+                "/repos/owner/repo/compare/HEAD...user:fix",
+                Response::ok("{}"),
+            ),
+            (
+                "/repos/owner/repo/compare/v1.1.0...v1.2.0",
+                Response::status(404),
+            ),
+        ]);
+        let api = ForgeApi::new().set_github_api(server.url("/"));
+        let client = Client::new();
+        let inputs = vec![
+            ("owner/repo/compare/v1.0.0...v1.1.0", true),
+            ("owner/repo/compare/v1.0.0..v1.1.0.diff", true),
+            ("owner/repo/compare/user:fix", true),
+            ("owner/repo/compare/v1.1.0...v1.2.0", false),
+        ];
+
+        for (path, should_be) in inputs {
+            let url = Url::parse("https://github.com/").unwrap().join(path);
+            let url = url.unwrap();
+            let comparison = api.comparison(&url).unwrap();
+            let got =
+                api.check_comparison(&client, &url, &comparison, None).await;
+            assert_eq!(got.is_ok(), should_be, "{}", url);
+        }
+
+        let url = Url::parse("https://github.com/owner/repo/compare").unwrap();
+        assert_eq!(api.comparison(&url), None);
+    }
+}
@@ -0,0 +1,368 @@
+use crate::validation::{Context, Reason};
+use http::{header, HeaderValue, StatusCode};
+use reqwest::{Client, Response, Url};
+use std::{
+    fmt::{self, Display, Formatter},
+    time::Instant,
+};
+
+/// The manifest formats we are happy to receive.
+const MANIFEST_TYPES: &str = concat!(
+    "application/vnd.oci.image.index.v1+json, ",
+    "application/vnd.oci.image.manifest.v1+json, ",
+    "application/vnd.docker.distribution.manifest.list.v2+json, ",
+    "application/vnd.docker.distribution.manifest.v2+json",
+);
+
+/// A reference to a container image (e.g. `ghcr.io/org/image:tag`).
+///
+/// Image references aren't URLs, so links to them are written as
+/// `docker://` URLs (see [`ImageRef::to_url()`]). These are checked by
+/// asking the registry for the image's manifest.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::ImageRef;
+///
+/// let image = ImageRef::parse("nginx").unwrap();
+/// assert_eq!(image.registry, "docker.io");
+/// assert_eq!(image.repository, "library/nginx");
+/// assert_eq!(image.reference, "latest");
+///
+/// let image = ImageRef::parse("ghcr.io/org/image:1.2").unwrap();
+/// assert_eq!(image.to_url().as_str(), "docker://ghcr.io/org/image:1.2");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageRef {
+    /// The registry the image is hosted on (e.g. `ghcr.io`).
+    pub registry: String,
+    /// The image's name within the registry (e.g. `org/image`).
+    pub repository: String,
+    /// The tag or digest (e.g. `1.2` or `sha256:...`).
+    pub reference: String,
+}
+
+impl ImageRef {
+    /// The registry used when an image doesn't specify one.
+    pub const DEFAULT_REGISTRY: &'static str = "docker.io";
+
+    /// Parse an image reference the same way `docker pull` would.
+    pub fn parse(src: &str) -> Option<Self> {
+        let (name, reference) = match src.split_once('@') {
+            Some((name, digest)) if is_digest(digest) => {
+                (name, digest.to_string())
+            },
+            Some(_) => return None,
+            None => match src.rsplit_once(':') {
+                Some((name, tag)) if !tag.contains('/') => {
+                    if !is_tag(tag) {
+                        return None;
+                    }
+                    (name, tag.to_string())
+                },
+                _ => (src, String::from("latest")),
+            },
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((first, rest))
+                if first.contains('.')
+                    || first.contains(':')
+                    || first == "localhost" =>
+            {
+                (first.to_string(), rest.to_string())
+            },
+            _ => (Self::DEFAULT_REGISTRY.to_string(), name.to_string()),
+        };
+
+        let repository = if registry == Self::DEFAULT_REGISTRY
+            && !repository.contains('/')
+        {
+            format!("library/{}", repository)
+        } else {
+            repository
+        };
+
+        if !repository.split('/').all(is_path_component) {
+            return None;
+        }
+
+        Some(ImageRef {
+            registry,
+            repository,
+            reference,
+        })
+    }
+
+    /// Get the [`ImageRef`] back out of a `docker://` URL.
+    pub fn from_url(url: &Url) -> Option<Self> {
+        if url.scheme() != "docker" {
+            return None;
+        }
+
+        let host = url.host_str()?;
+        let registry = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        let image = format!("{}{}", registry, url.path());
+
+        ImageRef::parse(&image)
+    }
+
+    /// Write this image reference as a `docker://` URL.
+    pub fn to_url(&self) -> Url {
+        Url::parse(&format!("docker://{}", self))
+            .expect("Image references are always valid URLs")
+    }
+
+    fn is_digest(&self) -> bool { self.reference.contains(':') }
+
+    /// Where the registry's API lives.
+    fn api(&self) -> String {
+        let registry = match self.registry.as_str() {
+            "docker.io" | "index.docker.io" => "registry-1.docker.io",
+            other => other,
+        };
+        // like docker, we trust registries on the local machine to not
+        // need TLS
+        let is_local = registry.starts_with("localhost")
+            || registry.starts_with("127.0.0.1");
+        let scheme = if is_local { "http" } else { "https" };
+
+        format!("{}://{}/v2/", scheme, registry)
+    }
+}
+
+impl Display for ImageRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let separator = if self.is_digest() { '@' } else { ':' };
+        write!(
+            f,
+            "{}/{}{}{}",
+            self.registry, self.repository, separator, self.reference
+        )
+    }
+}
+
+/// Check that a container image exists by asking its registry for the
+/// manifest.
+///
+/// Registries which need a token (e.g. Docker Hub and `ghcr.io`) are sent an
+/// anonymous one, so only public images can be checked.
+pub async fn check_image<C>(image: &ImageRef, ctx: &C) -> Result<(), Reason>
+where
+    C: Context + ?Sized,
+{
+    log::debug!("Looking for the \"{}\" image", image);
+
+    let manifest = format!(
+        "{}{}/manifests/{}",
+        image.api(),
+        image.repository,
+        image.reference
+    );
+    let manifest = Url::parse(&manifest)
+        .map_err(|_| Reason::ImageNotFound(image.to_string()))?;
+    let client = ctx.client();
+    let deadline = ctx.deadline();
+
+    let mut response = head_manifest(client, &manifest, None, deadline).await?;
+
+    if response.status() == StatusCode::UNAUTHORIZED {
+        let challenge = response
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+
+        if let Some(challenge) = challenge {
+            let token = anonymous_token(client, &challenge).await?;
+            response =
+                head_manifest(client, &manifest, token.as_deref(), deadline)
+                    .await?;
+        }
+    }
+
+    match response.status() {
+        StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED => {
+            Err(Reason::ImageNotFound(image.to_string()))
+        },
+        StatusCode::TOO_MANY_REQUESTS => {
+            Err(Reason::RateLimited(image.registry.clone()))
+        },
+        _ => {
+            response.error_for_status()?;
+            Ok(())
+        },
+    }
+}
+
+/// Check a `docker://` URL with [`check_image()`].
+pub(crate) async fn check_image_url<C>(url: &Url, ctx: &C) -> Result<(), Reason>
+where
+    C: Context + ?Sized,
+{
+    match ImageRef::from_url(url) {
+        Some(image) => check_image(&image, ctx).await,
+        None => Err(Reason::ImageNotFound(url.to_string())),
+    }
+}
+
+async fn head_manifest(
+    client: &Client,
+    manifest: &Url,
+    token: Option<&str>,
+    deadline: Option<Instant>,
+) -> Result<Response, reqwest::Error> {
+    let mut request = client
+        .head(manifest.clone())
+        .header(header::ACCEPT, HeaderValue::from_static(MANIFEST_TYPES));
+
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    if let Some(deadline) = deadline {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        request = request.timeout(timeout);
+    }
+
+    request.send().await
+}
+
+/// Follow a `WWW-Authenticate: Bearer realm="...",service="...",scope="..."`
+/// challenge to get an anonymous token.
+async fn anonymous_token(
+    client: &Client,
+    challenge: &str,
+) -> Result<Option<String>, Reason> {
+    let params = match challenge.strip_prefix("Bearer ") {
+        Some(params) => parse_challenge(params),
+        None => return Ok(None),
+    };
+
+    let realm = match params.iter().find(|(key, _)| key == "realm") {
+        Some((_, realm)) => realm,
+        None => return Ok(None),
+    };
+    let mut url = match Url::parse(realm) {
+        Ok(url) => url,
+        Err(_) => return Ok(None),
+    };
+    url.query_pairs_mut()
+        .extend_pairs(params.iter().filter(|(key, _)| key != "realm"));
+
+    let body = client.get(url).send().await?.error_for_status()?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body.bytes().await?).unwrap_or_default();
+    let token = body["token"]
+        .as_str()
+        .or_else(|| body["access_token"].as_str())
+        .map(String::from);
+
+    Ok(token)
+}
+
+/// Parse the `key="value",key="value"` part of a challenge.
+fn parse_challenge(params: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut rest = params.trim();
+
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let (value, remainder) = match value.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, remainder)) => (value, remainder),
+                None => (quoted, ""),
+            },
+            None => match value.split_once(',') {
+                Some((value, remainder)) => (value, remainder),
+                None => (value, ""),
+            },
+        };
+
+        parsed.push((key, value.to_string()));
+        rest = remainder;
+    }
+
+    parsed
+}
+
+fn is_path_component(component: &str) -> bool {
+    !component.is_empty()
+        && component.chars().all(|c| {
+            c.is_ascii_lowercase()
+                || c.is_ascii_digit()
+                || matches!(c, '.' | '_' | '-')
+        })
+}
+
+fn is_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= 128
+        && !tag.starts_with(['.', '-'])
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn is_digest(digest: &str) -> bool {
+    match digest.split_once(':') {
+        Some((algorithm, hex)) => {
+            !algorithm.is_empty()
+                && !hex.is_empty()
+                && hex.chars().all(|c| c.is_ascii_hexdigit())
+        },
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+
+    #[tokio::test]
+    async fn ask_the_registry_for_the_manifest() {
+        let auth = Server::start(vec![(
+            "/token?service=registry&scope=pull",
+            Response::ok(r#"{"token": "anonymous"}"#),
+        )]);
+        let challenge = format!(
+            "Bearer realm=\"{}\",service=\"registry\",scope=\"pull\"",
+            auth.url("/token")
+        );
+        let server = Server::start(vec![
+            (
+                "/v2/org/app/manifests/1.0",
+                Response::status(401)
+                    .with_header("WWW-Authenticate", &challenge),
+            ),
+            ("/v2/org/app/manifests/latest", Response::ok("")),
+        ]);
+        let registry = server.url("/").host_str().unwrap().to_string();
+        let port = server.url("/").port().unwrap();
+        let ctx = BasicContext::default();
+
+        let image = format!("{}:{}/org/app", registry, port);
+        let image = ImageRef::parse(&image).unwrap();
+        check_image(&image, &ctx).await.unwrap();
+
+        let image = format!("{}:{}/org/app:1.0", registry, port);
+        let image = ImageRef::parse(&image).unwrap();
+        let got = check_image(&image, &ctx).await;
+        assert!(matches!(got, Err(Reason::ImageNotFound(_))));
+        let retried = server.requests().pop().unwrap();
+        assert!(retried.headers.contains(&(
+            String::from("authorization"),
+            String::from("Bearer anonymous")
+        )));
+
+        assert!(ImageRef::parse("Not An Image").is_none());
+        assert_eq!(ImageRef::from_url(&image.to_url()).as_ref(), Some(&image));
+    }
+}
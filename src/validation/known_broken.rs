@@ -0,0 +1,228 @@
+use globset::{Glob, GlobMatcher};
+use std::{
+    convert::TryFrom,
+    time::{Duration, SystemTime},
+};
+
+/// Links which are known to be broken, but should be accepted for now (e.g.
+/// while waiting for an upstream outage to be fixed).
+///
+/// Each entry is a glob matched against the link's `href` and may have an
+/// expiry date. Once an entry expires, the links it matches are reported as
+/// broken again, so a snoozed link can't be forgotten about. Broken links
+/// which are still covered by an entry end up in [`Outcomes::suppressed`].
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::KnownBroken;
+/// use std::time::{Duration, SystemTime};
+///
+/// let next_week = SystemTime::now() + Duration::from_secs(7 * 24 * 60 * 60);
+/// let known_broken = KnownBroken::new()
+///     .add("https://flaky.example.com/*", Some(next_week))?
+///     .add("https://example.com/forever-broken", None)?;
+///
+/// let now = SystemTime::now();
+/// assert!(known_broken.suppresses("https://flaky.example.com/docs", now));
+/// assert!(!known_broken.suppresses("https://example.com/", now));
+///
+/// let next_month = now + Duration::from_secs(30 * 24 * 60 * 60);
+/// let flaky = "https://flaky.example.com/";
+/// assert!(!known_broken.suppresses(flaky, next_month));
+/// # Ok::<(), globset::Error>(())
+/// ```
+///
+/// [`Outcomes::suppressed`]: crate::validation::Outcomes::suppressed
+#[derive(Debug, Default, Clone)]
+pub struct KnownBroken {
+    entries: Vec<KnownBrokenEntry>,
+}
+
+impl KnownBroken {
+    /// Create an empty [`KnownBroken`] list.
+    pub fn new() -> Self { KnownBroken::default() }
+
+    /// Accept broken links matching a glob until `expires` (or forever, if
+    /// there's no expiry).
+    pub fn add(
+        mut self,
+        pattern: &str,
+        expires: Option<SystemTime>,
+    ) -> Result<Self, globset::Error> {
+        self.entries.push(KnownBrokenEntry {
+            matcher: Glob::new(pattern)?.compile_matcher(),
+            expires,
+        });
+        Ok(self)
+    }
+
+    /// Parse a list with one entry per line, in the form
+    /// `pattern [YYYY-MM-DD]`.
+    ///
+    /// Entries are accepted until the end of their expiry date (UTC). Blank
+    /// lines and lines starting with `#` are skipped.
+    ///
+    /// ```text
+    /// # upstream outage, see issue #42
+    /// https://flaky.example.com/* 2024-07-01
+    /// https://example.com/forever-broken
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut known_broken = KnownBroken::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let pattern = words.next().unwrap_or_default();
+            let expires = match words.next() {
+                Some(date) => Some(end_of_day(date).ok_or_else(|| {
+                    ParseError::InvalidDate {
+                        line: line_number,
+                        date: date.to_string(),
+                    }
+                })?),
+                None => None,
+            };
+
+            known_broken =
+                known_broken.add(pattern, expires).map_err(|source| {
+                    ParseError::InvalidPattern {
+                        line: line_number,
+                        source,
+                    }
+                })?;
+        }
+
+        Ok(known_broken)
+    }
+
+    /// Iterate over the entries in this list.
+    pub fn entries(&self) -> impl Iterator<Item = &KnownBrokenEntry> + '_ {
+        self.entries.iter()
+    }
+
+    /// Find the first entry whose pattern matches an `href`, whether or not
+    /// it has expired.
+    pub fn find(&self, href: &str) -> Option<&KnownBrokenEntry> {
+        self.entries.iter().find(|entry| entry.matches(href))
+    }
+
+    /// Is a broken link with this `href` still accepted at time `now`?
+    pub fn suppresses(&self, href: &str, now: SystemTime) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.matches(href) && !entry.is_expired(now))
+    }
+}
+
+/// A single entry in the [`KnownBroken`] list.
+#[derive(Debug, Clone)]
+pub struct KnownBrokenEntry {
+    matcher: GlobMatcher,
+    expires: Option<SystemTime>,
+}
+
+impl KnownBrokenEntry {
+    /// The glob used to match a link's `href`.
+    pub fn pattern(&self) -> &str { self.matcher.glob().glob() }
+
+    /// When this entry stops suppressing broken links.
+    pub fn expires(&self) -> Option<SystemTime> { self.expires }
+
+    /// Has this entry expired?
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|expires| now >= expires)
+    }
+
+    /// Does this entry's pattern match an `href`?
+    pub fn matches(&self, href: &str) -> bool { self.matcher.is_match(href) }
+}
+
+/// Errors that can occur in [`KnownBroken::parse()`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The pattern wasn't a valid glob.
+    #[error("Invalid pattern on line {}", line)]
+    InvalidPattern {
+        /// The (one-based) line number.
+        line: usize,
+        /// The underlying error.
+        #[source]
+        source: globset::Error,
+    },
+    /// The expiry date wasn't in the form `YYYY-MM-DD`.
+    #[error("Invalid expiry date \"{}\" on line {}", date, line)]
+    InvalidDate {
+        /// The (one-based) line number.
+        line: usize,
+        /// The text that should have been a date.
+        date: String,
+    },
+}
+
+/// The first moment after a `YYYY-MM-DD` date (in UTC).
+fn end_of_day(date: &str) -> Option<SystemTime> {
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Howard Hinnant's "days_from_civil" algorithm
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = u64::try_from(days + 1).ok()? * 24 * 60 * 60;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_a_list_with_expiry_dates() {
+        let src = "
+# upstream outage
+https://flaky.example.com/* 2020-02-29
+https://example.com/forever-broken
+        ";
+
+        let known_broken = KnownBroken::parse(src).unwrap();
+
+        let entries: Vec<_> = known_broken.entries().collect();
+        assert_eq!(entries.len(), 2);
+        // 2020-03-01T00:00:00Z
+        let expiry =
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_583_020_800);
+        assert_eq!(entries[0].expires(), Some(expiry));
+        assert_eq!(entries[1].expires(), None);
+
+        let flaky = "https://flaky.example.com/";
+        let before = expiry - Duration::from_secs(1);
+        assert!(known_broken.suppresses(flaky, before));
+        assert!(!known_broken.suppresses(flaky, expiry));
+        let forever = "https://example.com/forever-broken";
+        assert!(known_broken.suppresses(forever, expiry));
+
+        assert!(matches!(
+            KnownBroken::parse("https://example.com/ 2020-13-01"),
+            Err(ParseError::InvalidDate { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::Category;
+
+/// How thoroughly a link should be checked (see [`Context::check_level()`]).
+///
+/// The levels build on each other, so the same API can be used for a quick
+/// pre-commit check and a thorough nightly run.
+///
+/// [`Context::check_level()`]: crate::validation::Context::check_level
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub enum CheckLevel {
+    /// Only make sure the link is well-formed, without touching the
+    /// filesystem or network.
+    Syntax,
+    /// Make sure the file exists or the server responds, ignoring fragments
+    /// and without asking APIs (e.g. the Wayback Machine or a package
+    /// registry) for more information.
+    Exists,
+    /// Check everything we can, including fragments.
+    #[default]
+    Deep,
+}
+
+/// The [`CheckLevel`] for each kind of link, as used by the
+/// [`BasicContext`].
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::{CheckLevel, CheckLevels};
+///
+/// // check local links properly, but don't wait for the network
+/// let levels = CheckLevels {
+///     web: CheckLevel::Syntax,
+///     ..CheckLevels::all(CheckLevel::Deep)
+/// };
+/// ```
+///
+/// [`BasicContext`]: crate::BasicContext
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckLevels {
+    /// Links to files on disk.
+    pub filesystem: CheckLevel,
+    /// Links to the web, including protocol-relative ones.
+    pub web: CheckLevel,
+    /// Everything else (e.g. `mailto:` links and opaque URIs).
+    pub other: CheckLevel,
+}
+
+impl CheckLevels {
+    /// Use the same [`CheckLevel`] for every kind of link.
+    pub const fn all(level: CheckLevel) -> Self {
+        CheckLevels {
+            filesystem: level,
+            web: level,
+            other: level,
+        }
+    }
+
+    /// The [`CheckLevel`] for a particular [`Category`] of link.
+    pub fn level(&self, category: &Category) -> CheckLevel {
+        match category {
+            Category::FileSystem { .. } | Category::CurrentFile { .. } => {
+                self.filesystem
+            },
+            Category::Url(_) | Category::ProtocolRelative(_) => self.web,
+            _ => self.other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        anchors::AnchorDb,
+        test_utils::{Response, Server},
+        validation::validate,
+        BasicContext, Link,
+    };
+
+    #[tokio::test]
+    async fn lower_levels_do_less_work() {
+        let server = Server::start(vec![("/", Response::ok(""))]);
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join("README.md"), "# Title").unwrap();
+        let mut ctx = BasicContext::default();
+        let mut db = AnchorDb::new();
+        db.insert_file(temp.path().join("README.md")).unwrap();
+        ctx.anchors = Some(db);
+        let links = || {
+            vec![
+                Link::new("README.md#missing", Default::default(), ()),
+                Link::new("missing.md", Default::default(), ()),
+                Link::new(server.url("/").as_str(), Default::default(), ()),
+                Link::new("mailto:nobody", Default::default(), ()),
+            ]
+        };
+
+        ctx.check_levels = CheckLevels::all(CheckLevel::Syntax);
+        let outcomes = validate(temp.path(), links(), &ctx).await;
+        assert_eq!(outcomes.valid.len(), 3);
+        assert_eq!(outcomes.invalid[0].link.href, "mailto:nobody");
+        assert!(server.requests().is_empty());
+
+        ctx.check_levels = CheckLevels::all(CheckLevel::Exists);
+        let outcomes = validate(temp.path(), links(), &ctx).await;
+        let mut invalid: Vec<_> =
+            outcomes.invalid.iter().map(|i| &i.link.href).collect();
+        invalid.sort();
+        assert_eq!(invalid, vec!["mailto:nobody", "missing.md"]);
+
+        ctx.check_levels = CheckLevels::default();
+        let outcomes = validate(temp.path(), links(), &ctx).await;
+        assert_eq!(outcomes.invalid.len(), 3);
+    }
+}
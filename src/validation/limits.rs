@@ -0,0 +1,281 @@
+use crate::validation::Reason;
+use reqwest::redirect::Policy;
+use std::fmt::{self, Display, Formatter};
+use url::Url;
+
+/// Caps which stop untrusted documents (e.g. user submissions) from making
+/// the link checker do too much work or send requests where it shouldn't.
+///
+/// The default is to have no limits, while [`SafetyLimits::hardened()`] is a
+/// sensible preset for untrusted input. Use
+/// [`crate::BasicContext::with_safety_limits()`] so the redirect limits are
+/// also applied to the HTTP client.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{validation::SafetyLimits, BasicContext};
+///
+/// let limits = SafetyLimits::hardened().set_max_links_per_document(Some(50));
+/// let ctx = BasicContext::with_safety_limits(limits);
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde-1", serde(default))]
+pub struct SafetyLimits {
+    max_links_per_document: Option<usize>,
+    max_url_length: Option<usize>,
+    max_redirects: Option<usize>,
+    max_response_size: Option<u64>,
+    web_schemes_only: bool,
+}
+
+impl SafetyLimits {
+    /// The number of redirects reqwest follows by default.
+    const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+    /// Create a new [`SafetyLimits`] without any limits.
+    pub fn new() -> Self { SafetyLimits::default() }
+
+    /// A preset for checking documents you don't trust.
+    ///
+    /// This allows 1000 links per document, URLs up to 2048 characters,
+    /// 5 redirects, and 5 MiB responses, and only `http` and `https` URLs
+    /// are checked (links to local files are refused without touching the
+    /// disk).
+    pub fn hardened() -> Self {
+        SafetyLimits {
+            max_links_per_document: Some(1000),
+            max_url_length: Some(2048),
+            max_redirects: Some(5),
+            max_response_size: Some(5 * 1024 * 1024),
+            web_schemes_only: true,
+        }
+    }
+
+    /// The most links which will be checked in a single document.
+    ///
+    /// Any links after this are reported in [`Outcomes::ignored`].
+    ///
+    /// [`Outcomes::ignored`]: crate::validation::Outcomes::ignored
+    pub fn max_links_per_document(&self) -> Option<usize> {
+        self.max_links_per_document
+    }
+
+    /// Set [`SafetyLimits::max_links_per_document()`].
+    pub fn set_max_links_per_document(self, max: Option<usize>) -> Self {
+        SafetyLimits {
+            max_links_per_document: max,
+            ..self
+        }
+    }
+
+    /// The longest URL (in bytes) which will be checked.
+    pub fn max_url_length(&self) -> Option<usize> { self.max_url_length }
+
+    /// Set [`SafetyLimits::max_url_length()`].
+    pub fn set_max_url_length(self, max: Option<usize>) -> Self {
+        SafetyLimits {
+            max_url_length: max,
+            ..self
+        }
+    }
+
+    /// How many redirects the client will follow.
+    pub fn max_redirects(&self) -> Option<usize> { self.max_redirects }
+
+    /// Set [`SafetyLimits::max_redirects()`].
+    pub fn set_max_redirects(self, max: Option<usize>) -> Self {
+        SafetyLimits {
+            max_redirects: max,
+            ..self
+        }
+    }
+
+    /// The largest response body (in bytes) which will be downloaded (e.g.
+    /// when crawling a website).
+    pub fn max_response_size(&self) -> Option<u64> { self.max_response_size }
+
+    /// Set [`SafetyLimits::max_response_size()`].
+    pub fn set_max_response_size(self, max: Option<u64>) -> Self {
+        SafetyLimits {
+            max_response_size: max,
+            ..self
+        }
+    }
+
+    /// Are links to local files and URLs with a scheme other than `http` or
+    /// `https` refused?
+    pub fn web_schemes_only(&self) -> bool { self.web_schemes_only }
+
+    /// Set [`SafetyLimits::web_schemes_only()`].
+    pub fn set_web_schemes_only(self, web_schemes_only: bool) -> Self {
+        SafetyLimits {
+            web_schemes_only,
+            ..self
+        }
+    }
+
+    /// A [`Policy`] for the HTTP client which respects
+    /// [`SafetyLimits::max_redirects()`] and
+    /// [`SafetyLimits::web_schemes_only()`].
+    ///
+    /// Redirect loops are stopped early and reported as a
+    /// [`Reason::RedirectLoop`].
+    pub fn redirect_policy(&self) -> Policy {
+        let max = self.max_redirects.unwrap_or(Self::DEFAULT_MAX_REDIRECTS);
+        let web_schemes_only = self.web_schemes_only;
+
+        Policy::custom(move |attempt| {
+            if attempt.previous().contains(attempt.url()) {
+                let mut chain = attempt.previous().to_vec();
+                chain.push(attempt.url().clone());
+                attempt.error(RedirectLoop(chain))
+            } else if web_schemes_only && !is_web(attempt.url()) {
+                let msg = format!("Refusing to redirect to {}", attempt.url());
+                attempt.error(msg)
+            } else if attempt.previous().len() > max {
+                attempt.error(format!("More than {} redirects", max))
+            } else {
+                attempt.follow()
+            }
+        })
+    }
+
+    /// Make sure a link's `href` is within the limits before checking it.
+    pub(crate) fn check_href(&self, href: &str) -> Result<(), Reason> {
+        match self.max_url_length {
+            Some(max) if href.len() > max => {
+                Err(Reason::UrlTooLong(href.len()))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Make sure a web link is within the limits before sending a request.
+    pub(crate) fn check_url(&self, url: &Url) -> Result<(), Reason> {
+        if self.web_schemes_only && !is_web(url) {
+            return Err(Reason::ForbiddenScheme(url.scheme().to_string()));
+        }
+
+        self.check_href(url.as_str())
+    }
+}
+
+fn is_web(url: &Url) -> bool { matches!(url.scheme(), "http" | "https") }
+
+/// The error given to `reqwest` when [`SafetyLimits::redirect_policy()`]
+/// finds a redirect loop, so the chain can be recovered afterwards.
+#[derive(Debug)]
+pub(crate) struct RedirectLoop(pub(crate) Vec<Url>);
+
+impl RedirectLoop {
+    /// Look for a [`RedirectLoop`] in the error's sources.
+    pub(crate) fn find(error: &reqwest::Error) -> Option<&RedirectLoop> {
+        let mut source = std::error::Error::source(error);
+
+        while let Some(error) = source {
+            if let Some(redirect_loop) = error.downcast_ref() {
+                return Some(redirect_loop);
+            }
+            source = error.source();
+        }
+
+        None
+    }
+}
+
+impl Display for RedirectLoop {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Redirect loop")
+    }
+}
+
+impl std::error::Error for RedirectLoop {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        validation::{check_web, validate},
+        BasicContext, Link,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn hardened_limits_refuse_long_and_non_web_urls() {
+        let limits = SafetyLimits::hardened().set_max_url_length(Some(30));
+        let inputs = vec![
+            ("https://example.com/", true),
+            ("http://example.com/", true),
+            ("ftp://example.com/file.txt", false),
+            ("file:///etc/passwd", false),
+            ("https://example.com/a/very/long/path", false),
+        ];
+
+        for (url, should_be) in inputs {
+            let url = Url::parse(url).unwrap();
+            assert_eq!(limits.check_url(&url).is_ok(), should_be, "{}", url);
+        }
+
+        let url = Url::parse("ftp://example.com/").unwrap();
+        assert!(SafetyLimits::new().check_url(&url).is_ok());
+    }
+
+    #[tokio::test]
+    async fn hardened_limits_refuse_local_files() {
+        let links = vec![
+            Link::new("../../etc/passwd", Default::default(), ()),
+            Link::new("Cargo.toml", Default::default(), ()),
+        ];
+        let ctx = BasicContext::with_safety_limits(SafetyLimits::hardened());
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+        let outcomes = validate(dir, links, &ctx).await;
+
+        assert!(outcomes.valid.is_empty());
+        assert_eq!(outcomes.invalid.len(), 2);
+        for invalid in &outcomes.invalid {
+            assert!(
+                matches!(
+                    &invalid.reason,
+                    Reason::ForbiddenScheme(scheme) if scheme == "file"
+                ),
+                "{}",
+                invalid.reason
+            );
+        }
+        assert!(outcomes.candidates.is_empty());
+
+        let cargo_toml = vec![Link::new("Cargo.toml", Default::default(), ())];
+        let outcomes =
+            validate(dir, cargo_toml, &BasicContext::default()).await;
+        assert_eq!(outcomes.valid.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn report_the_chain_for_redirect_loops() {
+        let server = Server::start(vec![
+            ("/a", Response::redirect("/b")),
+            ("/b", Response::redirect("/c")),
+            ("/c", Response::redirect("/b")),
+        ]);
+        let ctx = BasicContext::default();
+
+        let got = check_web(&server.url("/a"), &ctx).await.unwrap_err();
+
+        match got {
+            Reason::RedirectLoop { chain } => assert_eq!(
+                chain,
+                vec![
+                    server.url("/a"),
+                    server.url("/b"),
+                    server.url("/c"),
+                    server.url("/b"),
+                ]
+            ),
+            other => panic!("Unexpected error: {}", other),
+        }
+    }
+}
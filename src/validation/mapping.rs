@@ -0,0 +1,171 @@
+use percent_encoding::percent_decode_str;
+use std::path::{Component, Path, PathBuf};
+use url::Url;
+
+/// A mapping between the URL a site is deployed to and the directory its
+/// sources live in (e.g. `https://example.com/docs/` and `./book/`).
+///
+/// When a [`crate::Link`] points at a URL covered by one of the
+/// [`Options::url_mappings()`], it is checked against the local file instead
+/// of going to the web. This lets absolute links to your own site be checked
+/// before it has been deployed.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::UrlMapping;
+/// use std::path::{Path, PathBuf};
+///
+/// let mapping = UrlMapping::new(
+///     "https://example.com/docs".parse().unwrap(),
+///     "./book",
+/// );
+///
+/// let url = "https://example.com/docs/guide/intro.html#setup";
+/// let url = url.parse().unwrap();
+/// assert_eq!(
+///     mapping.url_to_path(&url),
+///     Some(PathBuf::from("./book/guide/intro.html")),
+/// );
+///
+/// let path = Path::new("./book/guide/intro.html");
+/// assert_eq!(
+///     mapping.path_to_url(path).unwrap().as_str(),
+///     "https://example.com/docs/guide/intro.html",
+/// );
+/// ```
+///
+/// [`Options::url_mappings()`]: crate::validation::Options::url_mappings
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub struct UrlMapping {
+    url: Url,
+    directory: PathBuf,
+}
+
+impl UrlMapping {
+    /// Create a new [`UrlMapping`], where everything under `url` is served
+    /// from `directory`.
+    pub fn new<P: Into<PathBuf>>(mut url: Url, directory: P) -> Self {
+        // make sure "/docs" doesn't also match "/docs-old/"
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+        url.set_query(None);
+        url.set_fragment(None);
+
+        UrlMapping {
+            url,
+            directory: directory.into(),
+        }
+    }
+
+    /// The URL being mapped.
+    pub fn url(&self) -> &Url { &self.url }
+
+    /// The directory [`UrlMapping::url()`] corresponds to.
+    pub fn directory(&self) -> &Path { &self.directory }
+
+    /// Find the path a [`Url`] maps to, ignoring its query and fragment.
+    ///
+    /// Returns `None` when the [`Url`] isn't covered by this mapping or would
+    /// escape [`UrlMapping::directory()`].
+    pub fn url_to_path(&self, url: &Url) -> Option<PathBuf> {
+        self.relative_path(url)
+            .map(|relative| self.directory.join(relative))
+    }
+
+    /// The path a [`Url`] maps to, relative to [`UrlMapping::directory()`].
+    pub(crate) fn relative_path(&self, url: &Url) -> Option<PathBuf> {
+        if url.origin() != self.url.origin() {
+            return None;
+        }
+
+        let prefix = self.url.path();
+        let relative = match url.path().strip_prefix(prefix) {
+            Some(relative) => relative,
+            // the mapped URL without its trailing slash
+            None if prefix.strip_suffix('/') == Some(url.path()) => "",
+            None => return None,
+        };
+
+        let decoded = percent_decode_str(relative).decode_utf8().ok()?;
+        let relative = PathBuf::from(decoded.as_ref());
+
+        if relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            Some(relative)
+        } else {
+            None
+        }
+    }
+
+    /// Find the [`Url`] a path maps to, returning `None` if it isn't inside
+    /// [`UrlMapping::directory()`].
+    pub fn path_to_url(&self, path: &Path) -> Option<Url> {
+        let relative = path.strip_prefix(&self.directory).ok()?;
+        let mut url = self.url.clone();
+
+        {
+            let mut segments = url.path_segments_mut().ok()?;
+            segments.pop_if_empty();
+
+            for component in relative.components() {
+                match component {
+                    Component::Normal(segment) => {
+                        segments.push(segment.to_str()?);
+                    },
+                    Component::CurDir => {},
+                    _ => return None,
+                }
+            }
+        }
+
+        Some(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext, Link,
+    };
+
+    #[tokio::test]
+    async fn mapped_urls_are_checked_on_disk() {
+        let server = Server::start(vec![("/other", Response::ok(""))]);
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp.path().join("guide")).unwrap();
+        std::fs::write(temp.path().join("guide").join("intro.html"), "")
+            .unwrap();
+        let mut ctx = BasicContext::default();
+        ctx.options = ctx.options.set_url_mappings(vec![UrlMapping::new(
+            server.url("/docs"),
+            temp.path(),
+        )]);
+        let hrefs = [
+            server.url("/docs/guide/intro.html#setup"),
+            server.url("/docs/guide/missing.html"),
+            server.url("/other"),
+        ];
+        let file = codespan::Files::new().add("index.md", "");
+        let links = hrefs
+            .iter()
+            .map(|url| Link::new(url.to_string(), Default::default(), file));
+
+        let outcomes = crate::validate(temp.path(), links, &ctx).await;
+
+        assert_eq!(outcomes.valid.len(), 2);
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert_eq!(outcomes.invalid[0].link.href, hrefs[1].to_string());
+        assert!(outcomes.invalid[0].reason.file_not_found());
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/other");
+    }
+}
@@ -1,20 +1,93 @@
 //! Code for validating the various types of [`Link`].
 
+mod archive;
+mod attempts;
+mod baseline;
+mod body;
+mod bots;
 mod cache;
 mod context;
+mod decorated;
+mod diff;
+mod domains;
+mod expected;
 mod filesystem;
+#[cfg(feature = "forges")]
+mod forges;
+#[cfg(feature = "container-images")]
+mod images;
+mod known_broken;
+mod levels;
+mod limits;
+mod mapping;
+mod normalise;
+mod packages;
+mod plan;
+mod proxy;
+mod redact;
+mod scheduler;
+#[cfg(feature = "tower")]
+mod service;
+mod single;
+mod stats;
+mod syntax;
 mod web;
 
+pub use archive::{ArchivedPage, WaybackMachine};
+pub use attempts::Attempt;
+use attempts::Attempts;
+pub(crate) use baseline::file_name;
+pub use baseline::{Baseline, BaselineEntry};
+pub use body::{read_body, Body};
+pub use bots::{ResponseRule, ResponseRules};
 pub use cache::{Cache, CacheEntry};
 pub use context::{BasicContext, Context};
+pub use decorated::{ContextExt, Decorated};
+#[cfg(feature = "serde-1")]
+pub(crate) use diff::statuses;
+pub use diff::{Diff, Snapshot, SnapshotEntry, Status};
+pub use domains::{DomainPolicy, Unlisted};
+pub use expected::ExpectedFailures;
+use filesystem::Directories;
 pub use filesystem::{check_filesystem, resolve_link, Options};
+#[cfg(feature = "forges")]
+pub use forges::{Comparison, Forge, ForgeApi, Issue, IssueKind, RepoFile};
+#[cfg(feature = "container-images")]
+pub use images::{check_image, ImageRef};
+pub use known_broken::{KnownBroken, KnownBrokenEntry, ParseError};
+pub use levels::{CheckLevel, CheckLevels};
+pub use limits::SafetyLimits;
+pub use mapping::UrlMapping;
+use normalise::encode_href;
+pub use normalise::{normalise_url, TrailingSlash};
+pub use packages::{Package, PackageRegistries, Registry};
+pub(crate) use plan::plan_from;
+pub use plan::{plan, plan_one, Check, PlannedCheck};
+pub use proxy::ProxySettings;
+pub use redact::Redactor;
+#[cfg(feature = "tower")]
+pub use service::{check_web_with, WebChecker};
+pub use single::{check_path, check_url, Checked};
+pub use stats::{Histogram, HostStats, Stats};
+pub use syntax::check_syntax;
 #[allow(deprecated)]
 pub use web::get;
 pub use web::{check_web, head};
 
-use crate::{Category, Link};
-use futures::{Future, StreamExt};
-use std::path::Path;
+use crate::{
+    fix::{Suggestion, SuggestionKind},
+    lints::{Lint, LintKind},
+    Category, Link,
+};
+use codespan::{FileId, Files};
+use futures::{Future, Stream, StreamExt};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
 
 /// Possible reasons for a bad link.
 #[derive(Debug, thiserror::Error)]
@@ -28,7 +101,121 @@ pub enum Reason {
     Io(#[from] std::io::Error),
     /// The HTTP client returned an error.
     #[error("The web client encountered an error")]
-    Web(#[from] reqwest::Error),
+    Web(#[source] reqwest::Error),
+    /// The host's name couldn't be resolved, which usually means the domain
+    /// is dead.
+    #[error("The host \"{0}\" doesn't exist")]
+    HostNotFound(String),
+    /// Nothing was listening on the other end, so the service may only be
+    /// down temporarily.
+    #[error("\"{0}\" refused the connection")]
+    ConnectionRefused(String),
+    /// The TLS handshake failed (e.g. because of an expired or self-signed
+    /// certificate).
+    #[error("Unable to establish a secure connection to \"{0}\"")]
+    Tls(String, #[source] reqwest::Error),
+    /// An intra-doc link didn't resolve to a known item.
+    #[error("Unable to resolve \"{0}\" to an item")]
+    UnresolvedItem(String),
+    /// The linked document exists, but doesn't contain the anchor from the
+    /// link's fragment. The closest anchor which does exist is included, if
+    /// there is one.
+    #[error("The \"#{0}\" anchor doesn't exist{}", did_you_mean(.1))]
+    MissingAnchor(String, Option<String>),
+    /// A link in a translated document only works in the source language's
+    /// tree (see [`crate::translations`]).
+    #[error("\"{}\" hasn't been translated yet", _0.display())]
+    Untranslated(PathBuf),
+    /// A `mailto:` link contains something which isn't an email address.
+    #[error("\"{0}\" isn't a valid email address")]
+    InvalidEmailAddress(String),
+    /// A `tel:` link doesn't contain a valid phone number.
+    #[error("\"{0}\" isn't a valid phone number")]
+    InvalidPhoneNumber(String),
+    /// A `javascript:` link, which only works when scripts are enabled and
+    /// can't be checked.
+    #[error("\"javascript:\" links can't be checked")]
+    JavaScript,
+    /// The link is empty (or just a `#`), so it doesn't go anywhere.
+    #[error("The link is empty")]
+    EmptyLink,
+    /// The link points to a domain the [`DomainPolicy`] forbids.
+    #[error("Links to \"{0}\" are forbidden")]
+    BlockedDomain(String),
+    /// The link points to a domain which isn't on the [`DomainPolicy`]'s
+    /// allowlist.
+    #[error("\"{0}\" isn't on the list of allowed domains")]
+    DomainNotAllowed(String),
+    /// The link is longer than [`SafetyLimits::max_url_length()`].
+    #[error("The link is too long ({0} bytes)")]
+    UrlTooLong(usize),
+    /// The URL's scheme isn't `http` or `https` and
+    /// [`SafetyLimits::web_schemes_only()`] is set.
+    #[error("Checking \"{0}:\" links is forbidden")]
+    ForbiddenScheme(String),
+    /// The issue or pull request doesn't exist (or is private).
+    #[error("The issue \"{0}\" doesn't exist")]
+    IssueNotFound(String),
+    /// The server refused to answer because we sent too many requests.
+    #[error("Rate limited by \"{0}\"")]
+    RateLimited(String),
+    /// The container image (or its tag) doesn't exist.
+    #[error("The \"{0}\" image doesn't exist")]
+    ImageNotFound(String),
+    /// The file doesn't exist in the repository (or at that ref).
+    #[error("The file \"{0}\" doesn't exist")]
+    MissingRepositoryFile(String),
+    /// A link to a line which is past the end of the file.
+    #[error("Line {0} is past the end of the file ({1} lines)")]
+    LineOutOfBounds(usize, usize),
+    /// One of the branches, tags, or commits in a comparison doesn't exist.
+    #[error("Unable to compare \"{0}\" because a ref doesn't exist")]
+    MissingRef(String),
+    /// The server refuses to talk to automated clients (see
+    /// [`ResponseRules`]), so we can't tell whether the link works.
+    #[error("\"{0}\" blocks automated requests (status {1})")]
+    BlockedByServer(String, u16),
+    /// The Wayback Machine doesn't have a snapshot of the page from that
+    /// time.
+    #[error("There is no snapshot of \"{0}\" from {1}")]
+    MissingSnapshot(String, String),
+    /// The package (or the version being linked to) isn't in the registry.
+    #[error("The {0} package \"{1}\" doesn't exist")]
+    PackageNotFound(Registry, String),
+    /// The server redirected in a circle. The `chain` is every URL which was
+    /// visited, ending with the one which was already seen.
+    #[error("Redirect loop: {}", display_chain(chain))]
+    RedirectLoop {
+        /// The URLs we were redirected through.
+        chain: Vec<url::Url>,
+    },
+    /// The link contains spaces or non-ASCII characters which need to be
+    /// percent-encoded. The encoded form (which does work) is included.
+    #[error("The URL needs to be percent-encoded as \"{0}\"")]
+    UnencodedUrl(String),
+    /// The link looks like a URL, but couldn't be parsed.
+    #[error("\"{0}\" isn't a valid URL ({1})")]
+    MalformedUrl(String, url::ParseError),
+    /// The link couldn't be understood as a URL or a path.
+    #[error("\"{0}\" isn't a valid URL or path")]
+    Unparseable(String),
+    /// A `%` which isn't followed by two hex digits, or which decodes to
+    /// something that can't be in a path (e.g. invalid UTF-8).
+    #[error("\"{0}\" isn't percent-encoded properly")]
+    InvalidPercentEncoding(String),
+    /// The fragment contains whitespace or a second `#`.
+    #[error("The \"#{0}\" fragment is malformed")]
+    MalformedFragment(String),
+    /// The link was supposed to be broken (see
+    /// [`Context::expected_failures()`]), but it works.
+    #[error("The link was expected to be broken, but it works")]
+    UnexpectedlyValid,
+    /// A custom validator (e.g. [`Context::check_opaque_uri()`] or
+    /// [`Options::set_custom_validation()`]) rejected the link. Use
+    /// [`Reason::custom()`] to create one, and [`Reason::downcast_ref()`] to
+    /// get the original error back.
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
 }
 
 impl Reason {
@@ -40,6 +227,22 @@ impl Reason {
         }
     }
 
+    /// Did the server refuse to answer because we aren't a browser?
+    pub fn blocked_by_server(&self) -> bool {
+        matches!(self, Reason::BlockedByServer(..))
+    }
+
+    /// Does this mean we couldn't tell whether the link works, rather than
+    /// the link being broken (see [`Outcomes::unverifiable`])?
+    pub fn is_unverifiable(&self) -> bool {
+        matches!(self, Reason::BlockedByServer(..) | Reason::RateLimited(_))
+    }
+
+    /// Was this failure because the host couldn't be resolved?
+    pub fn host_not_found(&self) -> bool {
+        matches!(self, Reason::HostNotFound(_))
+    }
+
     /// Did the HTTP client time out?
     pub fn timed_out(&self) -> bool {
         match self {
@@ -47,110 +250,1262 @@ impl Reason {
             _ => false,
         }
     }
+
+    /// Report an error from a custom validator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use linkcheck::validation::Reason;
+    ///
+    /// #[derive(Debug, thiserror::Error)]
+    /// #[error("Ticket {0} is closed")]
+    /// struct ClosedTicket(u32);
+    ///
+    /// let reason = Reason::custom(ClosedTicket(42));
+    /// assert_eq!(reason.to_string(), "Ticket 42 is closed");
+    /// assert_eq!(reason.downcast_ref::<ClosedTicket>().unwrap().0, 42);
+    ///
+    /// // plain messages work too
+    /// let reason = Reason::custom("Unknown ticket");
+    /// assert_eq!(reason.to_string(), "Unknown ticket");
+    /// ```
+    pub fn custom<E>(error: E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Reason::Custom(error.into())
+    }
+
+    /// Did a custom validator reject the link (see [`Reason::Custom`])?
+    pub fn is_custom(&self) -> bool { matches!(self, Reason::Custom(_)) }
+
+    /// Get the error a custom validator returned, if it was an `E`.
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: std::error::Error + 'static,
+    {
+        match self {
+            Reason::Custom(error) => error.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Reason {
+    fn from(error: reqwest::Error) -> Self { web::into_reason(error) }
 }
 
 /// Validate several [`Link`]s relative to a particular directory.
-pub fn validate<'a, L, C>(
+///
+/// Web links are grouped by host and checked round-robin, so at most
+/// [`Context::concurrency_per_host()`] requests go to the same host at a time.
+/// Links to files on disk are checked first, on a pool of threads (see
+/// [`validate_local()`]).
+///
+/// The [`Link::file`] can be any type, so tools which don't use
+/// [`codespan::Files`] can still use the validators directly.
+pub fn validate<'a, L, F, C>(
     current_directory: &'a Path,
     links: L,
     ctx: &'a C,
-) -> impl Future<Output = Outcomes> + 'a
+) -> impl Future<Output = Outcomes<F>> + 'a
 where
-    L: IntoIterator<Item = Link>,
+    L: IntoIterator<Item = Link<F>>,
     L::IntoIter: 'a,
+    F: Clone + Eq + Hash + Send + 'a,
+    C: Context + Sync + ?Sized,
+{
+    let links = links
+        .into_iter()
+        .map(move |link| (link, Origin::from(current_directory)));
+    validate_many(links, ctx)
+}
+
+/// Validate a [`Stream`] of [`Link`]s relative to a particular directory,
+/// yielding results as they become available.
+///
+/// Unlike [`validate()`], nothing is buffered up front. Only a few links are
+/// read from the input ahead of time, and each item from the returned
+/// [`Stream`] holds the [`Outcomes`] for a single [`Link`]. This keeps memory
+/// usage flat, even when checking millions of links. Links to files on disk
+/// are checked as they arrive instead of on a pool of threads.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use futures::StreamExt;
+/// use linkcheck::{validation::validate_stream, BasicContext, Link};
+/// # use std::path::Path;
+///
+/// # #[tokio::main] async fn main() {
+/// # let links = futures::stream::empty::<Link>();
+/// let ctx = BasicContext::default();
+/// let mut results = Box::pin(validate_stream(Path::new("."), links, &ctx));
+///
+/// while let Some(outcomes) = results.next().await {
+///     for invalid in &outcomes.invalid {
+///         println!("{}: {}", invalid.link.href, invalid.reason);
+///     }
+/// }
+/// # }
+/// ```
+pub fn validate_stream<'a, S, F, C>(
+    current_directory: &'a Path,
+    links: S,
+    ctx: &'a C,
+) -> impl Stream<Item = Outcomes<F>> + 'a
+where
+    S: Stream<Item = Link<F>> + 'a,
+    F: Clone + Eq + Hash + 'a,
     C: Context + ?Sized,
 {
-    futures::stream::iter(links)
-        .map(move |link| validate_one(link, current_directory, ctx))
-        .buffer_unordered(ctx.concurrency())
+    let links = links.map(move |link| (link, Origin::from(current_directory)));
+    validate_stream_many(links, ctx)
+}
+
+/// Like [`validate_stream()`], except each [`Link`] may be relative to a
+/// different directory.
+pub(crate) fn validate_stream_many<'a, S, F, C>(
+    links: S,
+    ctx: &'a C,
+) -> impl Stream<Item = Outcomes<F>> + 'a
+where
+    S: Stream<Item = (Link<F>, Origin<'a>)> + 'a,
+    F: Clone + Eq + Hash + 'a,
+    C: Context + ?Sized,
+{
+    let limits = scheduler::Limits {
+        concurrency: ctx.concurrency(),
+        per_key: ctx.concurrency_per_host(),
+        lookahead: ctx.concurrency().saturating_mul(4),
+    };
+    let directories = Arc::new(Directories::default());
+
+    let mut counter = LinkCounter::new(ctx);
+
+    let links = links.map(move |(link, origin)| {
+        let category = categorise(&link.href, ctx);
+        let allowed = counter.allow(&link, ctx);
+        (link, origin, category, allowed)
+    });
+
+    scheduler::schedule(
+        links,
+        |(_, _, category, _)| host(category),
+        limits,
+        move |(link, origin, category, allowed)| {
+            let directories = Arc::clone(&directories);
+            async move {
+                if !allowed {
+                    return Outcome::Ignored(link);
+                }
+
+                validate_one(link, category, origin, ctx, &directories).await
+            }
+        },
+    )
+    .map(|outcome| {
+        let mut outcomes = Outcomes::empty();
+        outcomes.extend(Some(outcome));
+        outcomes
+    })
+}
+
+/// Validate [`Link`]s which have already been sorted into [`Category`]s,
+/// relative to a particular directory.
+///
+/// This is for tools which already know what each link is (e.g. from an
+/// index or a previous run), so [`Context::categorise()`] is skipped and the
+/// links are checked exactly as categorised. Protocol-relative URLs still
+/// get the [`Context::default_scheme()`].
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{validation::validate_categorised, BasicContext, Category};
+/// # use linkcheck::Link;
+/// # use std::path::{Path, PathBuf};
+/// # #[tokio::main] async fn main() {
+/// # let file = codespan::Files::new().add("README.md", "");
+/// # let span = codespan::Span::default();
+/// // an index told us this is served from "src/lib.rs"
+/// let link = Link::new("https://example.com/lib.rs", span, file);
+/// let category = Category::FileSystem {
+///     path: PathBuf::from("src/lib.rs"),
+///     query: None,
+///     fragment: None,
+/// };
+///
+/// let ctx = BasicContext::default();
+/// let outcomes =
+///     validate_categorised(Path::new("."), vec![(link, category)], &ctx)
+///         .await;
+///
+/// assert_eq!(outcomes.valid.len(), 1);
+/// # }
+/// ```
+pub fn validate_categorised<'a, L, F, C>(
+    current_directory: &'a Path,
+    links: L,
+    ctx: &'a C,
+) -> impl Future<Output = Outcomes<F>> + 'a
+where
+    L: IntoIterator<Item = (Link<F>, Category)>,
+    L::IntoIter: 'a,
+    F: Clone + Eq + Hash + Send + 'a,
+    C: Context + Sync + ?Sized,
+{
+    let links = links.into_iter().map(move |(link, category)| {
+        let category = resolve_protocol_relative(category, ctx);
+        (link, Origin::from(current_directory), category)
+    });
+    check_categorised(links, ctx)
+}
+
+/// Validate [`Link`]s which may each be relative to a different directory.
+pub(crate) async fn validate_many<'a, L, F, C>(links: L, ctx: &C) -> Outcomes<F>
+where
+    L: IntoIterator<Item = (Link<F>, Origin<'a>)>,
+    F: Clone + Eq + Hash + Send,
+    C: Context + Sync + ?Sized,
+{
+    check_categorised(categorise_all(links, ctx), ctx).await
+}
+
+async fn check_categorised<'a, L, F, C>(links: L, ctx: &C) -> Outcomes<F>
+where
+    L: IntoIterator<Item = Categorised<'a, F>>,
+    F: Clone + Eq + Hash + Send,
+    C: Context + Sync + ?Sized,
+{
+    let directories = Directories::default();
+
+    let mut counter = LinkCounter::new(ctx);
+    let (links, over_the_limit): (Vec<_>, Vec<_>) = links
+        .into_iter()
+        .partition(|(link, _, _)| counter.allow(link, ctx));
+
+    // filesystem checks do blocking IO, so they get spread across a pool of
+    // threads instead of holding up the executor one at a time
+    let (local, mut links): (Vec<_>, Vec<_>) =
+        links.into_iter().partition(|(_, _, category)| {
+            matches!(category, Some(Category::FileSystem { .. }))
+        });
+    let mut outcomes = check_in_parallel(local, ctx, &directories);
+    outcomes
+        .ignored
+        .extend(over_the_limit.into_iter().map(|(l, _, _)| l));
+
+    if ctx.deadline().is_some() {
+        // make sure links we know nothing about get checked before we run
+        // out of time
+        links.sort_by_key(|(_, _, category)| is_cached(category, ctx));
+    }
+
+    if let Some(dns) = ctx.dns_cache() {
+        dns.prefetch(links.iter().filter_map(|(_, _, c)| host(c)))
+            .await;
+    }
+
+    // equivalent URLs are only checked once, then the repeats can be
+    // answered by the cache
+    let (first, repeats) = split_repeated_urls(links, ctx);
+
+    for batch in [first, repeats] {
+        let batch: Outcomes<F> = scheduler::run(
+            batch,
+            |(_, _, category)| host(category),
+            ctx.concurrency(),
+            ctx.concurrency_per_host(),
+            |(link, origin, category)| {
+                validate_one(link, category, origin, ctx, &directories)
+            },
+        )
+        .await;
+        outcomes.merge(batch);
+    }
+
+    outcomes
+}
+
+type Categorised<'a, F> = (Link<F>, Origin<'a>, Option<Category>);
+
+/// Where a [`Link`] was found.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Origin<'a> {
+    /// The directory relative links are resolved from.
+    pub(crate) directory: &'a Path,
+    /// The document containing the link, if we know it. This is needed for
+    /// checking links to other parts of the same document (e.g. `#usage`).
+    pub(crate) document: Option<&'a Path>,
+}
+
+impl<'a> From<&'a Path> for Origin<'a> {
+    fn from(directory: &'a Path) -> Self {
+        Origin {
+            directory,
+            document: None,
+        }
+    }
+}
+
+/// Split out web links which point to the same place as an earlier link,
+/// using their [`Context::cache_key()`].
+fn split_repeated_urls<'a, F, C>(
+    links: Vec<Categorised<'a, F>>,
+    ctx: &C,
+) -> (Vec<Categorised<'a, F>>, Vec<Categorised<'a, F>>)
+where
+    C: Context + ?Sized,
+{
+    let mut seen = HashSet::new();
+
+    links
+        .into_iter()
+        .partition(|(_, _, category)| match category {
+            Some(Category::Url(url)) => seen.insert(ctx.cache_key(url)),
+            _ => true,
+        })
+}
+
+/// Find the [`Category`] for each [`Link`].
+///
+/// Documents tend to repeat the same links over and over (e.g. navigation
+/// bars), so each unique `href` is only parsed once.
+fn categorise_all<'a, L, F, C>(links: L, ctx: &C) -> Vec<Categorised<'a, F>>
+where
+    L: IntoIterator<Item = (Link<F>, Origin<'a>)>,
+    C: Context + ?Sized,
+{
+    let mut categories: HashMap<String, Option<Category>> = HashMap::new();
+
+    links
+        .into_iter()
+        .map(|(link, origin)| {
+            let category = match categories.get(&link.href) {
+                Some(category) => category.clone(),
+                None => {
+                    let category = categorise(&link.href, ctx);
+                    categories.insert(link.href.clone(), category.clone());
+                    category
+                },
+            };
+
+            (link, origin, category)
+        })
         .collect()
 }
 
+/// Ask the [`Context`] how to check a link, turning protocol-relative URLs
+/// into normal ones.
+fn categorise<C>(href: &str, ctx: &C) -> Option<Category>
+where
+    C: Context + ?Sized,
+{
+    resolve_protocol_relative(ctx.categorise(href)?, ctx)
+}
+
+fn resolve_protocol_relative<C>(category: Category, ctx: &C) -> Option<Category>
+where
+    C: Context + ?Sized,
+{
+    match category {
+        Category::ProtocolRelative(href) => {
+            let url = format!("{}:{}", ctx.default_scheme(), href);
+            url::Url::parse(&url).ok().map(Category::Url)
+        },
+        other => Some(other),
+    }
+}
+
+/// Does the [`Context::cache()`] say this web link is still valid?
+fn is_cached<C>(category: &Option<Category>, ctx: &C) -> bool
+where
+    C: Context + ?Sized,
+{
+    match category {
+        Some(Category::Url(url)) => web::already_valid(url, ctx),
+        _ => false,
+    }
+}
+
+/// Keeps track of how many links have been seen in each document, so we can
+/// stop at the [`SafetyLimits::max_links_per_document()`].
+struct LinkCounter<F> {
+    max: Option<usize>,
+    counts: HashMap<F, usize>,
+}
+
+impl<F: Clone + Eq + Hash> LinkCounter<F> {
+    fn new<C>(ctx: &C) -> Self
+    where
+        C: Context + ?Sized,
+    {
+        LinkCounter {
+            max: ctx.safety_limits().and_then(|l| l.max_links_per_document()),
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Count a link, returning whether it is still within the limit.
+    fn allow<C>(&mut self, link: &Link<F>, ctx: &C) -> bool
+    where
+        C: Context + ?Sized,
+    {
+        let max = match self.max {
+            Some(max) => max,
+            None => return true,
+        };
+
+        let count = self.counts.entry(link.file.clone()).or_default();
+        *count += 1;
+
+        if *count > max {
+            log::warn!(
+                "Not checking \"{}\" because its document has more than {} links",
+                redact::redacted(&link.href, ctx),
+                max
+            );
+        }
+
+        *count <= max
+    }
+}
+
+/// Have we run out of time to check this [`Url`]?
+///
+/// [`Url`]: url::Url
+fn past_deadline<C>(url: &url::Url, ctx: &C) -> bool
+where
+    C: Context + ?Sized,
+{
+    match ctx.deadline() {
+        Some(deadline) => {
+            Instant::now() >= deadline && !web::already_valid(url, ctx)
+        },
+        None => false,
+    }
+}
+
+fn host(category: &Option<Category>) -> Option<String> {
+    match category.as_ref()? {
+        Category::Url(url) => url.host_str().map(str::to_ascii_lowercase),
+        _ => None,
+    }
+}
+
 /// Try to validate a single link, deferring to the appropriate validator based
 /// on the link's [`Category`].
-async fn validate_one<C>(
-    link: Link,
-    current_directory: &Path,
+pub(crate) async fn validate_one<F, C>(
+    link: Link<F>,
+    category: Option<Category>,
+    origin: Origin<'_>,
+    ctx: &C,
+    directories: &Directories,
+) -> Outcome<F>
+where
+    F: Clone,
+    C: Context + ?Sized,
+{
+    let outcome = check_link(link, category, origin, ctx, directories).await;
+
+    #[cfg(feature = "metrics")]
+    crate::metrics::link_checked(&outcome);
+
+    outcome
+}
+
+/// [`validate_one()`], without recording any metrics.
+async fn check_link<F, C>(
+    link: Link<F>,
+    category: Option<Category>,
+    origin: Origin<'_>,
+    ctx: &C,
+    directories: &Directories,
+) -> Outcome<F>
+where
+    F: Clone,
+    C: Context + ?Sized,
+{
+    let (category, has_credentials) = strip_credentials(category, ctx);
+
+    let outcome = check_one(link, category, origin, ctx, directories)
+        .await
+        .mark_unverifiable(ctx)
+        .suppress_known_broken(ctx)
+        .expect_failures(ctx);
+
+    if has_credentials {
+        outcome.lint(LintKind::EmbeddedCredentials)
+    } else {
+        outcome
+    }
+}
+
+/// Remove the username and password from a web link, unless the [`Context`]
+/// says they should be sent. Also returns whether there were any.
+fn strip_credentials<C>(
+    category: Option<Category>,
     ctx: &C,
-) -> Outcome
+) -> (Option<Category>, bool)
 where
     C: Context + ?Sized,
+{
+    match category {
+        Some(Category::Url(mut url))
+            if !url.username().is_empty() || url.password().is_some() =>
+        {
+            if !ctx.send_credentials(&url) {
+                let _ = url.set_username("");
+                let _ = url.set_password(None);
+                log::warn!(
+                    "Removed the credentials from \"{}\"",
+                    ctx.redact(&url)
+                );
+            }
+            (Some(Category::Url(url)), true)
+        },
+        other => (other, false),
+    }
+}
+
+async fn check_one<F, C>(
+    link: Link<F>,
+    category: Option<Category>,
+    origin: Origin<'_>,
+    ctx: &C,
+    directories: &Directories,
+) -> Outcome<F>
+where
+    F: Clone,
+    C: Context + ?Sized,
 {
     if ctx.should_ignore(&link) {
-        log::debug!("Ignoring \"{}\"", link.href);
+        log::debug!("Ignoring \"{}\"", redact::redacted(&link.href, ctx));
         return Outcome::Ignored(link);
     }
 
-    match link.category() {
-        Some(Category::FileSystem { path, fragment }) => Outcome::from_result(
+    let limits = ctx.safety_limits();
+
+    if let Some(Err(reason)) = limits.map(|l| l.check_href(&link.href)) {
+        return Outcome::Invalid(InvalidLink { link, reason });
+    }
+
+    let started = Instant::now();
+    let level = category
+        .as_ref()
+        .map_or(CheckLevel::Deep, |category| ctx.check_level(category));
+
+    match category {
+        Some(Category::FileSystem { .. })
+            if limits.is_some_and(SafetyLimits::web_schemes_only) =>
+        {
+            Outcome::Invalid(InvalidLink {
+                link,
+                reason: Reason::ForbiddenScheme(String::from("file")),
+            })
+        },
+        Some(Category::FileSystem { path, fragment, .. }) => check_local(
             link,
-            check_filesystem(
-                current_directory,
-                &path,
-                fragment.as_deref(),
-                ctx,
-            ),
+            &path,
+            fragment.as_deref(),
+            level,
+            origin.directory,
+            ctx,
+            directories,
         ),
         Some(Category::CurrentFile { fragment }) => {
-            // TODO: How do we want to validate links to other parts of the
-            // current file?
-            //
-            // It seems wasteful to go through the whole filesystem resolution
-            // process when the filename was recorded when adding its text to
-            // `Files`... Maybe we could thread `Files` through and then join it
-            // with `ctx.filesystem_options().root_directory()`?
-            log::warn!("Not checking \"{}\" in the current file because fragment resolution isn't implemented", fragment);
-            Outcome::Ignored(link)
+            check_current_file(link, &fragment, level, origin, ctx)
         },
         Some(Category::Url(url)) => {
-            Outcome::from_result(link, check_web(&url, ctx).await)
+            match ctx.domain_policy().map(|policy| policy.check(&url)) {
+                Some(Ok(false)) => {
+                    log::debug!(
+                        "Ignoring \"{}\" because of its domain",
+                        ctx.redact(&url)
+                    );
+                    return Outcome::Ignored(link);
+                },
+                Some(Err(reason)) => {
+                    return Outcome::Invalid(InvalidLink { link, reason })
+                },
+                Some(Ok(true)) | None => {},
+            }
+
+            if let Some(Err(reason)) = limits.map(|l| l.check_url(&url)) {
+                return Outcome::Invalid(InvalidLink { link, reason });
+            }
+
+            if level == CheckLevel::Syntax {
+                let result = syntax::check_url(&link.href);
+                return Outcome::from_result(link, result);
+            }
+
+            let mapped = ctx
+                .filesystem_options()
+                .url_mappings()
+                .iter()
+                .find_map(|m| m.relative_path(&url).map(|path| (m, path)));
+
+            if let Some((mapping, path)) = mapped {
+                log::debug!(
+                    "Checking \"{}\" against \"{}\" instead of the web",
+                    ctx.redact(&url),
+                    mapping.directory().join(&path).display()
+                );
+                let result = filesystem::check_filesystem_with(
+                    mapping.directory(),
+                    &path,
+                    url.fragment().filter(|_| level == CheckLevel::Deep),
+                    ctx,
+                    directories,
+                );
+                return Outcome::from_result(link, result)
+                    .timed(started.elapsed());
+            }
+
+            if past_deadline(&url, ctx) {
+                log::debug!(
+                    "Out of time, not checking \"{}\"",
+                    ctx.redact(&url)
+                );
+                return Outcome::Unchecked(link);
+            }
+
+            #[cfg(feature = "container-images")]
+            if url.scheme() == "docker" {
+                let result = images::check_image_url(&url, ctx).await;
+                return Outcome::from_result(link, result)
+                    .timed(started.elapsed());
+            }
+
+            // asking an API for more information is only done for deep checks
+            if let (CheckLevel::Deep, Some(wayback)) =
+                (level, ctx.wayback_machine())
+            {
+                if let Some(page) = wayback.archived_page(&url) {
+                    let result = wayback.check(&page, ctx).await;
+                    let original = match &result {
+                        Ok(original) => original.clone(),
+                        Err(_) => None,
+                    };
+
+                    return Outcome::from_result(link, result)
+                        .timed(started.elapsed())
+                        .suggest(original, SuggestionKind::LiveOriginal);
+                }
+            }
+
+            if let (CheckLevel::Deep, Some(registries)) =
+                (level, ctx.package_registries())
+            {
+                if let Some(package) = registries.package(&url) {
+                    let result = registries
+                        .check(ctx.client(), &package, ctx.deadline())
+                        .await;
+                    return Outcome::from_result(link, result)
+                        .timed(started.elapsed());
+                }
+            }
+
+            #[cfg(feature = "forges")]
+            if let (CheckLevel::Deep, Some(api)) = (level, ctx.forge_api()) {
+                if let Some(issue) = api.issue(&url) {
+                    let result = api
+                        .check(ctx.client(), &url, &issue, ctx.deadline())
+                        .await;
+                    let moved = match &result {
+                        Ok(Some(moved)) => Some(moved.to_string()),
+                        _ => None,
+                    };
+
+                    return Outcome::from_result(link, result)
+                        .timed(started.elapsed())
+                        .suggest(moved, SuggestionKind::Transferred);
+                }
+
+                if let Some(file) = api.file(&url) {
+                    let result = api
+                        .check_file(ctx.client(), &url, &file, ctx.deadline())
+                        .await;
+                    return Outcome::from_result(link, result)
+                        .timed(started.elapsed());
+                }
+
+                if let Some(comparison) = api.comparison(&url) {
+                    let result = api
+                        .check_comparison(
+                            ctx.client(),
+                            &url,
+                            &comparison,
+                            ctx.deadline(),
+                        )
+                        .await;
+                    return Outcome::from_result(link, result)
+                        .timed(started.elapsed());
+                }
+            }
+
+            let mut attempts = Attempts::new(ctx.record_attempts());
+            let result =
+                web::check_web_and_follow(&url, ctx, &mut attempts).await;
+            let attempts = attempts.into_inner();
+
+            if result.as_ref().is_err_and(Reason::timed_out)
+                && past_deadline(&url, ctx)
+            {
+                log::debug!(
+                    "Ran out of time while checking \"{}\"",
+                    ctx.redact(&url)
+                );
+                return Outcome::Unchecked(link).attempted(attempts);
+            }
+
+            // the URL we requested was already encoded, so if it worked
+            // then encoding the link is all that's needed to fix it
+            if let (Ok(_), Some(encoded)) = (&result, encode_href(&link.href)) {
+                let reason = Reason::UnencodedUrl(encoded.clone());
+                return Outcome::Invalid(InvalidLink { link, reason })
+                    .timed(started.elapsed())
+                    .attempted(attempts)
+                    .suggest(Some(encoded), SuggestionKind::PercentEncoding);
+            }
+
+            let redirect = match &result {
+                Ok(Some(redirect)) => Some(redirect.clone()),
+                _ => None,
+            };
+            let outcome = Outcome::from_result(link, result)
+                .timed(started.elapsed())
+                .attempted(attempts);
+
+            match redirect {
+                // don't suggest "fixing" the link by switching to HTTP
+                Some(redirect)
+                    if url.scheme() == "https"
+                        && redirect.scheme() == "http" =>
+                {
+                    log::warn!(
+                        "\"{}\" redirects to \"{}\"",
+                        ctx.redact(&url),
+                        ctx.redact(&redirect)
+                    );
+                    outcome.lint(LintKind::ProtocolDowngrade(redirect.into()))
+                },
+                redirect => outcome.suggest(
+                    redirect.map(String::from),
+                    SuggestionKind::Redirect,
+                ),
+            }
+        },
+        Some(Category::MailTo(address)) => {
+            let result = syntax::check_mailto(&address);
+            Outcome::from_result(link, result)
+        },
+        Some(Category::Tel(number)) => {
+            let result = syntax::check_tel(&number);
+            Outcome::from_result(link, result)
+        },
+        Some(Category::Opaque(_)) if level == CheckLevel::Syntax => {
+            Outcome::Valid(link)
+        },
+        Some(Category::Opaque(uri)) => match ctx.check_opaque_uri(&uri) {
+            Some(result) => Outcome::from_result(link, result),
+            None => Outcome::UnknownCategory(link),
+        },
+        Some(Category::JavaScript(_)) => Outcome::Invalid(InvalidLink {
+            link,
+            reason: Reason::JavaScript,
+        }),
+        Some(Category::Empty) => Outcome::Invalid(InvalidLink {
+            link,
+            reason: Reason::EmptyLink,
+        }),
+        // categorise() resolves protocol-relative URLs whenever it can
+        Some(Category::ProtocolRelative(_)) | None => {
+            Outcome::UnknownCategory(link)
+        },
+    }
+}
+
+/// Check a link to a file on disk.
+fn check_local<F, C>(
+    link: Link<F>,
+    path: &Path,
+    fragment: Option<&str>,
+    level: CheckLevel,
+    current_directory: &Path,
+    ctx: &C,
+    directories: &Directories,
+) -> Outcome<F>
+where
+    F: Clone,
+    C: Context + ?Sized,
+{
+    let fragment = match level {
+        CheckLevel::Syntax => {
+            let result = syntax::check_path(&link.href);
+            return Outcome::from_result(link, result);
+        },
+        CheckLevel::Exists => None,
+        CheckLevel::Deep => fragment,
+    };
+    let started = Instant::now();
+    let result = filesystem::check_filesystem_with(
+        current_directory,
+        path,
+        fragment,
+        ctx,
+        directories,
+    );
+    let mut candidates = Vec::new();
+    let (suggestion, kind) = match &result {
+        Err(reason) if reason.file_not_found() => {
+            let options = ctx.filesystem_options();
+            let name =
+                filesystem::suggest_file_name(current_directory, path, options);
+            if name.is_none() {
+                candidates = filesystem::find_moved_file(
+                    current_directory,
+                    path,
+                    options,
+                    directories,
+                );
+            }
+            let href =
+                name.and_then(|name| replace_file_name(&link.href, &name));
+            (href, SuggestionKind::FileName)
+        },
+        Err(Reason::MissingAnchor(_, Some(anchor))) => {
+            (replace_fragment(&link.href, anchor), SuggestionKind::Anchor)
+        },
+        _ => (None, SuggestionKind::FileName),
+    };
+
+    Outcome::from_result(link, result)
+        .timed(started.elapsed())
+        .candidates(candidates)
+        .suggest(suggestion, kind)
+}
+
+/// Check a link to another part of the same document (e.g. `#usage`) using
+/// the [`Context::anchor_db()`].
+fn check_current_file<F, C>(
+    link: Link<F>,
+    fragment: &str,
+    level: CheckLevel,
+    origin: Origin<'_>,
+    ctx: &C,
+) -> Outcome<F>
+where
+    F: Clone,
+    C: Context + ?Sized,
+{
+    let document = match origin.document {
+        Some(document) if level == CheckLevel::Deep => document,
+        _ => return Outcome::Ignored(link),
+    };
+
+    match filesystem::check_fragment(document, fragment, ctx) {
+        Some(result) => {
+            let suggestion = match &result {
+                Err(Reason::MissingAnchor(_, Some(anchor))) => {
+                    replace_fragment(&link.href, anchor)
+                },
+                _ => None,
+            };
+            Outcome::from_result(link, result)
+                .suggest(suggestion, SuggestionKind::Anchor)
+        },
+        None => {
+            log::warn!(
+                "Not checking that the \"{}\" section exists in \"{}\" \
+                 because it isn't in the anchor database",
+                fragment,
+                document.display(),
+            );
+            Outcome::Ignored(link)
         },
-        Some(Category::MailTo(_)) => Outcome::Ignored(link),
-        None => Outcome::UnknownCategory(link),
     }
 }
 
+/// Check links to files on disk using a pool of threads.
+///
+/// Filesystem checks do blocking IO, so rather than doing them one at a time
+/// on the executor, they are spread across
+/// [`std::thread::available_parallelism()`] threads, and each directory only
+/// gets canonicalized once. This makes a big difference on network
+/// filesystems. [`validate()`] does this for you, but you may want to check
+/// local links on their own (e.g. before any web links).
+///
+/// Only filesystem links are checked, and they get exactly the same treatment
+/// as in [`validate()`] (e.g. [`Context::known_broken()`] and
+/// [`Context::safety_limits()`] are respected). Everything else (e.g. web
+/// links) is returned untouched so it can be passed to [`validate()`].
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{validation::validate_local, BasicContext, Link};
+/// # use std::path::Path;
+/// # let file = codespan::Files::new().add("README.md", "");
+/// # let span = codespan::Span::default();
+///
+/// let links = vec![
+///     (Link::new("src/lib.rs", span, file), Path::new(".")),
+///     (Link::new("https://example.com/", span, file), Path::new(".")),
+/// ];
+///
+/// let (outcomes, remaining) =
+///     validate_local(links, &BasicContext::default());
+///
+/// assert_eq!(outcomes.valid.len(), 1);
+/// assert_eq!(remaining.len(), 1);
+/// ```
+pub fn validate_local<'a, L, F, C>(
+    links: L,
+    ctx: &C,
+) -> (Outcomes<F>, Vec<(Link<F>, &'a Path)>)
+where
+    L: IntoIterator<Item = (Link<F>, &'a Path)>,
+    F: Clone + Eq + Hash + Send,
+    C: Context + Sync + ?Sized,
+{
+    let mut local = Vec::new();
+    let mut remaining = Vec::new();
+
+    let mut counter = LinkCounter::new(ctx);
+    let mut over_the_limit = Vec::new();
+
+    let links = links
+        .into_iter()
+        .map(|(link, directory)| (link, Origin::from(directory)));
+
+    for (link, origin, category) in categorise_all(links, ctx) {
+        if !counter.allow(&link, ctx) {
+            over_the_limit.push(link);
+            continue;
+        }
+
+        match category {
+            Some(Category::FileSystem { .. }) => {
+                local.push((link, origin, category))
+            },
+            _ => remaining.push((link, origin.directory)),
+        }
+    }
+
+    let mut outcomes = check_in_parallel(local, ctx, &Directories::default());
+    outcomes.ignored.extend(over_the_limit);
+
+    (outcomes, remaining)
+}
+
+/// Check filesystem links on a pool of threads, so the blocking IO isn't
+/// done one link at a time.
+fn check_in_parallel<'a, F, C>(
+    links: Vec<Categorised<'a, F>>,
+    ctx: &C,
+    directories: &Directories,
+) -> Outcomes<F>
+where
+    F: Clone + Eq + Hash + Send,
+    C: Context + Sync + ?Sized,
+{
+    let threads = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(links.len());
+    let queue = Mutex::new(links.into_iter());
+    let mut outcomes = Outcomes::empty();
+
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut outcomes = Vec::new();
+
+                    loop {
+                        let next = queue.lock().expect("Poisoned").next();
+                        let (link, origin, category) = match next {
+                            Some(item) => item,
+                            None => return outcomes,
+                        };
+
+                        // filesystem checks never wait on anything, so
+                        // there's no need for a proper executor
+                        let outcome = futures::executor::block_on(check_link(
+                            link,
+                            category,
+                            origin,
+                            ctx,
+                            directories,
+                        ));
+                        outcomes.push(outcome);
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            let checked = worker.join().expect("A worker thread panicked");
+            // the metrics recorder may only be installed for this thread
+            #[cfg(feature = "metrics")]
+            checked.iter().for_each(crate::metrics::link_checked);
+            outcomes.extend(checked);
+        }
+    });
+
+    outcomes
+}
+
+fn display_chain(chain: &[url::Url]) -> String {
+    let urls: Vec<_> = chain.iter().map(url::Url::as_str).collect();
+    urls.join(" -> ")
+}
+
+fn did_you_mean(anchor: &Option<String>) -> String {
+    match anchor {
+        Some(anchor) => format!(" (did you mean \"#{}\"?)", anchor),
+        None => String::new(),
+    }
+}
+
+/// Swap out the fragment in a link's `href`.
+fn replace_fragment(href: &str, fragment: &str) -> Option<String> {
+    let (before, _) = href.split_once('#')?;
+    Some(format!("{}#{}", before, fragment))
+}
+
+/// Swap out the file name in a link's `href`, keeping its directory, query,
+/// and fragment.
+fn replace_file_name(href: &str, name: &str) -> Option<String> {
+    let (path, rest) = match href.find(['?', '#']) {
+        Some(index) => href.split_at(index),
+        None => (href, ""),
+    };
+    if path.is_empty() || path.ends_with('/') {
+        return None;
+    }
+    let directory = &path[..path.rfind('/').map(|i| i + 1).unwrap_or(0)];
+
+    Some(format!("{}{}{}", directory, name, rest))
+}
+
 /// The result of validating a batch of [`Link`]s.
-#[derive(Debug, Default)]
-pub struct Outcomes {
+#[derive(Debug)]
+pub struct Outcomes<F = FileId> {
     /// Valid links.
-    pub valid: Vec<Link>,
+    pub valid: Vec<Link<F>>,
     /// Links which are broken.
-    pub invalid: Vec<InvalidLink>,
+    pub invalid: Vec<InvalidLink<F>>,
     /// Items that were explicitly ignored by the [`Context`].
-    pub ignored: Vec<Link>,
+    pub ignored: Vec<Link<F>>,
     /// Links which we weren't able to identify a suitable validator for.
-    pub unknown_category: Vec<Link>,
+    pub unknown_category: Vec<Link<F>>,
+    /// Links which weren't checked because we ran out of time (see
+    /// [`Context::deadline()`]).
+    pub unchecked: Vec<Link<F>>,
+    /// Broken links which were accepted anyway because they are in the
+    /// [`Context::known_broken()`] list (or a [`Baseline`]).
+    pub suppressed: Vec<InvalidLink<F>>,
+    /// Links we couldn't verify either way (e.g. because the server blocks
+    /// automated clients or rate limited us), and why.
+    pub unverifiable: Vec<InvalidLink<F>>,
+    /// Links which are broken on purpose (see
+    /// [`Context::expected_failures()`]), and why.
+    pub expected_failures: Vec<InvalidLink<F>>,
+    /// How long it took to check each [`Link`].
+    pub durations: HashMap<Link<F>, Duration>,
+    /// Files elsewhere with the same name as the missing file a [`Link`]
+    /// points to, in case it was moved (only searched for when there is a
+    /// [`Options::root_directory()`]).
+    pub candidates: HashMap<Link<F>, Vec<PathBuf>>,
+    /// Every request sent while checking each web [`Link`], when
+    /// [`Context::record_attempts()`] is enabled.
+    pub attempts: HashMap<Link<F>, Vec<Attempt>>,
+    /// Possible fixes for broken or redirected links (see
+    /// [`crate::fix`]).
+    pub suggestions: Vec<Suggestion<F>>,
+    /// Problems with the text of each link (see [`crate::lints`]).
+    pub lints: Vec<Lint<F>>,
+    /// Which run (e.g. a CI job or shard) each [`Link`] was checked by, when
+    /// the [`Outcomes`] were combined using [`Outcomes::merge_from()`].
+    pub sources: HashMap<Link<F>, String>,
 }
 
-impl Outcomes {
+impl<F> Default for Outcomes<F> {
+    fn default() -> Self {
+        Outcomes {
+            valid: Vec::new(),
+            invalid: Vec::new(),
+            ignored: Vec::new(),
+            unknown_category: Vec::new(),
+            unchecked: Vec::new(),
+            suppressed: Vec::new(),
+            unverifiable: Vec::new(),
+            expected_failures: Vec::new(),
+            durations: HashMap::new(),
+            candidates: HashMap::new(),
+            attempts: HashMap::new(),
+            suggestions: Vec::new(),
+            lints: Vec::new(),
+            sources: HashMap::new(),
+        }
+    }
+}
+
+impl<F: Eq + Hash> Outcomes<F> {
     /// Create an empty set of [`Outcomes`].
     pub fn empty() -> Self { Outcomes::default() }
 
     /// Merge two [`Outcomes`].
-    pub fn merge(&mut self, other: Outcomes) {
+    pub fn merge(&mut self, other: Outcomes<F>) {
         self.valid.extend(other.valid);
         self.invalid.extend(other.invalid);
         self.ignored.extend(other.ignored);
         self.unknown_category.extend(other.unknown_category);
+        self.unchecked.extend(other.unchecked);
+        self.suppressed.extend(other.suppressed);
+        self.unverifiable.extend(other.unverifiable);
+        self.expected_failures.extend(other.expected_failures);
+        self.durations.extend(other.durations);
+        self.candidates.extend(other.candidates);
+        self.attempts.extend(other.attempts);
+        self.suggestions.extend(other.suggestions);
+        self.lints.extend(other.lints);
+        self.sources.extend(other.sources);
+    }
+
+    /// Which run each [`Link`] came from (see [`Outcomes::merge_from()`]).
+    pub fn source(&self, link: &Link<F>) -> Option<&str> {
+        self.sources.get(link).map(String::as_str)
     }
 }
 
-impl Extend<Outcome> for Outcomes {
-    fn extend<T: IntoIterator<Item = Outcome>>(&mut self, items: T) {
+impl<F: Clone + Eq + Hash> Outcomes<F> {
+    /// Merge [`Outcomes`] produced by another run (e.g. a CI job or shard),
+    /// remembering that `source` is where its links came from.
+    ///
+    /// Links which already have a source (because `other` was itself merged
+    /// from several runs) keep it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use linkcheck::{validation::Outcomes, Link};
+    ///
+    /// let link = Link::new("./README.md", Default::default(), ());
+    /// let shard = Outcomes {
+    ///     valid: vec![link.clone()],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut outcomes = Outcomes::empty();
+    /// outcomes.merge_from("docs-shard-2", shard);
+    ///
+    /// assert_eq!(outcomes.source(&link), Some("docs-shard-2"));
+    /// ```
+    pub fn merge_from<S>(&mut self, source: S, mut other: Outcomes<F>)
+    where
+        S: Into<String>,
+    {
+        let source = source.into();
+        let untagged: Vec<_> = other
+            .links()
+            .filter(|link| !other.sources.contains_key(link))
+            .cloned()
+            .collect();
+        for link in untagged {
+            other.sources.insert(link, source.clone());
+        }
+
+        self.merge(other);
+    }
+
+    fn links(&self) -> impl Iterator<Item = &Link<F>> + '_ {
+        let invalid = self
+            .invalid
+            .iter()
+            .chain(&self.suppressed)
+            .chain(&self.unverifiable)
+            .chain(&self.expected_failures)
+            .map(|invalid| &invalid.link);
+
+        self.valid
+            .iter()
+            .chain(&self.ignored)
+            .chain(&self.unknown_category)
+            .chain(&self.unchecked)
+            .chain(invalid)
+    }
+}
+
+impl Outcomes {
+    /// Get a serializable [`Snapshot`] of these [`Outcomes`].
+    pub fn snapshot<S>(&self, files: &Files<S>) -> Snapshot
+    where
+        S: AsRef<str>,
+    {
+        Snapshot::new(self, files)
+    }
+
+    /// Compare these [`Outcomes`] with a [`Snapshot`] from a previous run,
+    /// finding which links are newly broken, newly fixed, or still broken.
+    ///
+    /// This makes it possible to only fail CI when a change introduces
+    /// regressions, using a previous [`Outcomes::snapshot()`] as a baseline.
+    pub fn diff<'a, S>(
+        &'a self,
+        previous: &'a Snapshot,
+        files: &Files<S>,
+    ) -> Diff<'a>
+    where
+        S: AsRef<str>,
+    {
+        Diff::new(self, previous, files)
+    }
+}
+
+impl<F: Clone + Eq + Hash> Extend<Outcome<F>> for Outcomes<F> {
+    fn extend<T: IntoIterator<Item = Outcome<F>>>(&mut self, items: T) {
         for outcome in items {
             match outcome {
                 Outcome::Valid(v) => self.valid.push(v),
                 Outcome::Invalid(i) => self.invalid.push(i),
                 Outcome::Ignored(i) => self.ignored.push(i),
                 Outcome::UnknownCategory(u) => self.unknown_category.push(u),
+                Outcome::Unchecked(u) => self.unchecked.push(u),
+                Outcome::Suppressed(s) => self.suppressed.push(s),
+                Outcome::Unverifiable(u) => self.unverifiable.push(u),
+                Outcome::ExpectedFailure(e) => self.expected_failures.push(e),
+                Outcome::Timed(outcome, duration) => {
+                    self.durations.insert(outcome.link().clone(), duration);
+                    self.extend(Some(*outcome));
+                },
+                Outcome::Suggested(outcome, suggestion) => {
+                    self.suggestions.push(suggestion);
+                    self.extend(Some(*outcome));
+                },
+                Outcome::Candidates(outcome, candidates) => {
+                    self.candidates.insert(outcome.link().clone(), candidates);
+                    self.extend(Some(*outcome));
+                },
+                Outcome::Attempted(outcome, attempts) => {
+                    self.attempts.insert(outcome.link().clone(), attempts);
+                    self.extend(Some(*outcome));
+                },
+                Outcome::Linted(outcome, lint) => {
+                    self.lints.push(lint);
+                    self.extend(Some(*outcome));
+                },
             }
         }
     }
 }
 
-impl Extend<Outcomes> for Outcomes {
-    fn extend<T: IntoIterator<Item = Outcomes>>(&mut self, items: T) {
+impl<F: Eq + Hash> Extend<Outcomes<F>> for Outcomes<F> {
+    fn extend<T: IntoIterator<Item = Outcomes<F>>>(&mut self, items: T) {
         for item in items {
             self.merge(item);
         }
@@ -159,23 +1514,32 @@ impl Extend<Outcomes> for Outcomes {
 
 /// A [`Link`] and the [`Reason`] why it is invalid.
 #[derive(Debug)]
-pub struct InvalidLink {
+pub struct InvalidLink<F = FileId> {
     /// The invalid link.
-    pub link: Link,
+    pub link: Link<F>,
     /// Why is this link invalid?
     pub reason: Reason,
 }
 
 #[derive(Debug)]
-enum Outcome {
-    Valid(Link),
-    Invalid(InvalidLink),
-    Ignored(Link),
-    UnknownCategory(Link),
+pub(crate) enum Outcome<F = FileId> {
+    Valid(Link<F>),
+    Invalid(InvalidLink<F>),
+    Ignored(Link<F>),
+    UnknownCategory(Link<F>),
+    Unchecked(Link<F>),
+    Suppressed(InvalidLink<F>),
+    Unverifiable(InvalidLink<F>),
+    ExpectedFailure(InvalidLink<F>),
+    Timed(Box<Outcome<F>>, Duration),
+    Suggested(Box<Outcome<F>>, Suggestion<F>),
+    Candidates(Box<Outcome<F>>, Vec<PathBuf>),
+    Attempted(Box<Outcome<F>>, Vec<Attempt>),
+    Linted(Box<Outcome<F>>, Lint<F>),
 }
 
-impl Outcome {
-    fn from_result<T, E>(link: Link, result: Result<T, E>) -> Self
+impl<F> Outcome<F> {
+    fn from_result<T, E>(link: Link<F>, result: Result<T, E>) -> Self
     where
         E: Into<Reason>,
     {
@@ -187,4 +1551,851 @@ impl Outcome {
             }),
         }
     }
+
+    fn timed(self, duration: Duration) -> Self {
+        Outcome::Timed(Box::new(self), duration)
+    }
+
+    fn candidates(self, candidates: Vec<PathBuf>) -> Self {
+        if candidates.is_empty() {
+            self
+        } else {
+            Outcome::Candidates(Box::new(self), candidates)
+        }
+    }
+
+    fn attempted(self, attempts: Vec<Attempt>) -> Self {
+        if attempts.is_empty() {
+            self
+        } else {
+            Outcome::Attempted(Box::new(self), attempts)
+        }
+    }
+
+    fn lint(self, kind: LintKind) -> Self
+    where
+        F: Clone,
+    {
+        let lint = Lint {
+            link: self.link().clone(),
+            kind,
+        };
+        Outcome::Linted(Box::new(self), lint)
+    }
+
+    fn suggest(self, replacement: Option<String>, kind: SuggestionKind) -> Self
+    where
+        F: Clone,
+    {
+        match replacement {
+            Some(replacement) => {
+                let suggestion = Suggestion {
+                    link: self.link().clone(),
+                    replacement,
+                    kind,
+                };
+                Outcome::Suggested(Box::new(self), suggestion)
+            },
+            None => self,
+        }
+    }
+
+    /// Move links which failed because we couldn't tell whether they work
+    /// into [`Outcomes::unverifiable`].
+    fn mark_unverifiable<C>(self, ctx: &C) -> Self
+    where
+        C: Context + ?Sized,
+    {
+        match self {
+            Outcome::Invalid(invalid) if invalid.reason.is_unverifiable() => {
+                log::warn!(
+                    "Unable to check \"{}\": {}",
+                    redact::redacted(&invalid.link.href, ctx),
+                    invalid.reason
+                );
+                Outcome::Unverifiable(invalid)
+            },
+            Outcome::Timed(outcome, duration) => Outcome::Timed(
+                Box::new(outcome.mark_unverifiable(ctx)),
+                duration,
+            ),
+            Outcome::Suggested(outcome, suggestion) => Outcome::Suggested(
+                Box::new(outcome.mark_unverifiable(ctx)),
+                suggestion,
+            ),
+            Outcome::Candidates(outcome, candidates) => Outcome::Candidates(
+                Box::new(outcome.mark_unverifiable(ctx)),
+                candidates,
+            ),
+            Outcome::Attempted(outcome, attempts) => Outcome::Attempted(
+                Box::new(outcome.mark_unverifiable(ctx)),
+                attempts,
+            ),
+            Outcome::Linted(outcome, lint) => {
+                Outcome::Linted(Box::new(outcome.mark_unverifiable(ctx)), lint)
+            },
+            other => other,
+        }
+    }
+
+    /// Accept a broken link if it is in the [`Context::known_broken()`]
+    /// list.
+    fn suppress_known_broken<C>(self, ctx: &C) -> Self
+    where
+        C: Context + ?Sized,
+    {
+        let known_broken = match ctx.known_broken() {
+            Some(known_broken) => known_broken,
+            None => return self,
+        };
+
+        match self {
+            Outcome::Invalid(invalid) => {
+                let href = redact::redacted(&invalid.link.href, ctx);
+
+                match known_broken.find(&invalid.link.href) {
+                    Some(entry) if entry.is_expired(SystemTime::now()) => {
+                        log::warn!(
+                            "\"{}\" is still broken and \"{}\" has expired",
+                            href,
+                            entry.pattern()
+                        );
+                        Outcome::Invalid(invalid)
+                    },
+                    Some(_) => {
+                        log::debug!("\"{}\" is known to be broken", href);
+                        Outcome::Suppressed(invalid)
+                    },
+                    None => Outcome::Invalid(invalid),
+                }
+            },
+            Outcome::Timed(outcome, duration) => Outcome::Timed(
+                Box::new(outcome.suppress_known_broken(ctx)),
+                duration,
+            ),
+            Outcome::Suggested(outcome, suggestion) => Outcome::Suggested(
+                Box::new(outcome.suppress_known_broken(ctx)),
+                suggestion,
+            ),
+            Outcome::Candidates(outcome, candidates) => Outcome::Candidates(
+                Box::new(outcome.suppress_known_broken(ctx)),
+                candidates,
+            ),
+            Outcome::Attempted(outcome, attempts) => Outcome::Attempted(
+                Box::new(outcome.suppress_known_broken(ctx)),
+                attempts,
+            ),
+            Outcome::Linted(outcome, lint) => Outcome::Linted(
+                Box::new(outcome.suppress_known_broken(ctx)),
+                lint,
+            ),
+            other => other,
+        }
+    }
+
+    /// Swap broken and working links around if they are supposed to be
+    /// broken (see [`Context::expected_failures()`]).
+    fn expect_failures<C>(self, ctx: &C) -> Self
+    where
+        C: Context + ?Sized,
+    {
+        let expected = ctx
+            .expected_failures()
+            .is_some_and(|expected| expected.expects_failure(self.link()));
+        if !expected {
+            return self;
+        }
+
+        match self {
+            Outcome::Invalid(invalid) => {
+                log::debug!(
+                    "\"{}\" is broken, as expected",
+                    redact::redacted(&invalid.link.href, ctx)
+                );
+                Outcome::ExpectedFailure(invalid)
+            },
+            Outcome::Valid(link) => Outcome::Invalid(InvalidLink {
+                link,
+                reason: Reason::UnexpectedlyValid,
+            }),
+            Outcome::Timed(outcome, duration) => {
+                Outcome::Timed(Box::new(outcome.expect_failures(ctx)), duration)
+            },
+            Outcome::Suggested(outcome, suggestion) => Outcome::Suggested(
+                Box::new(outcome.expect_failures(ctx)),
+                suggestion,
+            ),
+            Outcome::Candidates(outcome, candidates) => Outcome::Candidates(
+                Box::new(outcome.expect_failures(ctx)),
+                candidates,
+            ),
+            Outcome::Attempted(outcome, attempts) => Outcome::Attempted(
+                Box::new(outcome.expect_failures(ctx)),
+                attempts,
+            ),
+            Outcome::Linted(outcome, lint) => {
+                Outcome::Linted(Box::new(outcome.expect_failures(ctx)), lint)
+            },
+            other => other,
+        }
+    }
+
+    fn link(&self) -> &Link<F> {
+        match self {
+            Outcome::Valid(link)
+            | Outcome::Ignored(link)
+            | Outcome::UnknownCategory(link)
+            | Outcome::Unchecked(link) => link,
+            Outcome::Invalid(invalid)
+            | Outcome::Suppressed(invalid)
+            | Outcome::Unverifiable(invalid)
+            | Outcome::ExpectedFailure(invalid) => &invalid.link,
+            Outcome::Timed(outcome, _)
+            | Outcome::Suggested(outcome, _)
+            | Outcome::Candidates(outcome, _)
+            | Outcome::Attempted(outcome, _)
+            | Outcome::Linted(outcome, _) => outcome.link(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{Request, Response, Server};
+    use codespan::Files;
+    use std::time::SystemTime;
+
+    #[tokio::test]
+    async fn only_use_the_cache_once_we_run_out_of_time() {
+        let server = Server::start(vec![
+            ("/new", Response::ok("")),
+            ("/old", Response::ok("")),
+        ]);
+        let new = server.url("/new").to_string();
+        let old = server.url("/old").to_string();
+        let file = Files::<&str>::new().add("index.md", "");
+        let links = vec![
+            Link::new(new.clone(), Default::default(), file),
+            Link::new(old.clone(), Default::default(), file),
+        ];
+        let mut ctx = BasicContext::default();
+        ctx.deadline = Some(Instant::now());
+        ctx.cache().unwrap().insert(
+            server.url("/old"),
+            CacheEntry::new(SystemTime::now(), true),
+        );
+
+        let outcomes = validate(Path::new("."), links, &ctx).await;
+
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.valid[0].href, old);
+        assert_eq!(outcomes.unchecked.len(), 1);
+        assert_eq!(outcomes.unchecked[0].href, new);
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn links_can_use_their_own_file_ids() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let links = vec![
+            Link::new("Cargo.toml", Default::default(), "index.md"),
+            Link::new("missing.md", Default::default(), "other.md"),
+            Link::new("javascript:void(0)", Default::default(), "index.md"),
+        ];
+
+        let outcomes = validate(dir, links, &BasicContext::default()).await;
+
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.valid[0].file, "index.md");
+        assert_eq!(outcomes.invalid.len(), 2);
+        assert!(outcomes.invalid.iter().any(|i| i.link.file == "other.md"));
+    }
+
+    #[tokio::test]
+    async fn known_broken_links_are_suppressed_until_they_expire() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let file = Files::<&str>::new().add("index.md", "");
+        let links = vec![
+            Link::new("snoozed.md", Default::default(), file),
+            Link::new("expired.md", Default::default(), file),
+        ];
+        let mut ctx = BasicContext::default();
+        let yesterday = SystemTime::now() - Duration::from_secs(24 * 60 * 60);
+        ctx.known_broken = Some(
+            KnownBroken::new()
+                .add("snoozed.*", None)
+                .unwrap()
+                .add("expired.*", Some(yesterday))
+                .unwrap(),
+        );
+
+        let outcomes = validate(dir, links, &ctx).await;
+
+        assert_eq!(outcomes.suppressed.len(), 1);
+        assert_eq!(outcomes.suppressed[0].link.href, "snoozed.md");
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert_eq!(outcomes.invalid[0].link.href, "expired.md");
+    }
+
+    #[tokio::test]
+    async fn safety_limits_cap_links_and_redirects() {
+        let server = Server::start(vec![
+            ("/redirect", Response::redirect("/page")),
+            ("/page", Response::ok("")),
+        ]);
+        let mut files = Files::<&str>::new();
+        let first = files.add("first.md", "");
+        let second = files.add("second.md", "");
+        let redirect = server.url("/redirect").to_string();
+        let page = server.url("/page").to_string();
+        let links = vec![
+            Link::new(redirect, Default::default(), first),
+            Link::new(page.clone(), Default::default(), first),
+            Link::new(page, Default::default(), second),
+        ];
+        let limits = SafetyLimits::new()
+            .set_max_links_per_document(Some(1))
+            .set_max_redirects(Some(0));
+        let ctx = BasicContext::with_safety_limits(limits);
+
+        let outcomes = validate(Path::new("."), links, &ctx).await;
+
+        assert_eq!(outcomes.ignored.len(), 1);
+        assert_eq!(outcomes.ignored[0].file, first);
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert!(outcomes.invalid[0].link.href.ends_with("/redirect"));
+    }
+
+    #[tokio::test]
+    async fn equivalent_urls_are_only_requested_once() {
+        let server = Server::start(vec![("/page", Response::ok(""))]);
+        let url = server.url("/page");
+        let file = Files::<&str>::new().add("index.md", "");
+        let hrefs = [
+            url.to_string(),
+            format!("{}#section", url),
+            url.to_string().replace("/page", "/docs/../page"),
+        ];
+        let links: Vec<_> = hrefs
+            .iter()
+            .map(|href| Link::new(href.as_str(), Default::default(), file))
+            .collect();
+
+        let outcomes =
+            validate(Path::new("."), links, &BasicContext::default()).await;
+
+        assert_eq!(outcomes.valid.len(), 3);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn repeated_urls_are_only_requested_once() {
+        let server = Server::start(vec![("/page", Response::ok(""))]);
+        let url = server.url("/page");
+        let mut files = Files::<&str>::new();
+        let links: Vec<_> = ["index.md", "guide.md", "faq.md", "index.md"]
+            .iter()
+            .map(|name| files.add(*name, ""))
+            .map(|file| Link::new(url.as_str(), Default::default(), file))
+            .collect();
+
+        let outcomes =
+            validate(Path::new("."), links, &BasicContext::default()).await;
+
+        assert_eq!(outcomes.valid.len(), 4);
+        assert!(outcomes.invalid.is_empty());
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/page");
+    }
+
+    #[tokio::test]
+    async fn opt_into_treating_trailing_slashes_as_the_same_page() {
+        let server = Server::start(vec![
+            ("/docs", Response::ok("")),
+            ("/docs/", Response::ok("")),
+        ]);
+        let mut files = Files::<&str>::new();
+        let file = files.add("index.md", "");
+        let links: Vec<_> = ["/docs", "/docs/"]
+            .iter()
+            .map(|path| server.url(path))
+            .map(|url| Link::new(url.as_str(), Default::default(), file))
+            .collect();
+        let mut ctx = BasicContext::default();
+        ctx.trailing_slash = TrailingSlash::Remove;
+
+        let outcomes = validate(Path::new("."), links, &ctx).await;
+
+        assert_eq!(outcomes.valid.len(), 2);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn protocol_relative_urls_use_the_default_scheme() {
+        let ctx = BasicContext::default();
+
+        let got = categorise("//example.com/page#top", &ctx);
+
+        let url = url::Url::parse("https://example.com/page#top").unwrap();
+        assert_eq!(got, Some(Category::Url(url)));
+    }
+
+    #[test]
+    fn merged_outcomes_remember_where_links_came_from() {
+        let mut files = Files::<&str>::new();
+        let file = files.add("index.md", "");
+        let link = |href: &str| Link::new(href, Default::default(), file);
+        let mut first_job = Outcomes::empty();
+        first_job.merge_from(
+            "shard-1",
+            Outcomes {
+                valid: vec![link("a.md")],
+                ..Default::default()
+            },
+        );
+        first_job.merge(Outcomes {
+            valid: vec![link("b.md")],
+            ..Default::default()
+        });
+        let second_job = Outcomes {
+            invalid: vec![InvalidLink {
+                link: link("c.md"),
+                reason: Reason::EmptyLink,
+            }],
+            ..Default::default()
+        };
+
+        let mut outcomes = Outcomes::empty();
+        outcomes.merge_from("job-1", first_job);
+        outcomes.merge_from("job-2", second_job);
+
+        assert_eq!(outcomes.source(&link("a.md")), Some("shard-1"));
+        assert_eq!(outcomes.source(&link("b.md")), Some("job-1"));
+        let snapshot = outcomes.snapshot(&files);
+        let invalid: Vec<_> = snapshot.invalid().collect();
+        assert_eq!(invalid[0].source.as_deref(), Some("job-2"));
+    }
+
+    #[tokio::test]
+    async fn custom_validators_can_return_their_own_errors() {
+        #[derive(Debug, thiserror::Error)]
+        #[error("Unknown ISBN {0}")]
+        struct UnknownIsbn(String);
+
+        struct Isbns(BasicContext);
+
+        impl Context for Isbns {
+            fn client(&self) -> &reqwest::Client { self.0.client() }
+
+            fn filesystem_options(&self) -> &Options {
+                self.0.filesystem_options()
+            }
+
+            fn check_opaque_uri(
+                &self,
+                uri: &url::Url,
+            ) -> Option<Result<(), Reason>> {
+                let isbn = uri.path().strip_prefix("isbn:")?;
+                Some(Err(Reason::custom(UnknownIsbn(isbn.to_string()))))
+            }
+        }
+
+        let links = vec![Link::new("urn:isbn:123", Default::default(), ())];
+        let ctx = Isbns(BasicContext::default());
+
+        let outcomes = validate(Path::new("."), links, &ctx).await;
+
+        let reason = &outcomes.invalid[0].reason;
+        assert!(reason.is_custom());
+        assert_eq!(reason.to_string(), "Unknown ISBN 123");
+        let isbn = reason.downcast_ref::<UnknownIsbn>().unwrap();
+        assert_eq!(isbn.0, "123");
+        assert!(reason.downcast_ref::<std::io::Error>().is_none());
+    }
+
+    #[tokio::test]
+    async fn links_can_be_broken_on_purpose() {
+        let server = Server::start(vec![("/", Response::ok(""))]);
+        let gone = server.url("/gone").to_string();
+        let marked = |href: &str| {
+            let mut link = Link::new(href, Default::default(), ());
+            link.title = Some(String::from("linkcheck:expect-failure"));
+            link
+        };
+        let links = vec![
+            Link::new(gone.as_str(), Default::default(), ()),
+            marked("missing.md"),
+            marked("Cargo.toml"),
+            Link::new("missing.md", Default::default(), ()),
+        ];
+        let mut ctx = BasicContext::default();
+        ctx.expected_failures =
+            Some(ExpectedFailures::new().add_domain("127.0.0.1"));
+
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let outcomes = validate(dir, links, &ctx).await;
+
+        let mut expected: Vec<_> = outcomes
+            .expected_failures
+            .iter()
+            .map(|expected| expected.link.href.as_str())
+            .collect();
+        expected.sort();
+        assert_eq!(expected, vec![gone.as_str(), "missing.md"]);
+        assert_eq!(outcomes.invalid.len(), 2);
+        let unexpected = outcomes
+            .invalid
+            .iter()
+            .find(|invalid| invalid.link.href == "Cargo.toml")
+            .unwrap();
+        assert!(matches!(unexpected.reason, Reason::UnexpectedlyValid));
+    }
+
+    #[tokio::test]
+    async fn contexts_can_categorise_links_themselves() {
+        struct DocsScheme(BasicContext);
+
+        impl Context for DocsScheme {
+            fn client(&self) -> &reqwest::Client { self.0.client() }
+
+            fn filesystem_options(&self) -> &Options {
+                self.0.filesystem_options()
+            }
+
+            fn categorise(&self, href: &str) -> Option<Category> {
+                match href.strip_prefix("docs:") {
+                    Some(path) => Category::categorise(path),
+                    None => Category::categorise(href),
+                }
+            }
+        }
+
+        let file = Files::<&str>::new().add("index.md", "");
+        let links = vec![
+            Link::new("docs:Cargo.toml", Default::default(), file),
+            Link::new("docs:missing.md", Default::default(), file),
+        ];
+        let ctx = DocsScheme(BasicContext::default());
+
+        let outcomes =
+            validate(Path::new(env!("CARGO_MANIFEST_DIR")), links, &ctx).await;
+
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.valid[0].href, "docs:Cargo.toml");
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert!(outcomes.invalid[0].reason.file_not_found());
+    }
+
+    #[tokio::test]
+    async fn look_for_files_which_were_moved() {
+        let temp = tempfile::tempdir().unwrap();
+        let book = temp.path().join("book");
+        std::fs::create_dir_all(book.join("chapters")).unwrap();
+        std::fs::write(book.join("chapters").join("setup.md"), "").unwrap();
+        let links = vec![
+            Link::new("setup.md", Default::default(), "index.md"),
+            Link::new("nowhere.md", Default::default(), "index.md"),
+        ];
+        let mut ctx = BasicContext::default();
+        ctx.options = Options::default().with_root_directory(&book).unwrap();
+
+        let outcomes = validate(&book, links, &ctx).await;
+
+        assert_eq!(outcomes.invalid.len(), 2);
+        let setup = Link::new("setup.md", Default::default(), "index.md");
+        assert_eq!(
+            outcomes.candidates[&setup],
+            vec![book.join("chapters").join("setup.md")]
+        );
+        assert_eq!(outcomes.candidates.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn only_look_for_moved_files_under_the_root_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let book = temp.path().join("book");
+        std::fs::create_dir_all(book.join("chapters")).unwrap();
+        std::fs::create_dir_all(temp.path().join("elsewhere")).unwrap();
+        std::fs::write(book.join("chapters").join("setup.md"), "").unwrap();
+        std::fs::write(temp.path().join("elsewhere").join("setup.md"), "")
+            .unwrap();
+        let link = Link::new(
+            "../../../nowhere/setup.md",
+            Default::default(),
+            "index.md",
+        );
+        let mut ctx = BasicContext::default();
+        ctx.options = Options::default()
+            .with_root_directory(&book)
+            .unwrap()
+            .set_links_may_traverse_the_root_directory(true);
+
+        let outcomes = validate(&book, vec![link.clone()], &ctx).await;
+
+        assert_eq!(outcomes.invalid.len(), 1);
+        let root = ctx.options.root_directory().unwrap();
+        assert_eq!(
+            outcomes.candidates[&link],
+            vec![root.join("chapters").join("setup.md")]
+        );
+
+        let outcomes =
+            validate(&book, vec![link], &BasicContext::default()).await;
+
+        assert_eq!(outcomes.invalid.len(), 1);
+        assert!(outcomes.candidates.is_empty());
+    }
+
+    #[tokio::test]
+    async fn suggest_percent_encoding_links_with_spaces() {
+        let server = Server::start(vec![("/my%20page", Response::ok(""))]);
+        let href = format!("{}my page", server.url("/"));
+        let links = vec![Link::new(href, Default::default(), "index.md")];
+
+        let outcomes =
+            validate(Path::new("."), links, &BasicContext::default()).await;
+
+        assert_eq!(outcomes.invalid.len(), 1);
+        let suggestion = &outcomes.suggestions[0];
+        assert_eq!(suggestion.replacement, server.url("/my%20page").as_str());
+        assert_eq!(suggestion.kind, SuggestionKind::PercentEncoding);
+    }
+
+    #[tokio::test]
+    async fn strip_credentials_from_links_unless_told_otherwise() {
+        let server = Server::start(vec![("/private", Response::ok(""))]);
+        let mut url = server.url("/private");
+        url.set_username("user").unwrap();
+        url.set_password(Some("hunter2")).unwrap();
+        let links = || vec![Link::new(url.as_str(), Default::default(), ())];
+        let authorization = |request: &Request| {
+            request
+                .headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+        };
+        let mut ctx = BasicContext::default();
+
+        let outcomes = validate(Path::new("."), links(), &ctx).await;
+        ctx.send_credentials = true;
+        validate(Path::new("."), links(), &ctx).await;
+
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(outcomes.lints[0].kind, LintKind::EmbeddedCredentials);
+        let requests = server.requests();
+        assert!(!authorization(&requests[0]));
+        assert!(authorization(&requests[1]));
+    }
+
+    #[tokio::test]
+    async fn empty_links_are_broken() {
+        let hrefs = ["", " \t", "#"];
+        let links: Vec<_> = hrefs
+            .iter()
+            .map(|href| Link::new(*href, Default::default(), ()))
+            .collect();
+
+        let outcomes =
+            validate(Path::new("."), links, &BasicContext::default()).await;
+
+        assert!(outcomes.valid.is_empty());
+        assert!(outcomes.unknown_category.is_empty());
+        let mut got: Vec<_> = outcomes
+            .invalid
+            .iter()
+            .map(|invalid| invalid.link.href.as_str())
+            .collect();
+        got.sort();
+        assert_eq!(got, hrefs);
+        assert!(outcomes
+            .invalid
+            .iter()
+            .all(|invalid| matches!(invalid.reason, Reason::EmptyLink)));
+    }
+
+    #[tokio::test]
+    async fn warn_about_https_links_which_redirect_to_http() {
+        let insecure = Server::start(vec![("/page", Response::ok(""))]);
+        let target = insecure.url("/page");
+        let secure = Server::start_tls(vec![(
+            "/page",
+            Response::redirect(target.as_str()),
+        )]);
+        let url = secure.url("/page");
+        let links = vec![Link::new(url.as_str(), Default::default(), ())];
+        let client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let ctx = BasicContext::with_client(client);
+
+        let outcomes = validate(Path::new("."), links, &ctx).await;
+
+        assert_eq!(url.scheme(), "https");
+        assert_eq!(outcomes.valid.len(), 1);
+        assert_eq!(
+            outcomes.lints[0].kind,
+            LintKind::ProtocolDowngrade(target.to_string())
+        );
+        // switching the link to HTTP isn't a fix
+        assert!(outcomes.suggestions.is_empty());
+        assert_eq!(secure.requests().len(), 1);
+        assert_eq!(insecure.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cached_https_links_which_redirect_to_http_still_get_the_lint() {
+        let insecure = Server::start(vec![("/page", Response::ok(""))]);
+        let target = insecure.url("/page");
+        let secure = Server::start_tls(vec![(
+            "/page",
+            Response::redirect(target.as_str()),
+        )]);
+        let url = secure.url("/page");
+        let links = || {
+            vec![
+                Link::new(url.as_str(), Default::default(), "index.md"),
+                Link::new(url.as_str(), Default::default(), "other.md"),
+            ]
+        };
+        let client = || {
+            reqwest::Client::builder()
+                .danger_accept_invalid_certs(true)
+                .build()
+                .unwrap()
+        };
+        let ctx = BasicContext::with_client(client());
+        let downgrade = LintKind::ProtocolDowngrade(target.to_string());
+
+        let outcomes = validate(Path::new("."), links(), &ctx).await;
+
+        assert_eq!(outcomes.valid.len(), 2);
+        assert_eq!(outcomes.lints.len(), 2);
+        assert!(outcomes.lints.iter().all(|lint| lint.kind == downgrade));
+        assert_eq!(secure.requests().len(), 1);
+
+        // a later run which reuses the cache
+        let next_run = BasicContext::with_client(client());
+        *next_run.cache().unwrap() = ctx.cache().unwrap().clone();
+
+        let outcomes = validate(Path::new("."), links(), &next_run).await;
+
+        assert_eq!(outcomes.lints.len(), 2);
+        assert!(outcomes.lints.iter().all(|lint| lint.kind == downgrade));
+        assert!(outcomes.suggestions.is_empty());
+        assert_eq!(secure.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn opaque_uris_are_checked_according_to_their_scheme() {
+        struct Dids(BasicContext);
+
+        impl Context for Dids {
+            fn client(&self) -> &reqwest::Client { self.0.client() }
+
+            fn filesystem_options(&self) -> &Options {
+                self.0.filesystem_options()
+            }
+
+            fn check_opaque_uri(
+                &self,
+                uri: &url::Url,
+            ) -> Option<Result<(), Reason>> {
+                match uri.scheme() {
+                    "did" if uri.path().starts_with("example:") => Some(Ok(())),
+                    "did" => Some(Err(Reason::custom(std::fmt::Error))),
+                    _ => None,
+                }
+            }
+        }
+
+        let ctx = Dids(BasicContext::default());
+        let inputs = vec![
+            ("mailto:someone@example.com", "valid"),
+            ("mailto:not an address", "invalid"),
+            ("tel:+1-555-0100", "valid"),
+            ("tel:call-me", "invalid"),
+            ("javascript:void(0)", "invalid"),
+            ("did:example:123", "valid"),
+            ("did:unknown:123", "invalid"),
+            ("data:text/plain,hello", "unknown"),
+            ("urn:isbn:0451450523", "unknown"),
+        ];
+
+        for (href, should_be) in inputs {
+            let links = vec![Link::new(href, Default::default(), ())];
+
+            let outcomes = validate(Path::new("."), links, &ctx).await;
+
+            let got = if !outcomes.valid.is_empty() {
+                "valid"
+            } else if !outcomes.invalid.is_empty() {
+                "invalid"
+            } else if !outcomes.unknown_category.is_empty() {
+                "unknown"
+            } else {
+                panic!("{} wasn't checked: {:?}", href, outcomes);
+            };
+            assert_eq!(got, should_be, "{}", href);
+
+            if let Some(invalid) = outcomes.invalid.first() {
+                let reason = &invalid.reason;
+                match href.split(':').next().unwrap() {
+                    "mailto" => assert!(
+                        matches!(reason, Reason::InvalidEmailAddress(_)),
+                        "{}",
+                        reason
+                    ),
+                    "tel" => assert!(
+                        matches!(reason, Reason::InvalidPhoneNumber(_)),
+                        "{}",
+                        reason
+                    ),
+                    "javascript" => assert!(
+                        matches!(reason, Reason::JavaScript),
+                        "{}",
+                        reason
+                    ),
+                    _ => assert!(reason.is_custom(), "{}", reason),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn unverifiable_links_survive_being_merged() {
+        let server = Server::start(vec![
+            ("/ok", Response::ok("")),
+            (
+                "/blocked",
+                Response::status(403).with_header("cf-mitigated", "challenge"),
+            ),
+        ]);
+        let blocked = server.url("/blocked").to_string();
+        let links = vec![
+            Link::new(blocked.as_str(), Default::default(), ()),
+            Link::new(server.url("/ok").as_str(), Default::default(), ()),
+        ];
+
+        let got =
+            validate(Path::new("."), links, &BasicContext::default()).await;
+
+        assert!(got.invalid.is_empty());
+        assert_eq!(got.valid.len(), 1);
+        assert_eq!(got.unverifiable.len(), 1);
+        assert_eq!(got.unverifiable[0].link.href, blocked);
+        assert!(got.unverifiable[0].reason.blocked_by_server());
+
+        let mut outcomes = Outcomes::empty();
+        outcomes.merge_from("job-1", got);
+
+        assert!(outcomes.invalid.is_empty());
+        assert_eq!(outcomes.unverifiable.len(), 1);
+        let link = &outcomes.unverifiable[0].link;
+        assert_eq!(link.href, blocked);
+        assert_eq!(outcomes.source(link), Some("job-1"));
+    }
 }
@@ -0,0 +1,141 @@
+use url::Url;
+
+/// What to do with the trailing slash on a URL's path when normalising it
+/// (see [`normalise_url()`]).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrailingSlash {
+    /// Leave the path alone, so `/docs` and `/docs/` are different pages.
+    #[default]
+    Keep,
+    /// Make sure every path ends with a `/`.
+    Add,
+    /// Remove the trailing `/` from every path (except the root, `/`).
+    Remove,
+}
+
+/// Convert a [`Url`] to a canonical form, so equivalent URLs share a single
+/// check and [`crate::validation::Cache`] entry.
+///
+/// Parsing a [`Url`] already lowercases the scheme and host, strips the
+/// scheme's default port, and resolves `.` and `..` segments. On top of that,
+/// the fragment is removed (it is never sent to the server) and the
+/// `trailing_slash` policy is applied.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::{normalise_url, TrailingSlash};
+/// use url::Url;
+///
+/// let url = Url::parse("HTTPS://Example.com:443/a/../b#heading").unwrap();
+///
+/// let normalised = normalise_url(&url, TrailingSlash::Keep);
+///
+/// assert_eq!(normalised.as_str(), "https://example.com/b");
+/// ```
+pub fn normalise_url(url: &Url, trailing_slash: TrailingSlash) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+
+    if url.cannot_be_a_base() {
+        return url;
+    }
+
+    match trailing_slash {
+        TrailingSlash::Keep => {},
+        TrailingSlash::Add if !url.path().ends_with('/') => {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        },
+        TrailingSlash::Remove if url.path().len() > 1 => {
+            let path = url.path().trim_end_matches('/').to_string();
+            url.set_path(if path.is_empty() { "/" } else { &path });
+        },
+        TrailingSlash::Add | TrailingSlash::Remove => {},
+    }
+
+    url
+}
+
+/// Percent-encode any spaces or non-ASCII characters in a web link's path,
+/// query, or fragment, returning `None` when it is already encoded properly.
+///
+/// Non-ASCII host names are left alone because they are valid as-is.
+pub(crate) fn encode_href(href: &str) -> Option<String> {
+    let authority = href.find("//").map(|i| i + 2).unwrap_or(0);
+    let tail = href[authority..]
+        .find(['/', '?', '#'])
+        .map(|i| authority + i)
+        .unwrap_or(href.len());
+    let (head, rest) = href.split_at(tail);
+
+    if !rest.chars().any(|c| c == ' ' || !c.is_ascii()) {
+        return None;
+    }
+
+    let mut encoded = String::from(head);
+    for c in rest.chars() {
+        if c == ' ' || !c.is_ascii() {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+
+    Some(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equivalent_urls_are_normalised_the_same() {
+        let inputs = vec![
+            ("HTTPS://Example.com:443/a/../b", TrailingSlash::Keep),
+            ("https://example.com/b#top", TrailingSlash::Keep),
+            ("https://example.com:443/./b", TrailingSlash::Keep),
+            ("https://example.com/b/", TrailingSlash::Remove),
+            ("https://EXAMPLE.com/b//", TrailingSlash::Remove),
+        ];
+
+        for (src, policy) in inputs {
+            let url = Url::parse(src).unwrap();
+            let got = normalise_url(&url, policy);
+            assert_eq!(got.as_str(), "https://example.com/b", "{}", src);
+        }
+
+        let http = Url::parse("http://example.com:443/b").unwrap();
+        let got = normalise_url(&http, TrailingSlash::Keep);
+        assert_eq!(got.as_str(), "http://example.com:443/b");
+        let url = Url::parse("https://example.com/b?q=1").unwrap();
+        let got = normalise_url(&url, TrailingSlash::Add);
+        assert_eq!(got.as_str(), "https://example.com/b/?q=1");
+        let root = Url::parse("https://example.com/").unwrap();
+        assert_eq!(normalise_url(&root, TrailingSlash::Remove), root);
+    }
+
+    #[test]
+    fn encode_spaces_and_unicode_but_not_the_host() {
+        let inputs = vec![
+            (
+                "https://example.com/my page",
+                Some("https://example.com/my%20page"),
+            ),
+            (
+                "https://bücher.de/straße?q=a b",
+                Some("https://bücher.de/stra%C3%9Fe?q=a%20b"),
+            ),
+            ("https://bücher.de/", None),
+            ("https://example.com/my%20page", None),
+        ];
+
+        for (href, should_be) in inputs {
+            assert_eq!(encode_href(href).as_deref(), should_be, "{}", href);
+        }
+    }
+}
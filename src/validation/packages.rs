@@ -0,0 +1,306 @@
+use crate::validation::Reason;
+use http::StatusCode;
+use reqwest::{Client, Url};
+use std::{
+    fmt::{self, Display, Formatter},
+    time::Instant,
+};
+
+/// Check links to packages on npm, PyPI, and Maven Central by asking the
+/// registry whether the package (and version) exists.
+///
+/// The websites for these registries will often respond with a `200 OK` (or
+/// a search page) for packages which don't exist, so checking the link the
+/// normal way isn't enough.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::{PackageRegistries, Registry};
+/// use url::Url;
+///
+/// let registries = PackageRegistries::new();
+///
+/// let url = Url::parse("https://www.npmjs.com/package/@types/node/v/20.1.0")
+///     .unwrap();
+/// let package = registries.package(&url).unwrap();
+/// assert_eq!(package.registry, Registry::Npm);
+/// assert_eq!(package.to_string(), "@types/node@20.1.0");
+///
+/// let url = Url::parse("https://pypi.org/project/requests/").unwrap();
+/// assert_eq!(registries.package(&url).unwrap().to_string(), "requests");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageRegistries {
+    npm: Url,
+    pypi: Url,
+    maven: Url,
+}
+
+impl PackageRegistries {
+    /// Create a [`PackageRegistries`] which uses the public registries.
+    pub fn new() -> Self { PackageRegistries::default() }
+
+    /// The npm registry's API.
+    pub fn npm(&self) -> &Url { &self.npm }
+
+    /// Use a different npm registry (e.g. a mirror).
+    pub fn set_npm(self, npm: Url) -> Self { PackageRegistries { npm, ..self } }
+
+    /// The base URL for PyPI's JSON API.
+    pub fn pypi(&self) -> &Url { &self.pypi }
+
+    /// Use a different PyPI server (e.g. a mirror).
+    pub fn set_pypi(self, pypi: Url) -> Self {
+        PackageRegistries { pypi, ..self }
+    }
+
+    /// The Maven repository packages are looked up in.
+    pub fn maven(&self) -> &Url { &self.maven }
+
+    /// Use a different Maven repository (e.g. a mirror).
+    pub fn set_maven(self, maven: Url) -> Self {
+        PackageRegistries { maven, ..self }
+    }
+
+    /// Figure out which package a URL points to, if any.
+    pub fn package(&self, url: &Url) -> Option<Package> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let host = host.trim_start_matches("www.");
+        let segments: Vec<_> =
+            url.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+        match host {
+            "npmjs.com" => npm_package(&segments),
+            "pypi.org" => pypi_package(&segments),
+            "central.sonatype.com"
+            | "mvnrepository.com"
+            | "search.maven.org" => maven_package(&segments),
+            _ => None,
+        }
+    }
+
+    /// Ask the registry whether a package exists.
+    pub(crate) async fn check(
+        &self,
+        client: &Client,
+        package: &Package,
+        deadline: Option<Instant>,
+    ) -> Result<(), Reason> {
+        let endpoint = self.endpoint(package).map_err(|_| {
+            Reason::PackageNotFound(package.registry, package.to_string())
+        })?;
+        log::debug!("Looking for {} at \"{}\"", package, endpoint);
+
+        let mut request = client.get(endpoint);
+        if let Some(deadline) = deadline {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            request = request.timeout(timeout);
+        }
+
+        let response = request.send().await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Err(
+                Reason::PackageNotFound(package.registry, package.to_string()),
+            ),
+            StatusCode::TOO_MANY_REQUESTS => {
+                let host = response.url().host_str().unwrap_or_default();
+                Err(Reason::RateLimited(host.to_string()))
+            },
+            _ => {
+                response.error_for_status()?;
+                Ok(())
+            },
+        }
+    }
+
+    fn endpoint(&self, package: &Package) -> Result<Url, url::ParseError> {
+        let version = package.version.as_deref();
+
+        match package.registry {
+            Registry::Npm => {
+                let name = package.name.replace('/', "%2F");
+                match version {
+                    Some(version) => {
+                        self.npm.join(&format!("{}/{}", name, version))
+                    },
+                    None => self.npm.join(&name),
+                }
+            },
+            Registry::PyPI => match version {
+                Some(version) => self
+                    .pypi
+                    .join(&format!("pypi/{}/{}/json", package.name, version)),
+                None => self.pypi.join(&format!("pypi/{}/json", package.name)),
+            },
+            Registry::Maven => {
+                let (group, artifact) = package
+                    .name
+                    .split_once(':')
+                    .unwrap_or((package.name.as_str(), ""));
+                let directory =
+                    format!("{}/{}/", group.replace('.', "/"), artifact);
+
+                match version {
+                    Some(version) => self.maven.join(&format!(
+                        "{}{}/{}-{}.pom",
+                        directory, version, artifact, version
+                    )),
+                    None => self
+                        .maven
+                        .join(&format!("{}maven-metadata.xml", directory)),
+                }
+            },
+        }
+    }
+}
+
+impl Default for PackageRegistries {
+    fn default() -> Self {
+        PackageRegistries {
+            npm: Url::parse("https://registry.npmjs.org/").unwrap(),
+            pypi: Url::parse("https://pypi.org/").unwrap(),
+            maven: Url::parse("https://repo1.maven.org/maven2/").unwrap(),
+        }
+    }
+}
+
+/// A package (and possibly a specific version) on a [`Registry`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Package {
+    /// The registry the package is published to.
+    pub registry: Registry,
+    /// The package's name. Maven packages are named `group:artifact`.
+    pub name: String,
+    /// The version in the link, if there was one.
+    pub version: Option<String>,
+}
+
+impl Display for Package {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let separator = match self.registry {
+            Registry::Npm => "@",
+            Registry::PyPI => "==",
+            Registry::Maven => ":",
+        };
+
+        match &self.version {
+            Some(version) => write!(f, "{}{}{}", self.name, separator, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// The package registries known to [`PackageRegistries`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Registry {
+    /// The npm registry for JavaScript packages.
+    Npm,
+    /// The Python Package Index.
+    PyPI,
+    /// Maven Central, for Java packages.
+    Maven,
+}
+
+impl Display for Registry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Registry::Npm => write!(f, "npm"),
+            Registry::PyPI => write!(f, "PyPI"),
+            Registry::Maven => write!(f, "Maven"),
+        }
+    }
+}
+
+/// `package/name`, `package/@scope/name`, or `package/name/v/1.0.0`.
+fn npm_package(segments: &[&str]) -> Option<Package> {
+    let (name, rest) = match segments {
+        ["package", scope, name, rest @ ..] if scope.starts_with('@') => {
+            (format!("{}/{}", scope, name), rest)
+        },
+        ["package", name, rest @ ..] if !name.starts_with('@') => {
+            (name.to_string(), rest)
+        },
+        _ => return None,
+    };
+
+    let version = match rest {
+        ["v", version, ..] => Some(version.to_string()),
+        _ => None,
+    };
+
+    Some(Package {
+        registry: Registry::Npm,
+        name,
+        version,
+    })
+}
+
+/// `project/name/` or `project/name/1.0.0/`.
+fn pypi_package(segments: &[&str]) -> Option<Package> {
+    match segments {
+        ["project", name, rest @ ..] => Some(Package {
+            registry: Registry::PyPI,
+            name: name.to_string(),
+            version: rest.first().map(|v| v.to_string()),
+        }),
+        _ => None,
+    }
+}
+
+/// `artifact/group/artifact` or `artifact/group/artifact/1.0.0`.
+fn maven_package(segments: &[&str]) -> Option<Package> {
+    match segments {
+        ["artifact", group, artifact, rest @ ..] => Some(Package {
+            registry: Registry::Maven,
+            name: format!("{}:{}", group, artifact),
+            version: rest.first().map(|v| v.to_string()),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{Response, Server};
+
+    #[tokio::test]
+    async fn look_up_packages_in_each_registry() {
+        let server = Server::start(vec![
+            ("/npm/left-pad", Response::ok("{}")),
+            ("/npm/@types%2Fnode/20.1.0", Response::ok("{}")),
+            ("/pypi/pypi/requests/json", Response::ok("{}")),
+            (
+                "/maven/org/slf4j/slf4j-api/2.0.7/slf4j-api-2.0.7.pom",
+                Response::ok("<project/>"),
+            ),
+        ]);
+        let registries = PackageRegistries::new()
+            .set_npm(server.url("/npm/"))
+            .set_pypi(server.url("/pypi/"))
+            .set_maven(server.url("/maven/"));
+        let client = Client::new();
+        let inputs = vec![
+            ("https://www.npmjs.com/package/left-pad", true),
+            ("https://www.npmjs.com/package/@types/node/v/20.1.0", true),
+            ("https://www.npmjs.com/package/@types/node/v/0.0.0", false),
+            ("https://pypi.org/project/requests/", true),
+            ("https://pypi.org/project/not-a-real-package/", false),
+            (
+                "https://central.sonatype.com/artifact/org.slf4j/slf4j-api/2.0.7",
+                true,
+            ),
+            ("https://mvnrepository.com/artifact/org.slf4j/nope", false),
+        ];
+
+        for (url, should_be) in inputs {
+            let package = registries.package(&url.parse().unwrap()).unwrap();
+            let got = registries.check(&client, &package, None).await;
+            assert_eq!(got.is_ok(), should_be, "{}", url);
+        }
+    }
+}
@@ -0,0 +1,396 @@
+#[cfg(feature = "container-images")]
+use crate::validation::ImageRef;
+#[cfg(feature = "forges")]
+use crate::validation::{Comparison, Issue, RepoFile};
+use crate::{
+    validation::{
+        categorise, is_cached, strip_credentials, ArchivedPage, CheckLevel,
+        Context, Options, Origin, Package, Reason,
+    },
+    Category, Link,
+};
+use codespan::FileId;
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+/// How a [`Link`] would be checked, as worked out by [`plan()`].
+#[derive(Debug)]
+pub struct PlannedCheck<F = FileId> {
+    /// The [`Link`] being checked.
+    pub link: Link<F>,
+    /// What kind of link the [`Context`] thinks it is.
+    pub category: Option<Category>,
+    /// What would be done with it.
+    pub check: Check,
+}
+
+/// The validator a [`Link`] would be passed to.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Check {
+    /// The link would be skipped (see [`Context::should_ignore()`] and
+    /// [`Context::domain_policy()`]).
+    Ignored,
+    /// The link would fail without being checked (e.g. a `javascript:`
+    /// link or one exceeding the [`Context::safety_limits()`]).
+    Rejected(Reason),
+    /// Only the link's syntax would be checked.
+    Syntax,
+    /// Look for a file on disk, trying each candidate in order (a
+    /// candidate which turns out to be a directory gets the
+    /// [`Options::default_file()`] appended).
+    FileSystem {
+        /// The paths which would be tried.
+        candidates: Vec<PathBuf>,
+        /// The anchor which would need to exist in the file.
+        fragment: Option<String>,
+    },
+    /// Send a request to a web server.
+    Web {
+        /// The URL being requested.
+        url: Url,
+        /// Does the [`Context::cache()`] already say this URL is valid?
+        cached: bool,
+    },
+    /// Look the page up on the Wayback Machine.
+    Archive(ArchivedPage),
+    /// Ask the package registry whether the package exists.
+    Package(Package),
+    /// Ask the forge's API whether the issue or pull request exists.
+    #[cfg(feature = "forges")]
+    Issue(Issue),
+    /// Ask the forge's API whether the file exists.
+    #[cfg(feature = "forges")]
+    RepoFile(RepoFile),
+    /// Ask the forge's API whether both sides of the comparison exist.
+    #[cfg(feature = "forges")]
+    Comparison(Comparison),
+    /// Ask the container registry whether the image exists.
+    #[cfg(feature = "container-images")]
+    ContainerImage(ImageRef),
+    /// Pass the URI to [`Context::check_opaque_uri()`].
+    OpaqueUri(Url),
+    /// We don't know how to check the link.
+    Unknown,
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Ignored => write!(f, "ignore"),
+            Check::Rejected(reason) => write!(f, "reject ({})", reason),
+            Check::Syntax => write!(f, "check the syntax"),
+            Check::FileSystem {
+                candidates,
+                fragment,
+            } => {
+                let candidates: Vec<_> = candidates
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                write!(f, "look for {}", candidates.join(" or "))?;
+                match fragment {
+                    Some(fragment) => write!(f, " containing #{}", fragment),
+                    None => Ok(()),
+                }
+            },
+            Check::Web { url, cached: false } => write!(f, "request {}", url),
+            Check::Web { url, cached: true } => {
+                write!(f, "request {} (already in the cache)", url)
+            },
+            Check::Archive(page) => write!(
+                f,
+                "look up {} at {} on the Wayback Machine",
+                page.original, page.timestamp
+            ),
+            Check::Package(package) => {
+                write!(f, "look up {} on {}", package, package.registry)
+            },
+            #[cfg(feature = "forges")]
+            Check::Issue(issue) => write!(f, "look up {}", issue),
+            #[cfg(feature = "forges")]
+            Check::RepoFile(file) => write!(f, "look up {}", file),
+            #[cfg(feature = "forges")]
+            Check::Comparison(comparison) => {
+                write!(f, "look up {}", comparison)
+            },
+            #[cfg(feature = "container-images")]
+            Check::ContainerImage(image) => write!(f, "look up {}", image),
+            Check::OpaqueUri(uri) => {
+                write!(f, "ask the context about {}", uri)
+            },
+            Check::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Work out how each [`Link`] would be checked by [`crate::validate()`],
+/// without sending any requests or touching the filesystem.
+///
+/// This is handy for debugging a [`Context`] (e.g. URL mappings, check
+/// levels, or which API a link is sent to) before starting a long run.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{
+///     validation::{plan, Check},
+///     BasicContext, Link,
+/// };
+/// use std::path::Path;
+///
+/// let links = vec![
+///     Link::new("../README.md", Default::default(), ()),
+///     Link::new("javascript:void(0)", Default::default(), ()),
+/// ];
+///
+/// let got = plan(Path::new("docs"), links, &BasicContext::default());
+///
+/// match &got[0].check {
+///     Check::FileSystem { candidates, .. } => {
+///         assert_eq!(candidates[0], Path::new("docs").join("../README.md"));
+///     },
+///     other => panic!("Unexpected check: {:?}", other),
+/// }
+/// assert!(matches!(got[1].check, Check::Rejected(_)));
+/// ```
+pub fn plan<L, F, C>(
+    current_directory: &Path,
+    links: L,
+    ctx: &C,
+) -> Vec<PlannedCheck<F>>
+where
+    L: IntoIterator<Item = Link<F>>,
+    C: Context + ?Sized,
+{
+    links
+        .into_iter()
+        .map(|link| plan_one(link, current_directory, ctx))
+        .collect()
+}
+
+/// Work out how a single [`Link`] would be checked (see [`plan()`]).
+pub fn plan_one<F, C>(
+    link: Link<F>,
+    current_directory: &Path,
+    ctx: &C,
+) -> PlannedCheck<F>
+where
+    C: Context + ?Sized,
+{
+    plan_from(link, Origin::from(current_directory), ctx)
+}
+
+/// [`plan_one()`] for a [`Link`] whose document may be known.
+pub(crate) fn plan_from<F, C>(
+    link: Link<F>,
+    origin: Origin<'_>,
+    ctx: &C,
+) -> PlannedCheck<F>
+where
+    C: Context + ?Sized,
+{
+    let (category, _) = strip_credentials(categorise(&link.href, ctx), ctx);
+    let check = check_for(&link, category.as_ref(), origin, ctx);
+
+    PlannedCheck {
+        link,
+        category,
+        check,
+    }
+}
+
+/// Mirrors the decisions made by `check_one()`.
+fn check_for<F, C>(
+    link: &Link<F>,
+    category: Option<&Category>,
+    origin: Origin<'_>,
+    ctx: &C,
+) -> Check
+where
+    C: Context + ?Sized,
+{
+    if ctx.should_ignore(link) {
+        return Check::Ignored;
+    }
+
+    let limits = ctx.safety_limits();
+    if let Some(Err(reason)) = limits.map(|l| l.check_href(&link.href)) {
+        return Check::Rejected(reason);
+    }
+
+    let level =
+        category.map_or(CheckLevel::Deep, |category| ctx.check_level(category));
+
+    match category {
+        Some(Category::FileSystem { .. }) if level == CheckLevel::Syntax => {
+            Check::Syntax
+        },
+        Some(Category::FileSystem { path, fragment, .. }) => files(
+            origin.directory,
+            path,
+            fragment.as_deref().filter(|_| level == CheckLevel::Deep),
+            ctx.filesystem_options(),
+        ),
+        Some(Category::CurrentFile { fragment }) => match origin.document {
+            Some(document) if level == CheckLevel::Deep => Check::FileSystem {
+                candidates: vec![document.to_path_buf()],
+                fragment: Some(fragment.clone()),
+            },
+            _ => Check::Ignored,
+        },
+        Some(Category::Url(url)) => {
+            match ctx.domain_policy().map(|policy| policy.check(url)) {
+                Some(Ok(false)) => return Check::Ignored,
+                Some(Err(reason)) => return Check::Rejected(reason),
+                Some(Ok(true)) | None => {},
+            }
+            if let Some(Err(reason)) = limits.map(|l| l.check_url(url)) {
+                return Check::Rejected(reason);
+            }
+            if level == CheckLevel::Syntax {
+                return Check::Syntax;
+            }
+
+            let options = ctx.filesystem_options();
+            let mapped = options
+                .url_mappings()
+                .iter()
+                .find_map(|m| m.relative_path(url).map(|path| (m, path)));
+            if let Some((mapping, path)) = mapped {
+                let fragment =
+                    url.fragment().filter(|_| level == CheckLevel::Deep);
+                return files(mapping.directory(), &path, fragment, options);
+            }
+
+            web(url, level, ctx)
+        },
+        Some(Category::MailTo(_)) | Some(Category::Tel(_)) => Check::Syntax,
+        Some(Category::Opaque(_)) if level == CheckLevel::Syntax => {
+            Check::Syntax
+        },
+        Some(Category::Opaque(uri)) => Check::OpaqueUri(uri.clone()),
+        Some(Category::JavaScript(_)) => Check::Rejected(Reason::JavaScript),
+        Some(Category::Empty) => Check::Rejected(Reason::EmptyLink),
+        Some(Category::ProtocolRelative(_)) | None => Check::Unknown,
+    }
+}
+
+fn files(
+    directory: &Path,
+    path: &Path,
+    fragment: Option<&str>,
+    options: &Options,
+) -> Check {
+    let mut candidates = Vec::new();
+
+    for form in super::filesystem::normalisation_forms(path) {
+        match options.join(directory, &form) {
+            Ok(joined) => candidates.extend(options.possible_names(joined)),
+            Err(reason) => return Check::Rejected(reason),
+        }
+    }
+
+    Check::FileSystem {
+        candidates,
+        fragment: fragment.map(String::from),
+    }
+}
+
+fn web<C>(url: &Url, level: CheckLevel, ctx: &C) -> Check
+where
+    C: Context + ?Sized,
+{
+    #[cfg(feature = "container-images")]
+    if url.scheme() == "docker" {
+        if let Some(image) = ImageRef::from_url(url) {
+            return Check::ContainerImage(image);
+        }
+    }
+
+    // APIs are only used for deep checks
+    if level == CheckLevel::Deep {
+        let archived = ctx
+            .wayback_machine()
+            .and_then(|wayback| wayback.archived_page(url));
+        if let Some(page) = archived {
+            return Check::Archive(page);
+        }
+
+        let package = ctx
+            .package_registries()
+            .and_then(|registries| registries.package(url));
+        if let Some(package) = package {
+            return Check::Package(package);
+        }
+
+        #[cfg(feature = "forges")]
+        if let Some(api) = ctx.forge_api() {
+            if let Some(issue) = api.issue(url) {
+                return Check::Issue(issue);
+            }
+            if let Some(file) = api.file(url) {
+                return Check::RepoFile(file);
+            }
+            if let Some(comparison) = api.comparison(url) {
+                return Check::Comparison(comparison);
+            }
+        }
+    }
+
+    let category = Some(Category::Url(url.clone()));
+    Check::Web {
+        url: url.clone(),
+        cached: is_cached(&category, ctx),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        validation::{PackageRegistries, UrlMapping},
+        BasicContext,
+    };
+
+    #[test]
+    fn describe_each_check_without_doing_it() {
+        let mut ctx = BasicContext::default();
+        ctx.packages = Some(PackageRegistries::new());
+        ctx.options = Options::default()
+            .set_alternate_extensions(vec![("md", vec!["html"])])
+            .set_url_mappings(vec![UrlMapping::new(
+                "https://example.com/docs/".parse().unwrap(),
+                "book",
+            )]);
+        let hrefs = [
+            "intro.md#setup",
+            "https://example.com/docs/guide.md",
+            "https://pypi.org/project/requests/",
+            "https://example.org/",
+            "mailto:me@example.com",
+        ];
+        let links = hrefs
+            .iter()
+            .map(|href| Link::new(*href, Default::default(), ()));
+
+        let got: Vec<_> = plan(Path::new("src"), links, &ctx)
+            .into_iter()
+            .map(|planned| planned.check.to_string())
+            .collect();
+
+        assert_eq!(
+            got,
+            vec![
+                "look for src/intro.md or src/intro.html containing #setup",
+                "look for book/guide.md or book/guide.html",
+                "look up requests on PyPI",
+                "request https://example.org/",
+                "check the syntax",
+            ]
+        );
+    }
+}
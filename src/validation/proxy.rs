@@ -0,0 +1,180 @@
+use reqwest::{ClientBuilder, Proxy};
+
+/// Which proxies the [`BasicContext`]'s [`Client`] sends requests through.
+///
+/// By default we behave like other HTTP tools and use the system's proxy
+/// configuration. That means the `HTTP_PROXY`, `HTTPS_PROXY`, and `NO_PROXY`
+/// environment variables, falling back to the platform-wide settings on
+/// Windows and macOS.
+///
+/// Explicit proxies always take precedence over the system ones.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{validation::ProxySettings, BasicContext};
+///
+/// let proxies = ProxySettings::new()
+///     .set_system(false)
+///     .with_proxy("http://proxy.corp.example:3128")
+///     .unwrap();
+///
+/// let ctx = BasicContext::default().set_proxies(proxies);
+/// ```
+///
+/// [`BasicContext`]: crate::BasicContext
+/// [`Client`]: reqwest::Client
+#[derive(Debug, Clone)]
+pub struct ProxySettings {
+    system: bool,
+    proxies: Vec<Proxy>,
+}
+
+impl ProxySettings {
+    /// Create [`ProxySettings`] which use the system's proxy configuration.
+    pub fn new() -> Self { ProxySettings::default() }
+
+    /// [`ProxySettings`] which connect to everything directly.
+    pub fn direct() -> Self { ProxySettings::new().set_system(false) }
+
+    /// Should the system's proxy configuration be detected?
+    pub fn system(&self) -> bool { self.system }
+
+    /// Set [`ProxySettings::system()`].
+    pub fn set_system(self, system: bool) -> Self {
+        ProxySettings { system, ..self }
+    }
+
+    /// The proxies which were explicitly added.
+    pub fn proxies(&self) -> &[Proxy] { &self.proxies }
+
+    /// Send all requests through the proxy at this URL.
+    pub fn with_proxy(self, url: &str) -> Result<Self, reqwest::Error> {
+        Ok(self.with(Proxy::all(url)?))
+    }
+
+    /// Send all requests through a SOCKS5 proxy, like the one started by
+    /// `ssh -D 1080`.
+    ///
+    /// The `address` is either a `socks5://` URL or a `host:port` pair. A
+    /// `host:port` is treated as `socks5h://`, meaning hostnames are resolved
+    /// by the proxy instead of locally.
+    #[cfg(feature = "socks")]
+    pub fn with_socks5(self, address: &str) -> Result<Self, reqwest::Error> {
+        if address.contains("://") {
+            self.with_proxy(address)
+        } else {
+            self.with_proxy(&format!("socks5h://{}", address))
+        }
+    }
+
+    /// Add a [`Proxy`], for when more control over which requests go
+    /// through it is needed.
+    pub fn with(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    pub(crate) fn apply(&self, mut builder: ClientBuilder) -> ClientBuilder {
+        if !self.system {
+            builder = builder.no_proxy();
+        }
+
+        for proxy in &self.proxies {
+            builder = builder.proxy(proxy.clone());
+        }
+
+        builder
+    }
+}
+
+impl Default for ProxySettings {
+    fn default() -> Self {
+        ProxySettings {
+            system: true,
+            proxies: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        validation::check_web,
+        BasicContext,
+    };
+    use reqwest::Url;
+
+    #[tokio::test]
+    async fn send_requests_through_an_explicit_proxy() {
+        let proxy = Server::start(vec![(
+            "http://example.invalid/page",
+            Response::ok("Hello, World!"),
+        )]);
+        let proxies = ProxySettings::direct()
+            .with_proxy(proxy.url("/").as_str())
+            .unwrap();
+        let ctx = BasicContext::default().set_proxies(proxies);
+        let url = Url::parse("http://example.invalid/page").unwrap();
+
+        check_web(&url, &ctx).await.unwrap();
+
+        let requests = proxy.requests();
+        assert_eq!(requests[0].path, "http://example.invalid/page");
+    }
+
+    /// A SOCKS5 proxy which forwards every connection to the `upstream`
+    /// server, regardless of where the client asked to go.
+    #[cfg(feature = "socks")]
+    fn socks5_proxy(upstream: &Url) -> String {
+        use std::{
+            io::{self, Read, Write},
+            net::{TcpListener, TcpStream},
+            thread,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let upstream = upstream.socket_addrs(|| None).unwrap()[0];
+
+        thread::spawn(move || {
+            for client in listener.incoming() {
+                let mut client = client.unwrap();
+                // greeting, then CONNECT with a domain name and port
+                let mut buffer = [0; 512];
+                let _ = client.read(&mut buffer).unwrap();
+                client.write_all(&[5, 0]).unwrap();
+                let _ = client.read(&mut buffer).unwrap();
+                client.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+
+                let server = TcpStream::connect(upstream).unwrap();
+                let (mut from_client, mut to_server) =
+                    (client.try_clone().unwrap(), server.try_clone().unwrap());
+                thread::spawn(move || {
+                    io::copy(&mut from_client, &mut to_server)
+                });
+                let (mut from_server, mut to_client) = (server, client);
+                let _ = io::copy(&mut from_server, &mut to_client);
+            }
+        });
+
+        address
+    }
+
+    #[cfg(feature = "socks")]
+    #[tokio::test]
+    async fn tunnel_requests_through_socks5() {
+        let server = Server::start(vec![("/page", Response::ok("Hi"))]);
+        let proxies = ProxySettings::direct()
+            .with_socks5(&socks5_proxy(&server.url("/")))
+            .unwrap();
+        let ctx = BasicContext::default().set_proxies(proxies);
+        let url = Url::parse("http://example.invalid/page").unwrap();
+
+        check_web(&url, &ctx).await.unwrap();
+
+        assert_eq!(server.requests()[0].path, "/page");
+    }
+}
@@ -0,0 +1,332 @@
+use crate::{
+    fix::Suggestion,
+    lints::{Lint, LintKind},
+    validation::{
+        ArchivedPage, Check, Context, InvalidLink, Outcomes, PlannedCheck,
+        Reason,
+    },
+    Category, Link,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    hash::Hash,
+    sync::Arc,
+};
+use url::Url;
+
+/// A function which hides secrets (e.g. access tokens or signed parameters)
+/// in a [`Url`] before it is logged, used as a cache key, or written to a
+/// report (see [`Context::redact()`]).
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::validation::Redactor;
+/// use url::Url;
+///
+/// let redactor = Redactor::query_parameters(&["token"]);
+/// let url = Url::parse("https://example.com/file?token=abc&page=2").unwrap();
+///
+/// assert_eq!(
+///     redactor.redact(&url).as_str(),
+///     "https://example.com/file?token=REDACTED&page=2"
+/// );
+/// ```
+///
+/// [`Context::redact()`]: crate::validation::Context::redact
+#[derive(Clone)]
+pub struct Redactor(Arc<dyn Fn(&Url) -> Url + Send + Sync>);
+
+impl Redactor {
+    /// The text secrets are replaced with.
+    pub const PLACEHOLDER: &'static str = "REDACTED";
+
+    /// Create a [`Redactor`] from a function.
+    pub fn new<R>(redact: R) -> Self
+    where
+        R: Fn(&Url) -> Url + Send + Sync + 'static,
+    {
+        Redactor(Arc::new(redact))
+    }
+
+    /// A [`Redactor`] which hides the values of these query parameters
+    /// (compared case-insensitively), and any password.
+    pub fn query_parameters(names: &[&str]) -> Self {
+        let names: Vec<String> =
+            names.iter().map(|name| name.to_ascii_lowercase()).collect();
+
+        Redactor::new(move |url| {
+            let mut url = url.clone();
+
+            if url.password().is_some() {
+                let _ = url.set_password(Some(Redactor::PLACEHOLDER));
+            }
+
+            let secret =
+                |name: &str| names.contains(&name.to_ascii_lowercase());
+            if url.query_pairs().any(|(name, _)| secret(&name)) {
+                let pairs: Vec<(String, String)> = url
+                    .query_pairs()
+                    .map(|(name, value)| {
+                        let value = if secret(&name) {
+                            Redactor::PLACEHOLDER.into()
+                        } else {
+                            value.into_owned()
+                        };
+                        (name.into_owned(), value)
+                    })
+                    .collect();
+                url.query_pairs_mut().clear().extend_pairs(pairs);
+            }
+
+            url
+        })
+    }
+
+    /// Hide any secrets in a [`Url`].
+    pub fn redact(&self, url: &Url) -> Url { (self.0)(url) }
+}
+
+impl Debug for Redactor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Redactor").finish_non_exhaustive()
+    }
+}
+
+impl<F: Eq + Hash> Outcomes<F> {
+    /// Hide secrets in every URL using [`Context::redact()`], so the
+    /// [`Outcomes`] can be written to a report.
+    pub fn redact<C>(self, ctx: &C) -> Self
+    where
+        C: Context + ?Sized,
+    {
+        let href = |href: &str| redact_href(href, |url| ctx.redact(url));
+        let link = |link: Link<F>| Link {
+            href: href(&link.href),
+            ..link
+        };
+        let invalid = |invalid: InvalidLink<F>| InvalidLink {
+            link: link(invalid.link),
+            reason: redact_reason(invalid.reason, ctx),
+        };
+
+        Outcomes {
+            valid: self.valid.into_iter().map(link).collect(),
+            invalid: self.invalid.into_iter().map(invalid).collect(),
+            ignored: self.ignored.into_iter().map(link).collect(),
+            unknown_category: self
+                .unknown_category
+                .into_iter()
+                .map(link)
+                .collect(),
+            unchecked: self.unchecked.into_iter().map(link).collect(),
+            suppressed: self.suppressed.into_iter().map(invalid).collect(),
+            unverifiable: self.unverifiable.into_iter().map(invalid).collect(),
+            expected_failures: self
+                .expected_failures
+                .into_iter()
+                .map(invalid)
+                .collect(),
+            durations: self
+                .durations
+                .into_iter()
+                .map(|(l, duration)| (link(l), duration))
+                .collect(),
+            candidates: self
+                .candidates
+                .into_iter()
+                .map(|(l, candidates)| (link(l), candidates))
+                .collect(),
+            attempts: self
+                .attempts
+                .into_iter()
+                .map(|(l, mut attempts)| {
+                    for attempt in &mut attempts {
+                        attempt.url = ctx.redact(&attempt.url);
+                    }
+                    (link(l), attempts)
+                })
+                .collect(),
+            suggestions: self
+                .suggestions
+                .into_iter()
+                .map(|suggestion| Suggestion {
+                    link: link(suggestion.link),
+                    replacement: href(&suggestion.replacement),
+                    ..suggestion
+                })
+                .collect(),
+            lints: self
+                .lints
+                .into_iter()
+                .map(|lint| Lint {
+                    link: link(lint.link),
+                    kind: match lint.kind {
+                        LintKind::ProtocolDowngrade(url) => {
+                            LintKind::ProtocolDowngrade(href(&url))
+                        },
+                        other => other,
+                    },
+                })
+                .collect(),
+            sources: self
+                .sources
+                .into_iter()
+                .map(|(l, source)| (link(l), source))
+                .collect(),
+        }
+    }
+}
+
+impl<F> PlannedCheck<F> {
+    /// Hide secrets in the link and any URLs it would be checked against
+    /// using [`Context::redact()`], so the plan can be printed.
+    pub fn redact<C>(self, ctx: &C) -> Self
+    where
+        C: Context + ?Sized,
+    {
+        let category = match self.category {
+            Some(Category::Url(url)) => Some(Category::Url(ctx.redact(&url))),
+            other => other,
+        };
+        let check = match self.check {
+            Check::Rejected(reason) => {
+                Check::Rejected(redact_reason(reason, ctx))
+            },
+            Check::Web { url, cached } => Check::Web {
+                url: ctx.redact(&url),
+                cached,
+            },
+            Check::Archive(page) => Check::Archive(ArchivedPage {
+                original: redacted(&page.original, ctx),
+                ..page
+            }),
+            other => other,
+        };
+
+        PlannedCheck {
+            link: Link {
+                href: redacted(&self.link.href, ctx),
+                ..self.link
+            },
+            category,
+            check,
+        }
+    }
+}
+
+/// Redact any URLs stored in a [`Reason`].
+fn redact_reason<C>(reason: Reason, ctx: &C) -> Reason
+where
+    C: Context + ?Sized,
+{
+    match reason {
+        Reason::RedirectLoop { chain } => Reason::RedirectLoop {
+            chain: chain.iter().map(|url| ctx.redact(url)).collect(),
+        },
+        Reason::UnencodedUrl(encoded) => {
+            Reason::UnencodedUrl(redacted(&encoded, ctx))
+        },
+        Reason::Web(e) => Reason::Web(e.without_url()),
+        other => other,
+    }
+}
+
+/// Redact an `href` with [`Context::redact()`] (e.g. before logging it).
+pub(crate) fn redacted<C>(href: &str, ctx: &C) -> String
+where
+    C: Context + ?Sized,
+{
+    redact_href(href, |url| ctx.redact(url))
+}
+
+/// Redact a link's `href`, if it is a URL.
+pub(crate) fn redact_href<R>(href: &str, redact: R) -> String
+where
+    R: Fn(&Url) -> Url,
+{
+    match Url::parse(href) {
+        Ok(url) if !url.cannot_be_a_base() => {
+            let redacted = redact(&url);
+            if redacted == url {
+                href.to_string()
+            } else {
+                redacted.into()
+            }
+        },
+        _ => href.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        validation::{plan, validate},
+        BasicContext,
+    };
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn hide_tokens_from_cache_keys_and_reports() {
+        let path = "/file?Token=secret&page=2";
+        let server = Server::start(vec![(path, Response::ok(""))]);
+        let url = server.url(path);
+        let links = vec![Link::new(url.as_str(), Default::default(), ())];
+        let mut ctx = BasicContext::default();
+        ctx.redactor = Some(Redactor::query_parameters(&["token"]));
+
+        let outcomes = validate(Path::new("."), links, &ctx).await.redact(&ctx);
+
+        let redacted = server.url("/file?Token=REDACTED&page=2");
+        assert_eq!(outcomes.valid[0].href, redacted.as_str());
+        assert_eq!(ctx.cache_key(&url).query(), redacted.query());
+        let other_token = server.url("/file?Token=other&page=2");
+        assert_ne!(ctx.cache_key(&url), ctx.cache_key(&other_token));
+        assert_eq!(server.requests()[0].path, path);
+    }
+
+    #[tokio::test]
+    async fn hide_tokens_in_redirect_chains() {
+        let server = Server::start(vec![
+            ("/a?token=secret", Response::redirect("/b?token=secret")),
+            ("/b?token=secret", Response::redirect("/a?token=secret")),
+        ]);
+        let url = server.url("/a?token=secret");
+        let links = vec![Link::new(url.as_str(), Default::default(), ())];
+        let mut ctx = BasicContext::default();
+        ctx.redactor = Some(Redactor::query_parameters(&["token"]));
+
+        let outcomes = validate(Path::new("."), links, &ctx).await.redact(&ctx);
+
+        assert_eq!(outcomes.invalid.len(), 1);
+        let chain = match &outcomes.invalid[0].reason {
+            Reason::RedirectLoop { chain } => chain,
+            other => panic!("Unexpected error: {}", other),
+        };
+        assert_eq!(chain.len(), 3);
+        for url in chain {
+            assert_eq!(url.query(), Some("token=REDACTED"));
+        }
+        assert!(!outcomes.invalid[0].reason.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn hide_tokens_when_printing_a_plan() {
+        let href = "https://example.com/file?token=secret";
+        let links = vec![Link::new(href, Default::default(), ())];
+        let mut ctx = BasicContext::default();
+        ctx.redactor = Some(Redactor::query_parameters(&["token"]));
+
+        let planned: Vec<_> = plan(Path::new("."), links, &ctx)
+            .into_iter()
+            .map(|planned| planned.redact(&ctx))
+            .collect();
+
+        let printed =
+            format!("{} => {}", planned[0].link.href, planned[0].check);
+        assert!(!printed.contains("secret"), "{}", printed);
+        assert!(printed.contains("token=REDACTED"), "{}", printed);
+    }
+}
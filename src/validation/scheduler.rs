@@ -0,0 +1,255 @@
+//! Running checks concurrently without letting one slow host hog every slot.
+//!
+//! Items are put into a queue per host and started round-robin, so a batch
+//! of links to a slow domain can only ever use
+//! [`Context::concurrency_per_host()`] of the [`Context::concurrency()`]
+//! slots, leaving the rest free for everything else.
+//!
+//! Only a limited number of items are pulled from the input ahead of time,
+//! so memory usage stays flat no matter how many links there are.
+//!
+//! [`Context::concurrency()`]: crate::validation::Context::concurrency
+//! [`Context::concurrency_per_host()`]:
+//! crate::validation::Context::concurrency_per_host
+
+use futures::{
+    stream::{FusedStream, FuturesUnordered},
+    Future, Stream, StreamExt,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    task::Poll,
+};
+
+/// How many items may be in flight or waiting to start.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Limits {
+    /// The maximum number of futures in flight at once.
+    pub(crate) concurrency: usize,
+    /// The maximum number of in-flight futures which share a key.
+    pub(crate) per_key: usize,
+    /// How many items can be pulled from the input before they are started.
+    pub(crate) lookahead: usize,
+}
+
+/// Run `start` on every item in a [`Stream`], yielding outputs as they
+/// complete.
+///
+/// Items with a `None` key (e.g. files on disk) are only limited by
+/// [`Limits::concurrency`].
+pub(crate) fn schedule<'a, S, K, F, Fut>(
+    items: S,
+    key: impl Fn(&S::Item) -> Option<K> + 'a,
+    limits: Limits,
+    mut start: F,
+) -> impl Stream<Item = Fut::Output> + 'a
+where
+    S: Stream + 'a,
+    K: Hash + Eq + 'a,
+    F: FnMut(S::Item) -> Fut + 'a,
+    Fut: Future + 'a,
+{
+    let concurrency = limits.concurrency.max(1);
+    let lookahead = limits.lookahead.max(1);
+    let mut items = Box::pin(items.fuse());
+    let mut queues = Queues::new(limits.per_key.max(1));
+    let mut running = FuturesUnordered::new();
+
+    futures::stream::poll_fn(move |cx| {
+        while queues.len < lookahead {
+            match items.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => queues.push(key(&item), item),
+                _ => break,
+            }
+        }
+
+        while running.len() < concurrency {
+            match queues.next() {
+                Some((index, item)) => {
+                    let fut = start(item);
+                    running.push(async move { (index, fut.await) });
+                },
+                None => break,
+            }
+        }
+
+        match running.poll_next_unpin(cx) {
+            Poll::Ready(Some((index, output))) => {
+                queues.finished(index);
+                Poll::Ready(Some(output))
+            },
+            // nothing is running, so everything queued must have started
+            Poll::Ready(None) if items.is_terminated() => Poll::Ready(None),
+            // we're waiting on the input
+            Poll::Ready(None) | Poll::Pending => Poll::Pending,
+        }
+    })
+}
+
+/// Run `start` on every item and collect the outputs.
+pub(crate) async fn run<I, K, F, Fut, O>(
+    items: I,
+    key: impl Fn(&I::Item) -> Option<K>,
+    concurrency: usize,
+    per_key: usize,
+    start: F,
+) -> O
+where
+    I: IntoIterator,
+    K: Hash + Eq,
+    F: FnMut(I::Item) -> Fut,
+    Fut: Future,
+    O: Default + Extend<Fut::Output>,
+{
+    let limits = Limits {
+        concurrency,
+        per_key,
+        lookahead: usize::MAX,
+    };
+
+    schedule(futures::stream::iter(items), key, limits, start)
+        .collect()
+        .await
+}
+
+/// A queue of waiting items per key, visited round-robin.
+struct Queues<K, T> {
+    indices: HashMap<K, usize>,
+    unkeyed: Option<usize>,
+    queues: Vec<Queue<T>>,
+    /// The queues which have items waiting.
+    ring: VecDeque<usize>,
+    /// The total number of items waiting.
+    len: usize,
+    per_key: usize,
+}
+
+struct Queue<T> {
+    limited: bool,
+    items: VecDeque<T>,
+    in_flight: usize,
+}
+
+impl<K: Hash + Eq, T> Queues<K, T> {
+    fn new(per_key: usize) -> Self {
+        Queues {
+            indices: HashMap::new(),
+            unkeyed: None,
+            queues: Vec::new(),
+            ring: VecDeque::new(),
+            len: 0,
+            per_key,
+        }
+    }
+
+    fn push(&mut self, key: Option<K>, item: T) {
+        let queues = &mut self.queues;
+        let mut new_queue = |limited| {
+            queues.push(Queue {
+                limited,
+                items: VecDeque::new(),
+                in_flight: 0,
+            });
+            queues.len() - 1
+        };
+        let index = match key {
+            Some(key) => {
+                *self.indices.entry(key).or_insert_with(|| new_queue(true))
+            },
+            None => *self.unkeyed.get_or_insert_with(|| new_queue(false)),
+        };
+
+        let queue = &mut self.queues[index];
+        if queue.items.is_empty() {
+            self.ring.push_back(index);
+        }
+        queue.items.push_back(item);
+        self.len += 1;
+    }
+
+    /// Take the next item which is allowed to start, taking turns between
+    /// queues.
+    fn next(&mut self) -> Option<(usize, T)> {
+        for _ in 0..self.ring.len() {
+            let index = self.ring.pop_front()?;
+            let queue = &mut self.queues[index];
+
+            if queue.limited && queue.in_flight >= self.per_key {
+                self.ring.push_back(index);
+                continue;
+            }
+
+            let item = queue.items.pop_front()?;
+            queue.in_flight += 1;
+            self.len -= 1;
+            if !queue.items.is_empty() {
+                self.ring.push_back(index);
+            }
+
+            return Some((index, item));
+        }
+
+        None
+    }
+
+    fn finished(&mut self, index: usize) { self.queues[index].in_flight -= 1; }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    fn take_turns_between_hosts() {
+        let items = vec![
+            ("slow.com", 1),
+            ("slow.com", 2),
+            ("slow.com", 3),
+            ("fast.com", 4),
+            ("fast.com", 5),
+            ("file", 6),
+        ];
+        let started = RefCell::new(Vec::new());
+
+        let mut finished: Vec<u32> = futures::executor::block_on(run(
+            items,
+            |&(host, _)| Some(host).filter(|&h| h != "file"),
+            3,
+            1,
+            |(_, n)| {
+                started.borrow_mut().push(n);
+                async move { n }
+            },
+        ));
+
+        // the fast host and local files don't have to wait for the slow host
+        let started = started.into_inner();
+        assert_eq!(started[..3], [1, 4, 6]);
+        finished.sort();
+        assert_eq!(finished, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn only_pull_a_few_items_ahead() {
+        let pulled = Cell::new(0);
+        let items = futures::stream::iter(0..1_000_000).map(|n| {
+            pulled.set(pulled.get() + 1);
+            n
+        });
+        let limits = Limits {
+            concurrency: 4,
+            per_key: 1,
+            lookahead: 16,
+        };
+
+        let outputs =
+            schedule(items, |n| Some(n % 8), limits, |n| async move { n });
+        let first: Vec<_> =
+            futures::executor::block_on(outputs.take(10).collect());
+
+        assert_eq!(first.len(), 10);
+        assert!(pulled.get() <= 16 + 10 + 4, "{}", pulled.get());
+    }
+}
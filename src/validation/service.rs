@@ -0,0 +1,143 @@
+use crate::validation::{
+    web::{already_valid, head, update_cache},
+    CacheEntry, Context, Reason,
+};
+use futures::future::{self, BoxFuture};
+use http::HeaderMap;
+use reqwest::{Client, Url};
+use std::{
+    task::{Context as TaskContext, Poll},
+    time::SystemTime,
+};
+use tower_service::Service;
+
+/// The web checker as a [`tower_service::Service`], so it can be wrapped
+/// in your own middleware (retries, rate limiting, authentication, etc.).
+///
+/// Each call sends a `HEAD` request to the [`Url`], resolving to `Ok(())`
+/// when the server responds with a successful status code.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use linkcheck::validation::{check_web_with, WebChecker};
+/// # use linkcheck::BasicContext;
+///
+/// # #[tokio::main] async fn main() {
+/// # let ctx = BasicContext::default();
+/// // wrap this in whatever tower layers you want
+/// let mut checker = WebChecker::new(reqwest::Client::new());
+///
+/// let url = "https://example.com/".parse().unwrap();
+/// let result = check_web_with(&url, &ctx, &mut checker).await;
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebChecker {
+    client: Client,
+    headers: HeaderMap,
+}
+
+impl WebChecker {
+    /// Create a new [`WebChecker`] which sends requests using a particular
+    /// [`Client`].
+    pub fn new(client: Client) -> Self {
+        WebChecker {
+            client,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Add extra headers to every request.
+    pub fn with_headers(self, headers: HeaderMap) -> Self {
+        WebChecker { headers, ..self }
+    }
+}
+
+impl Service<Url> for WebChecker {
+    type Error = Reason;
+    type Future = BoxFuture<'static, Result<(), Reason>>;
+    type Response = ();
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, url: Url) -> Self::Future {
+        let client = self.client.clone();
+        let headers = self.headers.clone();
+
+        Box::pin(async move {
+            head(&client, url, headers).await.map_err(Reason::from)
+        })
+    }
+}
+
+/// Check whether a [`Url`] is valid like [`crate::validation::check_web()`],
+/// except the request is sent using a [`Service`].
+///
+/// The [`Context`]'s cache is still consulted and updated, but its
+/// [`Context::client()`] and [`Context::url_specific_headers()`] are ignored
+/// in favour of whatever the [`Service`] does.
+pub async fn check_web_with<C, S>(
+    url: &Url,
+    ctx: &C,
+    service: &mut S,
+) -> Result<(), Reason>
+where
+    C: Context + ?Sized,
+    S: Service<Url, Response = ()>,
+    S::Error: Into<Reason>,
+{
+    log::debug!("Checking \"{}\" on the web", ctx.redact(url));
+
+    if already_valid(url, ctx) {
+        log::debug!("The cache says \"{}\" is still valid", ctx.redact(url));
+        return Ok(());
+    }
+
+    future::poll_fn(|cx| service.poll_ready(cx))
+        .await
+        .map_err(Into::into)?;
+    let result = service.call(url.clone()).await.map_err(Into::into);
+
+    let entry = CacheEntry::new(SystemTime::now(), result.is_ok());
+    update_cache(url, ctx, entry);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+    use http::HeaderValue;
+
+    #[tokio::test]
+    async fn check_urls_using_the_service() {
+        let server = Server::start(vec![("/", Response::ok(""))]);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-token", HeaderValue::from_static("secret"));
+        let mut checker = WebChecker::new(Client::new()).with_headers(headers);
+        let ctx = BasicContext::default();
+
+        check_web_with(&server.url("/"), &ctx, &mut checker)
+            .await
+            .unwrap();
+        let missing_url = server.url("/missing");
+        let missing = check_web_with(&missing_url, &ctx, &mut checker).await;
+
+        assert!(matches!(missing, Err(Reason::Web(_))));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0]
+            .headers
+            .contains(&("x-token".to_string(), "secret".to_string())));
+    }
+}
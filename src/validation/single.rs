@@ -0,0 +1,160 @@
+use crate::{
+    fix::Suggestion,
+    lints::Lint,
+    validation::{self, Attempt, Context, Outcomes, Reason, Status},
+    Link,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Everything we found out while checking a single link with
+/// [`check_url()`] or [`check_path()`].
+#[derive(Debug)]
+pub struct Checked {
+    /// Whether the link works.
+    pub status: Status,
+    /// Why the link is broken, when the [`Checked::status`] is
+    /// [`Status::Invalid`], [`Status::Suppressed`], [`Status::Unverifiable`],
+    /// or [`Status::ExpectedFailure`].
+    pub reason: Option<Reason>,
+    /// How long it took to check the link.
+    pub duration: Option<Duration>,
+    /// Possible fixes (e.g. where the link redirects to).
+    pub suggestions: Vec<Suggestion<()>>,
+    /// Problems with the link which don't make it broken.
+    pub lints: Vec<Lint<()>>,
+    /// Every request that was sent, when [`Context::record_attempts()`] is
+    /// enabled.
+    pub attempts: Vec<Attempt>,
+    /// Files elsewhere with the same name, when a local file is missing.
+    pub candidates: Vec<PathBuf>,
+}
+
+impl Checked {
+    /// Does the link work?
+    pub fn is_valid(&self) -> bool { self.status == Status::Valid }
+}
+
+impl From<Outcomes<()>> for Checked {
+    fn from(outcomes: Outcomes<()>) -> Self {
+        let Outcomes {
+            valid,
+            invalid,
+            ignored,
+            unknown_category,
+            unchecked,
+            suppressed,
+            unverifiable,
+            expected_failures,
+            durations,
+            candidates,
+            attempts,
+            suggestions,
+            lints,
+            sources: _,
+        } = outcomes;
+
+        let (status, reason) = if !valid.is_empty() {
+            (Status::Valid, None)
+        } else if let Some(invalid) = invalid.into_iter().next() {
+            (Status::Invalid, Some(invalid.reason))
+        } else if let Some(suppressed) = suppressed.into_iter().next() {
+            (Status::Suppressed, Some(suppressed.reason))
+        } else if let Some(unverifiable) = unverifiable.into_iter().next() {
+            (Status::Unverifiable, Some(unverifiable.reason))
+        } else if let Some(expected) = expected_failures.into_iter().next() {
+            (Status::ExpectedFailure, Some(expected.reason))
+        } else if !unchecked.is_empty() {
+            (Status::Unchecked, None)
+        } else if !unknown_category.is_empty() {
+            (Status::UnknownCategory, None)
+        } else {
+            debug_assert!(!ignored.is_empty());
+            (Status::Ignored, None)
+        };
+
+        Checked {
+            status,
+            reason,
+            duration: durations.into_values().next(),
+            suggestions,
+            lints,
+            attempts: attempts.into_values().flatten().collect(),
+            candidates: candidates.into_values().flatten().collect(),
+        }
+    }
+}
+
+/// Check a single URL, using the same policies and caching as a full run.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{validation::Status, BasicContext};
+///
+/// # #[tokio::main] async fn main() {
+/// let ctx = BasicContext::default();
+///
+/// let checked = linkcheck::check_url("mailto:not-an-address", &ctx).await;
+///
+/// assert_eq!(checked.status, Status::Invalid);
+/// # }
+/// ```
+pub async fn check_url<C>(url: &str, ctx: &C) -> Checked
+where
+    C: Context + Sync + ?Sized,
+{
+    check_href(Path::new("."), url, ctx).await
+}
+
+/// Check a single `path`, written the way it would appear in a link (e.g.
+/// `../README.md#usage`), relative to the `base` directory.
+pub async fn check_path<C>(base: &Path, path: &str, ctx: &C) -> Checked
+where
+    C: Context + Sync + ?Sized,
+{
+    check_href(base, path, ctx).await
+}
+
+async fn check_href<C>(current_directory: &Path, href: &str, ctx: &C) -> Checked
+where
+    C: Context + Sync + ?Sized,
+{
+    let link = Link::new(href, Default::default(), ());
+    validation::validate(current_directory, vec![link], ctx)
+        .await
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{Response, Server},
+        BasicContext,
+    };
+
+    #[tokio::test]
+    async fn check_one_link_at_a_time() {
+        let server = Server::start(vec![
+            ("/old", Response::redirect("/new")),
+            ("/new", Response::ok("")),
+        ]);
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join("README.md"), "").unwrap();
+        let ctx = BasicContext::default();
+
+        let web = check_url(server.url("/old").as_str(), &ctx).await;
+        let file = check_path(temp.path(), "README.md", &ctx).await;
+        let missing = check_path(temp.path(), "missing.md", &ctx).await;
+
+        assert!(web.is_valid());
+        assert_eq!(web.suggestions[0].replacement, server.url("/new").as_str());
+        assert!(web.duration.is_some());
+        assert!(file.is_valid());
+        assert_eq!(missing.status, Status::Invalid);
+        assert!(missing.reason.unwrap().file_not_found());
+    }
+}
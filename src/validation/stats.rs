@@ -0,0 +1,164 @@
+use crate::validation::Outcomes;
+use std::{collections::HashMap, convert::TryFrom, time::Duration};
+use url::Url;
+
+/// Timing statistics for the web links in some [`Outcomes`] (see
+/// [`Outcomes::stats()`]).
+///
+/// These make it easier to decide which domains should be cached for longer
+/// or skipped when checking pull requests.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    /// How long it took to check every web link.
+    pub histogram: Histogram,
+    /// The timings for each host.
+    pub hosts: HashMap<String, HostStats>,
+}
+
+impl Stats {
+    /// Record how long it took to check a link to this `host`.
+    pub fn record(&mut self, host: &str, duration: Duration) {
+        self.histogram.record(duration);
+        self.hosts
+            .entry(host.to_ascii_lowercase())
+            .or_default()
+            .record(duration);
+    }
+
+    /// The `n` hosts with the slowest responses, slowest first.
+    pub fn slowest_hosts(&self, n: usize) -> Vec<(&str, &HostStats)> {
+        let mut hosts: Vec<_> = self
+            .hosts
+            .iter()
+            .map(|(host, stats)| (host.as_str(), stats))
+            .collect();
+
+        hosts.sort_by(|(left_host, left), (right_host, right)| {
+            right
+                .max
+                .cmp(&left.max)
+                .then(right.mean().cmp(&left.mean()))
+                .then(left_host.cmp(right_host))
+        });
+        hosts.truncate(n);
+
+        hosts
+    }
+}
+
+/// The timings for links to a single host.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct HostStats {
+    /// The number of links to this host which were checked.
+    pub links: usize,
+    /// The total time spent checking them.
+    pub total: Duration,
+    /// The slowest link.
+    pub max: Duration,
+    /// How long each link took.
+    pub histogram: Histogram,
+}
+
+impl HostStats {
+    /// The average time it took to check a link.
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.links) {
+            Ok(0) | Err(_) => Duration::default(),
+            Ok(links) => self.total / links,
+        }
+    }
+
+    fn record(&mut self, duration: Duration) {
+        self.links += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+        self.histogram.record(duration);
+    }
+}
+
+/// A histogram of response times.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    /// How many responses fell into each bucket. The `i`'th bucket counts
+    /// responses which took less than [`Histogram::BOUNDS`]`[i]`, and the
+    /// last one counts everything else.
+    pub counts: [usize; Histogram::BOUNDS.len() + 1],
+}
+
+impl Histogram {
+    /// The upper bound for each bucket.
+    pub const BOUNDS: [Duration; 6] = [
+        Duration::from_millis(100),
+        Duration::from_millis(250),
+        Duration::from_millis(500),
+        Duration::from_secs(1),
+        Duration::from_secs(5),
+        Duration::from_secs(10),
+    ];
+
+    /// Add a response time to the histogram.
+    pub fn record(&mut self, duration: Duration) {
+        let bucket = Histogram::BOUNDS
+            .iter()
+            .position(|&bound| duration < bound)
+            .unwrap_or(Histogram::BOUNDS.len());
+        self.counts[bucket] += 1;
+    }
+
+    /// The total number of responses.
+    pub fn len(&self) -> usize { self.counts.iter().sum() }
+
+    /// Is the histogram empty?
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+impl<F> Outcomes<F> {
+    /// Aggregate the [`Outcomes::durations`] for web links by host.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+
+        for (link, &duration) in &self.durations {
+            if let Some(host) =
+                Url::parse(&link.href).ok().as_ref().and_then(Url::host_str)
+            {
+                stats.record(host, duration);
+            }
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Link;
+
+    #[test]
+    fn aggregate_durations_by_host() {
+        let mut outcomes = Outcomes::<()>::default();
+        let timings = [
+            ("https://example.com/a", 50),
+            ("https://EXAMPLE.com/b", 1_500),
+            ("https://docs.rs/", 300),
+            ("./README.md", 20_000),
+        ];
+        for (href, millis) in timings {
+            let link = Link::new(href, Default::default(), ());
+            outcomes
+                .durations
+                .insert(link, Duration::from_millis(millis));
+        }
+
+        let stats = outcomes.stats();
+
+        assert_eq!(stats.histogram.counts, [1, 0, 1, 0, 1, 0, 0]);
+        let slowest = stats.slowest_hosts(1);
+        assert_eq!(slowest.len(), 1);
+        let (host, example) = slowest[0];
+        assert_eq!(host, "example.com");
+        assert_eq!(example.links, 2);
+        assert_eq!(example.max, Duration::from_millis(1_500));
+        assert_eq!(example.mean(), Duration::from_millis(775));
+    }
+}
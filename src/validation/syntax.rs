@@ -0,0 +1,278 @@
+//! Checks for links which can't be followed, but which we can at least make
+//! sure are well-formed.
+//!
+//! These are also used for [`CheckLevel::Syntax`], where no IO is done at
+//! all.
+//!
+//! [`CheckLevel::Syntax`]: crate::validation::CheckLevel::Syntax
+
+use crate::{
+    validation::{normalise::encode_href, Reason},
+    Category,
+};
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+/// Characters which may be used to make a phone number easier to read (see
+/// [RFC 3966](https://tools.ietf.org/html/rfc3966#section-5.1.1)).
+const VISUAL_SEPARATORS: &[char] = &['-', '.', '(', ')', ' '];
+
+/// Make sure a link is well-formed without touching the filesystem or
+/// network, returning the [`Category`] it belongs to.
+///
+/// This is handy for validating links provided by users (e.g. in a web
+/// service) before accepting them.
+///
+/// # Examples
+///
+/// ```rust
+/// use linkcheck::{
+///     validation::{check_syntax, Reason},
+///     Category,
+/// };
+///
+/// assert!(matches!(
+///     check_syntax("https://example.com/#intro"),
+///     Ok(Category::Url(_))
+/// ));
+/// assert!(matches!(
+///     check_syntax("docs/100%.md"),
+///     Err(Reason::InvalidPercentEncoding(_))
+/// ));
+/// ```
+pub fn check_syntax(href: &str) -> Result<Category, Reason> {
+    let category = Category::categorise(href).ok_or_else(|| {
+        malformed_url(href)
+            .unwrap_or_else(|| Reason::Unparseable(href.to_string()))
+    })?;
+
+    match &category {
+        Category::Url(_) | Category::ProtocolRelative(_) => check_url(href)?,
+        Category::FileSystem { .. } | Category::CurrentFile { .. } => {
+            check_path(href)?
+        },
+        Category::MailTo(address) => check_mailto(address)?,
+        Category::Tel(number) => check_tel(number)?,
+        Category::JavaScript(_) => return Err(Reason::JavaScript),
+        Category::Empty => return Err(Reason::EmptyLink),
+        _ => check_percent_encoding(href)?,
+    }
+
+    Ok(category)
+}
+
+/// Make sure a web link has a sensible fragment and is percent-encoded.
+pub(crate) fn check_url(href: &str) -> Result<(), Reason> {
+    check_fragment(href)?;
+    check_percent_encoding(href)?;
+
+    match encode_href(href) {
+        Some(encoded) => Err(Reason::UnencodedUrl(encoded)),
+        None => Ok(()),
+    }
+}
+
+/// Make sure a link to a file can be decoded and has a sensible fragment.
+pub(crate) fn check_path(href: &str) -> Result<(), Reason> {
+    if let Some(reason) = malformed_url(href) {
+        return Err(reason);
+    }
+    check_fragment(href)?;
+
+    let path = href.split(['?', '#']).next().unwrap_or_default();
+    check_percent_encoding(path)
+}
+
+/// Something like `https://exa mple.com/` is a broken URL rather than a
+/// strange path.
+fn malformed_url(href: &str) -> Option<Reason> {
+    let (scheme, _) = href.split_once("://")?;
+    let looks_like_scheme = scheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+
+    if !looks_like_scheme {
+        return None;
+    }
+
+    Url::parse(href)
+        .err()
+        .map(|e| Reason::MalformedUrl(href.to_string(), e))
+}
+
+fn check_fragment(href: &str) -> Result<(), Reason> {
+    match href.split_once('#') {
+        Some((_, fragment))
+            if fragment.contains('#')
+                || fragment.contains(char::is_whitespace) =>
+        {
+            Err(Reason::MalformedFragment(fragment.to_string()))
+        },
+        _ => Ok(()),
+    }
+}
+
+fn check_percent_encoding(text: &str) -> Result<(), Reason> {
+    let bytes = text.as_bytes();
+    let well_formed = bytes.iter().enumerate().all(|(i, &b)| {
+        b != b'%'
+            || bytes
+                .get(i + 1..i + 3)
+                .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+    });
+    let decodes = percent_decode_str(text)
+        .decode_utf8()
+        .is_ok_and(|decoded| !decoded.contains('\0'));
+
+    if well_formed && decodes {
+        Ok(())
+    } else {
+        Err(Reason::InvalidPercentEncoding(text.to_string()))
+    }
+}
+
+/// Make sure every address in a `mailto:` link looks like an email address.
+///
+/// The `address` may contain several comma-separated addresses and a query
+/// string (e.g. `?subject=Hello`), and can even be empty as long as the query
+/// says who to send the email `to`.
+pub(crate) fn check_mailto(address: &str) -> Result<(), Reason> {
+    let (to, query) = match address.find('?') {
+        Some(index) => (&address[..index], Some(&address[index + 1..])),
+        None => (address, None),
+    };
+    let to = percent_decode_str(to).decode_utf8_lossy();
+
+    if to.is_empty() {
+        let has_recipient = query.is_some_and(|query| {
+            query.split('&').any(|pair| pair.starts_with("to="))
+        });
+
+        return if has_recipient {
+            Ok(())
+        } else {
+            Err(Reason::InvalidEmailAddress(address.to_string()))
+        };
+    }
+
+    match to.split(',').map(str::trim).find(|a| !is_email_address(a)) {
+        Some(bad) => Err(Reason::InvalidEmailAddress(bad.to_string())),
+        None => Ok(()),
+    }
+}
+
+fn is_email_address(address: &str) -> bool {
+    let (local, domain) = match address.rfind('@') {
+        Some(at) => (&address[..at], &address[at + 1..]),
+        None => return false,
+    };
+
+    !local.is_empty()
+        && !local.contains(char::is_whitespace)
+        && !domain.is_empty()
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+/// Make sure a `tel:` link contains a phone number.
+///
+/// Any parameters (e.g. `;ext=123`) are ignored, and visual separators like
+/// dashes and parentheses are allowed.
+pub(crate) fn check_tel(number: &str) -> Result<(), Reason> {
+    let decoded = percent_decode_str(number).decode_utf8_lossy();
+    let digits = decoded.split(';').next().unwrap_or_default();
+    let digits = digits.strip_prefix('+').unwrap_or(digits);
+
+    let valid = digits.chars().any(|c| c.is_ascii_digit())
+        && digits.chars().all(|c| {
+            c.is_ascii_digit()
+                || VISUAL_SEPARATORS.contains(&c)
+                || c == '*'
+                || c == '#'
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Reason::InvalidPhoneNumber(number.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_mailto_and_tel_links() {
+        let emails = vec![
+            ("michael@example.com", true),
+            ("a@example.com,%20b@example.org", true),
+            ("?to=michael@example.com&subject=Hi", true),
+            ("michael@example.com?subject=Hello%20World", true),
+            ("michael", false),
+            ("michael@", false),
+            ("@example.com", false),
+            ("michael@example..com", false),
+            ("?subject=Hi", false),
+            ("", false),
+        ];
+        for (address, should_be) in emails {
+            assert_eq!(check_mailto(address).is_ok(), should_be, "{}", address);
+        }
+
+        let numbers = vec![
+            ("+1-201-555-0123", true),
+            ("+61%20(2)%209876%205432", true),
+            ("7042;phone-context=example.com", true),
+            ("+1-201-555-0123;ext=42", true),
+            ("", false),
+            ("+", false),
+            ("call-me-maybe", false),
+        ];
+        for (number, should_be) in numbers {
+            assert_eq!(check_tel(number).is_ok(), should_be, "{}", number);
+        }
+    }
+
+    #[test]
+    fn check_links_without_any_io() {
+        let inputs = vec![
+            ("https://example.com/page#intro", true),
+            ("//example.com/page", true),
+            ("../README.md#license", true),
+            ("./my%20file.md", true),
+            ("mailto:michael@example.com", true),
+            ("urn:isbn:0451450523", true),
+            ("https://example.com/a page", false),
+            ("https://exa mple.com/", false),
+            ("README.md#one#two", false),
+            ("README.md#has space", false),
+            ("docs/100%.md", false),
+            ("docs/%FF.md", false),
+            ("docs/%00.md", false),
+            ("javascript:void(0)", false),
+            ("", false),
+        ];
+
+        for (href, should_be) in inputs {
+            assert_eq!(check_syntax(href).is_ok(), should_be, "{}", href);
+        }
+
+        match check_syntax("http://[::1/").unwrap_err() {
+            Reason::MalformedUrl(href, url::ParseError::InvalidIpv6Address) => {
+                assert_eq!(href, "http://[::1/")
+            },
+            other => panic!("Unexpected error: {}", other),
+        }
+    }
+}
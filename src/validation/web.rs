@@ -1,7 +1,13 @@
-use crate::validation::{CacheEntry, Context, Reason};
-use http::HeaderMap;
-use reqwest::{Client, Url};
-use std::time::SystemTime;
+use crate::validation::{
+    limits::RedirectLoop, Attempts, CacheEntry, Context, Reason,
+};
+use http::{HeaderMap, Method};
+use reqwest::{Client, Response, Url};
+use std::{
+    error::Error,
+    io::{self, ErrorKind},
+    time::{Instant, SystemTime},
+};
 
 #[deprecated]
 /// Send a HEAD request to a particular endpoint.
@@ -22,14 +28,46 @@ pub async fn head(
     url: Url,
     extra_headers: HeaderMap,
 ) -> Result<(), reqwest::Error> {
-    client
-        .head(url)
-        .headers(extra_headers)
-        .send()
+    head_response(client, url, extra_headers).await?;
+    Ok(())
+}
+
+async fn head_response(
+    client: &Client,
+    url: Url,
+    extra_headers: HeaderMap,
+) -> Result<Response, reqwest::Error> {
+    head_response_before(client, url, extra_headers, None).await
+}
+
+/// Send a HEAD request, giving up if it isn't done by the `deadline`.
+async fn head_response_before(
+    client: &Client,
+    url: Url,
+    extra_headers: HeaderMap,
+    deadline: Option<Instant>,
+) -> Result<Response, reqwest::Error> {
+    send_head_before(client, url, extra_headers, deadline)
         .await?
-        .error_for_status()?;
+        .error_for_status()
+}
 
-    Ok(())
+/// Like [`head_response_before()`], except error statuses are returned as a
+/// normal [`Response`].
+async fn send_head_before(
+    client: &Client,
+    url: Url,
+    extra_headers: HeaderMap,
+    deadline: Option<Instant>,
+) -> Result<Response, reqwest::Error> {
+    let mut request = client.head(url).headers(extra_headers);
+
+    if let Some(deadline) = deadline {
+        request =
+            request.timeout(deadline.saturating_duration_since(Instant::now()));
+    }
+
+    request.send().await
 }
 
 /// Check whether a [`Url`] points to a valid resource on the internet.
@@ -37,43 +75,219 @@ pub async fn check_web<C>(url: &Url, ctx: &C) -> Result<(), Reason>
 where
     C: Context + ?Sized,
 {
-    log::debug!("Checking \"{}\" on the web", url);
+    check_web_and_follow(url, ctx, &mut Attempts::default())
+        .await
+        .map(|_| ())
+}
 
-    if already_valid(&url, ctx) {
-        log::debug!("The cache says \"{}\" is still valid", url);
-        return Ok(());
+/// Like [`check_web()`], except when the server redirected us somewhere
+/// else we also return the final [`Url`].
+///
+/// Every request that gets sent is recorded in `attempts`.
+pub(crate) async fn check_web_and_follow<C>(
+    url: &Url,
+    ctx: &C,
+    attempts: &mut Attempts,
+) -> Result<Option<Url>, Reason>
+where
+    C: Context + ?Sized,
+{
+    log::debug!("Checking \"{}\" on the web", ctx.redact(url));
+
+    let cached = still_valid(url, ctx);
+    #[cfg(feature = "metrics")]
+    if ctx.cache().is_some() {
+        crate::metrics::cache_lookup(cached.is_some());
+    }
+
+    if let Some(entry) = cached {
+        log::debug!("The cache says \"{}\" is still valid", ctx.redact(url));
+        return Ok(entry.redirected.map(|mut redirected| {
+            redirected.set_fragment(url.fragment());
+            redirected
+        }));
     }
 
-    let result =
-        head(ctx.client(), url.clone(), ctx.url_specific_headers(&url)).await;
+    let sent = SystemTime::now();
+    let result = send_head_before(
+        ctx.client(),
+        url.clone(),
+        ctx.url_specific_headers(url),
+        ctx.deadline(),
+    )
+    .await;
+    attempts.record(sent, Method::HEAD, url, &result);
+
+    let result = match (result, ctx.response_rules()) {
+        (Ok(response), Some(rules)) if !response.status().is_success() => {
+            if rules.is_blocked(url, &response, ctx, attempts).await {
+                let status = response.status().as_u16();
+                let host = url.host_str().unwrap_or_default().to_string();
+                log::debug!(
+                    "\"{}\" blocks automated requests",
+                    ctx.redact(url)
+                );
+                return Err(Reason::BlockedByServer(host, status));
+            }
+            response.error_for_status()
+        },
+        (result, _) => result.and_then(Response::error_for_status),
+    };
 
     if let Some(fragment) = url.fragment() {
         // TODO: check the fragment
-        log::warn!("Fragment checking isn't implemented, not checking if there is a \"{}\" header in \"{}\"", fragment, url);
+        log::warn!("Fragment checking isn't implemented, not checking if there is a \"{}\" header in \"{}\"", fragment, ctx.redact(url));
     }
 
-    let entry = CacheEntry::new(SystemTime::now(), result.is_ok());
+    let redirected = result.as_ref().ok().and_then(|response| {
+        let mut redirected = response.url().clone();
+        redirected.set_fragment(url.fragment());
+        Some(redirected).filter(|redirected| redirected != url)
+    });
+    let remembered = redirected.as_ref().map(|redirected| {
+        let mut redirected = ctx.redact(redirected);
+        redirected.set_fragment(None);
+        redirected
+    });
+    let entry = CacheEntry::new(SystemTime::now(), result.is_ok())
+        .set_redirected(remembered);
     update_cache(url, ctx, entry);
 
-    result.map_err(Reason::from)
+    result.map_err(into_reason)?;
+
+    if let Some(redirected) = &redirected {
+        log::debug!(
+            "\"{}\" redirected to \"{}\"",
+            ctx.redact(url),
+            ctx.redact(redirected)
+        );
+    }
+
+    Ok(redirected)
+}
+
+/// Turn a [`reqwest::Error`] into a [`Reason`], picking out redirect loops
+/// and the different ways connecting to a host can fail.
+pub(crate) fn into_reason(error: reqwest::Error) -> Reason {
+    if let Some(RedirectLoop(chain)) = RedirectLoop::find(&error) {
+        return Reason::RedirectLoop {
+            chain: chain.clone(),
+        };
+    }
+
+    let host = match error.url() {
+        Some(url) => url.host_str().unwrap_or(url.as_str()).to_string(),
+        None => return Reason::Web(error),
+    };
+
+    match ConnectFailure::find(&error) {
+        Some(ConnectFailure::HostNotFound) => Reason::HostNotFound(host),
+        Some(ConnectFailure::Refused) => Reason::ConnectionRefused(host),
+        Some(ConnectFailure::Tls) => Reason::Tls(host, error),
+        None => Reason::Web(error),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ConnectFailure {
+    HostNotFound,
+    Refused,
+    Tls,
+}
+
+impl ConnectFailure {
+    fn find(error: &reqwest::Error) -> Option<ConnectFailure> {
+        if !error.is_connect() {
+            return None;
+        }
+
+        let mut source = Error::source(error);
+
+        while let Some(error) = source {
+            // hyper doesn't expose its connect error type, but resolver
+            // failures (from any resolver) are always reported like this
+            if error.to_string().starts_with("dns error") {
+                return Some(ConnectFailure::HostNotFound);
+            }
+            let io = error.downcast_ref::<io::Error>();
+            if io.map(io::Error::kind) == Some(ErrorKind::ConnectionRefused) {
+                return Some(ConnectFailure::Refused);
+            }
+            // TLS errors are sometimes wrapped in an io::Error, which hides
+            // them from the source() chain
+            let wrapped = io.and_then(io::Error::get_ref);
+            if error.is::<native_tls::Error>()
+                || wrapped.is_some_and(|e| e.is::<native_tls::Error>())
+            {
+                return Some(ConnectFailure::Tls);
+            }
+            source = error.source();
+        }
+
+        None
+    }
 }
 
-fn already_valid<C>(url: &Url, ctx: &C) -> bool
+pub(crate) fn already_valid<C>(url: &Url, ctx: &C) -> bool
 where
     C: Context + ?Sized,
 {
-    if let Some(cache) = ctx.cache() {
-        return cache.url_is_still_valid(url, ctx.cache_timeout());
-    }
+    still_valid(url, ctx).is_some()
+}
 
-    false
+/// Look up the [`CacheEntry`] for a [`Url`], if the [`Context::cache()`]
+/// says it is still valid.
+fn still_valid<C>(url: &Url, ctx: &C) -> Option<CacheEntry>
+where
+    C: Context + ?Sized,
+{
+    let cache = ctx.cache()?;
+    let key = ctx.cache_key(url);
+    cache.still_valid(&key, ctx.cache_timeout()).cloned()
 }
 
-fn update_cache<C>(url: &Url, ctx: &C, entry: CacheEntry)
+pub(crate) fn update_cache<C>(url: &Url, ctx: &C, entry: CacheEntry)
 where
     C: Context + ?Sized,
 {
     if let Some(mut cache) = ctx.cache() {
-        cache.insert(url.clone(), entry);
+        cache.insert(ctx.cache_key(url), entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dns::DnsCache, validation::check_web, BasicContext};
+    use hyper::client::connect::dns::Name;
+    use reqwest::dns::{Resolve, Resolving};
+    use std::net::TcpListener;
+
+    struct NoSuchHost;
+
+    impl Resolve for NoSuchHost {
+        fn resolve(&self, name: Name) -> Resolving {
+            let error = io::Error::new(io::ErrorKind::NotFound, name.as_str());
+            Box::pin(futures::future::ready(Err(error.into())))
+        }
+    }
+
+    #[tokio::test]
+    async fn tell_dead_domains_apart_from_refused_connections() {
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr();
+        let url = Url::parse(&format!("http://{}/", closed.unwrap())).unwrap();
+        let dns = DnsCache::new().set_resolver(NoSuchHost);
+        let ctx = BasicContext::default().set_dns_cache(dns);
+
+        let refused = check_web(&url, &ctx).await.unwrap_err();
+        let dead = Url::parse("http://dead.example.com/").unwrap();
+        let not_found = check_web(&dead, &ctx).await.unwrap_err();
+
+        assert_eq!(refused.to_string(), "\"127.0.0.1\" refused the connection");
+        assert!(not_found.host_not_found());
+        assert_eq!(
+            not_found.to_string(),
+            "The host \"dead.example.com\" doesn't exist"
+        );
     }
 }
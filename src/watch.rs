@@ -0,0 +1,347 @@
+//! Watching a [`Site`] for changes and re-checking the links they affect.
+//!
+//! This is the core loop behind any "serve and check" tool. Each time
+//! something in the [`Site`] changes, only the documents which were touched
+//! are re-scanned, and the only other links to be checked again are the ones
+//! pointing at a changed file (or which were broken last time).
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use futures::{channel::mpsc, StreamExt};
+//! use linkcheck::{site::Site, watch::Watcher, BasicContext};
+//!
+//! # #[tokio::main] async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let watcher = Watcher::new(Site::new("./src").include("**/*.md")?)?;
+//! let ctx = BasicContext::default();
+//! let (tx, mut rx) = mpsc::unbounded();
+//!
+//! let checking = watcher.run(&ctx, tx);
+//! let reporting = async {
+//!     while let Some(update) = rx.next().await {
+//!         for invalid in &update.outcomes.invalid {
+//!             println!("{:?}", invalid);
+//!         }
+//!     }
+//! };
+//!
+//! let (result, _) = futures::join!(checking, reporting);
+//! result?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backlinks::Backlinks,
+    site::{self, Document, Site},
+    validation::{Context, Outcomes},
+    Category, Link,
+};
+use codespan::Files;
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    StreamExt,
+};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Keeps track of a [`Site`] and re-checks it whenever something changes.
+#[derive(Debug)]
+pub struct Watcher {
+    site: Site,
+    files: Files<String>,
+    documents: HashMap<PathBuf, Document>,
+    backlinks: Backlinks,
+    /// Local links which were broken the last time they were checked.
+    broken: HashSet<Link>,
+    events: UnboundedReceiver<notify::Result<Event>>,
+    _watcher: RecommendedWatcher,
+}
+
+/// The results of re-checking a [`Site`] after something changed.
+///
+/// Every [`Link`] in [`Update::outcomes`] supersedes any previous result for
+/// the same [`Link`].
+#[derive(Debug)]
+pub struct Update {
+    /// Documents which were (re-)scanned. The [`Outcomes`] contain a result
+    /// for every one of their [`Link`]s.
+    pub changed: Vec<Document>,
+    /// Documents which have been deleted. Any results for their [`Link`]s
+    /// should be forgotten.
+    pub removed: Vec<PathBuf>,
+    /// The results of checking every affected [`Link`].
+    pub outcomes: Outcomes,
+}
+
+impl Watcher {
+    /// Start watching a [`Site`] for changes.
+    pub fn new(site: Site) -> notify::Result<Self> {
+        let (tx, events) = mpsc::unbounded();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.unbounded_send(event);
+        })?;
+        watcher.watch(site.root(), RecursiveMode::Recursive)?;
+
+        Ok(Watcher {
+            site,
+            files: Files::new(),
+            documents: HashMap::new(),
+            backlinks: Backlinks::new(),
+            broken: HashSet::new(),
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// The source for every document that has been scanned so far.
+    ///
+    /// A document keeps the same [`codespan::FileId`] when it is re-scanned.
+    pub fn files(&self) -> &Files<String> { &self.files }
+
+    /// Scan and check every document in the [`Site`] from scratch.
+    pub async fn check_all<C>(&mut self, ctx: &C) -> io::Result<Update>
+    where
+        C: Context + Sync + ?Sized,
+    {
+        let mut paths: HashSet<PathBuf> =
+            self.documents.keys().cloned().collect();
+        paths.extend(self.site.paths()?.iter().map(|path| key(path)));
+
+        self.recheck(paths, ctx).await
+    }
+
+    /// Wait until something in the [`Site`] changes, then re-check the
+    /// affected links.
+    ///
+    /// Returns `None` when no more changes can be received.
+    pub async fn next_update<C>(
+        &mut self,
+        ctx: &C,
+    ) -> Option<io::Result<Update>>
+    where
+        C: Context + Sync + ?Sized,
+    {
+        let mut changed = HashSet::new();
+        let first = self.events.next().await?;
+        record_event(first, &mut changed);
+
+        // editors tend to emit several events per save, so handle everything
+        // that has already arrived as a single batch
+        while let Ok(event) = self.events.try_recv() {
+            record_event(event, &mut changed);
+        }
+
+        Some(self.recheck(changed, ctx).await)
+    }
+
+    /// Check the whole [`Site`], then keep sending an [`Update`] whenever
+    /// something changes.
+    ///
+    /// This only returns when the receiving end of `updates` is dropped or
+    /// there are no more changes to watch for.
+    pub async fn run<C>(
+        mut self,
+        ctx: &C,
+        updates: UnboundedSender<Update>,
+    ) -> io::Result<()>
+    where
+        C: Context + Sync + ?Sized,
+    {
+        let update = self.check_all(ctx).await?;
+        if updates.unbounded_send(update).is_err() {
+            return Ok(());
+        }
+
+        while let Some(update) = self.next_update(ctx).await {
+            if updates.unbounded_send(update?).is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn recheck<C>(
+        &mut self,
+        changed: HashSet<PathBuf>,
+        ctx: &C,
+    ) -> io::Result<Update>
+    where
+        C: Context + Sync + ?Sized,
+    {
+        let current: HashMap<PathBuf, PathBuf> = self
+            .site
+            .paths()?
+            .into_iter()
+            .map(|path| (key(&path), path))
+            .collect();
+
+        let gone: Vec<PathBuf> = self
+            .documents
+            .keys()
+            .filter(|k| !current.contains_key(*k))
+            .cloned()
+            .collect();
+        let removed: Vec<PathBuf> = gone
+            .iter()
+            .filter_map(|k| self.documents.remove(k))
+            .map(|doc| doc.path)
+            .collect();
+
+        let mut rescanned = Vec::new();
+        for (k, path) in &current {
+            if changed.contains(k) || !self.documents.contains_key(k) {
+                let doc = self.scan(path)?;
+                self.documents.insert(k.clone(), doc.clone());
+                rescanned.push(doc);
+            }
+        }
+
+        // links in the other documents which may now have a different result
+        let rescanned_files: HashSet<_> =
+            rescanned.iter().map(|doc| doc.file).collect();
+        let affected: HashSet<Link> = changed
+            .iter()
+            .flat_map(|path| self.backlinks.links_to(path))
+            .chain(&self.broken)
+            .filter(|link| !rescanned_files.contains(&link.file))
+            .filter(|link| {
+                self.documents.values().any(|doc| doc.file == link.file)
+            })
+            .cloned()
+            .collect();
+
+        let mut to_check = rescanned.clone();
+        to_check.extend(self.documents.values().filter_map(|doc| {
+            let links: Vec<Link> = doc
+                .links
+                .iter()
+                .filter(|link| affected.contains(link))
+                .cloned()
+                .collect();
+
+            if links.is_empty() {
+                None
+            } else {
+                Some(Document {
+                    links,
+                    ..doc.clone()
+                })
+            }
+        }));
+
+        log::debug!(
+            "Re-checking {} links after {} files changed",
+            to_check.iter().map(|doc| doc.links.len()).sum::<usize>(),
+            changed.len()
+        );
+        let outcomes = site::validate(&to_check, ctx).await;
+
+        let live_files: HashSet<_> =
+            self.documents.values().map(|doc| doc.file).collect();
+        self.broken.retain(|link| {
+            live_files.contains(&link.file)
+                && !rescanned_files.contains(&link.file)
+                && !affected.contains(link)
+        });
+        // there's no point hammering web servers with links we know are
+        // broken, but retrying local links is cheap
+        self.broken.extend(
+            outcomes
+                .invalid
+                .iter()
+                .map(|invalid| &invalid.link)
+                .filter(|link| {
+                    !matches!(link.category(), Some(Category::Url(_)))
+                })
+                .cloned(),
+        );
+        self.backlinks = Backlinks::from_links(
+            self.documents.values().flat_map(|doc| doc.links.clone()),
+            &self.files,
+            ctx.filesystem_options(),
+        );
+
+        Ok(Update {
+            changed: rescanned,
+            removed,
+            outcomes,
+        })
+    }
+
+    fn scan(&mut self, path: &Path) -> io::Result<Document> {
+        let scanner = Site::scanner_for(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("No scanner for \"{}\"", path.display()),
+            )
+        })?;
+
+        log::debug!("Scanning \"{}\"", path.display());
+        let src = std::fs::read_to_string(path)?;
+        let file = match self.documents.get(&key(path)) {
+            Some(previous) => {
+                self.files.update(previous.file, src);
+                previous.file
+            },
+            None => self.files.add(path, src),
+        };
+        let links = scanner(self.files.source(file))
+            .into_iter()
+            .map(|(href, span)| Link::new(href, span, file))
+            .collect();
+
+        Ok(Document {
+            file,
+            path: path.to_path_buf(),
+            links,
+        })
+    }
+}
+
+/// Normalise a path so the ones from the file watcher can be compared with
+/// the ones found when walking the [`Site`].
+fn key(path: &Path) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn record_event(event: notify::Result<Event>, changed: &mut HashSet<PathBuf>) {
+    match event {
+        Ok(event) => changed.extend(event.paths.iter().map(|path| key(path))),
+        Err(e) => log::warn!("Unable to watch for changes: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasicContext;
+
+    #[tokio::test]
+    async fn recheck_links_to_a_deleted_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(temp.path()).unwrap();
+        std::fs::write(root.join("index.md"), "[other](other.md)").unwrap();
+        std::fs::write(root.join("other.md"), "[home](index.md)").unwrap();
+        let ctx = BasicContext::default();
+        let mut watcher = Watcher::new(Site::new(&root)).unwrap();
+
+        let first = watcher.check_all(&ctx).await.unwrap();
+        assert_eq!(first.changed.len(), 2);
+        assert_eq!(first.outcomes.valid.len(), 2);
+
+        std::fs::remove_file(root.join("other.md")).unwrap();
+        let second = watcher.next_update(&ctx).await.unwrap().unwrap();
+
+        assert!(second.changed.is_empty());
+        assert_eq!(second.removed, vec![root.join("other.md")]);
+        assert!(second.outcomes.valid.is_empty());
+        assert_eq!(second.outcomes.invalid.len(), 1);
+        assert_eq!(second.outcomes.invalid[0].link.href, "other.md");
+    }
+}